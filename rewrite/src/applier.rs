@@ -0,0 +1,293 @@
+/// Applying fades on a dedicated thread
+/// With --fade-thread the gamma ramps are applied by a thread of their own
+/// that steps fades on monotonic deadlines, so a main loop held up by
+/// signals, control input or a loaded system does not make fades stutter.
+/// The main loop keeps computing targets and pushes them over a channel.
+
+use crate::display_loss::{
+    apply_recovering, DisplayLossPolicy, RECONNECT_ATTEMPTS, RECONNECT_DELAY,
+};
+use crate::error::Error;
+use crate::fade::Fade;
+use crate::gamma::GammaMethod;
+use crate::signals;
+use crate::types::ColorSetting;
+use log::{debug, error};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{Scope, ScopedJoinHandle};
+use std::time::{Duration, Instant};
+
+/// Nice value requested for the applier thread. Lowering it needs
+/// CAP_SYS_NICE, so without that the thread runs at normal priority.
+const APPLIER_NICE: libc::c_int = -5;
+
+/// Requests from the main loop to the applier thread
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApplierCommand {
+    /// Fade towards `setting` if the change is too big to apply instantly,
+    /// or for any change when `force` is set, as on a toggle
+    Target { setting: ColorSetting, force: bool },
+    /// Apply `setting` immediately, cancelling any fade
+    Jump(ColorSetting),
+    /// Stop stepping the fade until the next target, e.g. while locked
+    Hold,
+    /// Send the ramps again on the next application
+    Reapply,
+    /// Interpolate the temperature of future fade steps in mireds
+    InterpolateInMireds(bool),
+}
+
+/// What the applier thread has done so far
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppliedState {
+    /// Setting last applied
+    pub current: ColorSetting,
+    /// Whether a fade is in progress
+    pub fading: bool,
+    pub fades_started: u64,
+    pub applies: u64,
+    /// Invalid settings replaced by the last good one
+    pub rejected: u64,
+    /// Commands handled so far, to tell when the thread has caught up
+    pub handled: u64,
+    /// Error that stopped the thread
+    pub error: Option<Error>,
+    /// Whether the thread ended without recording an error, e.g. after
+    /// a panic
+    pub stopped: bool,
+}
+
+/// Fade stepping on deadlines, apart from the thread and gamma method.
+/// Times are passed in so the schedule can be tested without waiting.
+#[derive(Debug, Clone)]
+pub struct FadeStepper {
+    fade: Fade,
+    step: Duration,
+    target: ColorSetting,
+    force: bool,
+    deadline: Option<Instant>,
+}
+
+impl FadeStepper {
+    /// Step `fade` every `step` while it is in progress
+    pub fn new(fade: Fade, step: Duration) -> Self {
+        Self {
+            target: fade.current(),
+            fade,
+            step,
+            force: false,
+            deadline: None,
+        }
+    }
+
+    pub fn fade(&self) -> &Fade {
+        &self.fade
+    }
+
+    /// When the next fade step is due, unless no fade is being stepped
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Handle `command` received at `now`, returning the setting to apply
+    /// right away, if any. A target pushed during a fade is picked up by
+    /// the next step rather than adding steps of its own.
+    pub fn handle(&mut self, command: ApplierCommand, now: Instant) -> Option<ColorSetting> {
+        match command {
+            ApplierCommand::Target { setting, force } => {
+                self.target = setting;
+                self.force |= force;
+                if self.deadline.is_some() {
+                    return None;
+                }
+                Some(self.advance(now, now))
+            }
+            ApplierCommand::Jump(setting) => {
+                self.target = setting;
+                self.force = false;
+                self.deadline = None;
+                Some(self.fade.jump(&setting))
+            }
+            ApplierCommand::Hold => {
+                self.deadline = None;
+                None
+            }
+            ApplierCommand::Reapply => None,
+            ApplierCommand::InterpolateInMireds(in_mireds) => {
+                self.fade.set_interpolate_in_mireds(in_mireds);
+                None
+            }
+        }
+    }
+
+    /// Take the fade step due at `now`, if any
+    pub fn tick(&mut self, now: Instant) -> Option<ColorSetting> {
+        let deadline = self.deadline.filter(|&deadline| deadline <= now)?;
+        Some(self.advance(deadline, now))
+    }
+
+    /* Step towards the target and schedule the next step one interval
+       after `due`, or after `now` if the thread fell further behind than
+       that, so late steps are not made up in a burst */
+    fn advance(&mut self, due: Instant, now: Instant) -> ColorSetting {
+        let setting = self.fade.update(&self.target, self.force);
+        self.force = false;
+        self.deadline = self.fade.is_active().then(|| {
+            let next = due + self.step;
+            if next <= now {
+                now + self.step
+            } else {
+                next
+            }
+        });
+        setting
+    }
+}
+
+/// Apply settings from `stepper` to `gamma` as commands arrive and fade
+/// steps fall due, recording progress in `state`, until the channel is
+/// closed or applying fails. Invalid settings are never applied; the last
+/// good one is sent instead. A lost display is handled as `on_display_loss`
+/// says.
+pub fn run_applier(
+    mut stepper: FadeStepper,
+    commands: Receiver<ApplierCommand>,
+    gamma: &mut dyn GammaMethod,
+    state: &Mutex<AppliedState>,
+    on_display_loss: DisplayLossPolicy,
+    now: impl Fn() -> Instant,
+) {
+    let mut last_good = stepper.fade().current();
+
+    loop {
+        let received = match stepper.deadline() {
+            Some(deadline) => commands.recv_timeout(deadline.saturating_duration_since(now())),
+            None => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let handled = received.is_ok();
+        let setting = match received {
+            Ok(ApplierCommand::Reapply) => {
+                gamma.invalidate();
+                None
+            }
+            Ok(command) => stepper.handle(command, now()),
+            Err(RecvTimeoutError::Timeout) => stepper.tick(now()),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut rejected = false;
+        if let Some(setting) = setting {
+            let setting = match setting.validate() {
+                Ok(()) => setting,
+                Err(e) => {
+                    error!("Invalid color setting, keeping last good one: {}", e);
+                    rejected = true;
+                    last_good
+                }
+            };
+            if let Err(e) = apply_recovering(
+                gamma,
+                &setting,
+                on_display_loss,
+                RECONNECT_ATTEMPTS,
+                RECONNECT_DELAY,
+                &mut signals::sleep_unless_exiting,
+            ) {
+                state.lock().unwrap().error = Some(e);
+                break;
+            }
+            last_good = setting;
+        }
+
+        let mut state = state.lock().unwrap();
+        state.current = last_good;
+        state.fading = stepper.fade().is_active();
+        state.fades_started = stepper.fade().fades_started();
+        state.applies += setting.is_some() as u64;
+        state.rejected += rejected as u64;
+        state.handled += handled as u64;
+    }
+}
+
+/// Main loop end of an applier thread
+pub struct FadeApplier<'scope> {
+    commands: Sender<ApplierCommand>,
+    state: Arc<Mutex<AppliedState>>,
+    sent: u64,
+    thread: ScopedJoinHandle<'scope, ()>,
+}
+
+impl<'scope> FadeApplier<'scope> {
+    /// Start applying `fade` to `gamma` on a thread in `scope`, stepping
+    /// every `step`. The thread stops once the applier is dropped.
+    pub fn spawn(
+        scope: &'scope Scope<'scope, '_>,
+        fade: Fade,
+        step: Duration,
+        gamma: &'scope mut dyn GammaMethod,
+        on_display_loss: DisplayLossPolicy,
+    ) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let state = Arc::new(Mutex::new(AppliedState {
+            current: fade.current(),
+            ..AppliedState::default()
+        }));
+        let thread_state = Arc::clone(&state);
+
+        let thread = scope.spawn(move || {
+            raise_priority();
+            let stepper = FadeStepper::new(fade, step);
+            run_applier(
+                stepper,
+                receiver,
+                gamma,
+                &thread_state,
+                on_display_loss,
+                Instant::now,
+            );
+        });
+
+        Self {
+            commands,
+            state,
+            sent: 0,
+            thread,
+        }
+    }
+
+    /// Push `command` to the thread. Commands sent after the thread
+    /// stopped are dropped; the error is in the state.
+    pub fn send(&mut self, command: ApplierCommand) {
+        if self.commands.send(command).is_ok() {
+            self.sent += 1;
+        }
+    }
+
+    /// Snapshot of what the thread has done so far. A thread that
+    /// stopped without recording an error, e.g. after a panic, is
+    /// reported as stopped, so the main loop does not wait on it.
+    pub fn state(&self) -> AppliedState {
+        let mut state = self.state.lock().unwrap().clone();
+        state.stopped = state.error.is_none() && self.thread.is_finished();
+        state
+    }
+
+    /// Whether every command sent has been handled and no fade is left
+    pub fn is_settled(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.handled == self.sent && !state.fading
+    }
+}
+
+/* Ask for a lower nice value for the calling thread, which on Linux
+   applies to the thread alone */
+fn raise_priority() {
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, APPLIER_NICE) } != 0 {
+        debug!(
+            "Could not raise the fade thread priority: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
@@ -1,10 +1,14 @@
 /// City database for interactive location selection
 /// Contains major cities organized by country
 
+use crate::types::{MAX_LAT, MAX_LON, MIN_LAT, MIN_LON};
 use lazy_static::lazy_static;
+use log::info;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct City {
@@ -39,8 +43,43 @@ impl City {
     pub fn longitude(&self) -> Result<f64, std::num::ParseFloatError> {
         self.lng.parse()
     }
+
+    /// Check that the entry names a city and country and has coordinates
+    /// in range
+    pub fn validate(&self) -> Result<(), String> {
+        if self.city.trim().is_empty() || self.city_ascii.trim().is_empty() {
+            return Err("City name must not be empty".to_string());
+        }
+        if self.country.trim().is_empty() {
+            return Err("Country must not be empty".to_string());
+        }
+
+        let lat = self
+            .latitude()
+            .map_err(|_| format!("Invalid latitude: {}", self.lat))?;
+        if !(MIN_LAT..=MAX_LAT).contains(&lat) {
+            return Err(format!(
+                "Latitude must be between {} and {}",
+                MIN_LAT, MAX_LAT
+            ));
+        }
+        let lon = self
+            .longitude()
+            .map_err(|_| format!("Invalid longitude: {}", self.lng))?;
+        if !(MIN_LON..=MAX_LON).contains(&lon) {
+            return Err(format!(
+                "Longitude must be between {} and {}",
+                MIN_LON, MAX_LON
+            ));
+        }
+        Ok(())
+    }
 }
 
+/// Cities read from --cities-file, merged in when CITIES_BY_COUNTRY is
+/// first accessed
+static USER_CITIES: OnceLock<(PathBuf, Vec<City>)> = OnceLock::new();
+
 lazy_static! {
     /// Global hash map of countries to cities, loaded at first access
     pub static ref CITIES_BY_COUNTRY: HashMap<String, Vec<City>> = {
@@ -49,15 +88,74 @@ lazy_static! {
             .expect("Failed to parse filtered_world_cities.json");
 
         let mut map: HashMap<String, Vec<City>> = HashMap::new();
-        for city in cities {
-            map.entry(city.country.clone())
-                .or_insert_with(Vec::new)
-                .push(city);
+        merge_cities(&mut map, cities);
+
+        if let Some((path, cities)) = USER_CITIES.get() {
+            let (added, replaced) = merge_cities(&mut map, cities.clone());
+            info!(
+                "Added {} cities and replaced {} from {}",
+                added,
+                replaced,
+                path.display()
+            );
         }
         map
     };
 }
 
+/// Parse cities in the bundled JSON schema, rejecting the whole list if
+/// any entry is invalid
+pub fn parse_cities(json: &str) -> Result<Vec<City>, String> {
+    let cities: Vec<City> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid cities data: {}", e))?;
+
+    for (index, city) in cities.iter().enumerate() {
+        city.validate()
+            .map_err(|e| format!("City {} ({}): {}", index + 1, city.city_ascii, e))?;
+    }
+    Ok(cities)
+}
+
+/// Add `cities` to `map`. A city with the same country and ASCII name as
+/// one already present replaces it. Returns the number of cities added and
+/// replaced.
+pub fn merge_cities(map: &mut HashMap<String, Vec<City>>, cities: Vec<City>) -> (usize, usize) {
+    let (mut added, mut replaced) = (0, 0);
+
+    for city in cities {
+        let entries = map.entry(city.country.clone()).or_default();
+        match entries
+            .iter_mut()
+            .find(|existing| existing.city_ascii.eq_ignore_ascii_case(&city.city_ascii))
+        {
+            Some(existing) => {
+                *existing = city;
+                replaced += 1;
+            }
+            None => {
+                entries.push(city);
+                added += 1;
+            }
+        }
+    }
+    (added, replaced)
+}
+
+/// Read extra cities from `path` to merge into CITIES_BY_COUNTRY. Must be
+/// called before the city list is first used. Returns the number of
+/// entries read.
+pub fn load_cities_file(path: &Path) -> Result<usize, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let cities = parse_cities(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let count = cities.len();
+
+    USER_CITIES
+        .set((path.to_path_buf(), cities))
+        .map_err(|_| "A cities file has already been loaded".to_string())?;
+    Ok(count)
+}
+
 /// Check if the current locale supports UTF-8
 fn is_utf8_locale() -> bool {
     env::var("LANG")
@@ -78,17 +176,83 @@ pub fn get_cities_for_country(country: &str) -> Option<&Vec<City>> {
     CITIES_BY_COUNTRY.get(country)
 }
 
+/// Find a city by name, e.g. "Reykjavik", optionally narrowed down to a
+/// country with "San Jose, Costa Rica". Names match case-insensitively,
+/// with or without accents. Of several matching cities the most populous
+/// is taken.
+pub fn find_city(query: &str) -> Result<City, String> {
+    let (name, country) = match query.rsplit_once(',') {
+        Some((name, country)) => (name.trim().to_lowercase(), Some(country.trim().to_lowercase())),
+        None => (query.trim().to_lowercase(), None),
+    };
+
+    CITIES_BY_COUNTRY
+        .iter()
+        .filter(|(key, _)| country.as_ref().is_none_or(|country| key.to_lowercase() == *country))
+        .flat_map(|(_, cities)| cities)
+        .filter(|city| city.city.to_lowercase() == name || city.city_ascii.to_lowercase() == name)
+        .max_by_key(|city| city.population.parse::<u64>().unwrap_or(0))
+        .cloned()
+        .ok_or_else(|| format!("Unknown city: {}", query.trim()))
+}
+
+/// Filter country names by a case-insensitive query.
+/// Countries starting with the query come first, followed by countries that
+/// only contain it; both groups keep the input order. An empty (or blank)
+/// query matches every country.
+pub fn filter_countries(countries: &[String], query: &str) -> Vec<String> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return countries.to_vec();
+    }
+
+    let mut prefix_matches = Vec::new();
+    let mut substring_matches = Vec::new();
+    for country in countries {
+        let name = country.to_lowercase();
+        if name.starts_with(&query) {
+            prefix_matches.push(country.clone());
+        } else if name.contains(&query) {
+            substring_matches.push(country.clone());
+        }
+    }
+
+    prefix_matches.extend(substring_matches);
+    prefix_matches
+}
+
 /// Interactive country selection
+/// The user first types part of the country name to narrow down the list.
+/// A single match is selected directly; empty input shows all countries.
 pub fn select_country() -> Result<String, dialoguer::Error> {
-    use dialoguer::Select;
+    use dialoguer::{Input, Select};
 
     let countries = get_countries();
+
+    let matches = loop {
+        let query: String = Input::new()
+            .with_prompt("Type part of your country name (empty to list all)")
+            .allow_empty(true)
+            .interact_text()?;
+
+        let matches = filter_countries(&countries, &query);
+        if matches.is_empty() {
+            println!("No country matches \"{}\", please try again.", query.trim());
+            continue;
+        }
+        break matches;
+    };
+
+    if matches.len() == 1 {
+        return Ok(matches[0].clone());
+    }
+
     let selection = Select::new()
         .with_prompt("Select your country")
-        .items(&countries)
+        .items(&matches)
         .interact()?;
 
-    Ok(countries[selection].clone())
+    Ok(matches[selection].clone())
 }
 
 /// Interactive city selection for a given country
@@ -0,0 +1,33 @@
+/// Sources of the current time for computing the period and target setting
+/// Continual mode reads "now" through a TimeSource, so tests can pin time
+/// without touching the system clock.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the current unix time in seconds
+pub trait TimeSource {
+    fn now(&self) -> f64;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealClock;
+
+impl TimeSource for RealClock {
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64()
+    }
+}
+
+/// A clock stopped at a given unix time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedClock(pub f64);
+
+impl TimeSource for FixedClock {
+    fn now(&self) -> f64 {
+        self.0
+    }
+}
@@ -0,0 +1,175 @@
+/// Following the desktop's dark mode preference
+/// The XDG settings portal publishes the light/dark preference as
+/// org.freedesktop.appearance color-scheme. With --follow-color-scheme the
+/// adjustment is enabled while the desktop prefers dark and disabled
+/// otherwise, as if toggled by hand.
+
+use futures_util::stream::StreamExt;
+use log::{debug, info};
+use std::sync::{Arc, Mutex};
+use zbus::zvariant::{OwnedValue, Value};
+
+/// Settings namespace and key holding the preference
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+
+/// Desktop light/dark preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    NoPreference,
+    Dark,
+    Light,
+}
+
+impl ColorScheme {
+    /// Decode the portal value: 1 is dark, 2 is light, anything else no
+    /// preference
+    pub fn from_portal(value: u32) -> Self {
+        match value {
+            1 => ColorScheme::Dark,
+            2 => ColorScheme::Light,
+            _ => ColorScheme::NoPreference,
+        }
+    }
+
+    /// Whether the adjustment should be enabled. Desktops default to no
+    /// preference, which looks light, so only dark enables it.
+    pub fn wants_enabled(self) -> bool {
+        self == ColorScheme::Dark
+    }
+}
+
+/// Source of the desktop's current color scheme preference
+pub trait ColorSchemeSource {
+    fn color_scheme(&mut self) -> Result<ColorScheme, String>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait PortalSettings {
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+    fn read(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    #[zbus(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
+
+/// Color scheme from the XDG desktop portal on the session bus. The value
+/// is read once and then kept up to date from SettingChanged signals on
+/// the runtime's threads, so reading it never waits on the bus.
+pub struct PortalColorSchemeSource {
+    /// Runs the signal listener; dropped with the source
+    _runtime: tokio::runtime::Runtime,
+    latest: Arc<Mutex<Result<ColorScheme, String>>>,
+}
+
+impl PortalColorSchemeSource {
+    pub fn connect() -> Result<Self, String> {
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
+        let (proxy, changes) = runtime.block_on(async {
+            let conn = zbus::Connection::session()
+                .await
+                .map_err(|e| format!("Failed to connect to the session bus: {}", e))?;
+            let proxy = PortalSettingsProxy::new(&conn)
+                .await
+                .map_err(|e| format!("Failed to reach the settings portal: {}", e))?;
+            let changes = proxy
+                .receive_setting_changed()
+                .await
+                .map_err(|e| format!("Failed to watch the settings portal: {}", e))?;
+            Ok::<_, String>((proxy, changes))
+        })?;
+
+        /* Subscribe before the first read so no change is missed */
+        let latest = Arc::new(Mutex::new(runtime.block_on(read_color_scheme(&proxy))));
+        let shared = Arc::clone(&latest);
+        runtime.spawn(async move {
+            let mut changes = changes;
+            while let Some(signal) = changes.next().await {
+                let Ok(args) = signal.args() else { continue };
+                if args.namespace() != &APPEARANCE_NAMESPACE || args.key() != &COLOR_SCHEME_KEY {
+                    continue;
+                }
+                *shared.lock().unwrap() = decode_color_scheme(args.value());
+            }
+            debug!("Settings portal stopped sending changes");
+        });
+
+        Ok(Self {
+            _runtime: runtime,
+            latest,
+        })
+    }
+}
+
+impl ColorSchemeSource for PortalColorSchemeSource {
+    fn color_scheme(&mut self) -> Result<ColorScheme, String> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+/// Read the current preference from the portal
+async fn read_color_scheme(proxy: &PortalSettingsProxy<'_>) -> Result<ColorScheme, String> {
+    /* ReadOne is only in version 2 of the interface; Read wraps the value
+       in an extra variant */
+    let value = match proxy.read_one(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY).await {
+        Ok(value) => Ok(value),
+        Err(_) => proxy.read(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY).await,
+    };
+    let value = value.map_err(|e| format!("Failed to read {}: {}", COLOR_SCHEME_KEY, e))?;
+    decode_color_scheme(&value)
+}
+
+fn decode_color_scheme(value: &Value) -> Result<ColorScheme, String> {
+    portal_u32(value)
+        .map(ColorScheme::from_portal)
+        .ok_or_else(|| format!("Unexpected {} value: {:?}", COLOR_SCHEME_KEY, value))
+}
+
+/// Unwrap nested variants down to a u32
+fn portal_u32(value: &Value) -> Option<u32> {
+    match value {
+        Value::U32(value) => Some(*value),
+        Value::Value(inner) => portal_u32(inner),
+        _ => None,
+    }
+}
+
+/// Tracks the color scheme preference and reports when it changes
+pub struct ColorSchemeWatcher {
+    source: Box<dyn ColorSchemeSource>,
+    scheme: Option<ColorScheme>,
+}
+
+impl ColorSchemeWatcher {
+    pub fn new(source: Box<dyn ColorSchemeSource>) -> Self {
+        Self {
+            source,
+            scheme: None,
+        }
+    }
+
+    /// Query the source and return whether the adjustment should now be
+    /// enabled, on the first successful read and whenever the preference
+    /// changes. Query errors keep the current state.
+    pub fn poll(&mut self) -> Option<bool> {
+        let scheme = match self.source.color_scheme() {
+            Ok(scheme) => scheme,
+            Err(e) => {
+                debug!("Color scheme query failed: {}", e);
+                return None;
+            }
+        };
+
+        if self.scheme == Some(scheme) {
+            return None;
+        }
+        info!("Desktop color scheme: {:?}", scheme);
+        self.scheme = Some(scheme);
+        Some(scheme.wants_enabled())
+    }
+}
@@ -2,7 +2,18 @@
 /// Ported from legacy/src/colorramp.c
 /// Blackbody color table provided by Ingo Thies, 2013
 
-use crate::types::ColorSetting;
+use crate::types::{
+    ColorSetting, MAX_GAMMA, MAX_TEMP, MIN_BRIGHTNESS, MIN_GAMMA, MIN_TEMP, NEUTRAL_TEMP,
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Brightness below which the white point is nudged warmer
+const BRIGHTNESS_COMPENSATION_START: f32 = 0.8;
+
+/// White point shift (in mireds) applied at minimum brightness
+const BRIGHTNESS_COMPENSATION_MIREDS: f64 = 30.0;
 
 /// Whitepoint values for temperatures at 100K intervals (1000K to 25100K)
 /// Format: [R, G, B] for each temperature
@@ -262,8 +273,116 @@ fn interpolate_color(a: f32, c1: &[f32; 3], c2: &[f32; 3]) -> [f32; 3] {
     ]
 }
 
-/// Get the white point RGB values for a given color temperature
+/// One measured white point of a custom table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhitePointEntry {
+    pub temperature: i32,
+    pub rgb: [f32; 3],
+}
+
+/// White point table read from --whitepoint-table, used by
+/// `get_white_point` instead of the built-in blackbody table
+static CUSTOM_WHITE_POINTS: OnceLock<Vec<WhitePointEntry>> = OnceLock::new();
+
+/// Parse a white point table from CSV lines of `tempK,r,g,b`. Blank lines,
+/// `#` comments and a leading header row are skipped. Channels must lie
+/// within 0.0 to 1.0 and temperatures must strictly increase.
+pub fn parse_white_point_table(csv: &str) -> Result<Vec<WhitePointEntry>, String> {
+    let mut entries: Vec<WhitePointEntry> = Vec::new();
+    let mut seen_row = false;
+
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+        /* Allow a header such as "temp,r,g,b" before the first row */
+        let is_header = !seen_row && fields[0].parse::<f64>().is_err();
+        seen_row = true;
+        if is_header {
+            continue;
+        }
+
+        let line_error = |message: String| format!("Line {}: {}", index + 1, message);
+        if fields.len() != 4 {
+            return Err(line_error(format!("expected tempK,r,g,b, got '{}'", line)));
+        }
+
+        let temperature: i32 = fields[0]
+            .parse()
+            .ok()
+            .filter(|&temperature| temperature > 0)
+            .ok_or_else(|| line_error(format!("invalid temperature '{}'", fields[0])))?;
+        let mut rgb = [0.0; 3];
+        for (channel, field) in rgb.iter_mut().zip(&fields[1..]) {
+            *channel = field
+                .parse()
+                .ok()
+                .filter(|value: &f32| (0.0..=1.0).contains(value))
+                .ok_or_else(|| line_error(format!("invalid channel value '{}'", field)))?;
+        }
+
+        if let Some(previous) = entries.last() {
+            if temperature <= previous.temperature {
+                return Err(line_error(format!(
+                    "{}K does not follow {}K, temperatures must increase",
+                    temperature, previous.temperature
+                )));
+            }
+        }
+        entries.push(WhitePointEntry { temperature, rgb });
+    }
+
+    if entries.len() < 2 {
+        return Err("A white point table needs at least two entries".to_string());
+    }
+    Ok(entries)
+}
+
+/// White point at `temperature` interpolated linearly between the
+/// surrounding entries of `table`, holding the end values outside it
+pub fn interpolate_white_point_table(table: &[WhitePointEntry], temperature: i32) -> [f32; 3] {
+    let (first, last) = match (table.first(), table.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return [1.0, 1.0, 1.0],
+    };
+    if temperature <= first.temperature {
+        return first.rgb;
+    }
+    if temperature >= last.temperature {
+        return last.rgb;
+    }
+
+    let upper = table.partition_point(|entry| entry.temperature <= temperature);
+    let (low, high) = (&table[upper - 1], &table[upper]);
+    let alpha = (temperature - low.temperature) as f32 / (high.temperature - low.temperature) as f32;
+    interpolate_color(alpha, &low.rgb, &high.rgb)
+}
+
+/// Read a white point table from `path` to use in place of the built-in
+/// blackbody table. Must be called before any ramps are computed. Returns
+/// the number of entries read.
+pub fn load_white_point_table(path: &Path) -> Result<usize, String> {
+    let csv = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let table = parse_white_point_table(&csv).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let count = table.len();
+
+    CUSTOM_WHITE_POINTS
+        .set(table)
+        .map_err(|_| "A white point table has already been loaded".to_string())?;
+    Ok(count)
+}
+
+/// Get the white point RGB values for a given color temperature, from the
+/// table loaded with `load_white_point_table` if any
 pub fn get_white_point(temperature: i32) -> [f32; 3] {
+    if let Some(table) = CUSTOM_WHITE_POINTS.get() {
+        return interpolate_white_point_table(table, temperature);
+    }
+
     let alpha = ((temperature % 100) as f32) / 100.0;
     let temp_index = ((temperature - 1000) / 100) as usize * 3;
 
@@ -281,32 +400,465 @@ pub fn get_white_point(temperature: i32) -> [f32; 3] {
     interpolate_color(alpha, &c1, &c2)
 }
 
+/// Estimate the color temperature whose white point is closest to
+/// `white_point`, the inverse of `get_white_point`.
+/// This is approximate: the white point is scaled so its largest channel is
+/// 1.0 (so brightness does not matter) and matched against the blackbody
+/// table by nearest distance, interpolating within the closest 100K step.
+/// Colors off the blackbody curve give the temperature that looks most
+/// alike, and above roughly 10000K the curve is so flat that small errors
+/// in the input move the estimate by hundreds of Kelvin. A black or
+/// invalid white point gives the neutral temperature.
+pub fn estimate_temperature(white_point: [f32; 3]) -> i32 {
+    let max = white_point.iter().cloned().fold(0.0f32, f32::max);
+    if !max.is_finite() || max <= 0.0 || white_point.iter().any(|c| !c.is_finite()) {
+        return NEUTRAL_TEMP;
+    }
+    let point = white_point.map(|c| (c / max) as f64);
+
+    let entry = |index: usize| {
+        let i = index * 3;
+        [
+            BLACKBODY_COLOR[i] as f64,
+            BLACKBODY_COLOR[i + 1] as f64,
+            BLACKBODY_COLOR[i + 2] as f64,
+        ]
+    };
+
+    /* Project onto each 100K segment of the table, keeping the closest */
+    let segments = ((MAX_TEMP - MIN_TEMP) / 100) as usize;
+    let mut best = (f64::INFINITY, NEUTRAL_TEMP);
+    for index in 0..segments {
+        let (c1, c2) = (entry(index), entry(index + 1));
+        let dir = [c2[0] - c1[0], c2[1] - c1[1], c2[2] - c1[2]];
+        let rel = [point[0] - c1[0], point[1] - c1[1], point[2] - c1[2]];
+
+        let length_sq = dir.iter().map(|d| d * d).sum::<f64>();
+        let alpha = if length_sq > 0.0 {
+            (rel.iter().zip(&dir).map(|(r, d)| r * d).sum::<f64>() / length_sq).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let distance_sq = rel
+            .iter()
+            .zip(&dir)
+            .map(|(r, d)| (r - alpha * d).powi(2))
+            .sum::<f64>();
+        if distance_sq < best.0 {
+            let temperature = MIN_TEMP + 100 * index as i32 + (alpha * 100.0).round() as i32;
+            best = (distance_sq, temperature);
+        }
+    }
+
+    best.1
+}
+
+/// Get the RGB white point for explicit CIE 1931 xy chromaticity
+/// coordinates (e.g. D50 at 0.3457, 0.3585), as an alternative to the
+/// blackbody white point from `get_white_point`.
+/// The chromaticity is converted to linear sRGB and scaled so the largest
+/// channel is 1.0, matching the normalization of the blackbody table.
+pub fn get_white_point_xy(x: f64, y: f64) -> [f32; 3] {
+    /* CIE XYZ with unit luminance */
+    let big_x = x / y;
+    let big_z = (1.0 - x - y) / y;
+
+    /* XYZ to linear sRGB (D65 reference white) */
+    let r = 3.2404542 * big_x - 1.5371385 - 0.4985314 * big_z;
+    let g = -0.9692660 * big_x + 1.8760108 + 0.0415560 * big_z;
+    let b = 0.0556434 * big_x - 0.2040259 + 1.0572252 * big_z;
+
+    let rgb = [r.max(0.0), g.max(0.0), b.max(0.0)];
+    let max = rgb[0].max(rgb[1]).max(rgb[2]);
+
+    [
+        (rgb[0] / max) as f32,
+        (rgb[1] / max) as f32,
+        (rgb[2] / max) as f32,
+    ]
+}
+
+/// Remove blue from `white_point` entirely when `temperature` is below
+/// `cutoff`, as a hard override of the blackbody curve for deep-night use
+pub fn apply_blue_cutoff(white_point: [f32; 3], temperature: i32, cutoff: Option<i32>) -> [f32; 3] {
+    match cutoff {
+        Some(cutoff) if temperature < cutoff => [white_point[0], white_point[1], 0.0],
+        _ => white_point,
+    }
+}
+
+/// Color vision deficiency the white point can be corrected for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvdProfile {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+/* Correction matrices, one row per output channel (R, G, B). Each is
+   I + E(I - S): S simulates the deficiency at full severity (Machado,
+   Oliveira and Fernandes, 2009) and E moves the lost difference into
+   channels that are still seen (Fidaner, Lin and Ozguven, 2005), red-green
+   differences into green and blue and blue differences into red and
+   green. Rows sum to 1, so neutral white is unchanged. */
+const PROTANOPIA_MATRIX: [[f64; 3]; 3] = [
+    [1.0, 0.0, 0.0],
+    [0.478897, 0.476911, 0.044192],
+    [0.597282, -0.688692, 1.09141],
+];
+const DEUTERANOPIA_MATRIX: [[f64; 3]; 3] = [
+    [1.0, 0.0, 0.0],
+    [0.16279, 0.725047, 0.112165],
+    [0.454695, -0.645392, 1.190697],
+];
+const TRITANOPIA_MATRIX: [[f64; 3]; 3] = [
+    [0.741159, -0.407208, 0.666049],
+    [0.075098, 0.585234, 0.339668],
+    [0.0, 0.0, 1.0],
+];
+
+impl CvdProfile {
+    /// Parse "protanopia", "deuteranopia" or "tritanopia"
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "protanopia" => Ok(CvdProfile::Protanopia),
+            "deuteranopia" => Ok(CvdProfile::Deuteranopia),
+            "tritanopia" => Ok(CvdProfile::Tritanopia),
+            _ => Err(format!(
+                "Invalid CVD profile: {} (expected protanopia, deuteranopia or tritanopia)",
+                s.trim()
+            )),
+        }
+    }
+
+    /// Matrix mixing the channels of a white point, rows giving R, G and B
+    pub fn matrix(self) -> [[f64; 3]; 3] {
+        match self {
+            CvdProfile::Protanopia => PROTANOPIA_MATRIX,
+            CvdProfile::Deuteranopia => DEUTERANOPIA_MATRIX,
+            CvdProfile::Tritanopia => TRITANOPIA_MATRIX,
+        }
+    }
+}
+
+/// Mix the channels of `white_point` through the correction matrix of
+/// `profile`, if any, and scale the result so its largest channel is 1.0.
+/// Warm white points keep more of the channels the deficiency makes hard
+/// to tell apart, so warm shifts cost less contrast.
+pub fn apply_cvd_profile(white_point: [f32; 3], profile: Option<CvdProfile>) -> [f32; 3] {
+    let Some(profile) = profile else {
+        return white_point;
+    };
+
+    let mixed = profile.matrix().map(|row| {
+        row.iter()
+            .zip(white_point)
+            .map(|(weight, value)| weight * value as f64)
+            .sum::<f64>()
+            .max(0.0)
+    });
+    let max = mixed[0].max(mixed[1]).max(mixed[2]);
+    if max <= 0.0 {
+        return white_point;
+    }
+    mixed.map(|value| (value / max) as f32)
+}
+
+/// Get the temperature whose white point looks as warm at the given
+/// brightness as `temperature` does at full brightness.
+/// Dimmed colors appear less saturated (Hunt effect), so the white point is
+/// shifted warmer by up to 30 mireds, scaling linearly from no shift at
+/// brightness 0.8 to the full shift at the minimum brightness.
+pub fn compensated_temperature(temperature: i32, brightness: f32) -> i32 {
+    if brightness >= BRIGHTNESS_COMPENSATION_START {
+        return temperature;
+    }
+
+    let strength = ((BRIGHTNESS_COMPENSATION_START - brightness)
+        / (BRIGHTNESS_COMPENSATION_START - MIN_BRIGHTNESS))
+        .clamp(0.0, 1.0) as f64;
+
+    let mireds = 1_000_000.0 / temperature as f64 + strength * BRIGHTNESS_COMPENSATION_MIREDS;
+    ((1_000_000.0 / mireds).round() as i32).clamp(MIN_TEMP, MAX_TEMP)
+}
+
+/// Map a brightness setting, read as CIE L* lightness (0.0 to 1.0 for
+/// L* 0 to 100), to the relative luminance the ramp is scaled by.
+/// Lightness follows perception, so equal brightness steps look equally
+/// large and a fade from 1.0 to 0.5 no longer seems to happen mostly at
+/// the start. Brightness 1.0 is left at 1.0, and overdrive above it is
+/// passed through linearly, since L* has no meaning beyond white.
+pub fn perceptual_brightness(brightness: f32) -> f32 {
+    if brightness >= 1.0 {
+        return brightness;
+    }
+    let lightness = brightness as f64 * 100.0;
+    let luminance = if lightness > 8.0 {
+        ((lightness + 16.0) / 116.0).powi(3)
+    } else {
+        lightness / 903.3
+    };
+    luminance as f32
+}
+
+/// How ramp values are converted to the 16-bit integers the hardware takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quantization {
+    /// Drop the fraction, as legacy redshift did. Biases the ramp slightly
+    /// darker.
+    Truncate,
+    /// Round to the nearest value
+    #[default]
+    Round,
+    /// Ordered dither, spreading the rounding error along the ramp
+    Dither,
+}
+
+/* 16-step ordered dither thresholds (a 4x4 Bayer matrix in row order) */
+const DITHER_THRESHOLDS: [f64; 16] = [
+    0.0, 8.0, 2.0, 10.0, 12.0, 4.0, 14.0, 6.0, 3.0, 11.0, 1.0, 9.0, 15.0, 7.0, 13.0, 5.0,
+];
+
+impl Quantization {
+    /// Parse "truncate", "round" or "dither"
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "truncate" => Ok(Quantization::Truncate),
+            "round" => Ok(Quantization::Round),
+            "dither" => Ok(Quantization::Dither),
+            _ => Err(format!(
+                "Invalid quantization: {} (expected truncate, round or dither)",
+                s.trim()
+            )),
+        }
+    }
+
+    /// Convert `value` (0.0 to 65536.0) at ramp position `index` to u16
+    pub fn quantize(self, value: f64, index: usize) -> u16 {
+        let value = match self {
+            Quantization::Truncate => value.floor(),
+            Quantization::Round => value.round(),
+            Quantization::Dither => {
+                (value + (DITHER_THRESHOLDS[index % 16] + 0.5) / 16.0).floor()
+            }
+        };
+        value.clamp(0.0, u16::MAX as f64) as u16
+    }
+}
+
+/// Scale a ramp value (0.0 to 1.0) around the midpoint by `contrast`.
+/// Contrast 1.0 returns the value untouched, without rounding error.
+pub fn apply_contrast(value: f64, contrast: f32) -> f64 {
+    if contrast == 1.0 {
+        return value;
+    }
+    ((value - 0.5) * contrast as f64 + 0.5).clamp(0.0, 1.0)
+}
+
+/// Mid-gray luminance below which a setting is considered illegible
+pub const MIN_LEGIBLE_LUMINANCE: f64 = 0.01;
+
+/// Exponent applied to a ramp value for `gamma`. Settings are validated
+/// long before this, but a gamma of 0 would divide by zero, so the value is
+/// clamped to MIN_GAMMA-MAX_GAMMA and anything non-finite means no change.
+fn gamma_exponent(gamma: f32) -> f64 {
+    if gamma.is_finite() {
+        1.0 / gamma.clamp(MIN_GAMMA, MAX_GAMMA) as f64
+    } else {
+        1.0
+    }
+}
+
+/// Estimate the relative luminance (0.0 to 1.0) of a mid-gray pixel once
+/// `setting` is applied, using the same ramp formula and Rec. 709 weights
+pub fn mid_gray_luminance(setting: &ColorSetting) -> f64 {
+    const WEIGHTS: [f64; 3] = [0.2126, 0.7152, 0.0722];
+
+    let white_point = get_white_point(setting.temperature);
+    let y = apply_contrast(0.5, setting.contrast);
+
+    (0..3)
+        .map(|c| {
+            let value = (y * setting.brightness as f64 * white_point[c] as f64)
+                .powf(gamma_exponent(setting.gamma[c]));
+            WEIGHTS[c] * value
+        })
+        .sum()
+}
+
+/// Check that text stays readable with `setting`, so a combination of low
+/// brightness and low gamma cannot leave the user unable to see the screen
+pub fn check_legibility(setting: &ColorSetting) -> Result<(), String> {
+    let luminance = mid_gray_luminance(setting);
+    if luminance < MIN_LEGIBLE_LUMINANCE {
+        return Err(format!(
+            "Mid-gray would be shown at {:.2}% luminance (at least {:.0}% is needed to stay legible)",
+            luminance * 100.0,
+            MIN_LEGIBLE_LUMINANCE * 100.0
+        ));
+    }
+    Ok(())
+}
+
+/// Largest ramp size on which warm shifts come out noticeably coarse
+pub const MAX_COARSE_RAMP_SIZE: usize = 3;
+
+/// Linear (identity) ramp of `size` entries to start filling from.
+/// Entry i maps to i/size as in legacy redshift, which never quite reaches
+/// full scale; on tiny ramps that would cost a third or half of the range,
+/// so those are spread from 0 to 65535 instead.
+pub fn linear_ramp(size: usize) -> Vec<u16> {
+    if size <= MAX_COARSE_RAMP_SIZE {
+        let last = size.saturating_sub(1).max(1) as f64;
+        return (0..size)
+            .map(|i| ((i as f64 / last) * u16::MAX as f64).round() as u16)
+            .collect();
+    }
+
+    (0..size)
+        .map(|i| ((i as f64 / size as f64) * 65536.0) as u16)
+        .collect()
+}
+
 /// Fill gamma ramps with color adjustment for u16 values
-/// This applies brightness, white point, and gamma correction
+/// This applies contrast, brightness, white point, and gamma correction.
+/// Kept for library users; the binary goes through
+/// `colorramp_fill_with_options`.
+#[allow(dead_code)]
 pub fn colorramp_fill(
     gamma_r: &mut [u16],
     gamma_g: &mut [u16],
     gamma_b: &mut [u16],
     setting: &ColorSetting,
 ) {
-    let white_point = get_white_point(setting.temperature);
+    colorramp_fill_with_compensation(gamma_r, gamma_g, gamma_b, setting, false);
+}
+
+/// Fill gamma ramps with color adjustment for u16 values, optionally
+/// compensating the white point for reduced brightness
+/// (see `compensated_temperature`)
+#[allow(dead_code)]
+pub fn colorramp_fill_with_compensation(
+    gamma_r: &mut [u16],
+    gamma_g: &mut [u16],
+    gamma_b: &mut [u16],
+    setting: &ColorSetting,
+    brightness_compensation: bool,
+) {
+    let options = RampOptions { brightness_compensation, ..RampOptions::default() };
+    colorramp_fill_with_options(gamma_r, gamma_g, gamma_b, setting, &options);
+}
+
+/// White point for the setting's temperature, optionally compensated for
+/// reduced brightness (see `compensated_temperature`)
+pub fn setting_white_point(setting: &ColorSetting, brightness_compensation: bool) -> [f32; 3] {
+    let temperature = if brightness_compensation {
+        compensated_temperature(setting.temperature, setting.brightness)
+    } else {
+        setting.temperature
+    };
+    get_white_point(temperature)
+}
+
+/// Fill gamma ramps using an explicit RGB white point instead of the one
+/// derived from the setting's temperature (see `get_white_point_xy`)
+#[allow(dead_code)]
+pub fn colorramp_fill_with_white_point(
+    gamma_r: &mut [u16],
+    gamma_g: &mut [u16],
+    gamma_b: &mut [u16],
+    setting: &ColorSetting,
+    white_point: &[f32; 3],
+) {
+    let options = RampOptions { white_point: Some(*white_point), ..RampOptions::default() };
+    colorramp_fill_with_options(gamma_r, gamma_g, gamma_b, setting, &options);
+}
+
+/// Fill gamma ramps using an explicit white point and quantization.
+/// Brightness above 1.0 (overdrive) scales values past linear; the top of
+/// the ramp clamps to 65535, so highlights lose detail.
+pub fn colorramp_fill_quantized(
+    gamma_r: &mut [u16],
+    gamma_g: &mut [u16],
+    gamma_b: &mut [u16],
+    setting: &ColorSetting,
+    white_point: &[f32; 3],
+    quantization: Quantization,
+) {
     let size = gamma_r.len();
 
     for i in 0..size {
-        let y_r = (gamma_r[i] as f64) / 65536.0;
-        let y_g = (gamma_g[i] as f64) / 65536.0;
-        let y_b = (gamma_b[i] as f64) / 65536.0;
-
-        gamma_r[i] = ((y_r * (setting.brightness as f64) * (white_point[0] as f64))
-            .powf(1.0 / (setting.gamma[0] as f64))
-            * 65536.0) as u16;
-        gamma_g[i] = ((y_g * (setting.brightness as f64) * (white_point[1] as f64))
-            .powf(1.0 / (setting.gamma[1] as f64))
-            * 65536.0) as u16;
-        gamma_b[i] = ((y_b * (setting.brightness as f64) * (white_point[2] as f64))
-            .powf(1.0 / (setting.gamma[2] as f64))
-            * 65536.0) as u16;
+        let y_r = apply_contrast((gamma_r[i] as f64) / 65536.0, setting.contrast);
+        let y_g = apply_contrast((gamma_g[i] as f64) / 65536.0, setting.contrast);
+        let y_b = apply_contrast((gamma_b[i] as f64) / 65536.0, setting.contrast);
+
+        gamma_r[i] = quantization.quantize(
+            (y_r * (setting.brightness as f64) * (white_point[0] as f64))
+                .powf(gamma_exponent(setting.gamma[0]))
+                * 65536.0,
+            i,
+        );
+        gamma_g[i] = quantization.quantize(
+            (y_g * (setting.brightness as f64) * (white_point[1] as f64))
+                .powf(gamma_exponent(setting.gamma[1]))
+                * 65536.0,
+            i,
+        );
+        gamma_b[i] = quantization.quantize(
+            (y_b * (setting.brightness as f64) * (white_point[2] as f64))
+                .powf(gamma_exponent(setting.gamma[2]))
+                * 65536.0,
+            i,
+        );
+    }
+}
+
+/// How ramps are built from a setting beyond the setting itself, shared by
+/// the RandR method and the vcgt export so both produce the same curves
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RampOptions {
+    /// Compensate the white point for reduced brightness
+    pub brightness_compensation: bool,
+    /// Read brightness as perceived lightness (see `perceptual_brightness`)
+    pub perceptual_brightness: bool,
+    /// Fixed white point instead of the one derived from the temperature
+    pub white_point: Option<[f32; 3]>,
+    /// How ramp values are converted to 16-bit integers
+    pub quantization: Quantization,
+    /// Zero the blue channel while the temperature is below this
+    pub blue_cutoff: Option<i32>,
+    /// Color vision deficiency to correct the white point for
+    pub cvd_profile: Option<CvdProfile>,
+}
+
+/// Fill gamma ramps for `setting` under `options`. The ramps are adjusted
+/// from their current contents, linear or saved ramps to preserve.
+pub fn colorramp_fill_with_options(
+    gamma_r: &mut [u16],
+    gamma_g: &mut [u16],
+    gamma_b: &mut [u16],
+    setting: &ColorSetting,
+    options: &RampOptions,
+) {
+    let white_point = options
+        .white_point
+        .unwrap_or_else(|| setting_white_point(setting, options.brightness_compensation));
+    let white_point = apply_cvd_profile(white_point, options.cvd_profile);
+    let white_point = apply_blue_cutoff(white_point, setting.temperature, options.blue_cutoff);
+    let mut fill_setting = *setting;
+    if options.perceptual_brightness {
+        fill_setting.brightness = perceptual_brightness(setting.brightness);
     }
+    colorramp_fill_quantized(
+        gamma_r,
+        gamma_g,
+        gamma_b,
+        &fill_setting,
+        &white_point,
+        options.quantization,
+    );
 }
 
 /// Fill gamma ramps with color adjustment for f32 values
@@ -320,11 +872,20 @@ pub fn colorramp_fill_float(
     let size = gamma_r.len();
 
     for i in 0..size {
-        gamma_r[i] = ((gamma_r[i] as f64) * (setting.brightness as f64) * (white_point[0] as f64))
-            .powf(1.0 / (setting.gamma[0] as f64)) as f32;
-        gamma_g[i] = ((gamma_g[i] as f64) * (setting.brightness as f64) * (white_point[1] as f64))
-            .powf(1.0 / (setting.gamma[1] as f64)) as f32;
-        gamma_b[i] = ((gamma_b[i] as f64) * (setting.brightness as f64) * (white_point[2] as f64))
-            .powf(1.0 / (setting.gamma[2] as f64)) as f32;
+        let y_r = apply_contrast(gamma_r[i] as f64, setting.contrast);
+        let y_g = apply_contrast(gamma_g[i] as f64, setting.contrast);
+        let y_b = apply_contrast(gamma_b[i] as f64, setting.contrast);
+
+        /* Clamp to 1.0 so overdriven brightness clips instead of
+           overflowing the ramp */
+        gamma_r[i] = (y_r * (setting.brightness as f64) * (white_point[0] as f64))
+            .powf(gamma_exponent(setting.gamma[0]))
+            .min(1.0) as f32;
+        gamma_g[i] = (y_g * (setting.brightness as f64) * (white_point[1] as f64))
+            .powf(gamma_exponent(setting.gamma[1]))
+            .min(1.0) as f32;
+        gamma_b[i] = (y_b * (setting.brightness as f64) * (white_point[2] as f64))
+            .powf(gamma_exponent(setting.gamma[2]))
+            .min(1.0) as f32;
     }
 }
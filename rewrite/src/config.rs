@@ -1,22 +1,34 @@
 /// Configuration file support for Redshift
 /// Stores user preferences and location data
 
+use crate::error::{Error, Result};
 use crate::types::Location;
+use log::warn;
 use serde::{Deserialize, Serialize};
+use std::env;
+use std::ffi::OsStr;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub location: Option<SavedLocation>,
     pub last_geoclue_check: Option<u64>, // Unix timestamp
+    /// Preset selected with SIGUSR2, kept when cycle-persist is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycle_position: Option<usize>,
+    /// Gamma method that last started when none was selected, tried first
+    /// on the next run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gamma_method: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SavedLocation {
-    pub lat: f32,
-    pub lon: f32,
+    pub lat: f64,
+    pub lon: f64,
     pub source: LocationSource,
     pub city_name: Option<String>,
 }
@@ -34,21 +46,79 @@ impl Default for Config {
         Self {
             location: None,
             last_geoclue_check: None,
+            cycle_position: None,
+            gamma_method: None,
         }
     }
 }
 
+/// Directory for configuration: the platform config directory, else
+/// `$XDG_CONFIG_HOME`, else a per-user directory under the temp directory.
+/// None if none of them is available.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().or_else(|| {
+        fallback_config_dir(env::var_os("XDG_CONFIG_HOME").as_deref(), &env::temp_dir())
+    })
+}
+
+/// Config directory to use when the platform one cannot be determined,
+/// e.g. without HOME in a minimal container
+pub fn fallback_config_dir(xdg_config_home: Option<&OsStr>, temp_dir: &Path) -> Option<PathBuf> {
+    if let Some(dir) = xdg_config_home.filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    let uid = unsafe { libc::getuid() };
+    private_temp_dir(&temp_dir.join(format!("redshift-{}", uid)), uid)
+}
+
+/// Create `dir` readable only by `uid`, or check that an existing one is.
+/// The name under the shared temp directory is predictable, so a directory
+/// or symlink another user put there first must not be used.
+fn private_temp_dir(dir: &Path, uid: u32) -> Option<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+    if let Err(e) = fs::DirBuilder::new().mode(0o700).create(dir) {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+            return None;
+        }
+    }
+
+    let metadata = fs::symlink_metadata(dir).ok()?;
+    if !metadata.file_type().is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        warn!(
+            "Not using {}: it is not a private directory owned by this user",
+            dir.display()
+        );
+        return None;
+    }
+    Some(dir.to_path_buf())
+}
+
+/// Like `config_dir`, logging once when there is none, since nothing is
+/// saved or loaded then
+fn persist_dir() -> Option<PathBuf> {
+    static NO_PERSIST: Once = Once::new();
+
+    let dir = config_dir();
+    if dir.is_none() {
+        NO_PERSIST.call_once(|| {
+            warn!("No config directory available, settings will not be saved or loaded")
+        });
+    }
+    dir
+}
+
 impl Config {
     /// Get the config file path
-    pub fn config_path() -> Result<PathBuf, String> {
-        let config_dir = dirs::config_dir()
-            .ok_or("Could not determine config directory")?;
+    pub fn config_path() -> Result<PathBuf> {
+        let config_dir = persist_dir()
+            .ok_or_else(|| Error::Config("Could not determine config directory".to_string()))?;
         let redshift_dir = config_dir.join("redshift");
         Ok(redshift_dir.join("config.toml"))
     }
 
     /// Load config from file
-    pub fn load() -> Result<Self, String> {
+    pub fn load() -> Result<Self> {
         let path = Self::config_path()?;
 
         if !path.exists() {
@@ -56,31 +126,52 @@ impl Config {
         }
 
         let contents = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read config file: {}", e))?;
+            .map_err(|e| Error::Config(format!("Failed to read config file: {}", e)))?;
 
         toml::from_str(&contents)
-            .map_err(|e| format!("Failed to parse config file: {}", e))
+            .map_err(|e| Error::Config(format!("Failed to parse config file: {}", e)))
     }
 
     /// Save config to file
-    pub fn save(&self) -> Result<(), String> {
+    pub fn save(&self) -> Result<()> {
         let path = Self::config_path()?;
 
         // Create parent directory if it doesn't exist
         if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+            fs::create_dir_all(parent).map_err(|e| {
+                Error::Config(format!(
+                    "Failed to create config directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
         }
 
         let contents = toml::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
+            .map_err(|e| Error::Config(format!("Failed to serialize config: {}", e)))?;
 
-        fs::write(&path, contents)
-            .map_err(|e| format!("Failed to write config file: {}", e))?;
+        fs::write(&path, contents).map_err(|e| {
+            Error::Config(format!("Failed to write config file {}: {}", path.display(), e))
+        })?;
 
         Ok(())
     }
 
+    /// Save config to file, logging a warning instead of failing.
+    /// Returns whether the config was saved.
+    pub fn save_or_warn(&self) -> bool {
+        if persist_dir().is_none() {
+            return false;
+        }
+        match self.save() {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Configuration not saved: {}", e);
+                false
+            }
+        }
+    }
+
     /// Check if we should try GeoClue2 again (once per day)
     pub fn should_check_geoclue(&self) -> bool {
         if let Some(last_check) = self.last_geoclue_check {
@@ -107,6 +198,11 @@ impl Config {
         );
     }
 
+    /// Forget the last GeoClue2 check so the next run retries immediately
+    pub fn reset_geoclue_check(&mut self) {
+        self.last_geoclue_check = None;
+    }
+
     /// Set location from a Location struct
     pub fn set_location(&mut self, location: Location, source: LocationSource, city_name: Option<String>) {
         self.location = Some(SavedLocation {
@@ -167,6 +263,17 @@ mod tests {
         assert!(config.should_check_geoclue());
     }
 
+    #[test]
+    fn test_config_reset_geoclue_check() {
+        let mut config = Config::default();
+        config.update_geoclue_check();
+        assert!(!config.should_check_geoclue());
+
+        config.reset_geoclue_check();
+        assert!(config.last_geoclue_check.is_none());
+        assert!(config.should_check_geoclue());
+    }
+
     #[test]
     fn test_config_location() {
         let mut config = Config::default();
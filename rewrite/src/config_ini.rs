@@ -1,11 +1,24 @@
 /// INI Configuration file support for Redshift
 /// Parses redshift.conf files in INI format (matching the C version)
 
+use crate::colorramp::Quantization;
+use crate::error::Error;
+use crate::fade::{FadeCurve, FadeThresholds};
+use crate::timeofday::ActiveDays;
 use crate::types::*;
 use ini::Ini;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use std::path::PathBuf;
 
+/// Environment variables read as a layer between the built-in defaults
+/// and the config file
+pub const ENV_METHOD: &str = "REDSHIFT_METHOD";
+pub const ENV_TEMP_DAY: &str = "REDSHIFT_TEMP_DAY";
+pub const ENV_TEMP_NIGHT: &str = "REDSHIFT_TEMP_NIGHT";
+
+/// INI sections understood by the parser
+const KNOWN_SECTIONS: &[&str] = &["redshift", "manual", "randr", "geoclue2", "gpsd", "schedule"];
+
 /// Configuration loaded from INI file
 #[derive(Debug, Clone, Default)]
 pub struct RedshiftConfig {
@@ -13,20 +26,54 @@ pub struct RedshiftConfig {
     pub temp_day: Option<i32>,
     pub temp_night: Option<i32>,
     pub fade: Option<bool>,
+    pub fade_threshold_temp: Option<i32>,
+    pub fade_threshold_brightness: Option<f32>,
+    pub fade_threshold_gamma: Option<f32>,
+    pub fade_curve: Option<FadeCurve>,
+    pub interpolate_in_mireds: Option<bool>,
+    pub cycle_temps: Option<Vec<i32>>,
+    pub cycle_persist: Option<bool>,
     pub brightness_day: Option<f32>,
     pub brightness_night: Option<f32>,
+    pub brightness_compensation: Option<bool>,
+    pub perceptual_brightness: Option<bool>,
+    pub white_point: Option<[f64; 2]>,
+    pub quantization: Option<Quantization>,
+    pub blue_cutoff_temp: Option<i32>,
+    pub contrast: Option<f32>,
     pub gamma_day: Option<[f32; 3]>,
     pub gamma_night: Option<[f32; 3]>,
     pub elevation_high: Option<f64>,
     pub elevation_low: Option<f64>,
+    pub elevation_hysteresis: Option<f64>,
+    pub intensity: Option<u8>,
+    pub temp_civil: Option<i32>,
+    pub temp_nautical: Option<i32>,
+    pub brightness_civil: Option<f32>,
+    pub brightness_nautical: Option<f32>,
+    pub temp_deep_night: Option<i32>,
+    pub temp_min: Option<i32>,
+    pub temp_max: Option<i32>,
+    pub elevation_deep_night: Option<f64>,
     pub dawn_time: Option<TimeRange>,
     pub dusk_time: Option<TimeRange>,
+    pub active_days: Option<ActiveDays>,
     pub location_provider: Option<String>,
     pub adjustment_method: Option<String>,
+    pub geoclue_desktop_id: Option<String>,
+    pub geoclue_samples: Option<usize>,
+    pub geoclue_settle_time: Option<u64>,
+
+    /* Temperature keyframes from [schedule], sorted by time */
+    pub schedule: Vec<(i32, i32)>,
 
     /* Manual location section */
-    pub manual_lat: Option<f32>,
-    pub manual_lon: Option<f32>,
+    pub manual_lat: Option<f64>,
+    pub manual_lon: Option<f64>,
+
+    /* gpsd location section */
+    pub gpsd_host: Option<String>,
+    pub gpsd_port: Option<u16>,
 
     /* Gamma method settings */
     pub randr_screen: Option<i32>,
@@ -34,15 +81,44 @@ pub struct RedshiftConfig {
 }
 
 impl RedshiftConfig {
-    /// Find and load the INI config file from standard locations
-    pub fn load() -> Result<Self, String> {
+    /// Find and load the INI config file from standard locations, with
+    /// the REDSHIFT_* environment variables filling in what it leaves unset
+    pub fn load() -> crate::error::Result<Self> {
         debug!("Searching for INI configuration file");
-        if let Some(path) = Self::find_config_file() {
+        let config = if let Some(path) = Self::find_config_file() {
             info!("Found INI config file: {}", path.display());
-            Self::load_from_file(&path)
+            Self::load_from_file(&path)?
         } else {
             debug!("No INI configuration file found, using defaults");
-            Ok(Self::default())
+            Self::default()
+        };
+        Ok(config.with_env())
+    }
+
+    /// Fill settings left unset from the REDSHIFT_* environment variables
+    pub fn with_env(mut self) -> Self {
+        self.apply_env_from(|name| std::env::var(name).ok());
+        self
+    }
+
+    /// Fill settings left unset from the variables `lookup` returns.
+    /// Invalid values are logged and ignored.
+    pub fn apply_env_from(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        if self.adjustment_method.is_none() {
+            self.adjustment_method = lookup(ENV_METHOD).filter(|method| !method.is_empty());
+        }
+
+        let temperature = |name: &str| {
+            let value = lookup(name)?;
+            resolve_temp(&value)
+                .map_err(|e| warn!("Ignoring {}: {}", name, e))
+                .ok()
+        };
+        if self.temp_day.is_none() {
+            self.temp_day = temperature(ENV_TEMP_DAY);
+        }
+        if self.temp_night.is_none() {
+            self.temp_night = temperature(ENV_TEMP_NIGHT);
         }
     }
 
@@ -88,24 +164,64 @@ impl RedshiftConfig {
         paths
     }
 
+    /// Path where a new user config file is written
+    /// (XDG_CONFIG_HOME/redshift/redshift.conf)
+    pub fn default_config_path() -> crate::error::Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| Error::Config("Could not determine config directory".to_string()))?;
+        Ok(config_dir.join("redshift").join("redshift.conf"))
+    }
+
     /// Load config from a specific file
-    pub fn load_from_file(path: &PathBuf) -> Result<Self, String> {
+    pub fn load_from_file(path: &PathBuf) -> crate::error::Result<Self> {
         debug!("Loading INI config from: {}", path.display());
-        let ini = Ini::load_from_file(path)
-            .map_err(|e| format!("Failed to load INI file: {}", e))?;
+        let contents = strip_inline_comments(&normalize_ini_text(
+            &std::fs::read_to_string(path)
+                .map_err(|e| Error::Config(format!("Failed to load INI file: {}", e)))?,
+        ));
+        let ini = Ini::load_from_str(&contents)
+            .map_err(|e| Error::Config(format!("Failed to load INI file: {}", e)))?;
+
+        /* The parsed Ini keeps only one of a repeated key, so say which.
+           Every [schedule] entry is read, and its times split at ':' so
+           entries of the same hour share a key. */
+        for duplicate in find_duplicate_keys(&contents)
+            .into_iter()
+            .filter(|duplicate| duplicate.section.as_deref() != Some("schedule"))
+        {
+            let used = ini
+                .section(duplicate.section.as_deref())
+                .and_then(|section| section.get(&duplicate.key))
+                .unwrap_or("");
+            warn!(
+                "Duplicate key {} in {} on lines {}, using {}",
+                duplicate.key,
+                duplicate
+                    .section
+                    .as_ref()
+                    .map_or("the top of the file".to_string(), |name| format!("[{}]", name)),
+                duplicate
+                    .lines
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                used
+            );
+        }
 
         let mut config = Self::default();
 
         /* Parse [redshift] section */
         if let Some(section) = ini.section(Some("redshift")) {
             if let Some(val) = section.get("temp-day") {
-                config.temp_day = val.parse().ok();
+                config.temp_day = resolve_temp(val).ok();
                 if let Some(temp) = config.temp_day {
                     debug!("Loaded temp-day from INI: {}K", temp);
                 }
             }
             if let Some(val) = section.get("temp-night") {
-                config.temp_night = val.parse().ok();
+                config.temp_night = resolve_temp(val).ok();
                 if let Some(temp) = config.temp_night {
                     debug!("Loaded temp-night from INI: {}K", temp);
                 }
@@ -125,9 +241,42 @@ impl RedshiftConfig {
                 };
             }
 
+            /* Presets cycled through with SIGUSR2 */
+            if let Some(val) = section.get("cycle-temps") {
+                config.cycle_temps = parse_temperature_list(val).ok();
+            }
+            if let Some(val) = section.get("cycle-persist") {
+                config.cycle_persist = match val {
+                    "0" => Some(false),
+                    "1" => Some(true),
+                    _ => val.parse().ok(),
+                };
+            }
+
+            /* Minimum differences that are faded rather than applied instantly */
+            if let Some(val) = section.get("fade-threshold-temp") {
+                config.fade_threshold_temp = val.parse().ok();
+            }
+            if let Some(val) = section.get("fade-threshold-brightness") {
+                config.fade_threshold_brightness = val.parse().ok();
+            }
+            if let Some(val) = section.get("fade-threshold-gamma") {
+                config.fade_threshold_gamma = val.parse().ok();
+            }
+            if let Some(val) = section.get("fade-curve") {
+                config.fade_curve = FadeCurve::parse(val).ok();
+            }
+            if let Some(val) = section.get("interpolate-in-mireds") {
+                config.interpolate_in_mireds = match val {
+                    "0" => Some(false),
+                    "1" => Some(true),
+                    _ => val.parse().ok(),
+                };
+            }
+
             /* Brightness settings */
             if let Some(val) = section.get("brightness") {
-                if let Ok((day, night)) = parse_brightness_string(val) {
+                if let Ok((day, night)) = parse_brightness_values(val) {
                     config.brightness_day = Some(day);
                     config.brightness_night = Some(night);
                 }
@@ -138,22 +287,63 @@ impl RedshiftConfig {
             if let Some(val) = section.get("brightness-night") {
                 config.brightness_night = val.parse().ok();
             }
+            if let Some(val) = section.get("brightness-compensation") {
+                config.brightness_compensation = match val {
+                    "0" => Some(false),
+                    "1" => Some(true),
+                    _ => val.parse().ok(),
+                };
+            }
+            if let Some(val) = section.get("perceptual-brightness") {
+                config.perceptual_brightness = match val {
+                    "0" => Some(false),
+                    "1" => Some(true),
+                    _ => val.parse().ok(),
+                };
+            }
+
+            if let Some(val) = section.get("blue-cutoff-temp") {
+                config.blue_cutoff_temp = resolve_temp(val).ok();
+            }
+
+            if let Some(val) = section.get("quantization") {
+                config.quantization = Quantization::parse(val).ok();
+            }
+
+            if let Some(val) = section.get("white-point") {
+                config.white_point = parse_white_point_string(val).ok();
+                if let Some([x, y]) = config.white_point {
+                    debug!("Loaded white point from INI: x={}, y={}", x, y);
+                }
+            }
+
+            /* Contrast */
+            if let Some(val) = section.get("contrast") {
+                config.contrast = val.parse().ok();
+            }
 
             /* Gamma settings */
+            /* Range checks happen when the scheme is built, so that
+               --clamp-invalid can clamp out-of-range values */
             if let Some(val) = section.get("gamma") {
-                if let Ok(gamma) = parse_gamma_string(val) {
-                    config.gamma_day = Some(gamma);
-                    config.gamma_night = Some(gamma);
+                match parse_gamma_values(val) {
+                    Ok(gamma) => {
+                        config.gamma_day = Some(gamma);
+                        config.gamma_night = Some(gamma);
+                    }
+                    Err(e) => warn!("Ignoring gamma: {}", e),
                 }
             }
             if let Some(val) = section.get("gamma-day") {
-                if let Ok(gamma) = parse_gamma_string(val) {
-                    config.gamma_day = Some(gamma);
+                match parse_gamma_values(val) {
+                    Ok(gamma) => config.gamma_day = Some(gamma),
+                    Err(e) => warn!("Ignoring gamma-day: {}", e),
                 }
             }
             if let Some(val) = section.get("gamma-night") {
-                if let Ok(gamma) = parse_gamma_string(val) {
-                    config.gamma_night = Some(gamma);
+                match parse_gamma_values(val) {
+                    Ok(gamma) => config.gamma_night = Some(gamma),
+                    Err(e) => warn!("Ignoring gamma-night: {}", e),
                 }
             }
 
@@ -164,6 +354,45 @@ impl RedshiftConfig {
             if let Some(val) = section.get("elevation-low") {
                 config.elevation_low = val.parse().ok();
             }
+            if let Some(val) = section.get("elevation-hysteresis") {
+                config.elevation_hysteresis =
+                    val.parse().ok().filter(|margin: &f64| *margin >= 0.0);
+            }
+
+            /* Strength of the night setting in percent */
+            if let Some(val) = section.get("intensity") {
+                config.intensity = val.parse().ok().filter(|percent: &u8| *percent <= 100);
+            }
+
+            /* Multi-stage twilight settings */
+            if let Some(val) = section.get("temp-civil") {
+                config.temp_civil = resolve_temp(val).ok();
+            }
+            if let Some(val) = section.get("temp-nautical") {
+                config.temp_nautical = resolve_temp(val).ok();
+            }
+            if let Some(val) = section.get("brightness-civil") {
+                config.brightness_civil = val.parse().ok();
+            }
+            if let Some(val) = section.get("brightness-nautical") {
+                config.brightness_nautical = val.parse().ok();
+            }
+
+            /* Narrower temperature bounds, e.g. for kiosks */
+            if let Some(val) = section.get("temp-min") {
+                config.temp_min = resolve_temp(val).ok();
+            }
+            if let Some(val) = section.get("temp-max") {
+                config.temp_max = resolve_temp(val).ok();
+            }
+
+            /* Deep night past astronomical twilight */
+            if let Some(val) = section.get("temp-deep-night") {
+                config.temp_deep_night = resolve_temp(val).ok();
+            }
+            if let Some(val) = section.get("elevation-deep-night") {
+                config.elevation_deep_night = val.parse().ok();
+            }
 
             /* Time-based transition settings */
             if let Some(val) = section.get("dawn-time") {
@@ -173,6 +402,13 @@ impl RedshiftConfig {
                 config.dusk_time = parse_time_range(val).ok();
             }
 
+            /* Days of the week the schedule applies on */
+            if let Some(val) = section.get("active-days") {
+                config.active_days = ActiveDays::parse(val)
+                    .map_err(|e| warn!("Ignoring active-days: {}", e))
+                    .ok();
+            }
+
             /* Provider/method settings */
             if let Some(val) = section.get("location-provider") {
                 config.location_provider = Some(val.to_string());
@@ -180,6 +416,18 @@ impl RedshiftConfig {
             if let Some(val) = section.get("adjustment-method") {
                 config.adjustment_method = Some(val.to_string());
             }
+            if let Some(val) = section.get("geoclue-desktop-id") {
+                if !val.is_empty() {
+                    config.geoclue_desktop_id = Some(val.to_string());
+                    debug!("Loaded GeoClue2 desktop id from INI: {}", val);
+                }
+            }
+            if let Some(val) = section.get("geoclue-samples") {
+                config.geoclue_samples = val.parse().ok().filter(|count: &usize| *count > 0);
+            }
+            if let Some(val) = section.get("geoclue-settle-time") {
+                config.geoclue_settle_time = val.parse().ok().filter(|secs: &u64| *secs > 0);
+            }
         }
 
         /* Parse [manual] section for location */
@@ -195,6 +443,16 @@ impl RedshiftConfig {
             }
         }
 
+        /* Parse [gpsd] section for the gpsd location provider */
+        if let Some(section) = ini.section(Some("gpsd")) {
+            if let Some(val) = section.get("host") {
+                config.gpsd_host = Some(val.to_string()).filter(|host| !host.is_empty());
+            }
+            if let Some(val) = section.get("port") {
+                config.gpsd_port = val.parse().ok();
+            }
+        }
+
         /* Parse [randr] section for gamma method settings */
         if let Some(section) = ini.section(Some("randr")) {
             if let Some(val) = section.get("screen") {
@@ -211,10 +469,51 @@ impl RedshiftConfig {
             }
         }
 
+        /* Parse [geoclue2] section for location provider settings.
+           The [redshift] geoclue-desktop-id key takes precedence. */
+        if let Some(section) = ini.section(Some("geoclue2")) {
+            if let Some(val) = section.get("desktop-id") {
+                if !val.is_empty() && config.geoclue_desktop_id.is_none() {
+                    config.geoclue_desktop_id = Some(val.to_string());
+                    debug!("Loaded GeoClue2 desktop id from [geoclue2]: {}", val);
+                }
+            }
+        }
+
+        /* Parse [schedule] section of HH:MM=temperature keyframes */
+        if let Some(section) = ini.section(Some("schedule")) {
+            for (key, val) in section.iter() {
+                match parse_schedule_entry(key, val) {
+                    Ok(keyframe) => config.schedule.push(keyframe),
+                    Err(e) => warn!("Ignoring schedule entry: {}", e),
+                }
+            }
+            config.schedule.sort_by_key(|&(time, _)| time);
+            debug!("Loaded {} schedule keyframes from INI", config.schedule.len());
+        }
+
+        /* Sections for methods and providers from upstream redshift that
+           are not supported here are ignored */
+        for name in ini.sections().flatten() {
+            if !KNOWN_SECTIONS.contains(&name) {
+                debug!("Ignoring unsupported INI section [{}]", name);
+            }
+        }
+
         trace!("INI configuration loaded successfully");
         Ok(config)
     }
 
+    /// Fade thresholds, falling back to the defaults for unset values
+    pub fn fade_thresholds(&self) -> FadeThresholds {
+        let defaults = FadeThresholds::default();
+        FadeThresholds {
+            temperature: self.fade_threshold_temp.unwrap_or(defaults.temperature),
+            brightness: self.fade_threshold_brightness.unwrap_or(defaults.brightness),
+            gamma: self.fade_threshold_gamma.unwrap_or(defaults.gamma),
+        }
+    }
+
     /// Get manual location if specified
     pub fn get_manual_location(&self) -> Option<Location> {
         if let (Some(lat), Some(lon)) = (self.manual_lat, self.manual_lon) {
@@ -225,8 +524,121 @@ impl RedshiftConfig {
     }
 }
 
-/// Parse brightness string: "0.9" or "0.7:0.4" (day:night)
-pub fn parse_brightness_string(s: &str) -> Result<(f32, f32), String> {
+/// Strip a leading UTF-8 byte order mark and convert CRLF line endings,
+/// so configs edited on Windows parse the same as ones written on Unix
+pub fn normalize_ini_text(text: &str) -> String {
+    text.strip_prefix('\u{feff}')
+        .unwrap_or(text)
+        .replace("\r\n", "\n")
+}
+
+/// Remove inline comments from every line of INI text (see
+/// `strip_inline_comment`), keeping line numbers unchanged
+pub fn strip_inline_comments(text: &str) -> String {
+    let mut stripped: String = text
+        .lines()
+        .map(strip_inline_comment)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.ends_with('\n') {
+        stripped.push('\n');
+    }
+    stripped
+}
+
+/// Cut a `;` or `#` comment that follows whitespace off the value of a
+/// key=value or key: value line, e.g. `temp-day=5700 ; daytime` becomes
+/// `temp-day=5700`.
+/// A quoted value is kept whole, so only comments after its closing quote
+/// are removed.
+pub fn strip_inline_comment(line: &str) -> &str {
+    let Some(separator) = line.find(['=', ':']) else {
+        return line;
+    };
+    let value = &line[separator + 1..];
+    let start = separator + 1 + (value.len() - value.trim_start().len());
+
+    let scan_from = match line[start..].chars().next() {
+        Some(quote @ ('"' | '\'')) => match line[start + 1..].find(quote) {
+            Some(end) => start + 1 + end + 1,
+            None => return line,
+        },
+        _ => start,
+    };
+
+    let bytes = line.as_bytes();
+    (scan_from..line.len())
+        .find(|&i| matches!(bytes[i], b';' | b'#') && bytes[i - 1].is_ascii_whitespace())
+        .map_or(line, |i| line[..i].trim_end())
+}
+
+/// A key set more than once within one section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    /// Section name, or None for keys before the first section
+    pub section: Option<String>,
+    pub key: String,
+    /// 1-based line numbers of every occurrence
+    pub lines: Vec<usize>,
+}
+
+/// Scan raw INI text for keys set more than once in the same section.
+/// Sections repeated further down the file count as the same section. Like
+/// the INI parser, a key ends at the first '=' or ':', so "lat:48.1" sets
+/// the same key as "lat=48.1".
+pub fn find_duplicate_keys(text: &str) -> Vec<DuplicateKey> {
+    let mut section: Option<String> = None;
+    let mut seen: Vec<DuplicateKey> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            section = Some(name.trim().to_string());
+            continue;
+        }
+        let Some(separator) = line.find(['=', ':']) else {
+            continue;
+        };
+        let key = line[..separator].trim();
+
+        match seen.iter_mut().find(|entry| entry.section == section && entry.key == key) {
+            Some(entry) => entry.lines.push(index + 1),
+            None => seen.push(DuplicateKey {
+                section: section.clone(),
+                key: key.to_string(),
+                lines: vec![index + 1],
+            }),
+        }
+    }
+
+    seen.retain(|entry| entry.lines.len() > 1);
+    seen
+}
+
+/// Parse brightness string: "0.9" or "0.7:0.4" (day:night), with both
+/// values within MIN_BRIGHTNESS to `max`, e.g. MAX_BRIGHTNESS or
+/// MAX_OVERDRIVE_BRIGHTNESS. Errors name the offending value.
+pub fn parse_brightness_in_range(s: &str, max: f32) -> Result<(f32, f32), String> {
+    let (day, night) = parse_brightness_values(s)?;
+    let names = if s.contains(':') {
+        ["Day brightness", "Night brightness"]
+    } else {
+        ["Brightness", "Brightness"]
+    };
+    for (name, value) in names.iter().zip([day, night]) {
+        if !(MIN_BRIGHTNESS..=max).contains(&value) {
+            return Err(format!("{} {} is outside {}-{}", name, value, MIN_BRIGHTNESS, max));
+        }
+    }
+    Ok((day, night))
+}
+
+/// Parse a brightness string like `parse_brightness_in_range`, without range
+/// checks
+pub fn parse_brightness_values(s: &str) -> Result<(f32, f32), String> {
     let parts: Vec<&str> = s.split(':').collect();
 
     if parts.len() == 1 {
@@ -246,8 +658,50 @@ pub fn parse_brightness_string(s: &str) -> Result<(f32, f32), String> {
     }
 }
 
-/// Parse gamma string: "0.8" or "0.8:0.7:0.8" (R:G:B)
+/// Parse a comma-separated list of temperatures (Kelvin or preset names),
+/// e.g. "6500,4000,candle"
+pub fn parse_temperature_list(s: &str) -> Result<Vec<i32>, String> {
+    let temperatures = s
+        .split(',')
+        .map(|part| {
+            let temperature = resolve_temp(part.trim())?;
+            if !(MIN_TEMP..=MAX_TEMP).contains(&temperature) {
+                return Err(format!(
+                    "Temperature must be between {} and {}",
+                    MIN_TEMP, MAX_TEMP
+                ));
+            }
+            Ok(temperature)
+        })
+        .collect::<Result<Vec<i32>, String>>()?;
+
+    if temperatures.is_empty() {
+        return Err("Temperature list must not be empty".to_string());
+    }
+    Ok(temperatures)
+}
+
+/// Parse gamma string: "0.8" or "0.8:0.7:0.8" (R:G:B), with every value
+/// within MIN_GAMMA to MAX_GAMMA. Errors name the offending channel.
 pub fn parse_gamma_string(s: &str) -> Result<[f32; 3], String> {
+    let gamma = parse_gamma_values(s)?;
+    for (channel, value) in CHANNEL_NAMES.iter().zip(gamma) {
+        if !(MIN_GAMMA..=MAX_GAMMA).contains(&value) {
+            return Err(if s.contains(':') {
+                format!(
+                    "Gamma {} of the {} channel is outside {}-{}",
+                    value, channel, MIN_GAMMA, MAX_GAMMA
+                )
+            } else {
+                format!("Gamma {} is outside {}-{}", value, MIN_GAMMA, MAX_GAMMA)
+            });
+        }
+    }
+    Ok(gamma)
+}
+
+/// Parse a gamma string like `parse_gamma_string`, without range checks
+pub fn parse_gamma_values(s: &str) -> Result<[f32; 3], String> {
     let parts: Vec<&str> = s.split(':').collect();
 
     if parts.len() == 1 {
@@ -269,6 +723,26 @@ pub fn parse_gamma_string(s: &str) -> Result<[f32; 3], String> {
     }
 }
 
+/// Parse white point string: "0.3457,0.3585" (CIE 1931 x,y)
+pub fn parse_white_point_string(s: &str) -> Result<[f64; 2], String> {
+    let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+
+    if parts.len() != 2 {
+        return Err("White point must be x,y".to_string());
+    }
+
+    let x: f64 = parts[0].parse()
+        .map_err(|_| format!("Invalid white point x: {}", parts[0]))?;
+    let y: f64 = parts[1].parse()
+        .map_err(|_| format!("Invalid white point y: {}", parts[1]))?;
+
+    if !(x > 0.0 && y > 0.0 && x + y < 1.0) {
+        return Err(format!("White point {},{} is not a valid chromaticity", x, y));
+    }
+
+    Ok([x, y])
+}
+
 /// Parse time range string: "6:00" or "6:00-7:45"
 fn parse_time_range(s: &str) -> Result<TimeRange, String> {
     let parts: Vec<&str> = s.split('-').collect();
@@ -288,6 +762,23 @@ fn parse_time_range(s: &str) -> Result<TimeRange, String> {
     })
 }
 
+/// Parse a [schedule] entry such as "21:30=2700K" into
+/// (seconds since midnight, temperature).
+/// The INI parser also splits keys at ':', so "21:30=2700K" arrives as
+/// key "21" and value "30=2700K"; both halves are joined back here.
+pub fn parse_schedule_entry(key: &str, value: &str) -> Result<(i32, i32), String> {
+    let entry = format!("{}:{}", key.trim(), value.trim());
+    let (time, temp) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("Expected HH:MM=TEMP: {}", entry))?;
+
+    let time = parse_time(time.trim())?;
+    let temp = temp.trim();
+    let temp = resolve_temp(temp.strip_suffix(['K', 'k']).unwrap_or(temp))?;
+
+    Ok((time, temp))
+}
+
 /// Parse time string "HH:MM" to seconds since midnight
 fn parse_time(s: &str) -> Result<i32, String> {
     let parts: Vec<&str> = s.split(':').collect();
@@ -317,14 +808,14 @@ mod tests {
 
     #[test]
     fn test_parse_brightness_single() {
-        let (day, night) = parse_brightness_string("0.9").unwrap();
+        let (day, night) = parse_brightness_in_range("0.9", MAX_BRIGHTNESS).unwrap();
         assert_eq!(day, 0.9);
         assert_eq!(night, 0.9);
     }
 
     #[test]
     fn test_parse_brightness_separate() {
-        let (day, night) = parse_brightness_string("0.7:0.4").unwrap();
+        let (day, night) = parse_brightness_in_range("0.7:0.4", MAX_BRIGHTNESS).unwrap();
         assert_eq!(day, 0.7);
         assert_eq!(night, 0.4);
     }
@@ -341,6 +832,15 @@ mod tests {
         assert_eq!(gamma, [0.8, 0.7, 0.9]);
     }
 
+    #[test]
+    fn test_parse_white_point() {
+        assert_eq!(parse_white_point_string("0.3457,0.3585").unwrap(), [0.3457, 0.3585]);
+        assert_eq!(parse_white_point_string("0.3127, 0.329").unwrap(), [0.3127, 0.329]);
+        assert!(parse_white_point_string("0.3457").is_err());
+        assert!(parse_white_point_string("0.7,0.5").is_err());
+        assert!(parse_white_point_string("0.3,0").is_err());
+    }
+
     #[test]
     fn test_parse_time() {
         assert_eq!(parse_time("6:00").unwrap(), 6 * 3600);
@@ -0,0 +1,84 @@
+/// Detection of other gamma tools running alongside us
+/// Two programs setting the gamma ramp make the tint oscillate between
+/// their settings. This check is advisory only and never stops startup.
+
+use std::fs;
+use std::path::Path;
+
+/// Process names of known tools that set the gamma ramp, with a
+/// description for the warning
+pub const CONFLICTING_TOOLS: &[(&str, &str)] = &[
+    ("redshift", "Redshift"),
+    ("redshift-gtk", "Redshift"),
+    ("redshift-rebooted", "another redshift-rebooted instance"),
+    ("gammastep", "Gammastep"),
+    ("gammastep-indicator", "Gammastep"),
+    ("gsd-color", "GNOME Night Light"),
+    ("wlsunset", "wlsunset"),
+    ("hyprsunset", "hyprsunset"),
+    ("sct", "sct"),
+    ("xflux", "f.lux"),
+];
+
+/// Longest process name the kernel reports in /proc/<pid>/comm
+const COMM_MAX_LEN: usize = 15;
+
+/// A running process as listed in /proc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// List running processes from a procfs mounted at `proc_root`.
+/// Processes that exit while scanning are skipped.
+pub fn scan_processes(proc_root: &Path) -> Vec<ProcessInfo> {
+    let entries = match fs::read_dir(proc_root) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse().ok()?;
+            let name = fs::read_to_string(entry.path().join("comm")).ok()?;
+            Some(ProcessInfo {
+                pid,
+                name: name.trim_end().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Return "description (name, pid N)" for each process in `processes`
+/// that is a known conflicting tool, ignoring our own pid
+pub fn find_conflicts(processes: &[ProcessInfo], own_pid: u32) -> Vec<String> {
+    processes
+        .iter()
+        .filter(|process| process.pid != own_pid)
+        .filter_map(|process| {
+            CONFLICTING_TOOLS
+                .iter()
+                .find(|(name, _)| comm_matches(&process.name, name))
+                .map(|(name, description)| {
+                    format!("{} ({}, pid {})", description, name, process.pid)
+                })
+        })
+        .collect()
+}
+
+/* The kernel truncates comm to 15 bytes, so long tool names only match
+   on their prefix */
+fn comm_matches(comm: &str, tool: &str) -> bool {
+    if tool.len() > COMM_MAX_LEN {
+        comm == &tool[..COMM_MAX_LEN]
+    } else {
+        comm == tool
+    }
+}
+
+/// Scan /proc for conflicting gamma tools other than this process
+pub fn detect_conflicts() -> Vec<String> {
+    find_conflicts(&scan_processes(Path::new("/proc")), std::process::id())
+}
@@ -0,0 +1,321 @@
+/// Control socket for a running continual mode instance
+/// Accepts one-line commands over a Unix socket, e.g. to hold a fixed
+/// temperature for a while before returning to the schedule
+
+use crate::types::TemperatureBounds;
+use log::{debug, warn};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// File name of the control socket in the runtime directory
+pub const SOCKET_NAME: &str = "redshift-rebooted.sock";
+
+/// Longest override that can be requested
+pub const MAX_OVERRIDE_DURATION: Duration = Duration::from_secs(24 * 3600);
+
+/// Time a client gets to send its command line before it is dropped
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Clients waiting to send a command at once
+const MAX_PENDING_CLIENTS: usize = 16;
+
+/// Longest command line accepted from a client
+const MAX_LINE_LENGTH: usize = 256;
+
+/// Commands understood by the control socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlCommand {
+    /// Hold `temperature` for `duration`, then revert to the schedule
+    SetTemporaryOverride { temperature: i32, duration: Duration },
+}
+
+impl ControlCommand {
+    /// Parse a command line such as "SetTemporaryOverride 3000 1800",
+    /// resolving the temperature against `bounds`
+    pub fn parse(line: &str, bounds: &TemperatureBounds) -> Result<Self, String> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        match parts.as_slice() {
+            ["SetTemporaryOverride", temperature, duration_secs] => {
+                let temperature: i32 = temperature
+                    .parse()
+                    .map_err(|_| format!("Invalid temperature: {}", temperature))?;
+                let temperature = bounds.resolve(temperature)?;
+                if !duration_secs.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(format!("Invalid duration: {}", duration_secs));
+                }
+                Ok(ControlCommand::SetTemporaryOverride {
+                    temperature,
+                    duration: parse_duration(duration_secs)?,
+                })
+            }
+            _ => Err(format!("Unknown command: {}", line.trim())),
+        }
+    }
+
+    /// Format the command as a line for the control socket
+    pub fn line(&self) -> String {
+        match self {
+            ControlCommand::SetTemporaryOverride { temperature, duration } => {
+                format!("SetTemporaryOverride {} {}\n", temperature, duration.as_secs())
+            }
+        }
+    }
+}
+
+/// Default path of the control socket.
+/// Only the per-user runtime directory is used, as a socket in a shared
+/// directory could be created or taken over by another user.
+pub fn socket_path() -> Result<PathBuf, String> {
+    dirs::runtime_dir()
+        .map(|dir| dir.join(SOCKET_NAME))
+        .ok_or_else(|| "XDG_RUNTIME_DIR is not set, cannot place the control socket".to_string())
+}
+
+/// Parse a duration such as "90s", "30m", "2h" or a plain number of seconds,
+/// up to MAX_OVERRIDE_DURATION
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, 's')) => (&s[..i], 1),
+        Some((i, 'm')) => (&s[..i], 60),
+        Some((i, 'h')) => (&s[..i], 3600),
+        _ => (s, 1),
+    };
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration: {} (expected e.g. 90s, 30m or 2h)", s))?;
+    if value == 0 {
+        return Err("Duration must be greater than zero".to_string());
+    }
+
+    let duration = value
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .filter(|d| *d <= MAX_OVERRIDE_DURATION)
+        .ok_or_else(|| {
+            format!(
+                "Duration {} is too long (at most {}h)",
+                s,
+                MAX_OVERRIDE_DURATION.as_secs() / 3600
+            )
+        })?;
+    Ok(duration)
+}
+
+/// A temperature held until a deadline
+#[derive(Debug, Clone, Copy)]
+pub struct TemporaryOverride {
+    pub temperature: i32,
+    pub expires: Instant,
+}
+
+/// Tracks the current temporary override, if any.
+/// Times are passed in so expiry can be tested without waiting.
+#[derive(Debug, Default)]
+pub struct OverrideState {
+    current: Option<TemporaryOverride>,
+}
+
+impl OverrideState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start holding `temperature` for `duration` from `now`, replacing
+    /// any previous override. Durations beyond MAX_OVERRIDE_DURATION are
+    /// shortened to it.
+    pub fn set(&mut self, temperature: i32, duration: Duration, now: Instant) {
+        let duration = duration.min(MAX_OVERRIDE_DURATION);
+        self.current = now
+            .checked_add(duration)
+            .map(|expires| TemporaryOverride { temperature, expires });
+    }
+
+    /// Temperature to hold at `now`, or None once the override expired
+    pub fn temperature(&self, now: Instant) -> Option<i32> {
+        self.current
+            .filter(|o| now < o.expires)
+            .map(|o| o.temperature)
+    }
+
+    /// Drop the override if it has expired at `now`.
+    /// Returns true if it expired on this call.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.current {
+            Some(o) if now >= o.expires => {
+                self.current = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A connected client that has not sent a full command line yet
+struct PendingClient {
+    stream: UnixStream,
+    buffer: Vec<u8>,
+    accepted: Instant,
+}
+
+/// Listening end of the control socket, polled from the main loop.
+/// Clients are read without blocking, so a client that connects and stays
+/// silent does not hold up the loop; it is dropped after CLIENT_TIMEOUT.
+pub struct ControlServer {
+    listener: UnixListener,
+    path: PathBuf,
+    pending: Vec<PendingClient>,
+}
+
+impl ControlServer {
+    /// Bind the control socket at `path`.
+    /// A stale socket left by a crashed instance is replaced, but a socket
+    /// another running instance is listening on is not, and neither is a
+    /// file that is not a socket.
+    pub fn bind(path: &Path) -> Result<Self, String> {
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("{} exists and is not a socket", path.display()));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!("Another instance is listening on {}", path.display()));
+            }
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove stale socket: {}", e))?;
+        }
+
+        let listener = UnixListener::bind(path)
+            .map_err(|e| format!("Failed to bind {}: {}", path.display(), e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure control socket: {}", e))?;
+
+        debug!("Listening for control commands on {}", path.display());
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Accept pending connections and return the commands received, with
+    /// temperatures resolved against `bounds`. Each client gets "OK" or
+    /// "ERROR <reason>" in reply once its command line is complete.
+    pub fn poll(&mut self, bounds: &TemperatureBounds) -> Vec<ControlCommand> {
+        self.accept_clients();
+
+        let now = Instant::now();
+        let mut commands = Vec::new();
+        self.pending.retain_mut(|client| match client.read_line() {
+            Ok(Some(line)) => {
+                match client.reply(ControlCommand::parse(&line, bounds)) {
+                    Ok(command) => commands.push(command),
+                    Err(e) => warn!("Control socket: {}", e),
+                }
+                false
+            }
+            Ok(None) if now.duration_since(client.accepted) < CLIENT_TIMEOUT => true,
+            Ok(None) => {
+                warn!("Control socket: client sent no command in time");
+                false
+            }
+            Err(e) => {
+                warn!("Control socket: {}", e);
+                false
+            }
+        });
+
+        commands
+    }
+
+    fn accept_clients(&mut self) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if self.pending.len() >= MAX_PENDING_CLIENTS {
+                warn!("Control socket: too many clients, dropping one");
+                continue;
+            }
+            if let Err(e) = stream.set_nonblocking(true) {
+                warn!("Control socket: failed to configure client: {}", e);
+                continue;
+            }
+            self.pending.push(PendingClient {
+                stream,
+                buffer: Vec::new(),
+                accepted: Instant::now(),
+            });
+        }
+    }
+}
+
+impl PendingClient {
+    /// Read what the client has sent so far. Returns the command line once
+    /// it is complete, or None while more is expected.
+    fn read_line(&mut self) -> Result<Option<String>, String> {
+        let mut chunk = [0u8; 64];
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                return Ok(Some(String::from_utf8_lossy(&self.buffer[..end]).into_owned()));
+            }
+            if self.buffer.len() > MAX_LINE_LENGTH {
+                return Err("Command line too long".to_string());
+            }
+            match self.stream.read(&mut chunk) {
+                // A client that closes without a newline still sent a command
+                Ok(0) if self.buffer.is_empty() => return Err("Client sent no command".to_string()),
+                Ok(0) => return Ok(Some(String::from_utf8_lossy(&self.buffer).into_owned())),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(format!("Failed to read command: {}", e)),
+            }
+        }
+    }
+
+    fn reply(
+        &mut self,
+        result: Result<ControlCommand, String>,
+    ) -> Result<ControlCommand, String> {
+        let reply = match &result {
+            Ok(_) => "OK\n".to_string(),
+            Err(e) => format!("ERROR {}\n", e),
+        };
+        self.stream.write_all(reply.as_bytes()).ok();
+        result
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Send `command` to the instance listening on `path` and wait for its reply
+pub fn send_command(path: &Path, command: &ControlCommand) -> Result<(), String> {
+    let mut stream = UnixStream::connect(path).map_err(|e| {
+        format!(
+            "Could not reach a running redshift at {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    stream
+        .write_all(command.line().as_bytes())
+        .map_err(|e| format!("Failed to send command: {}", e))?;
+
+    let mut reply = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut reply)
+        .map_err(|e| format!("Failed to read reply: {}", e))?;
+
+    match reply.trim() {
+        "OK" => Ok(()),
+        other => Err(other.trim_start_matches("ERROR ").to_string()),
+    }
+}
@@ -0,0 +1,71 @@
+/// Cycling through preset temperatures with SIGUSR2
+/// Each signal moves to the next preset, and past the last one back to
+/// following the schedule ("auto").
+
+/// Presets cycled through when none are configured
+pub const DEFAULT_CYCLE_TEMPS: [i32; 3] = [6500, 4000, 2700];
+
+/// Position in a circular list of preset temperatures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemperatureCycle {
+    presets: Vec<i32>,
+    position: Option<usize>,
+}
+
+impl TemperatureCycle {
+    /// Start following the schedule, before the first preset
+    pub fn new(presets: Vec<i32>) -> Self {
+        Self {
+            presets,
+            position: None,
+        }
+    }
+
+    /// Resume at `position`, e.g. one saved by an earlier run. Positions
+    /// past the end of the list fall back to the schedule.
+    pub fn with_position(mut self, position: Option<usize>) -> Self {
+        self.position = position.filter(|&index| index < self.presets.len());
+        self
+    }
+
+    /// Index of the active preset, or None while following the schedule
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Temperature of the active preset, or None while following the
+    /// schedule
+    pub fn temperature(&self) -> Option<i32> {
+        self.position.map(|index| self.presets[index])
+    }
+
+    /// Move to the next preset, or back to the schedule after the last
+    /// one, and return the new temperature
+    pub fn advance(&mut self) -> Option<i32> {
+        self.position = match self.position {
+            None if !self.presets.is_empty() => Some(0),
+            Some(index) if index + 1 < self.presets.len() => Some(index + 1),
+            _ => None,
+        };
+        self.temperature()
+    }
+
+    /// Go back to following the schedule
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+}
+
+impl Default for TemperatureCycle {
+    fn default() -> Self {
+        Self::new(DEFAULT_CYCLE_TEMPS.to_vec())
+    }
+}
@@ -0,0 +1,49 @@
+/// Demo mode: a whole day of the scheme compressed into a few seconds
+/// The timeline is computed as usual, but from a simulated clock that runs
+/// from local midnight to the next midnight over the demo duration.
+
+use crate::timeofday::local_seconds_since_midnight;
+use std::time::Duration;
+
+/// Interval between demo updates. Fades count steps, so this is shorter
+/// than in continual mode to keep a fade well under a second.
+pub const DEMO_STEP: Duration = Duration::from_millis(20);
+
+const SECONDS_PER_DAY: f64 = 24.0 * 3600.0;
+
+/// Maps real time elapsed since the demo started onto a simulated day
+#[derive(Debug, Clone, Copy)]
+pub struct DemoClock {
+    day_start: f64,
+    duration: Duration,
+}
+
+impl DemoClock {
+    /// Simulate the day starting at unix time `day_start` over `duration`
+    pub fn new(day_start: f64, duration: Duration) -> Self {
+        Self {
+            day_start,
+            duration,
+        }
+    }
+
+    /// Simulated unix time after `elapsed`, stopping at the end of the day
+    pub fn simulated_time(&self, elapsed: Duration) -> f64 {
+        let fraction = if self.duration.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+        };
+        self.day_start + fraction * SECONDS_PER_DAY
+    }
+
+    /// True once `elapsed` covers the whole simulated day
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        elapsed >= self.duration
+    }
+}
+
+/// Unix time of the local midnight starting the day of `timestamp`
+pub fn local_midnight(timestamp: f64) -> f64 {
+    timestamp.floor() - local_seconds_since_midnight(timestamp) as f64
+}
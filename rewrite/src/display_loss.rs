@@ -0,0 +1,96 @@
+/// Handling a lost connection to the display
+/// When the X server goes away, e.g. at logout or when it crashes, every
+/// request fails. --on-display-loss chooses between exiting with a clear
+/// message and reconnecting a bounded number of times, for servers that
+/// are restarted in place.
+
+use crate::error::{Error, Result};
+use crate::gamma::GammaMethod;
+use crate::types::ColorSetting;
+use log::{error, info, warn};
+use std::time::Duration;
+
+/// Reconnection attempts before giving up
+pub const RECONNECT_ATTEMPTS: u32 = 10;
+
+/// Wait before each reconnection attempt
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// What to do when the connection to the display is lost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayLossPolicy {
+    /// Try to connect to the display again
+    Reconnect,
+    /// Exit with an error
+    #[default]
+    Exit,
+}
+
+impl DisplayLossPolicy {
+    /* Parse "reconnect" or "exit". */
+    pub fn parse(s: &str) -> std::result::Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "reconnect" => Ok(DisplayLossPolicy::Reconnect),
+            "exit" => Ok(DisplayLossPolicy::Exit),
+            _ => Err(format!(
+                "Invalid display loss policy: {} (expected reconnect or exit)",
+                s.trim()
+            )),
+        }
+    }
+}
+
+/// Deal with `error` from applying a setting to `gamma`. A lost display is
+/// reconnected under the reconnect policy, trying up to `attempts` times
+/// after waiting `delay` each. `sleep` waits and returns false to give up
+/// early, e.g. on an exit signal. Returns Ok once reconnected; any other
+/// error, or a display that could not be reconnected, is returned.
+pub fn recover_display(
+    gamma: &mut dyn GammaMethod,
+    policy: DisplayLossPolicy,
+    error: Error,
+    attempts: u32,
+    delay: Duration,
+    sleep: &mut dyn FnMut(Duration) -> bool,
+) -> Result<()> {
+    let Error::DisplayLost(message) = error else {
+        return Err(error);
+    };
+    if policy == DisplayLossPolicy::Exit {
+        return Err(Error::DisplayLost(format!("{}, exiting", message)));
+    }
+
+    warn!("{}, reconnecting", message);
+    for attempt in 1..=attempts {
+        if !sleep(delay) {
+            break;
+        }
+        match gamma.init().and_then(|()| gamma.start()) {
+            Ok(()) => {
+                info!("Reconnected to the display");
+                gamma.invalidate();
+                return Ok(());
+            }
+            Err(e) => error!("Reconnection attempt {} of {} failed: {}", attempt, attempts, e),
+        }
+    }
+    Err(Error::DisplayLost(format!("{}, could not reconnect", message)))
+}
+
+/// Apply `setting` to `gamma`, recovering a lost display as
+/// `recover_display` does and applying `setting` again once reconnected,
+/// so the new connection does not wait for the next update.
+pub fn apply_recovering(
+    gamma: &mut dyn GammaMethod,
+    setting: &ColorSetting,
+    policy: DisplayLossPolicy,
+    attempts: u32,
+    delay: Duration,
+    sleep: &mut dyn FnMut(Duration) -> bool,
+) -> Result<()> {
+    if let Err(e) = gamma.set_temperature(setting, false) {
+        recover_display(gamma, policy, e, attempts, delay, sleep)?;
+        gamma.set_temperature(setting, false)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+/// EDID parsing for matching monitors by serial number
+/// Output names such as "HDMI-1" depend on the port a monitor is plugged
+/// into, while the serial in its EDID stays the same.
+
+/// Fixed header every EDID base block starts with
+const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Size of the EDID base block
+pub const EDID_BLOCK_SIZE: usize = 128;
+
+/// Offsets of the four 18-byte display descriptors in the base block
+const DESCRIPTOR_OFFSETS: [usize; 4] = [54, 72, 90, 108];
+
+/// Descriptor tag of the display product serial number string
+const SERIAL_STRING_TAG: u8 = 0xff;
+
+/// Monitor identification read from an EDID base block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdidInfo {
+    /// Three-letter PNP manufacturer id, e.g. "DEL"
+    pub manufacturer: String,
+    pub product_code: u16,
+    /// Numeric serial number, 0 if the monitor does not set one
+    pub serial_number: u32,
+    /// Serial number string from a display descriptor, if present
+    pub serial_string: Option<String>,
+}
+
+impl EdidInfo {
+    /// Serial as shown to the user: the serial string if present, else
+    /// the numeric serial
+    pub fn serial(&self) -> String {
+        match &self.serial_string {
+            Some(serial) => serial.clone(),
+            None => self.serial_number.to_string(),
+        }
+    }
+
+    /// Whether `serial` names this monitor, matching either the serial
+    /// string (ignoring case) or a non-zero numeric serial
+    pub fn matches_serial(&self, serial: &str) -> bool {
+        let serial = serial.trim();
+        if serial.is_empty() {
+            return false;
+        }
+        if let Some(string) = &self.serial_string {
+            if string.eq_ignore_ascii_case(serial) {
+                return true;
+            }
+        }
+        self.serial_number != 0 && serial.parse() == Ok(self.serial_number)
+    }
+}
+
+/// Parse the manufacturer, product code and serial numbers from an EDID
+/// base block
+pub fn parse_edid(data: &[u8]) -> Result<EdidInfo, String> {
+    if data.len() < EDID_BLOCK_SIZE {
+        return Err(format!(
+            "EDID is {} bytes, expected at least {}",
+            data.len(),
+            EDID_BLOCK_SIZE
+        ));
+    }
+    if data[..8] != EDID_HEADER {
+        return Err("EDID header is missing".to_string());
+    }
+
+    /* Three 5-bit letters, big endian, with 1 meaning 'A' */
+    let id = u16::from_be_bytes([data[8], data[9]]);
+    let manufacturer = [10, 5, 0]
+        .iter()
+        .map(|shift| {
+            let letter = ((id >> shift) & 0x1f) as u8;
+            if (1..=26).contains(&letter) {
+                (b'A' + letter - 1) as char
+            } else {
+                '?'
+            }
+        })
+        .collect();
+
+    let serial_string = DESCRIPTOR_OFFSETS.iter().find_map(|&offset| {
+        let descriptor = &data[offset..offset + 18];
+        if descriptor[..3] != [0, 0, 0] || descriptor[3] != SERIAL_STRING_TAG {
+            return None;
+        }
+        /* Up to 13 characters, ended by a newline and padded with spaces */
+        let text = &descriptor[5..];
+        let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+        let serial = String::from_utf8_lossy(&text[..end]).trim().to_string();
+        (!serial.is_empty()).then_some(serial)
+    });
+
+    Ok(EdidInfo {
+        manufacturer,
+        product_code: u16::from_le_bytes([data[10], data[11]]),
+        serial_number: u32::from_le_bytes([data[12], data[13], data[14], data[15]]),
+        serial_string,
+    })
+}
+
+/// Resolve requested serials against the EDIDs of discovered outputs
+/// (None where an output has no readable EDID), returning the indices of
+/// all matching outputs. A serial matching no output produces an error
+/// listing the serials found.
+pub fn match_edid_serials(
+    requested: &[String],
+    discovered: &[Option<EdidInfo>],
+) -> Result<Vec<usize>, String> {
+    let mut indices = Vec::new();
+
+    for serial in requested {
+        let matches: Vec<usize> = discovered
+            .iter()
+            .enumerate()
+            .filter(|(_, edid)| edid.as_ref().is_some_and(|edid| edid.matches_serial(serial)))
+            .map(|(index, _)| index)
+            .collect();
+
+        if matches.is_empty() {
+            let available: Vec<String> = discovered
+                .iter()
+                .flatten()
+                .map(|edid| format!("{} {}", edid.manufacturer, edid.serial()))
+                .collect();
+            return Err(format!(
+                "No monitor with EDID serial {} found. Available monitors: {}",
+                serial,
+                if available.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ));
+        }
+
+        for index in matches {
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+    }
+
+    Ok(indices)
+}
@@ -0,0 +1,65 @@
+/// Crate-wide error type
+/// Each variant carries the message shown to the user and says which part
+/// of the program it came from
+
+use thiserror::Error;
+
+/// Errors returned by the public APIs of this crate
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Error {
+    /// Reading, parsing or writing a configuration file
+    #[error("{0}")]
+    Config(String),
+
+    /// Determining the location
+    #[error("{0}")]
+    Location(String),
+
+    /// Adjusting the gamma ramps
+    #[error("{0}")]
+    Gamma(String),
+
+    /// The connection to the display was lost, e.g. because the X server
+    /// exited; unlike a gamma error, every later request fails too
+    #[error("{0}")]
+    DisplayLost(String),
+
+    /// Calculating the solar position or a transition scheme
+    #[error("{0}")]
+    Solar(String),
+}
+
+/// Exit status for failures without a more specific one
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit status for invalid configuration or command-line usage, the same
+/// as for arguments clap rejects
+pub const EXIT_USAGE: i32 = 2;
+
+/// Exit status when no location could be determined
+pub const EXIT_NO_LOCATION: i32 = 3;
+
+/// Exit status when no gamma method works
+pub const EXIT_NO_GAMMA: i32 = 4;
+
+impl Error {
+    /// Exit status telling scripts which part of the program failed
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Config(_) => EXIT_USAGE,
+            Error::Location(_) => EXIT_NO_LOCATION,
+            Error::Gamma(_) | Error::DisplayLost(_) => EXIT_NO_GAMMA,
+            Error::Solar(_) => EXIT_FAILURE,
+        }
+    }
+}
+
+/// Result type used throughout the crate
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// For callers that still report errors as plain messages
+impl From<Error> for String {
+    fn from(error: Error) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,90 @@
+/// Continual mode events in a machine-parseable log format
+/// With `--log-format kv`, period changes, fades and toggles are logged as
+/// single `key=value` lines that scripts can grep, instead of the human
+/// readable messages.
+
+use crate::types::Period;
+use std::fmt;
+
+/// How continual mode events are written to the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable messages
+    #[default]
+    Human,
+    /// One `key=value` line per event
+    Kv,
+}
+
+impl LogFormat {
+    /// Parse "human" or "kv"
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "human" => Ok(LogFormat::Human),
+            "kv" => Ok(LogFormat::Kv),
+            _ => Err(format!("Invalid log format: {} (expected human or kv)", s.trim())),
+        }
+    }
+}
+
+/// Something that happened in the continual mode loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContinualEvent {
+    /// The period changed; `progress` is the transition progress from
+    /// night (0.0) to day (1.0)
+    PeriodChange {
+        from: Period,
+        to: Period,
+        progress: f64,
+        temperature: i32,
+    },
+    /// A fade started between two temperatures
+    FadeStart { from: i32, to: i32 },
+    /// Adjustment was switched on or off
+    Toggle { enabled: bool },
+    /// Periodic sample of the applied setting (see --status-interval)
+    Status {
+        temperature: i32,
+        brightness: f32,
+        period: Period,
+        enabled: bool,
+    },
+}
+
+impl fmt::Display for ContinualEvent {
+    /// Format as `event=NAME` followed by the event's fields
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContinualEvent::PeriodChange {
+                from,
+                to,
+                progress,
+                temperature,
+            } => write!(
+                f,
+                "event=period_change from={} to={} progress={:.2} temp={}",
+                from.name(),
+                to.name(),
+                progress,
+                temperature
+            ),
+            ContinualEvent::FadeStart { from, to } => {
+                write!(f, "event=fade_start from={} to={}", from, to)
+            }
+            ContinualEvent::Toggle { enabled } => write!(f, "event=toggle enabled={}", enabled),
+            ContinualEvent::Status {
+                temperature,
+                brightness,
+                period,
+                enabled,
+            } => write!(
+                f,
+                "event=status temp={} brightness={:.2} period={} enabled={}",
+                temperature,
+                brightness,
+                period.name(),
+                enabled
+            ),
+        }
+    }
+}
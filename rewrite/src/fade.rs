@@ -0,0 +1,260 @@
+/* fade.rs -- Fading between color settings in continual mode
+ * Ported from the fade handling in run_continual_mode() in legacy/src/redshift.c
+ *
+ * The fade advances one step per call to update(), so the caller's loop
+ * (sleeping SLEEP_DURATION_SHORT between steps) acts as the clock.
+ */
+
+use crate::types::ColorSetting;
+
+/* Length of fade in numbers of short sleep durations. */
+pub const FADE_LENGTH: i32 = 40;
+
+/* Differences above which a change is faded rather than applied
+   instantly. Smaller thresholds give finer-grained fades. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeThresholds {
+    pub temperature: i32,
+    pub brightness: f32,
+    pub gamma: f32,
+}
+
+impl Default for FadeThresholds {
+    fn default() -> Self {
+        Self {
+            temperature: 25,
+            brightness: 0.1,
+            gamma: 0.1,
+        }
+    }
+}
+
+/* Easing applied to fade progress. Every curve runs from 0 at the start
+   of a fade to 1 at the end. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FadeCurve {
+    /* Constant speed */
+    Linear,
+    /* Gentle start and end, as legacy redshift */
+    #[default]
+    Smoothstep,
+    /* Slower start and end with a quicker middle */
+    EaseInOutCubic,
+}
+
+impl FadeCurve {
+    /* Parse "linear", "smoothstep" or "ease-in-out-cubic". */
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "linear" => Ok(FadeCurve::Linear),
+            "smoothstep" => Ok(FadeCurve::Smoothstep),
+            "ease-in-out-cubic" => Ok(FadeCurve::EaseInOutCubic),
+            _ => Err(format!(
+                "Invalid fade curve: {} (expected linear, smoothstep or ease-in-out-cubic)",
+                s.trim()
+            )),
+        }
+    }
+
+    /* Eased fade progress for `t`, clamped to [0, 1]. */
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::Smoothstep => ease_fade(t),
+            FadeCurve::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/* Return true if color settings have major differences.
+   Used to determine if a fade should be applied in continual mode. */
+pub fn color_setting_diff_is_major(
+    first: &ColorSetting,
+    second: &ColorSetting,
+    thresholds: &FadeThresholds,
+) -> bool {
+    (first.temperature - second.temperature).abs() > thresholds.temperature
+        || (first.brightness - second.brightness).abs() > thresholds.brightness
+        || (first.gamma[0] - second.gamma[0]).abs() > thresholds.gamma
+        || (first.gamma[1] - second.gamma[1]).abs() > thresholds.gamma
+        || (first.gamma[2] - second.gamma[2]).abs() > thresholds.gamma
+}
+
+/* Interpolate between two temperatures using alpha (0.0 to 1.0), either
+   linearly in Kelvin or linearly in mireds (1e6/K). Equal mired steps look
+   more even, so a mired interpolation spends more of the way at the warm
+   end. */
+pub fn interpolate_temperature(first: i32, second: i32, alpha: f64, in_mireds: bool) -> i32 {
+    let alpha = alpha.clamp(0.0, 1.0);
+
+    if in_mireds {
+        let mireds = (1.0 - alpha) * (1e6 / first as f64) + alpha * (1e6 / second as f64);
+        (1e6 / mireds).round() as i32
+    } else {
+        ((1.0 - alpha) * (first as f64) + alpha * (second as f64)) as i32
+    }
+}
+
+/* Interpolate between two color settings using alpha (0.0 to 1.0), with
+   the temperature optionally interpolated in mireds. */
+pub fn interpolate_color_settings_with(
+    first: &ColorSetting,
+    second: &ColorSetting,
+    alpha: f64,
+    in_mireds: bool,
+    result: &mut ColorSetting,
+) {
+    let alpha = alpha.clamp(0.0, 1.0);
+
+    result.temperature =
+        interpolate_temperature(first.temperature, second.temperature, alpha, in_mireds);
+    result.brightness = ((1.0 - alpha) * (first.brightness as f64)
+        + alpha * (second.brightness as f64)) as f32;
+    result.gamma[0] = ((1.0 - alpha) * (first.gamma[0] as f64)
+        + alpha * (second.gamma[0] as f64)) as f32;
+    result.gamma[1] = ((1.0 - alpha) * (first.gamma[1] as f64)
+        + alpha * (second.gamma[1] as f64)) as f32;
+    result.gamma[2] = ((1.0 - alpha) * (first.gamma[2] as f64)
+        + alpha * (second.gamma[2] as f64)) as f32;
+    result.contrast = ((1.0 - alpha) * (first.contrast as f64)
+        + alpha * (second.contrast as f64)) as f32;
+}
+
+/* Ease fade function - cubic interpolation for smooth transitions.
+   Clamped to [0, 1] like the legacy version, since the final fade step
+   is taken at t slightly above 1. */
+pub fn ease_fade(t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/* Fade state between the currently applied setting and a moving target. */
+#[derive(Debug, Clone)]
+pub struct Fade {
+    length: i32,
+    time: i32,
+    start: ColorSetting,
+    current: ColorSetting,
+    prev_target: ColorSetting,
+    thresholds: FadeThresholds,
+    curve: FadeCurve,
+    in_mireds: bool,
+    fades_started: u64,
+}
+
+impl Fade {
+    /* Create fade state with `initial` as the currently applied setting. */
+    pub fn new(initial: ColorSetting) -> Self {
+        Self::with_thresholds(initial, FadeThresholds::default())
+    }
+
+    /* Create fade state that fades changes larger than `thresholds`. */
+    pub fn with_thresholds(initial: ColorSetting, thresholds: FadeThresholds) -> Self {
+        Fade {
+            length: 0,
+            time: 0,
+            start: initial,
+            current: initial,
+            prev_target: initial,
+            thresholds,
+            curve: FadeCurve::default(),
+            in_mireds: false,
+            fades_started: 0,
+        }
+    }
+
+    /* Ease future fade steps with `curve`. */
+    pub fn set_curve(&mut self, curve: FadeCurve) {
+        self.curve = curve;
+    }
+
+    /* Interpolate the temperature of future fade steps in mireds. */
+    pub fn set_interpolate_in_mireds(&mut self, in_mireds: bool) {
+        self.in_mireds = in_mireds;
+    }
+
+    /* Return true while a fade is in progress. */
+    pub fn is_active(&self) -> bool {
+        self.length != 0
+    }
+
+    /* Number of fades started so far, including restarted ones. */
+    pub fn fades_started(&self) -> u64 {
+        self.fades_started
+    }
+
+    /* The setting that should currently be applied. */
+    pub fn current(&self) -> ColorSetting {
+        self.current
+    }
+
+    /* Apply `target` immediately, cancelling any ongoing fade. */
+    pub fn jump(&mut self, target: &ColorSetting) -> ColorSetting {
+        self.length = 0;
+        self.time = 0;
+        self.start = *target;
+        self.current = *target;
+        self.prev_target = *target;
+        self.current
+    }
+
+    /* Advance one step towards `target` and return the setting to apply.
+       A fade is started if the difference is too big to apply instantly,
+       or for any difference at all when `force` is set (e.g. on a manual
+       toggle). A forced update restarts from the current setting, so a
+       fade under way is redirected to the new target, and one whose new
+       target is already reached stops. */
+    pub fn update(&mut self, target: &ColorSetting, force: bool) -> ColorSetting {
+        if force && self.current == *target && self.length != 0 {
+            log::debug!("Fade target already reached, stopping fade");
+            self.length = 0;
+            self.time = 0;
+        }
+
+        if (force && self.current != *target)
+            || (self.length == 0
+                && color_setting_diff_is_major(&self.current, target, &self.thresholds))
+            || (self.length != 0
+                && color_setting_diff_is_major(target, &self.prev_target, &self.thresholds))
+        {
+            log::debug!("Starting fade: {} steps", FADE_LENGTH);
+            self.length = FADE_LENGTH;
+            self.time = 0;
+            self.start = self.current;
+            self.fades_started += 1;
+        }
+
+        if self.length != 0 {
+            self.time += 1;
+            let frac = self.time as f64 / self.length as f64;
+            let alpha = self.curve.apply(frac);
+
+            interpolate_color_settings_with(
+                &self.start,
+                target,
+                alpha,
+                self.in_mireds,
+                &mut self.current,
+            );
+            log::trace!("Fade progress: {}/{} (alpha: {:.3})", self.time, self.length, alpha);
+
+            if self.time > self.length {
+                log::debug!("Fade complete");
+                self.time = 0;
+                self.length = 0;
+            }
+        } else {
+            self.current = *target;
+        }
+
+        self.prev_target = *target;
+        self.current
+    }
+}
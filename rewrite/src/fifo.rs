@@ -0,0 +1,187 @@
+/// Opt-in control FIFO for simple shell scripting
+/// Commands are written one per line, e.g.
+/// `echo disable > $XDG_RUNTIME_DIR/redshift.fifo`
+
+use crate::types::{resolve_temp, TemperatureBounds};
+use log::{debug, warn};
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+/// File name of the control FIFO in the runtime directory
+pub const FIFO_NAME: &str = "redshift.fifo";
+
+/// Longest command line kept while waiting for its newline
+const MAX_LINE_LENGTH: usize = 256;
+
+/// Commands understood by the control FIFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FifoCommand {
+    /// Switch between enabled and disabled, like SIGUSR1
+    Toggle,
+    /// Resume following the schedule
+    Enable,
+    /// Return to neutral until enabled again
+    Disable,
+    /// Re-read the configuration file
+    Reload,
+    /// Hold a fixed temperature until enabled or disabled
+    Temperature(i32),
+}
+
+impl FifoCommand {
+    /// Parse a command line such as "disable" or "temp 3000", resolving
+    /// the temperature against `bounds`
+    pub fn parse(line: &str, bounds: &TemperatureBounds) -> Result<Self, String> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        match parts.as_slice() {
+            ["toggle"] => Ok(FifoCommand::Toggle),
+            ["enable"] => Ok(FifoCommand::Enable),
+            ["disable"] => Ok(FifoCommand::Disable),
+            ["reload"] => Ok(FifoCommand::Reload),
+            ["temp", temperature] => {
+                let temperature = bounds.resolve(resolve_temp(temperature)?)?;
+                Ok(FifoCommand::Temperature(temperature))
+            }
+            _ => Err(format!("Unknown command: {}", line.trim())),
+        }
+    }
+}
+
+/// Default path of the control FIFO
+pub fn fifo_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(FIFO_NAME)
+}
+
+/// Parse the complete lines in `pending`, leaving a trailing partial line
+/// for the next read. Empty lines are skipped and invalid ones logged.
+pub fn drain_commands(pending: &mut String, bounds: &TemperatureBounds) -> Vec<FifoCommand> {
+    let complete = match pending.rfind('\n') {
+        Some(end) => pending.drain(..=end).collect::<String>(),
+        None => return Vec::new(),
+    };
+
+    complete
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match FifoCommand::parse(line, bounds) {
+            Ok(command) => Some(command),
+            Err(e) => {
+                warn!("Control FIFO: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reading end of the control FIFO, polled from the main loop
+pub struct ControlFifo {
+    file: File,
+    path: PathBuf,
+    pending: String,
+    /// Skipping the rest of a line that grew past `MAX_LINE_LENGTH`
+    discarding: bool,
+}
+
+impl ControlFifo {
+    /// Create the FIFO at `path` and open it without blocking.
+    /// A FIFO left by a crashed instance is replaced, but one another
+    /// running instance is reading from is not.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_fifo() {
+                return Err(format!("{} exists and is not a FIFO", path.display()));
+            }
+            /* Opening for writing without blocking only succeeds if
+               someone has the FIFO open for reading */
+            if OpenOptions::new()
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(path)
+                .is_ok()
+            {
+                return Err(format!("Another instance is reading {}", path.display()));
+            }
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove stale FIFO: {}", e))?;
+        }
+
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| format!("Invalid FIFO path: {}", path.display()))?;
+        if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+            return Err(format!(
+                "Failed to create {}: {}",
+                path.display(),
+                io::Error::last_os_error()
+            ));
+        }
+
+        /* Holding a write end ourselves keeps reads from hitting end of
+           file each time a writer closes */
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+        debug!("Reading control commands from {}", path.display());
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            pending: String::new(),
+            discarding: false,
+        })
+    }
+
+    /// Read whatever has been written since the last poll and return the
+    /// complete commands, with temperatures resolved against `bounds`
+    pub fn poll(&mut self, bounds: &TemperatureBounds) -> Vec<FifoCommand> {
+        let mut buf = [0u8; 512];
+
+        loop {
+            match self.file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => self.pending.push_str(&String::from_utf8_lossy(&buf[..n])),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    warn!("Failed to read control FIFO: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if self.discarding {
+            match self.pending.find('\n') {
+                Some(end) => {
+                    self.pending.drain(..=end);
+                    self.discarding = false;
+                }
+                None => self.pending.clear(),
+            }
+        }
+
+        let commands = drain_commands(&mut self.pending, bounds);
+
+        /* Drop a line that never ends rather than buffer it forever */
+        if self.pending.len() > MAX_LINE_LENGTH {
+            warn!("Control FIFO: command line too long, discarding it");
+            self.pending.clear();
+            self.discarding = true;
+        }
+        commands
+    }
+}
+
+impl Drop for ControlFifo {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.path).ok();
+    }
+}
@@ -1,24 +1,34 @@
 /// Gamma adjustment methods
 /// Ported from legacy/src/gamma-*.c
 
+use crate::error::{Error, Result};
 use crate::types::ColorSetting;
+use log::{debug, info, warn};
 use std::fmt;
 
 /// Trait for gamma adjustment methods
-pub trait GammaMethod {
+pub trait GammaMethod: Send {
     /// Initialize the method with optional configuration
-    fn init(&mut self) -> Result<(), String>;
+    fn init(&mut self) -> Result<()>;
 
     /// Start the method (allocate resources, establish connections)
-    fn start(&mut self) -> Result<(), String>;
+    fn start(&mut self) -> Result<()>;
 
     /// Set a color temperature adjustment
-    fn set_temperature(&mut self, setting: &ColorSetting, preserve: bool)
-        -> Result<(), String>;
+    fn set_temperature(&mut self, setting: &ColorSetting, preserve: bool) -> Result<()>;
 
     /// Restore the display to original state
     fn restore(&mut self);
 
+    /// Whether dropping the method restores the display, for methods that
+    /// do so at all. Turned off once the exit policy has decided what is
+    /// left on the screen, so dropping the method does not undo it.
+    fn set_restore_on_drop(&mut self, _restore: bool) {}
+
+    /// Forget what was last applied, so the next `set_temperature` sends
+    /// the ramps again even if the setting is unchanged
+    fn invalidate(&mut self) {}
+
     /// Get the method name
     fn name(&self) -> &str;
 
@@ -26,13 +36,126 @@ pub trait GammaMethod {
     fn print_help(&self);
 }
 
+/// Resolve requested output names (e.g. "DP-2") against the outputs a
+/// gamma method discovered, returning the indices of the matches.
+/// Unknown names produce an error listing the discovered outputs.
+pub fn match_output_names(
+    requested: &[String],
+    discovered: &[String],
+) -> std::result::Result<Vec<usize>, String> {
+    let mut indices = Vec::new();
+
+    for name in requested {
+        let index = discovered
+            .iter()
+            .position(|output| output == name)
+            .ok_or_else(|| {
+                format!(
+                    "Output {} not found. Available outputs: {}",
+                    name,
+                    if discovered.is_empty() {
+                        "(none)".to_string()
+                    } else {
+                        discovered.join(", ")
+                    }
+                )
+            })?;
+
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+
+    Ok(indices)
+}
+
+/// Set every method in `methods` to neutral (6500K), moving on to the next
+/// when one fails to initialize, start or apply. The methods are dropped
+/// without restoring, so the reset stays on the screen.
+/// Returns each method's name with its outcome, in order.
+pub fn reset_all(methods: Vec<Box<dyn GammaMethod>>) -> Vec<(String, Result<()>)> {
+    methods
+        .into_iter()
+        .map(|mut method| {
+            debug!("Resetting gamma method: {}", method.name());
+            let result = method
+                .init()
+                .and_then(|_| method.start())
+                .and_then(|_| method.set_temperature(&ColorSetting::default(), false));
+            method.set_restore_on_drop(false);
+            (method.name().to_string(), result)
+        })
+        .collect()
+}
+
+/// A method picked by `detect_method`, with the outcome of starting it
+pub struct DetectedMethod {
+    pub method: Box<dyn GammaMethod>,
+    /// An error only when no candidate started
+    pub started: Result<()>,
+}
+
+/// Initialize and start the first of `candidates` that does both, trying
+/// the one named `remembered` before the others so a method that worked
+/// last time is not held up by probing ones that did not. A remembered
+/// method that no longer starts is passed over like any other.
+/// If none starts, the first that initialized is returned with its start
+/// error, to be handled like a selected method that failed to start.
+/// Returns the last error if none of them initializes.
+pub fn detect_method(
+    mut candidates: Vec<Box<dyn GammaMethod>>,
+    remembered: Option<&str>,
+) -> Result<DetectedMethod> {
+    if let Some(index) = remembered
+        .and_then(|name| candidates.iter().position(|method| method.name() == name))
+    {
+        let method = candidates.remove(index);
+        candidates.insert(0, method);
+    }
+
+    let mut last_error = Error::Gamma("No gamma method available".to_string());
+    let mut not_started: Option<DetectedMethod> = None;
+    for mut method in candidates {
+        info!("Initializing gamma method: {}", method.name());
+        if let Err(e) = method.init() {
+            debug!("Gamma method {} is unavailable: {}", method.name(), e);
+            last_error = e;
+            continue;
+        }
+        match method.start() {
+            Ok(()) => {
+                return Ok(DetectedMethod {
+                    method,
+                    started: Ok(()),
+                })
+            }
+            Err(e) => {
+                debug!("Gamma method {} failed to start: {}", method.name(), e);
+                not_started.get_or_insert(DetectedMethod {
+                    method,
+                    started: Err(e),
+                });
+            }
+        }
+    }
+    not_started.ok_or(last_error)
+}
+
 /// Dummy gamma method (no-op, for testing)
 /// Ported from legacy/src/gamma-dummy.c
-pub struct DummyGammaMethod {}
+pub struct DummyGammaMethod {
+    quiet: bool,
+}
 
 impl DummyGammaMethod {
     pub fn new() -> Self {
-        Self {}
+        Self { quiet: false }
+    }
+
+    /// Send the startup warning and applied temperatures to the log
+    /// instead of printing them to the terminal
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
     }
 }
 
@@ -43,14 +166,18 @@ impl Default for DummyGammaMethod {
 }
 
 impl GammaMethod for DummyGammaMethod {
-    fn init(&mut self) -> Result<(), String> {
+    fn init(&mut self) -> Result<()> {
         Ok(())
     }
 
-    fn start(&mut self) -> Result<(), String> {
-        eprintln!(
-            "WARNING: Using dummy gamma method! Display will not be affected by this gamma method."
-        );
+    fn start(&mut self) -> Result<()> {
+        if self.quiet {
+            debug!("Using dummy gamma method");
+        } else {
+            eprintln!(
+                "WARNING: Using dummy gamma method! Display will not be affected by this gamma method."
+            );
+        }
         Ok(())
     }
 
@@ -58,8 +185,13 @@ impl GammaMethod for DummyGammaMethod {
         &mut self,
         setting: &ColorSetting,
         _preserve: bool,
-    ) -> Result<(), String> {
-        println!("Temperature: {}", setting.temperature);
+    ) -> Result<()> {
+        setting.validate().map_err(Error::Gamma)?;
+        if self.quiet {
+            info!("Temperature: {}", setting.temperature);
+        } else {
+            println!("Temperature: {}", setting.temperature);
+        }
         Ok(())
     }
 
@@ -82,3 +214,69 @@ impl fmt::Display for DummyGammaMethod {
         write!(f, "Dummy")
     }
 }
+
+/// Fallback used when the selected method finds no output it can control.
+/// Unlike the dummy method it is not chosen by the user, so it warns once
+/// and logs the intended settings instead of printing every update.
+pub struct NullApplyGammaMethod {
+    last_setting: Option<ColorSetting>, // Only changes are logged
+}
+
+impl NullApplyGammaMethod {
+    pub fn new() -> Self {
+        Self { last_setting: None }
+    }
+}
+
+impl Default for NullApplyGammaMethod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GammaMethod for NullApplyGammaMethod {
+    fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        warn!("No controllable outputs, following the schedule without applying it (no hardware applied)");
+        Ok(())
+    }
+
+    fn set_temperature(
+        &mut self,
+        setting: &ColorSetting,
+        _preserve: bool,
+    ) -> Result<()> {
+        setting.validate().map_err(Error::Gamma)?;
+        if self.last_setting != Some(*setting) {
+            debug!(
+                "Intended {}K, brightness {:.2} (no hardware applied)",
+                setting.temperature, setting.brightness
+            );
+        }
+        self.last_setting = Some(*setting);
+        Ok(())
+    }
+
+    fn restore(&mut self) {
+        self.last_setting = None;
+    }
+
+    fn name(&self) -> &str {
+        "null"
+    }
+
+    fn print_help(&self) {
+        println!("Used automatically when no output can be adjusted. Logs the intended");
+        println!("settings without applying them.");
+        println!();
+    }
+}
+
+impl fmt::Display for NullApplyGammaMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Null")
+    }
+}
@@ -6,28 +6,57 @@
 use crate::gamma::GammaMethod;
 use crate::types::ColorSetting;
 
-/* Guard that restores gamma to neutral (6500K) on drop.
+/* What happens to the screen when the guard is dropped. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitPolicy {
+    /* Put back the ramps saved when the gamma method started */
+    Restore,
+    /* Leave the last applied setting on the screen */
+    Keep,
+    /* Apply the neutral setting (6500K) */
+    Neutral,
+}
+
+impl ExitPolicy {
+    /* Parse "restore", "keep" or "neutral". */
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "restore" => Ok(ExitPolicy::Restore),
+            "keep" => Ok(ExitPolicy::Keep),
+            "neutral" => Ok(ExitPolicy::Neutral),
+            _ => Err(format!(
+                "Invalid exit policy: {} (expected restore, keep or neutral)",
+                s.trim()
+            )),
+        }
+    }
+}
+
+/* Guard that applies an exit policy on drop.
  * This ensures cleanup happens on normal exit, panic, or signal. */
 pub struct GammaRestoreGuard<'a> {
     gamma_method: &'a mut dyn GammaMethod,
-    restore_on_drop: bool,
+    policy: ExitPolicy,
 }
 
 impl<'a> GammaRestoreGuard<'a> {
-    /* Create a new gamma restore guard.
-     * The gamma will be restored when this guard is dropped. */
-    pub fn new(gamma_method: &'a mut dyn GammaMethod) -> Self {
+    /* Create a guard that applies `policy` when dropped. */
+    pub fn with_policy(gamma_method: &'a mut dyn GammaMethod, policy: ExitPolicy) -> Self {
         GammaRestoreGuard {
             gamma_method,
-            restore_on_drop: true,
+            policy,
         }
     }
 
     /* Disable automatic restoration.
      * Call this if you want to keep the current gamma on exit. */
-    #[allow(dead_code)]
     pub fn disable_restore(&mut self) {
-        self.restore_on_drop = false;
+        self.policy = ExitPolicy::Keep;
+    }
+
+    /* The policy applied when the guard is dropped. */
+    pub fn policy(&self) -> ExitPolicy {
+        self.policy
     }
 
     /* Get mutable reference to the gamma method.
@@ -39,16 +68,18 @@ impl<'a> GammaRestoreGuard<'a> {
 
 impl<'a> Drop for GammaRestoreGuard<'a> {
     fn drop(&mut self) {
-        if self.restore_on_drop {
-            /* Restore to neutral temperature (6500K) */
-            let neutral = ColorSetting {
-                temperature: 6500,
-                brightness: 1.0,
-                gamma: [1.0, 1.0, 1.0],
-            };
-
-            /* Ignore errors during cleanup - we're likely shutting down anyway */
-            let _ = self.gamma_method.set_temperature(&neutral, false);
+        match self.policy {
+            ExitPolicy::Restore => self.gamma_method.restore(),
+            ExitPolicy::Keep => {}
+            ExitPolicy::Neutral => {
+                /* Ignore errors during cleanup - we're likely shutting down anyway */
+                let _ = self
+                    .gamma_method
+                    .set_temperature(&ColorSetting::default(), false);
+            }
         }
+        /* The policy decided what stays on the screen; the method must not
+           put its saved ramps back when it is dropped later */
+        self.gamma_method.set_restore_on_drop(false);
     }
 }
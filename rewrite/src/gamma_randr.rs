@@ -1,19 +1,35 @@
 /// X11 RandR gamma adjustment method
 /// Ported from legacy/src/gamma-randr.c
 
-use crate::colorramp::colorramp_fill;
-use crate::gamma::GammaMethod;
+use crate::colorramp::{
+    colorramp_fill_with_options, linear_ramp, RampOptions, MAX_COARSE_RAMP_SIZE,
+};
+use crate::edid::{match_edid_serials, parse_edid, EdidInfo, EDID_BLOCK_SIZE};
+use crate::error::Error;
+use crate::gamma::{match_output_names, GammaMethod};
+use crate::log_limit::warn_limited;
 use crate::types::ColorSetting;
 use log::{debug, info, trace, warn};
+use std::collections::HashMap;
 use std::fmt;
 use x11rb::connection::Connection;
+use x11rb::cookie::VoidCookie;
+use x11rb::errors::{ConnectionError, ReplyError};
 use x11rb::protocol::randr;
-use x11rb::protocol::xproto;
+use x11rb::protocol::xproto::{self, ConnectionExt as _};
 use x11rb::rust_connection::RustConnection;
 
 const RANDR_VERSION_MAJOR: u32 = 1;
 const RANDR_VERSION_MINOR: u32 = 3;
 
+/// How far the probe ramp is moved from the saved one, well past what
+/// rounding to the hardware's ramp depth could hide
+const PROBE_OFFSET: u16 = 0x0400;
+
+/// How far a read-back value may be from what was set; 8-bit ramps
+/// round to multiples of 0x0101
+const PROBE_TOLERANCE: u16 = 0x0101;
+
 /// State for a single CRTC
 struct CrtcState {
     crtc: randr::Crtc,
@@ -21,13 +37,233 @@ struct CrtcState {
     saved_ramps: Vec<u16>, // R, G, B ramps concatenated (3 * ramp_size)
 }
 
+/// Ramps last applied to each CRTC, used to skip redundant applies.
+/// Ramps are R, G and B concatenated, indexed like the CRTC list.
+#[derive(Debug, Default)]
+pub struct AppliedRamps {
+    ramps: Vec<Option<Vec<u16>>>,
+}
+
+impl AppliedRamps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `ramps` are what was last applied to CRTC `index`
+    pub fn is_applied(&self, index: usize, ramps: &[u16]) -> bool {
+        matches!(self.ramps.get(index), Some(Some(applied)) if applied.as_slice() == ramps)
+    }
+
+    /// Record `ramps` as applied to CRTC `index`
+    pub fn store(&mut self, index: usize, ramps: Vec<u16>) {
+        if self.ramps.len() <= index {
+            self.ramps.resize(index + 1, None);
+        }
+        self.ramps[index] = Some(ramps);
+    }
+
+    /// Forget all applied ramps, e.g. after restoring the originals
+    pub fn clear(&mut self) {
+        self.ramps.clear();
+    }
+}
+
+/// Why a gamma ramp request failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RampError {
+    /// The connection to the X server is broken, so no later request
+    /// can succeed either
+    ConnectionLost(String),
+    /// The server rejected this request, e.g. for a CRTC that went away
+    Request(String),
+}
+
+impl RampError {
+    /// Classify an error sending `request`
+    pub fn from_connection(request: &str, error: ConnectionError) -> Self {
+        RampError::ConnectionLost(format!("Failed to send {}: {}", request, error))
+    }
+
+    /// Classify an error in the reply to `request`
+    pub fn from_reply(request: &str, error: ReplyError) -> Self {
+        match error {
+            ReplyError::ConnectionError(e) => Self::from_connection(request, e),
+            ReplyError::X11Error(e) => {
+                RampError::Request(format!("RANDR {} returned error: {:?}", request, e))
+            }
+        }
+    }
+
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(self, RampError::ConnectionLost(_))
+    }
+}
+
+impl fmt::Display for RampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RampError::ConnectionLost(e) => write!(f, "Lost the connection to the X server: {}", e),
+            RampError::Request(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<RampError> for Error {
+    fn from(error: RampError) -> Self {
+        match error {
+            RampError::ConnectionLost(_) => Error::DisplayLost(error.to_string()),
+            RampError::Request(_) => Error::Gamma(error.to_string()),
+        }
+    }
+}
+
+/// Connection that gamma ramp requests are sent over, abstracted so the
+/// batching can be tested without an X server
+pub trait RampConnection {
+    /// Handle for the reply to a queued request
+    type Cookie<'a>
+    where
+        Self: 'a;
+
+    /// Queue a request setting `ramps` (R, G and B concatenated) on `crtc`
+    fn send_ramps(&self, crtc: randr::Crtc, ramps: &[u16])
+        -> Result<Self::Cookie<'_>, RampError>;
+
+    /// Wait for the reply to a queued request
+    fn check_ramps<'a>(&'a self, cookie: Self::Cookie<'a>) -> Result<(), RampError>;
+
+    /// Read the ramps currently set on `crtc`, R, G and B concatenated
+    fn read_ramps(&self, crtc: randr::Crtc) -> Result<Vec<u16>, RampError>;
+}
+
+impl RampConnection for RustConnection {
+    type Cookie<'a> = VoidCookie<'a, RustConnection>;
+
+    fn send_ramps(
+        &self,
+        crtc: randr::Crtc,
+        ramps: &[u16],
+    ) -> Result<Self::Cookie<'_>, RampError> {
+        let ramp_size = ramps.len() / 3;
+        randr::set_crtc_gamma(
+            self,
+            crtc,
+            &ramps[0..ramp_size],
+            &ramps[ramp_size..2 * ramp_size],
+            &ramps[2 * ramp_size..3 * ramp_size],
+        )
+        .map_err(|e| RampError::from_connection("Set CRTC Gamma", e))
+    }
+
+    fn check_ramps<'a>(&'a self, cookie: Self::Cookie<'a>) -> Result<(), RampError> {
+        cookie
+            .check()
+            .map_err(|e| RampError::from_reply("Set CRTC Gamma", e))
+    }
+
+    fn read_ramps(&self, crtc: randr::Crtc) -> Result<Vec<u16>, RampError> {
+        let reply = randr::get_crtc_gamma(self, crtc)
+            .map_err(|e| RampError::from_connection("Get CRTC Gamma", e))?
+            .reply()
+            .map_err(|e| RampError::from_reply("Get CRTC Gamma", e))?;
+        Ok([reply.red, reply.green, reply.blue].concat())
+    }
+}
+
+/// Queue the ramps for every CRTC in `requests` before checking any reply,
+/// so a multi-monitor update costs one round trip instead of one per CRTC.
+/// Returns the result of each request, in order.
+pub fn send_ramps_batched<C: RampConnection>(
+    conn: &C,
+    requests: &[(randr::Crtc, &[u16])],
+) -> Vec<Result<(), RampError>> {
+    let cookies: Vec<_> = requests
+        .iter()
+        .map(|(crtc, ramps)| conn.send_ramps(*crtc, ramps))
+        .collect();
+
+    cookies
+        .into_iter()
+        .map(|cookie| cookie.and_then(|cookie| conn.check_ramps(cookie)))
+        .collect()
+}
+
+/// Put the ramps saved at start back on each CRTC, warning about any that
+/// fail since this runs on the way out
+pub fn restore_saved_ramps<C: RampConnection>(conn: &C, saved: &[(randr::Crtc, &[u16])]) {
+    for result in send_ramps_batched(conn, saved) {
+        if let Err(e) = result {
+            warn_limited(&format!("Failed to restore CRTC gamma: {}", e));
+        }
+    }
+}
+
+/// Ramps a little off `saved`, for checking that a CRTC applies ramps
+pub fn probe_ramps(saved: &[u16]) -> Vec<u16> {
+    saved
+        .iter()
+        .map(|&value| value.checked_sub(PROBE_OFFSET).unwrap_or_else(|| value + PROBE_OFFSET))
+        .collect()
+}
+
+/// Check that `crtc` actually takes ramps set on it. Some outputs, e.g.
+/// offload-rendered outputs on hybrid GPU laptops, report a gamma size but
+/// silently drop set requests. Sets a probe a little off `saved`, reads it
+/// back and puts `saved` back.
+pub fn verify_gamma_support<C: RampConnection>(
+    conn: &C,
+    crtc: randr::Crtc,
+    saved: &[u16],
+) -> Result<bool, RampError> {
+    let probe = probe_ramps(saved);
+    conn.check_ramps(conn.send_ramps(crtc, &probe)?)?;
+    let read_back = conn.read_ramps(crtc);
+    conn.check_ramps(conn.send_ramps(crtc, saved)?)?;
+
+    let read_back = read_back?;
+    Ok(read_back.len() == probe.len()
+        && read_back
+            .iter()
+            .zip(&probe)
+            .all(|(&got, &want)| got.abs_diff(want) <= PROBE_TOLERANCE))
+}
+
+/// Whether `crtc` applies ramps, probing it with `verify_gamma_support`
+/// only the first time it is seen. The probe briefly tints the screen, so
+/// its result is kept in `probed` for later starts, e.g. after
+/// reconnecting. A failed probe counts as not applying ramps.
+pub fn crtc_applies_ramps<C: RampConnection>(
+    conn: &C,
+    crtc: randr::Crtc,
+    saved: &[u16],
+    probed: &mut HashMap<randr::Crtc, bool>,
+) -> bool {
+    *probed
+        .entry(crtc)
+        .or_insert_with(|| match verify_gamma_support(conn, crtc, saved) {
+            Ok(applies) => applies,
+            Err(e) => {
+                debug!("Gamma probe of CRTC {} failed: {}", crtc, e);
+                false
+            }
+        })
+}
+
 /// X11 RandR gamma adjustment method
 pub struct RandrGammaMethod {
     conn: Option<RustConnection>,
     screen_num: Option<i32>,
     preferred_screen: usize,
     crtc_filter: Vec<usize>, // If non-empty, only adjust these CRTC indices
+    output_filter: Vec<String>, // If non-empty, only adjust CRTCs driving these outputs
+    edid_filter: Vec<String>, // If non-empty, also adjust CRTCs driving monitors with these EDID serials
     crtcs: Vec<CrtcState>,
+    options: RampOptions,
+    applied_ramps: AppliedRamps,
+    last_apply_changed: bool,
+    probe_gamma: bool,
+    probed: HashMap<randr::Crtc, bool>, // Probe results, kept across restarts
+    restore_on_drop: bool,
 }
 
 impl RandrGammaMethod {
@@ -37,7 +273,15 @@ impl RandrGammaMethod {
             screen_num: None,
             preferred_screen: 0,
             crtc_filter: Vec::new(),
+            output_filter: Vec::new(),
+            edid_filter: Vec::new(),
             crtcs: Vec::new(),
+            options: RampOptions::default(),
+            applied_ramps: AppliedRamps::new(),
+            last_apply_changed: false,
+            probe_gamma: false,
+            probed: HashMap::new(),
+            restore_on_drop: true,
         }
     }
 
@@ -51,6 +295,47 @@ impl RandrGammaMethod {
         self.crtc_filter = crtc_indices;
     }
 
+    /// Set which outputs to adjust by name, e.g. "DP-2" (empty = all).
+    /// Resolved to CRTCs when the method is started.
+    pub fn set_outputs(&mut self, outputs: Vec<String>) {
+        self.output_filter = outputs;
+    }
+
+    /// Set which monitors to adjust by EDID serial, which unlike the
+    /// output name stays the same across ports. Combined with
+    /// `set_outputs`, outputs matching either are adjusted.
+    pub fn set_edid_serials(&mut self, serials: Vec<String>) {
+        self.edid_filter = serials;
+    }
+
+    /// Set how ramps are built from the color setting
+    pub fn set_ramp_options(&mut self, options: RampOptions) {
+        self.options = options;
+    }
+
+    /// Skip CRTCs that accept ramps without applying them, found by
+    /// probing each CRTC once (see `crtc_applies_ramps`)
+    pub fn set_probe_gamma(&mut self, enabled: bool) {
+        self.probe_gamma = enabled;
+    }
+
+    /// Whether dropping the method puts back the ramps saved by `start`.
+    /// For library users checking the exit policy took effect.
+    #[allow(dead_code)]
+    pub fn restores_on_drop(&self) -> bool {
+        self.restore_on_drop
+    }
+
+    /// Whether the last `set_temperature` changed any ramp. Unchanged
+    /// ramps are not sent to the X server again, so a ramp overwritten by
+    /// another program is only corrected once the setting changes or the
+    /// method is invalidated. For verification tooling using the library;
+    /// the binary only holds the method as a `dyn GammaMethod`.
+    #[allow(dead_code)]
+    pub fn last_apply_changed(&self) -> bool {
+        self.last_apply_changed
+    }
+
     fn get_screen_root(&self) -> Result<xproto::Window, String> {
         let conn = self.conn.as_ref().ok_or("Not connected to X server")?;
 
@@ -64,13 +349,76 @@ impl RandrGammaMethod {
         Ok(screen.root)
     }
 
-    fn set_temperature_for_crtc(
+    /// Restrict adjustment to the CRTCs driving the outputs in
+    /// `output_filter` or the monitors in `edid_filter`
+    fn apply_output_filter(&mut self, config_timestamp: xproto::Timestamp) -> Result<(), String> {
+        let conn = self.conn.as_ref().ok_or("Not initialized")?;
+
+        let edid_atom = if self.edid_filter.is_empty() {
+            None
+        } else {
+            Some(
+                conn.intern_atom(false, b"EDID")
+                    .map_err(|e| format!("Failed to intern EDID atom: {}", e))?
+                    .reply()
+                    .map_err(|e| format!("Intern Atom returned error: {}", e))?
+                    .atom,
+            )
+        };
+
+        /* Collect output names and EDIDs along with the CRTC driving each */
+        let mut names = Vec::new();
+        let mut edids = Vec::new();
+        let mut crtc_indices = Vec::new();
+        for (idx, crtc_state) in self.crtcs.iter().enumerate() {
+            let crtc_info = randr::get_crtc_info(conn, crtc_state.crtc, config_timestamp)
+                .map_err(|e| format!("Failed to get CRTC info: {}", e))?
+                .reply()
+                .map_err(|e| format!("RANDR Get CRTC Info returned error: {}", e))?;
+
+            for output in crtc_info.outputs {
+                let output_info = randr::get_output_info(conn, output, config_timestamp)
+                    .map_err(|e| format!("Failed to get output info: {}", e))?
+                    .reply()
+                    .map_err(|e| format!("RANDR Get Output Info returned error: {}", e))?;
+
+                let name = String::from_utf8_lossy(&output_info.name).into_owned();
+                debug!("CRTC {} drives output {}", idx, name);
+                let edid = edid_atom.and_then(|atom| read_edid(conn, output, atom, &name));
+                names.push(name);
+                edids.push(edid);
+                crtc_indices.push(idx);
+            }
+        }
+
+        let mut matched = match_output_names(&self.output_filter, &names)?;
+        matched.extend(match_edid_serials(&self.edid_filter, &edids)?);
+
+        let mut filter = Vec::new();
+        let mut adjusted = Vec::new();
+        for index in matched {
+            if !filter.contains(&crtc_indices[index]) {
+                filter.push(crtc_indices[index]);
+            }
+            if !adjusted.contains(&names[index]) {
+                adjusted.push(names[index].clone());
+            }
+        }
+
+        info!("Adjusting outputs: {}", adjusted.join(", "));
+        self.crtc_filter = filter;
+        Ok(())
+    }
+
+    /// Compute the ramps for the CRTC at `idx`, R, G and B concatenated.
+    /// Returns None if they equal the ramps applied last time.
+    fn ramps_for_crtc(
         &self,
-        crtc_state: &CrtcState,
+        idx: usize,
         setting: &ColorSetting,
         preserve: bool,
-    ) -> Result<(), String> {
-        let conn = self.conn.as_ref().ok_or("Not connected to X server")?;
+    ) -> Option<Vec<u16>> {
+        let crtc_state = &self.crtcs[idx];
         let ramp_size = crtc_state.ramp_size as usize;
 
         trace!(
@@ -94,16 +442,20 @@ impl RandrGammaMethod {
         } else {
             /* Initialize to linear (pure state) */
             trace!("Starting with linear gamma ramps");
-            for i in 0..ramp_size {
-                let value = ((i as f64 / ramp_size as f64) * 65536.0) as u16;
-                gamma_r[i] = value;
-                gamma_g[i] = value;
-                gamma_b[i] = value;
-            }
+            let linear = linear_ramp(ramp_size);
+            gamma_r.copy_from_slice(&linear);
+            gamma_g.copy_from_slice(&linear);
+            gamma_b.copy_from_slice(&linear);
         }
 
         /* Apply color temperature adjustment */
-        colorramp_fill(&mut gamma_r, &mut gamma_g, &mut gamma_b, setting);
+        colorramp_fill_with_options(
+            &mut gamma_r,
+            &mut gamma_g,
+            &mut gamma_b,
+            setting,
+            &self.options,
+        );
 
         trace!("Gamma ramp sample (first 5 values): R=[{}, {}, {}, {}, {}]",
             gamma_r.get(0).unwrap_or(&0),
@@ -113,30 +465,17 @@ impl RandrGammaMethod {
             gamma_r.get(4).unwrap_or(&0),
         );
 
-        /* Set gamma ramps */
-        randr::set_crtc_gamma(
-            conn,
-            crtc_state.crtc,
-            &gamma_r,
-            &gamma_g,
-            &gamma_b,
-        )
-        .map_err(|e| format!("Failed to set CRTC gamma: {}", e))?
-        .check()
-        .map_err(|e| format!("RANDR Set CRTC Gamma returned error: {:?}", e))?;
-
-        Ok(())
-    }
-}
+        let ramps = [gamma_r, gamma_g, gamma_b].concat();
+        if self.applied_ramps.is_applied(idx, &ramps) {
+            trace!("CRTC {}: ramps unchanged, skipping", idx);
+            return None;
+        }
 
-impl Default for RandrGammaMethod {
-    fn default() -> Self {
-        Self::new()
+        Some(ramps)
     }
-}
 
-impl GammaMethod for RandrGammaMethod {
-    fn init(&mut self) -> Result<(), String> {
+    /// Connect to the X server and check the RandR version
+    fn connect(&mut self) -> Result<(), String> {
         debug!("Initializing RandR gamma method");
 
         /* Open X server connection */
@@ -167,12 +506,17 @@ impl GammaMethod for RandrGammaMethod {
         Ok(())
     }
 
-    fn start(&mut self) -> Result<(), String> {
+    /// Save the gamma ramps of every usable CRTC
+    fn save_crtcs(&mut self) -> Result<(), String> {
         let conn = self.conn.as_ref().ok_or("Not initialized")?;
         let root = self.get_screen_root()?;
 
         debug!("Getting screen resources");
 
+        /* Start afresh when called again after reconnecting */
+        self.crtcs.clear();
+        self.applied_ramps.clear();
+
         /* Get screen resources (list of CRTCs) */
         let res_reply = randr::get_screen_resources_current(conn, root)
             .map_err(|e| format!("Failed to get screen resources: {}", e))?
@@ -198,6 +542,12 @@ impl GammaMethod for RandrGammaMethod {
             }
 
             debug!("CRTC {}: ramp_size={}", idx, ramp_size);
+            if ramp_size as usize <= MAX_COARSE_RAMP_SIZE {
+                warn!(
+                    "CRTC {} has only {} gamma ramp entries, warm shifts will be coarse",
+                    idx, ramp_size
+                );
+            }
 
             /* Get current gamma ramps */
             let gamma_get_reply = randr::get_crtc_gamma(conn, *crtc)
@@ -213,6 +563,14 @@ impl GammaMethod for RandrGammaMethod {
 
             trace!("CRTC {}: saved {} gamma ramp values", idx, saved_ramps.len());
 
+            /* Skip CRTCs that accept ramps without applying them */
+            if self.probe_gamma
+                && !crtc_applies_ramps(conn, *crtc, &saved_ramps, &mut self.probed)
+            {
+                debug!("CRTC {} does not apply gamma ramps, skipping", idx);
+                continue;
+            }
+
             self.crtcs.push(CrtcState {
                 crtc: *crtc,
                 ramp_size,
@@ -226,55 +584,128 @@ impl GammaMethod for RandrGammaMethod {
 
         info!("Successfully initialized {} CRTCs for gamma adjustment", self.crtcs.len());
 
+        if !self.output_filter.is_empty() || !self.edid_filter.is_empty() {
+            self.apply_output_filter(res_reply.config_timestamp)?;
+        }
+
         Ok(())
     }
 
-    fn set_temperature(&mut self, setting: &ColorSetting, preserve: bool) -> Result<(), String> {
+    /// Apply `setting` to the selected CRTCs
+    fn apply_setting(&mut self, setting: &ColorSetting, preserve: bool) -> Result<(), Error> {
+        setting.validate().map_err(Error::Gamma)?;
+
         /* If no CRTC filter is set, adjust all CRTCs */
-        if self.crtc_filter.is_empty() {
-            for crtc_state in &self.crtcs {
-                self.set_temperature_for_crtc(crtc_state, setting, preserve)?;
-            }
+        let crtc_indices: Vec<usize> = if self.crtc_filter.is_empty() {
+            (0..self.crtcs.len()).collect()
         } else {
-            /* Only adjust specified CRTCs */
-            for &crtc_idx in &self.crtc_filter {
-                if crtc_idx >= self.crtcs.len() {
-                    return Err(format!(
-                        "CRTC {} does not exist. Valid CRTCs are [0-{}]",
-                        crtc_idx,
-                        self.crtcs.len() - 1
-                    ));
+            self.crtc_filter.clone()
+        };
+
+        let mut pending = Vec::new();
+        for crtc_idx in crtc_indices {
+            if crtc_idx >= self.crtcs.len() {
+                return Err(Error::Gamma(format!(
+                    "CRTC {} does not exist. Valid CRTCs are [0-{}]",
+                    crtc_idx,
+                    self.crtcs.len() - 1
+                )));
+            }
+            if let Some(ramps) = self.ramps_for_crtc(crtc_idx, setting, preserve) {
+                pending.push((crtc_idx, ramps));
+            }
+        }
+
+        self.last_apply_changed = !pending.is_empty();
+        if pending.is_empty() {
+            trace!("Gamma ramps unchanged, nothing sent");
+            return Ok(());
+        }
+
+        /* Send every changed CRTC before waiting for any reply, so all
+           outputs change together */
+        let conn = self
+            .conn
+            .as_ref()
+            .ok_or_else(|| Error::Gamma("Not connected to X server".to_string()))?;
+        let requests: Vec<(randr::Crtc, &[u16])> = pending
+            .iter()
+            .map(|(idx, ramps)| (self.crtcs[*idx].crtc, ramps.as_slice()))
+            .collect();
+        let results = send_ramps_batched(conn, &requests);
+
+        /* A lost connection outweighs any CRTC rejecting its ramps */
+        let mut first_error: Option<RampError> = None;
+        for ((idx, ramps), result) in pending.into_iter().zip(results) {
+            match result {
+                Ok(()) => self.applied_ramps.store(idx, ramps),
+                Err(e) => {
+                    if first_error.as_ref().is_none_or(|first| {
+                        e.is_connection_lost() && !first.is_connection_lost()
+                    }) {
+                        first_error = Some(e);
+                    }
                 }
-                self.set_temperature_for_crtc(&self.crtcs[crtc_idx], setting, preserve)?;
             }
         }
 
-        Ok(())
+        match first_error {
+            Some(e) => {
+                /* Nothing can be restored over a broken connection */
+                if e.is_connection_lost() {
+                    self.conn = None;
+                }
+                Err(e.into())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for RandrGammaMethod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GammaMethod for RandrGammaMethod {
+    fn init(&mut self) -> crate::error::Result<()> {
+        self.connect().map_err(Error::Gamma)
+    }
+
+    fn start(&mut self) -> crate::error::Result<()> {
+        self.save_crtcs().map_err(Error::Gamma)
+    }
+
+    fn set_temperature(
+        &mut self,
+        setting: &ColorSetting,
+        preserve: bool,
+    ) -> crate::error::Result<()> {
+        self.apply_setting(setting, preserve)
+    }
+
+    fn invalidate(&mut self) {
+        self.applied_ramps.clear();
     }
 
     fn restore(&mut self) {
+        self.applied_ramps.clear();
         if let Some(conn) = &self.conn {
             /* Restore original gamma ramps for all CRTCs */
-            for crtc_state in &self.crtcs {
-                let ramp_size = crtc_state.ramp_size as usize;
-                let gamma_r = &crtc_state.saved_ramps[0..ramp_size];
-                let gamma_g = &crtc_state.saved_ramps[ramp_size..2 * ramp_size];
-                let gamma_b = &crtc_state.saved_ramps[2 * ramp_size..3 * ramp_size];
-
-                match randr::set_crtc_gamma(conn, crtc_state.crtc, gamma_r, gamma_g, gamma_b) {
-                    Ok(cookie) => {
-                        if let Err(e) = cookie.check() {
-                            eprintln!("Warning: Failed to restore CRTC gamma: {:?}", e);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Warning: Failed to send restore CRTC gamma request: {:?}", e);
-                    }
-                }
-            }
+            let saved: Vec<(randr::Crtc, &[u16])> = self
+                .crtcs
+                .iter()
+                .map(|crtc_state| (crtc_state.crtc, crtc_state.saved_ramps.as_slice()))
+                .collect();
+            restore_saved_ramps(conn, &saved);
         }
     }
 
+    fn set_restore_on_drop(&mut self, restore: bool) {
+        self.restore_on_drop = restore;
+    }
+
     fn name(&self) -> &str {
         "randr"
     }
@@ -296,6 +727,48 @@ impl fmt::Display for RandrGammaMethod {
 
 impl Drop for RandrGammaMethod {
     fn drop(&mut self) {
-        self.restore();
+        if self.restore_on_drop {
+            self.restore();
+        }
+    }
+}
+
+/// Read and parse the EDID of `output`, None if it has no readable EDID
+fn read_edid(
+    conn: &RustConnection,
+    output: randr::Output,
+    edid_atom: xproto::Atom,
+    name: &str,
+) -> Option<EdidInfo> {
+    /* Only the base block is needed; the length is in 4-byte units */
+    let reply = randr::get_output_property(
+        conn,
+        output,
+        edid_atom,
+        xproto::AtomEnum::ANY,
+        0,
+        (EDID_BLOCK_SIZE / 4) as u32,
+        false,
+        false,
+    )
+    .ok()?
+    .reply()
+    .ok()?;
+
+    match parse_edid(&reply.data) {
+        Ok(edid) => {
+            debug!(
+                "Output {} is {} {:04x} with serial {}",
+                name,
+                edid.manufacturer,
+                edid.product_code,
+                edid.serial()
+            );
+            Some(edid)
+        }
+        Err(e) => {
+            debug!("Output {} has no usable EDID: {}", name, e);
+            None
+        }
     }
 }
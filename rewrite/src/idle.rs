@@ -0,0 +1,104 @@
+/// User idle detection for dimming while idle
+/// Queries the X11 MIT-SCREEN-SAVER extension for time since last input
+
+use crate::types::{ColorSetting, MIN_BRIGHTNESS};
+use log::{debug, info};
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::screensaver::ConnectionExt as _;
+use x11rb::protocol::xproto;
+use x11rb::rust_connection::RustConnection;
+
+/// Extra brightness factor applied on top of the scheduled value while idle
+pub const IDLE_DIM_FACTOR: f32 = 0.7;
+
+/// Source of the time since the user last provided input
+pub trait IdleSource {
+    fn idle_time(&mut self) -> Result<Duration, String>;
+}
+
+/// Idle source backed by the X11 screensaver extension
+pub struct X11IdleSource {
+    conn: RustConnection,
+    root: xproto::Window,
+}
+
+impl X11IdleSource {
+    /// Connect to the X server and check that the screensaver extension
+    /// is available
+    pub fn connect() -> Result<Self, String> {
+        let (conn, screen_num) = RustConnection::connect(None)
+            .map_err(|e| format!("Failed to connect to X server: {}", e))?;
+
+        let root = conn
+            .setup()
+            .roots
+            .get(screen_num)
+            .ok_or_else(|| format!("Screen {} could not be found", screen_num))?
+            .root;
+
+        conn.screensaver_query_version(1, 1)
+            .map_err(|e| format!("Failed to query screensaver version: {}", e))?
+            .reply()
+            .map_err(|e| format!("Screensaver extension not available: {}", e))?;
+
+        Ok(Self { conn, root })
+    }
+}
+
+impl IdleSource for X11IdleSource {
+    fn idle_time(&mut self) -> Result<Duration, String> {
+        let reply = self
+            .conn
+            .screensaver_query_info(self.root)
+            .map_err(|e| format!("Failed to query screensaver info: {}", e))?
+            .reply()
+            .map_err(|e| format!("Screensaver Query Info returned error: {}", e))?;
+
+        Ok(Duration::from_millis(reply.ms_since_user_input as u64))
+    }
+}
+
+/// Tracks whether the user is idle and dims the target setting accordingly
+pub struct IdleDimmer {
+    source: Box<dyn IdleSource>,
+    threshold: Duration,
+    idle: bool,
+}
+
+impl IdleDimmer {
+    pub fn new(source: Box<dyn IdleSource>, threshold: Duration) -> Self {
+        Self {
+            source,
+            threshold,
+            idle: false,
+        }
+    }
+
+    /// Query the idle source and return true if the idle state changed.
+    /// Query errors are treated as activity so the screen is never left
+    /// dimmed when the source goes away.
+    pub fn poll(&mut self) -> bool {
+        let idle = match self.source.idle_time() {
+            Ok(idle_time) => idle_time >= self.threshold,
+            Err(e) => {
+                debug!("Idle query failed: {}", e);
+                false
+            }
+        };
+
+        let changed = idle != self.idle;
+        if changed {
+            info!("User {}", if idle { "idle, dimming" } else { "active, restoring brightness" });
+        }
+        self.idle = idle;
+        changed
+    }
+
+    /// Apply the idle brightness reduction to `setting` if idle
+    pub fn apply(&self, setting: &mut ColorSetting) {
+        if self.idle {
+            setting.brightness = (setting.brightness * IDLE_DIM_FACTOR).max(MIN_BRIGHTNESS);
+        }
+    }
+}
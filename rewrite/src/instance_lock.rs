@@ -0,0 +1,169 @@
+/// Single-instance lock for continual mode
+/// Two daemons adjusting the same display make the tint oscillate, so the
+/// first one takes an flock on `$XDG_RUNTIME_DIR/redshift.lock` and writes
+/// its pid there. The lock is released when the process exits, even after
+/// a crash.
+
+use log::{debug, info};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// File name of the lock in the runtime directory
+pub const LOCK_NAME: &str = "redshift.lock";
+
+/// How long `--replace` waits for the old instance to fade out and exit
+pub const REPLACE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between attempts while waiting for the old instance
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Default path of the lock file. Only the per-user runtime directory is
+/// used, as a lock file in a shared directory could be planted by another
+/// user.
+pub fn lock_path() -> Result<PathBuf, String> {
+    dirs::runtime_dir()
+        .map(|dir| dir.join(LOCK_NAME))
+        .ok_or_else(|| "XDG_RUNTIME_DIR is not set".to_string())
+}
+
+/// Why the lock could not be taken
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockError {
+    /// Another instance holds the lock; its pid if it recorded one
+    Held(Option<u32>),
+    /// The lock file could not be opened, locked or written
+    Io(String),
+}
+
+impl std::fmt::Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Held(Some(pid)) => {
+                write!(f, "Another instance (pid {}) is already running", pid)
+            }
+            LockError::Held(None) => write!(f, "Another instance is already running"),
+            LockError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Held lock, released when dropped
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Take the lock at `path` without waiting, recording our pid in it.
+    /// A symlink or a file owned by another user is refused.
+    pub fn acquire(path: &Path) -> Result<Self, LockError> {
+        let io_error = |e: std::io::Error| LockError::Io(format!("{}: {}", path.display(), e));
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)
+            .map_err(io_error)?;
+
+        let metadata = file.metadata().map_err(io_error)?;
+        if !metadata.is_file() || metadata.uid() != unsafe { libc::getuid() } {
+            return Err(LockError::Io(format!(
+                "{} is not a regular file owned by this user",
+                path.display()
+            )));
+        }
+
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let e = std::io::Error::last_os_error();
+            if e.raw_os_error() == Some(libc::EWOULDBLOCK) {
+                return Err(LockError::Held(read_pid(&mut file)));
+            }
+            return Err(io_error(e));
+        }
+
+        file.set_len(0).map_err(io_error)?;
+        file.seek(SeekFrom::Start(0)).map_err(io_error)?;
+        writeln!(file, "{}", std::process::id()).map_err(io_error)?;
+        debug!("Took instance lock {}", path.display());
+
+        Ok(Self { _file: file })
+    }
+
+    /// Take the lock at `path`. If another instance holds it, call
+    /// `terminate` with its pid and keep trying until it lets go or
+    /// `timeout` passes.
+    pub fn acquire_replacing(
+        path: &Path,
+        timeout: Duration,
+        terminate: &dyn Fn(u32) -> Result<(), String>,
+    ) -> Result<Self, LockError> {
+        let pid = match Self::acquire(path) {
+            Err(LockError::Held(Some(pid))) => pid,
+            other => return other,
+        };
+
+        info!("Asking the running instance (pid {}) to exit", pid);
+        terminate(pid).map_err(LockError::Io)?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match Self::acquire(path) {
+                Err(LockError::Held(holder)) if Instant::now() < deadline => {
+                    debug!("Instance lock still held by {:?}", holder);
+                    std::thread::sleep(RETRY_INTERVAL);
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+/// Ask the process `pid` to exit with SIGTERM, after checking that it runs
+/// the same program as this one, so a stale or forged pid cannot get some
+/// other process killed
+pub fn terminate_process(pid: u32) -> Result<(), String> {
+    if !same_program(pid) {
+        return Err(format!(
+            "Not signalling pid {}: it is not another instance of this program",
+            pid
+        ));
+    }
+    let pid = libc::pid_t::try_from(pid).map_err(|_| format!("Invalid pid {}", pid))?;
+    if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+        return Err(format!(
+            "Failed to signal pid {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `pid` is another process running the same executable
+pub fn same_program(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return false;
+    }
+    match (
+        std::fs::read_link(format!("/proc/{}/exe", pid)),
+        std::fs::read_link("/proc/self/exe"),
+    ) {
+        (Ok(theirs), Ok(ours)) => theirs == ours,
+        _ => false,
+    }
+}
+
+/// Pid recorded by the holder of the lock, if readable
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
@@ -28,8 +28,8 @@ pub fn select_location_interactive() -> Result<Location, String> {
     );
 
     Ok(Location {
-        lat: city.latitude().map_err(|e| format!("Invalid latitude: {}", e))? as f32,
-        lon: city.longitude().map_err(|e| format!("Invalid longitude: {}", e))? as f32,
+        lat: city.latitude().map_err(|e| format!("Invalid latitude: {}", e))?,
+        lon: city.longitude().map_err(|e| format!("Invalid longitude: {}", e))?,
     })
 }
 
@@ -1,12 +1,45 @@
+pub mod applier;
 pub mod cities;
+pub mod clock;
+pub mod color_scheme;
 pub mod colorramp;
 pub mod config;
+pub mod conflicts;
+pub mod control;
+pub mod cycle;
+pub mod demo;
+pub mod display_loss;
+pub mod edid;
+pub mod error;
+pub mod event_log;
 pub mod config_ini;
+pub mod fade;
+pub mod fifo;
 pub mod gamma;
 pub mod gamma_guard;
 pub mod gamma_randr;
+pub mod idle;
+pub mod instance_lock;
 pub mod interactive;
 pub mod location;
+pub mod lock;
+pub mod log_limit;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod period;
+pub mod preview;
+pub mod query;
+pub mod reapply;
+pub mod scheme_file;
+pub mod setup;
 pub mod signals;
 pub mod solar;
+pub mod stats;
+pub mod status;
+pub mod stdin_control;
+pub mod timeofday;
 pub mod types;
+pub mod vcgt;
+pub mod vt;
+
+pub use error::{Error, Result};
@@ -1,22 +1,27 @@
 /// Location providers
 /// Ported from legacy/src/location-*.c
 
-use crate::types::Location;
+use crate::error::{Error, Result};
+use crate::types::{Location, MAX_LAT, MAX_LON, MIN_LAT, MIN_LON};
 use log::{debug, error, info, trace};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 use tokio::sync::oneshot;
 
 /// Trait for location providers
 pub trait LocationProvider {
     /// Initialize the provider
-    fn init(&mut self) -> Result<(), String>;
+    fn init(&mut self) -> Result<()>;
 
     /// Start the provider
-    fn start(&mut self) -> Result<(), String>;
+    fn start(&mut self) -> Result<()>;
 
     /// Get the current location
-    fn get_location(&mut self) -> Result<Location, String>;
+    fn get_location(&mut self) -> Result<Location>;
 
     /// Get the provider name
     fn name(&self) -> &str;
@@ -25,7 +30,74 @@ pub trait LocationProvider {
     fn print_help(&self);
 
     /// Set an option (key-value pair)
-    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String>;
+    fn set_option(&mut self, key: &str, value: &str) -> Result<()>;
+}
+
+/// `-l` value that lists the location providers instead of giving a location
+pub const LIST_PROVIDERS_ARG: &str = "list";
+
+/// All location providers, as named in `-l PROVIDER:OPTIONS`
+pub fn location_providers() -> Vec<Box<dyn LocationProvider>> {
+    vec![
+        Box::new(GeoClue2LocationProvider::new()),
+        Box::new(GpsdLocationProvider::new()),
+        Box::new(ManualLocationProvider::new()),
+    ]
+}
+
+/// Parse a `-l` location, given either as LAT:LON or, as with legacy
+/// redshift, as manual:lat=N:lon=N. A malformed or out-of-range value is
+/// a usage error, like any other bad argument.
+pub fn parse_location_arg(s: &str) -> Result<Location> {
+    let location = match s.split_once(':') {
+        Some((provider, options)) if provider.eq_ignore_ascii_case("manual") => {
+            let mut manual = ManualLocationProvider::new();
+            let mut keys = Vec::new();
+            for option in options.split(':').filter(|option| !option.is_empty()) {
+                let (key, value) = option.split_once('=').ok_or_else(|| {
+                    Error::Config(format!("Malformed provider option: {}", option))
+                })?;
+                manual
+                    .set_option(key, value)
+                    .map_err(|e| Error::Config(e.to_string()))?;
+                keys.push(key.to_lowercase());
+            }
+            /* The provider fills in 0 for a missing coordinate */
+            if !keys.iter().any(|key| key == "lat") || !keys.iter().any(|key| key == "lon") {
+                return Err(Error::Config("Latitude and longitude must be set.".to_string()));
+            }
+            manual.get_location().map_err(|e| Error::Config(e.to_string()))?
+        }
+        _ => {
+            let parts: Vec<&str> = s.split(':').collect();
+            if parts.len() != 2 {
+                return Err(Error::Config("Location must be in format LAT:LON".to_string()));
+            }
+
+            let lat: f64 = parts[0]
+                .parse()
+                .map_err(|_| Error::Config(format!("Invalid latitude: {}", parts[0])))?;
+            let lon: f64 = parts[1]
+                .parse()
+                .map_err(|_| Error::Config(format!("Invalid longitude: {}", parts[1])))?;
+            Location { lat, lon }
+        }
+    };
+
+    if location.lat < MIN_LAT || location.lat > MAX_LAT {
+        return Err(Error::Config(format!(
+            "Latitude must be between {} and {}",
+            MIN_LAT, MAX_LAT
+        )));
+    }
+    if location.lon < MIN_LON || location.lon > MAX_LON {
+        return Err(Error::Config(format!(
+            "Longitude must be between {} and {}",
+            MIN_LON, MAX_LON
+        )));
+    }
+
+    Ok(location)
 }
 
 /// Manual location provider
@@ -39,7 +111,7 @@ impl ManualLocationProvider {
         Self { location: None }
     }
 
-    pub fn with_location(lat: f32, lon: f32) -> Self {
+    pub fn with_location(lat: f64, lon: f64) -> Self {
         Self {
             location: Some(Location { lat, lon }),
         }
@@ -53,20 +125,20 @@ impl Default for ManualLocationProvider {
 }
 
 impl LocationProvider for ManualLocationProvider {
-    fn init(&mut self) -> Result<(), String> {
+    fn init(&mut self) -> Result<()> {
         Ok(())
     }
 
-    fn start(&mut self) -> Result<(), String> {
+    fn start(&mut self) -> Result<()> {
         if self.location.is_none() {
-            return Err("Latitude and longitude must be set.".to_string());
+            return Err(Error::Location("Latitude and longitude must be set.".to_string()));
         }
         Ok(())
     }
 
-    fn get_location(&mut self) -> Result<Location, String> {
+    fn get_location(&mut self) -> Result<Location> {
         self.location
-            .ok_or_else(|| "Location not set".to_string())
+            .ok_or_else(|| Error::Location("Location not set".to_string()))
     }
 
     fn name(&self) -> &str {
@@ -84,10 +156,10 @@ impl LocationProvider for ManualLocationProvider {
         println!();
     }
 
-    fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
-        let v: f32 = value
+    fn set_option(&mut self, key: &str, value: &str) -> Result<()> {
+        let v: f64 = value
             .parse()
-            .map_err(|_| format!("Malformed argument: {}", value))?;
+            .map_err(|_| Error::Location(format!("Malformed argument: {}", value)))?;
 
         match key.to_lowercase().as_str() {
             "lat" => {
@@ -102,27 +174,108 @@ impl LocationProvider for ManualLocationProvider {
                 self.location = Some(loc);
                 Ok(())
             }
-            _ => Err(format!("Unknown method parameter: `{}`", key)),
+            _ => Err(Error::Location(format!("Unknown method parameter: `{}`", key))),
         }
     }
 }
 
+/// Desktop id reported to GeoClue2 when none is configured
+pub const DEFAULT_GEOCLUE_DESKTOP_ID: &str = "redshift";
+
+/// Median of location samples, taken separately for latitude and
+/// longitude. With an even number of samples the two middle values are
+/// averaged. Longitudes are first moved to the same side as the first fix,
+/// so fixes on both sides of the antimeridian stay close together.
+pub fn median_location(samples: &[Location]) -> Option<Location> {
+    fn median(mut values: Vec<f64>) -> f64 {
+        values.sort_by(f64::total_cmp);
+        let middle = values.len() / 2;
+        if values.len().is_multiple_of(2) {
+            (values[middle - 1] + values[middle]) / 2.0
+        } else {
+            values[middle]
+        }
+    }
+
+    /* Shift a longitude by a full turn towards `reference` when that
+       brings it closer */
+    fn unwrap(lon: f64, reference: f64) -> f64 {
+        if lon - reference > 180.0 {
+            lon - 360.0
+        } else if lon - reference < -180.0 {
+            lon + 360.0
+        } else {
+            lon
+        }
+    }
+
+    let first = samples.first()?;
+    let lon = median(samples.iter().map(|sample| unwrap(sample.lon, first.lon)).collect());
+    Some(Location {
+        lat: median(samples.iter().map(|sample| sample.lat).collect()),
+        lon: unwrap(lon, 0.0),
+    })
+}
+
 /// GeoClue2 location provider (automatic location detection)
 /// Ported from legacy/src/location-geoclue2.c
 pub struct GeoClue2LocationProvider {
     location: Arc<Mutex<Option<Location>>>,
+    samples: Arc<Mutex<Vec<Location>>>,
     error: Arc<Mutex<Option<String>>>,
     thread_handle: Option<thread::JoinHandle<()>>,
     shutdown_tx: Option<oneshot::Sender<()>>,
+    desktop_id: String,
 }
 
 impl GeoClue2LocationProvider {
     pub fn new() -> Self {
         Self {
             location: Arc::new(Mutex::new(None)),
+            samples: Arc::new(Mutex::new(Vec::new())),
             error: Arc::new(Mutex::new(None)),
             thread_handle: None,
             shutdown_tx: None,
+            desktop_id: DEFAULT_GEOCLUE_DESKTOP_ID.to_string(),
+        }
+    }
+
+    /// Create a provider that identifies itself with the given desktop id
+    pub fn with_desktop_id(desktop_id: &str) -> Self {
+        let mut provider = Self::new();
+        provider.desktop_id = desktop_id.to_string();
+        provider
+    }
+
+    /// Get the desktop id sent to GeoClue2
+    /// GeoClue2 agents grant location permission per desktop id, so this
+    /// must match the installed .desktop file name (without extension).
+    pub fn desktop_id(&self) -> &str {
+        &self.desktop_id
+    }
+
+    /// Wait until `count` locations have been reported or `settle_time`
+    /// has passed, and return the median of those received.
+    /// The first fix is often a coarse IP-based guess that later updates
+    /// refine, so a single early sample can be far off.
+    pub fn wait_for_samples(&mut self, count: usize, settle_time: Duration) -> Result<Location> {
+        let deadline = std::time::Instant::now() + settle_time;
+        while self.samples.lock().unwrap().len() < count
+            && self.error.lock().unwrap().is_none()
+            && std::time::Instant::now() < deadline
+        {
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        let samples = self.samples.lock().unwrap();
+        let received = &samples[..samples.len().min(count)];
+        debug!("Received {} of {} GeoClue2 samples", received.len(), count);
+        match median_location(received) {
+            Some(location) => Ok(location),
+            None => {
+                drop(samples);
+                self.get_location()
+            }
         }
     }
 }
@@ -134,21 +287,23 @@ impl Default for GeoClue2LocationProvider {
 }
 
 impl LocationProvider for GeoClue2LocationProvider {
-    fn init(&mut self) -> Result<(), String> {
+    fn init(&mut self) -> Result<()> {
         Ok(())
     }
 
-    fn start(&mut self) -> Result<(), String> {
+    fn start(&mut self) -> Result<()> {
         debug!("Starting GeoClue2 location provider");
         let location = Arc::clone(&self.location);
+        let samples = Arc::clone(&self.samples);
         let error = Arc::clone(&self.error);
+        let desktop_id = self.desktop_id.clone();
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
 
         // Spawn a thread to run the tokio runtime for GeoClue2
         let handle = thread::spawn(move || {
             let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
             rt.block_on(async move {
-                if let Err(e) = geoclue2_async_task(location.clone(), error.clone(), shutdown_rx).await {
+                if let Err(e) = geoclue2_async_task(location.clone(), samples, error.clone(), desktop_id, shutdown_rx).await {
                     error!("GeoClue2 error: {}", e);
                     let mut err = error.lock().unwrap();
                     *err = Some(format!("GeoClue2 error: {}", e));
@@ -166,14 +321,14 @@ impl LocationProvider for GeoClue2LocationProvider {
         Ok(())
     }
 
-    fn get_location(&mut self) -> Result<Location, String> {
+    fn get_location(&mut self) -> Result<Location> {
         // Check for errors first
         if let Some(err_msg) = self.error.lock().unwrap().as_ref() {
-            return Err(err_msg.clone());
+            return Err(Error::Location(err_msg.clone()));
         }
 
         let loc = self.location.lock().unwrap();
-        loc.ok_or_else(|| "Location not yet available from GeoClue2".to_string())
+        loc.ok_or_else(|| Error::Location("Location not yet available from GeoClue2".to_string()))
     }
 
     fn name(&self) -> &str {
@@ -183,10 +338,21 @@ impl LocationProvider for GeoClue2LocationProvider {
     fn print_help(&self) {
         println!("Use the location as discovered by a GeoClue2 provider.");
         println!();
+        println!("  desktop-id=ID\tDesktop id reported to GeoClue2 (default: {})", DEFAULT_GEOCLUE_DESKTOP_ID);
+        println!();
     }
 
-    fn set_option(&mut self, key: &str, _value: &str) -> Result<(), String> {
-        Err(format!("Unknown method parameter: `{}`", key))
+    fn set_option(&mut self, key: &str, value: &str) -> Result<()> {
+        match key.to_lowercase().as_str() {
+            "desktop-id" => {
+                if value.is_empty() {
+                    return Err(Error::Location("Desktop id must not be empty".to_string()));
+                }
+                self.desktop_id = value.to_string();
+                Ok(())
+            }
+            _ => Err(Error::Location(format!("Unknown method parameter: `{}`", key))),
+        }
     }
 }
 
@@ -207,9 +373,11 @@ impl Drop for GeoClue2LocationProvider {
 /// Async task that handles GeoClue2 D-Bus communication
 async fn geoclue2_async_task(
     location: Arc<Mutex<Option<Location>>>,
+    samples: Arc<Mutex<Vec<Location>>>,
     error: Arc<Mutex<Option<String>>>,
+    desktop_id: String,
     mut shutdown_rx: oneshot::Receiver<()>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> std::result::Result<(), Box<dyn std::error::Error>> {
     use zbus::{Connection, proxy};
     use futures_util::stream::StreamExt;
 
@@ -281,7 +449,8 @@ async fn geoclue2_async_task(
         .await?;
 
     // Set desktop ID
-    if let Err(e) = client.set_desktop_id("redshift").await {
+    debug!("Setting GeoClue2 desktop ID: {}", desktop_id);
+    if let Err(e) = client.set_desktop_id(&desktop_id).await {
         debug!("Could not set desktop ID: {}", e);
     }
 
@@ -318,10 +487,8 @@ async fn geoclue2_async_task(
             if let Ok(geo_location) = geo_location_result {
                 if let (Ok(lat), Ok(lon)) = (geo_location.latitude().await, geo_location.longitude().await) {
                     let mut loc = location.lock().unwrap();
-                    *loc = Some(Location {
-                        lat: lat as f32,
-                        lon: lon as f32,
-                    });
+                    *loc = Some(Location { lat, lon });
+                    samples.lock().unwrap().push(Location { lat, lon });
                     info!("Initial location from GeoClue2: {:.2}, {:.2}", lat, lon);
                 }
             }
@@ -346,10 +513,8 @@ async fn geoclue2_async_task(
 
                 // Update shared location
                 let mut loc = location.lock().unwrap();
-                *loc = Some(Location {
-                    lat: lat as f32,
-                    lon: lon as f32,
-                });
+                *loc = Some(Location { lat, lon });
+                samples.lock().unwrap().push(Location { lat, lon });
 
                 info!("Location updated from GeoClue2: {:.2}, {:.2}", lat, lon);
                 trace!("New location path: {:?}", new_location_path);
@@ -363,3 +528,204 @@ async fn geoclue2_async_task(
         }
     }
 }
+
+/// Host of the gpsd daemon when none is configured
+pub const DEFAULT_GPSD_HOST: &str = "localhost";
+
+/// Port gpsd listens on by default
+pub const DEFAULT_GPSD_PORT: u16 = 2947;
+
+/// How long to wait for gpsd to accept the connection
+const GPSD_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Read timeout, so the reader thread notices a shutdown request
+const GPSD_READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Command that makes gpsd stream JSON reports
+const GPSD_WATCH_COMMAND: &str = "?WATCH={\"enable\":true,\"json\":true};\n";
+
+/// Extract the position from a gpsd report line.
+/// Only TPV (time-position-velocity) reports with at least a 2D fix
+/// (mode 2 or 3) and coordinates in range give a location.
+pub fn parse_tpv(line: &str) -> Option<Location> {
+    let report: serde_json::Value = serde_json::from_str(line).ok()?;
+    if report.get("class")?.as_str()? != "TPV" || report.get("mode")?.as_u64()? < 2 {
+        return None;
+    }
+
+    let lat = report.get("lat")?.as_f64()?;
+    let lon = report.get("lon")?.as_f64()?;
+    if !(MIN_LAT..=MAX_LAT).contains(&lat) || !(MIN_LON..=MAX_LON).contains(&lon) {
+        return None;
+    }
+    Some(Location { lat, lon })
+}
+
+/// gpsd location provider, for a GPS receiver shared by the gpsd daemon.
+/// A background thread follows the TPV reports and keeps the latest fix.
+pub struct GpsdLocationProvider {
+    host: String,
+    port: u16,
+    location: Arc<Mutex<Option<Location>>>,
+    error: Arc<Mutex<Option<String>>>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl GpsdLocationProvider {
+    pub fn new() -> Self {
+        Self {
+            host: DEFAULT_GPSD_HOST.to_string(),
+            port: DEFAULT_GPSD_PORT,
+            location: Arc::new(Mutex::new(None)),
+            error: Arc::new(Mutex::new(None)),
+            thread_handle: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Wait up to `timeout` for the first fix, returning early if the
+    /// connection to gpsd fails
+    pub fn wait_for_location(&mut self, timeout: Duration) -> Result<Location> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            match self.get_location() {
+                Err(Error::Location(_))
+                    if self.error.lock().unwrap().is_none()
+                        && std::time::Instant::now() < deadline =>
+                {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for GpsdLocationProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocationProvider for GpsdLocationProvider {
+    fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        debug!("Starting gpsd location provider at {}:{}", self.host, self.port);
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| Error::Location(format!("Failed to resolve gpsd host {}: {}", self.host, e)))?
+            .next()
+            .ok_or_else(|| Error::Location(format!("Failed to resolve gpsd host {}", self.host)))?;
+
+        let location = Arc::clone(&self.location);
+        let error = Arc::clone(&self.error);
+        let shutdown = Arc::clone(&self.shutdown);
+
+        let handle = thread::spawn(move || {
+            if let Err(e) = gpsd_task(address, &location, &shutdown) {
+                debug!("gpsd error: {}", e);
+                *error.lock().unwrap() = Some(format!("gpsd unavailable: {}", e));
+            }
+        });
+        self.thread_handle = Some(handle);
+
+        Ok(())
+    }
+
+    fn get_location(&mut self) -> Result<Location> {
+        if let Some(err_msg) = self.error.lock().unwrap().as_ref() {
+            return Err(Error::Location(err_msg.clone()));
+        }
+
+        let loc = self.location.lock().unwrap();
+        loc.ok_or_else(|| Error::Location("No GPS fix from gpsd yet".to_string()))
+    }
+
+    fn name(&self) -> &str {
+        "gpsd"
+    }
+
+    fn print_help(&self) {
+        println!("Use the position of a GPS receiver shared by gpsd.");
+        println!();
+        println!("  host=HOST\tgpsd host (default: {})", DEFAULT_GPSD_HOST);
+        println!("  port=PORT\tgpsd port (default: {})", DEFAULT_GPSD_PORT);
+        println!();
+    }
+
+    fn set_option(&mut self, key: &str, value: &str) -> Result<()> {
+        match key.to_lowercase().as_str() {
+            "host" => {
+                if value.is_empty() {
+                    return Err(Error::Location("gpsd host must not be empty".to_string()));
+                }
+                self.host = value.to_string();
+                Ok(())
+            }
+            "port" => {
+                self.port = value
+                    .parse()
+                    .map_err(|_| Error::Location(format!("Malformed argument: {}", value)))?;
+                Ok(())
+            }
+            _ => Err(Error::Location(format!("Unknown method parameter: `{}`", key))),
+        }
+    }
+}
+
+impl Drop for GpsdLocationProvider {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Connect to gpsd, ask it to stream reports and record each fix until
+/// `shutdown` is set or the connection ends
+fn gpsd_task(
+    address: std::net::SocketAddr,
+    location: &Mutex<Option<Location>>,
+    shutdown: &AtomicBool,
+) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect_timeout(&address, GPSD_CONNECT_TIMEOUT)?;
+    stream.set_read_timeout(Some(GPSD_READ_TIMEOUT))?;
+    stream.write_all(GPSD_WATCH_COMMAND.as_bytes())?;
+    debug!("Connected to gpsd at {}", address);
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while !shutdown.load(Ordering::Relaxed) {
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "gpsd closed the connection",
+                ))
+            }
+            Ok(_) => {
+                if let Some(fix) = parse_tpv(&line) {
+                    let mut loc = location.lock().unwrap();
+                    if loc.is_none() {
+                        info!("GPS fix from gpsd: {:.4}, {:.4}", fix.lat, fix.lon);
+                    }
+                    trace!("gpsd fix: {:.6}, {:.6}", fix.lat, fix.lon);
+                    *loc = Some(fix);
+                }
+                line.clear();
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
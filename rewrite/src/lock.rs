@@ -0,0 +1,207 @@
+/// Screen lock detection for pausing fades while locked
+/// Some lock screens grab the display and fight gamma changes, which shows
+/// as flicker during a fade, so the last applied setting is held until the
+/// screen is unlocked
+
+use log::{debug, info};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, AtomEnum, ConnectionExt as _};
+use x11rb::rust_connection::RustConnection;
+
+/// Root window property in which xscreensaver records its state
+const SCREENSAVER_STATUS_PROPERTY: &str = "_SCREENSAVER_STATUS";
+
+/// Source of whether the screen is currently locked
+pub trait LockSource {
+    fn is_locked(&mut self) -> Result<bool, String>;
+}
+
+/// Lock detection on X11. The screen counts as locked when xscreensaver
+/// reports LOCK.
+pub struct X11LockSource {
+    conn: RustConnection,
+    root: xproto::Window,
+    status_atom: xproto::Atom,
+    lock_atom: xproto::Atom,
+}
+
+impl X11LockSource {
+    pub fn connect() -> Result<Self, String> {
+        let (conn, screen_num) = RustConnection::connect(None)
+            .map_err(|e| format!("Failed to connect to X server: {}", e))?;
+
+        let root = conn
+            .setup()
+            .roots
+            .get(screen_num)
+            .ok_or_else(|| format!("Screen {} could not be found", screen_num))?
+            .root;
+
+        let status_atom = intern_atom(&conn, SCREENSAVER_STATUS_PROPERTY)?;
+        let lock_atom = intern_atom(&conn, "LOCK")?;
+
+        Ok(Self {
+            conn,
+            root,
+            status_atom,
+            lock_atom,
+        })
+    }
+
+    /// True if xscreensaver's status property says LOCK
+    fn screensaver_locked(&self) -> Result<bool, String> {
+        let reply = self
+            .conn
+            .get_property(false, self.root, self.status_atom, AtomEnum::INTEGER, 0, 1)
+            .map_err(|e| format!("Failed to read {}: {}", SCREENSAVER_STATUS_PROPERTY, e))?
+            .reply()
+            .map_err(|e| format!("Failed to read {}: {}", SCREENSAVER_STATUS_PROPERTY, e))?;
+
+        Ok(reply
+            .value32()
+            .and_then(|mut values| values.next())
+            .is_some_and(|state| state == self.lock_atom))
+    }
+}
+
+impl LockSource for X11LockSource {
+    fn is_locked(&mut self) -> Result<bool, String> {
+        self.screensaver_locked()
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait LogindSession {
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+/// Lock detection through logind's LockedHint, which GNOME, KDE and
+/// lockers run through xss-lock set. The property is cached by the proxy
+/// and kept up to date from change signals, so polling does not wait on
+/// the bus.
+pub struct LogindLockSource {
+    runtime: tokio::runtime::Runtime,
+    proxy: LogindSessionProxy<'static>,
+}
+
+impl LogindLockSource {
+    pub fn connect() -> Result<Self, String> {
+        let runtime = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
+        let proxy = runtime.block_on(async {
+            let conn = zbus::Connection::system()
+                .await
+                .map_err(|e| format!("Failed to connect to the system bus: {}", e))?;
+            let proxy = LogindSessionProxy::new(&conn)
+                .await
+                .map_err(|e| format!("Failed to reach logind: {}", e))?;
+            proxy
+                .locked_hint()
+                .await
+                .map_err(|e| format!("Failed to read LockedHint: {}", e))?;
+            Ok::<_, String>(proxy)
+        })?;
+
+        Ok(Self { runtime, proxy })
+    }
+}
+
+impl LockSource for LogindLockSource {
+    fn is_locked(&mut self) -> Result<bool, String> {
+        let proxy = &self.proxy;
+        self.runtime
+            .block_on(proxy.locked_hint())
+            .map_err(|e| format!("Failed to read LockedHint: {}", e))
+    }
+}
+
+/// Several lock sources at once: locked if any of them says so. Errors
+/// only count when no source could be read.
+pub struct AnyLockSource {
+    sources: Vec<Box<dyn LockSource>>,
+}
+
+impl AnyLockSource {
+    pub fn new(sources: Vec<Box<dyn LockSource>>) -> Self {
+        Self { sources }
+    }
+}
+
+impl LockSource for AnyLockSource {
+    fn is_locked(&mut self) -> Result<bool, String> {
+        let mut last_error = None;
+        let mut locked = false;
+        let mut read = false;
+        for source in &mut self.sources {
+            match source.is_locked() {
+                Ok(state) => {
+                    read = true;
+                    locked |= state;
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if !read => Err(e),
+            _ => Ok(locked),
+        }
+    }
+}
+
+fn intern_atom(conn: &RustConnection, name: &str) -> Result<xproto::Atom, String> {
+    Ok(conn
+        .intern_atom(false, name.as_bytes())
+        .map_err(|e| format!("Failed to look up {}: {}", name, e))?
+        .reply()
+        .map_err(|e| format!("Failed to look up {}: {}", name, e))?
+        .atom)
+}
+
+/// Tracks whether the screen is locked and whether updates should pause
+pub struct LockWatcher {
+    source: Box<dyn LockSource>,
+    locked: bool,
+}
+
+impl LockWatcher {
+    pub fn new(source: Box<dyn LockSource>) -> Self {
+        Self {
+            source,
+            locked: false,
+        }
+    }
+
+    /// Query the lock source and return true if the lock state changed.
+    /// Query errors count as unlocked so updates never stay paused when
+    /// the source goes away.
+    pub fn poll(&mut self) -> bool {
+        let locked = match self.source.is_locked() {
+            Ok(locked) => locked,
+            Err(e) => {
+                debug!("Lock query failed: {}", e);
+                false
+            }
+        };
+
+        let changed = locked != self.locked;
+        if changed {
+            info!(
+                "Screen {}",
+                if locked { "locked, pausing updates" } else { "unlocked, resuming updates" }
+            );
+        }
+        self.locked = locked;
+        changed
+    }
+
+    /// Whether to hold the last applied setting instead of updating.
+    /// A shutdown fade is never paused, so exiting cannot hang on a lock.
+    pub fn should_pause(&self, shutting_down: bool) -> bool {
+        self.locked && !shutting_down
+    }
+}
@@ -0,0 +1,76 @@
+/// Rate limiting for warnings that can repeat on every update
+/// An identical message is logged at most once per window. Repeats in
+/// between are counted and reported with the next copy that gets through.
+
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Window used by `warn_limited`
+pub const DEFAULT_LOG_WINDOW: Duration = Duration::from_secs(60);
+
+struct Seen {
+    last_logged: Instant,
+    suppressed: u32,
+}
+
+/// Tracks when each message was last logged
+pub struct LogLimiter {
+    window: Duration,
+    seen: HashMap<String, Seen>,
+}
+
+impl LogLimiter {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Text to log for `message` at `now`, or None while repeats of it are
+    /// being suppressed
+    pub fn check(&mut self, message: &str, now: Instant) -> Option<String> {
+        match self.seen.get_mut(message) {
+            Some(seen) if now.saturating_duration_since(seen.last_logged) < self.window => {
+                seen.suppressed += 1;
+                None
+            }
+            Some(seen) => {
+                let text = match seen.suppressed {
+                    0 => message.to_string(),
+                    count => format!("{} (repeated {} times)", message, count),
+                };
+                seen.last_logged = now;
+                seen.suppressed = 0;
+                Some(text)
+            }
+            None => {
+                self.seen.insert(
+                    message.to_string(),
+                    Seen {
+                        last_logged: now,
+                        suppressed: 0,
+                    },
+                );
+                Some(message.to_string())
+            }
+        }
+    }
+}
+
+/// Log a warning, at most once per `DEFAULT_LOG_WINDOW` for each distinct
+/// message
+pub fn warn_limited(message: &str) {
+    static LIMITER: OnceLock<Mutex<LogLimiter>> = OnceLock::new();
+
+    let limiter = LIMITER.get_or_init(|| Mutex::new(LogLimiter::new(DEFAULT_LOG_WINDOW)));
+    let text = match limiter.lock() {
+        Ok(mut limiter) => limiter.check(message, Instant::now()),
+        Err(_) => Some(message.to_string()),
+    };
+    if let Some(text) = text {
+        warn!("{}", text);
+    }
+}
@@ -1,44 +1,114 @@
+mod applier;
 mod cities;
+mod clock;
+mod color_scheme;
 mod colorramp;
 mod config;
+mod conflicts;
+mod control;
+mod cycle;
+mod demo;
+mod display_loss;
+mod edid;
+mod error;
+mod event_log;
 mod config_ini;
+mod fade;
+mod fifo;
 mod gamma;
 mod gamma_guard;
 mod gamma_randr;
+mod idle;
+mod instance_lock;
 mod interactive;
 mod location;
+mod lock;
+mod log_limit;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod period;
+mod preview;
+mod query;
+mod reapply;
+mod scheme_file;
+mod setup;
 mod signals;
 mod solar;
+mod stats;
+mod status;
+mod stdin_control;
+mod timeofday;
 mod types;
-
-use clap::{ArgAction, Parser, ValueEnum};
+mod vcgt;
+mod vt;
+
+use applier::{ApplierCommand, FadeApplier};
+use clap::{ArgAction, ArgGroup, CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use clock::{RealClock, TimeSource};
+use color_scheme::{ColorSchemeWatcher, PortalColorSchemeSource};
+use colorramp::{estimate_temperature, get_white_point_xy, CvdProfile, Quantization, RampOptions};
 use config::{Config, LocationSource};
-use gamma::{DummyGammaMethod, GammaMethod};
-use gamma_guard::GammaRestoreGuard;
+use control::{ControlCommand, ControlServer, OverrideState};
+use cycle::TemperatureCycle;
+use demo::DemoClock;
+use display_loss::{apply_recovering, DisplayLossPolicy, RECONNECT_ATTEMPTS, RECONNECT_DELAY};
+use event_log::{ContinualEvent, LogFormat};
+use fade::{Fade, FadeCurve, FadeThresholds};
+use fifo::{ControlFifo, FifoCommand};
+use gamma::{DummyGammaMethod, GammaMethod, NullApplyGammaMethod};
+use gamma_guard::{ExitPolicy, GammaRestoreGuard};
 use gamma_randr::RandrGammaMethod;
-use location::{GeoClue2LocationProvider, LocationProvider};
-use log::{debug, info, trace};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use idle::{IdleDimmer, X11IdleSource};
+use instance_lock::InstanceLock;
+use location::{
+    GeoClue2LocationProvider, GpsdLocationProvider, LocationProvider, DEFAULT_GEOCLUE_DESKTOP_ID,
+};
+use lock::{AnyLockSource, LockSource, LockWatcher, LogindLockSource, X11LockSource};
+use log::{debug, error, info, trace, warn};
+#[cfg(feature = "metrics")]
+use metrics::{MetricsServer, MetricsSnapshot};
+use period::{
+    apply_active_days, apply_deep_night, apply_schedule, get_current_period,
+    get_period_and_progress, get_period_and_progress_held, interpolate_transition_scheme,
+};
+use query::{QueryServer, QuerySnapshot};
+use reapply::ReapplyTimer;
+use stats::{ContinualStats, StatsLogger, STATS_LOG_INTERVAL};
+use status::wait_with_status;
+use scheme_file::SchemeFile;
+use stdin_control::{StdinCommand, STDIN_FADE_STEP};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread::Scope;
+use std::time::{Duration, Instant};
 use types::*;
+use vt::{SysfsVtSource, VtWatcher};
 
 /* Duration of sleep between screen updates (milliseconds). */
 const SLEEP_DURATION: u64 = 5000;
 const SLEEP_DURATION_SHORT: u64 = 100;
 
-/* Length of fade in numbers of short sleep durations. */
-const FADE_LENGTH: i32 = 40;
-
 #[derive(Debug, Clone, Copy, ValueEnum)]
 enum GammaMethodChoice {
     Randr,
     Dummy,
 }
 
-#[derive(Parser, Debug)]
+/* Methods tried in order when none is selected */
+const AUTO_GAMMA_METHODS: &[GammaMethodChoice] = &[GammaMethodChoice::Randr];
+
+#[derive(Parser, Debug, Clone)]
 #[command(name = "redshift")]
 #[command(about = "Adjusts screen color temperature", long_about = None)]
+#[command(group(
+    ArgGroup::new("mode")
+        .args(["continual", "one_shot", "print", "period", "reset", "reset_all", "setup", "override_temp", "demo", "stdin_control", "export_vcgt", "preview_city"])
+        .multiple(false)
+))]
 struct Args {
-    /// Location as LAT:LON (e.g., 40.7:-74.0), or leave empty for automatic detection
+    /// Location as LAT:LON (e.g., 40.7:-74.0) or manual:lat=N:lon=N, "list" to
+    /// list the location providers, or leave empty for automatic detection
     #[arg(short, long, value_name = "LAT:LON")]
     location: Option<String>,
 
@@ -46,9 +116,22 @@ struct Args {
     #[arg(long)]
     no_auto_location: bool,
 
-    /// Gamma adjustment method
-    #[arg(short = 'm', long, default_value = "randr")]
-    method: GammaMethodChoice,
+    /// Location provider to use when no -l location is given; gpsd reads
+    /// the position of a GPS receiver from a gpsd daemon
+    #[arg(long, value_name = "PROVIDER", value_parser = ["geoclue2", "gpsd", "manual"])]
+    location_provider: Option<String>,
+
+    /// Retry GeoClue2 now instead of waiting for the daily check
+    #[arg(long)]
+    refresh_location: bool,
+
+    /// Gamma adjustment method (default: randr)
+    #[arg(short = 'm', long)]
+    method: Option<GammaMethodChoice>,
+
+    /// Continual mode (keep adjusting the temperature; the default)
+    #[arg(long)]
+    continual: bool,
 
     /// One-shot mode (set temperature and exit)
     #[arg(short = 'o', long)]
@@ -58,18 +141,92 @@ struct Args {
     #[arg(short = 'p', long)]
     print: bool,
 
+    /// Print only the current period (daytime, night or transition) and exit
+    #[arg(long)]
+    period: bool,
+
+    /// Print the solar events and today's hourly timeline at CITY, e.g.
+    /// "Reykjavik" or "San Jose, Costa Rica", and exit without changing the
+    /// screen or saving the location
+    #[arg(long, value_name = "CITY")]
+    preview_city: Option<String>,
+
+    /// Reset mode (remove adjustment from screen and exit)
+    #[arg(short = 'x', long)]
+    reset: bool,
+
+    /// Reset every gamma method that can be started to neutral and exit,
+    /// to recover a screen left tinted by a crashed instance
+    #[arg(long)]
+    reset_all: bool,
+
+    /// Run the first-time setup wizard and write a starter configuration
+    #[arg(long)]
+    setup: bool,
+
+    /// Animate a whole day of the scheme over SECONDS (default: 10), then
+    /// restore the screen
+    #[arg(long, value_name = "SECONDS", num_args = 0..=1,
+          default_missing_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+    demo: Option<u64>,
+
+    /// Apply "TEMP [BRIGHTNESS]" lines read from standard input, ignoring
+    /// the schedule, until end of input
+    #[arg(long)]
+    stdin_control: bool,
+
+    /// Write the current adjustment to FILE as an ICC vcgt (video card
+    /// gamma table) tag with 256 entries per channel, and exit
+    #[arg(long, value_name = "FILE")]
+    export_vcgt: Option<PathBuf>,
+
+    /// Make the running instance hold TEMP (Kelvin or preset name) for the
+    /// --for duration, then return to the schedule
+    #[arg(long = "override", value_name = "TEMP", value_parser = resolve_temp, requires = "override_for")]
+    override_temp: Option<i32>,
+
+    /// How long to hold the --override temperature (e.g. 90s, 30m, 2h)
+    #[arg(long = "for", value_name = "DURATION", value_parser = control::parse_duration, requires = "override_temp")]
+    override_for: Option<Duration>,
+
+    /// Format of period change, fade and toggle messages in continual mode:
+    /// human (default) or kv for key=value lines that scripts can grep
+    #[arg(long, value_name = "FORMAT", value_parser = LogFormat::parse)]
+    log_format: Option<LogFormat>,
+
     /// Verbose output (can be repeated: -v=info, -vv=debug, -vvv=trace)
     #[arg(short, long, action = ArgAction::Count)]
     verbose: u8,
 
-    /// Day temperature (default: 6500K)
-    #[arg(short = 't', long, default_value = "6500")]
+    /// Suppress the dummy method warning and its per-update temperature
+    /// output (still logged with -v)
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Day temperature in Kelvin or a preset name such as daylight (default: 6500K)
+    #[arg(short = 't', long, default_value = "6500", value_parser = resolve_temp)]
     temp_day: i32,
 
-    /// Night temperature (default: 3500K)
-    #[arg(long, default_value = "3500")]
+    /// Night temperature in Kelvin or a preset name such as candle (default: 3500K)
+    #[arg(long, default_value = "3500", value_parser = resolve_temp)]
     temp_night: i32,
 
+    /// Add cities from a JSON file (same format as the bundled list) to
+    /// the interactive location selector, replacing bundled entries with
+    /// the same country and name
+    #[arg(long, value_name = "PATH")]
+    cities_file: Option<PathBuf>,
+
+    /// Use the measured white points in a CSV file of tempK,r,g,b lines
+    /// instead of the built-in blackbody table
+    #[arg(long, value_name = "FILE")]
+    whitepoint_table: Option<PathBuf>,
+
+    /// Read the transition scheme from a TOML file instead of the INI
+    /// config, for schemes the INI format cannot express
+    #[arg(long, value_name = "PATH")]
+    scheme_file: Option<PathBuf>,
+
     /// Brightness (day:night or single value)
     #[arg(short = 'b', long)]
     brightness: Option<String>,
@@ -77,6 +234,154 @@ struct Args {
     /// Gamma (R:G:B or single value)
     #[arg(short = 'g', long)]
     gamma: Option<String>,
+
+    /// Desktop id reported to GeoClue2 (default: redshift)
+    #[arg(long, value_name = "ID")]
+    geoclue_desktop_id: Option<String>,
+
+    /// Wait SECONDS before the first adjustment so the desktop can settle
+    /// after login; an exit signal during the wait aborts startup
+    #[arg(long, value_name = "SECONDS")]
+    startup_delay: Option<u64>,
+
+    /// Send the current setting again at least every SECONDS, even when it
+    /// has not changed, for drivers that reset the gamma ramps on their own
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    reapply_interval: Option<u64>,
+
+    /// Log the current setting every MS milliseconds in continual mode, in
+    /// addition to the messages on changes, without changing how often the
+    /// gamma ramps are applied
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    status_interval: Option<u64>,
+
+    /// Allow brightness above 1.0, up to 2.0, for dim panels. Ramps are
+    /// scaled past linear, so the brightest tones clip to white
+    #[arg(long)]
+    allow_overdrive: bool,
+
+    /// Strength of the night setting in percent: 0 leaves the screen
+    /// neutral at night, 100 (default) applies the configured night values
+    #[arg(long, value_name = "0-100", value_parser = clap::value_parser!(u8).range(0..=100))]
+    intensity: Option<u8>,
+
+    /// Use the configured brightness and gamma even if they would make the
+    /// screen illegible
+    #[arg(long)]
+    force: bool,
+
+    /// Clamp out-of-range brightness, gamma and contrast to the nearest
+    /// valid value with a warning instead of failing
+    #[arg(long)]
+    clamp_invalid: bool,
+
+    /// What to leave on the screen at exit: restore (the ramps found at
+    /// startup), keep (the last setting) or neutral (6500K). Defaults to
+    /// neutral in continual, demo and stdin control mode and keep otherwise
+    #[arg(long, value_name = "POLICY", value_parser = ExitPolicy::parse)]
+    on_exit: Option<ExitPolicy>,
+
+    /// Leave the last setting on the screen at exit, even in continual mode.
+    /// Same as --on-exit keep
+    #[arg(long, conflicts_with = "on_exit")]
+    no_restore: bool,
+
+    /// Apply the current target immediately on startup instead of fading to it
+    #[arg(long)]
+    instant: bool,
+
+    /// On SIGINT or SIGTERM, apply neutral (6500K) at once and exit instead
+    /// of fading out first. Also enabled by fade=0 in the config file
+    #[arg(long)]
+    fast_exit: bool,
+
+    /// Easing of fades: linear, smoothstep (default) or ease-in-out-cubic
+    #[arg(long, value_name = "CURVE", value_parser = FadeCurve::parse)]
+    fade_curve: Option<FadeCurve>,
+
+    /// Apply fades in continual mode from a dedicated thread stepping on
+    /// fixed deadlines, so a busy main loop does not make them stutter
+    #[arg(long)]
+    fade_thread: bool,
+
+    /// What to do in continual mode when the connection to the X server is
+    /// lost: exit (default) or reconnect, trying a few times before exiting
+    #[arg(long, value_name = "POLICY", value_parser = DisplayLossPolicy::parse)]
+    on_display_loss: Option<DisplayLossPolicy>,
+
+    /// Remove blue entirely while the temperature is below TEMP (Kelvin or
+    /// a preset name)
+    #[arg(long, value_name = "TEMP", value_parser = resolve_temp)]
+    blue_cutoff_temp: Option<i32>,
+
+    /// Correct the white point for a color vision deficiency, so warm
+    /// shifts cost less contrast: protanopia, deuteranopia or tritanopia
+    #[arg(long, value_name = "PROFILE", value_parser = CvdProfile::parse)]
+    cvd_profile: Option<CvdProfile>,
+
+    /// Probe each CRTC once and skip those that accept gamma ramps without
+    /// applying them, as some offload outputs do. Briefly tints the screen
+    #[arg(long)]
+    probe_gamma: bool,
+
+    /// How ramp values are rounded to 16 bits: round (default), truncate
+    /// (as legacy redshift) or dither
+    #[arg(long, value_name = "MODE", value_parser = Quantization::parse)]
+    quantization: Option<Quantization>,
+
+    /// Only adjust the named output (e.g. DP-2); can be repeated
+    #[arg(long = "output", value_name = "NAME")]
+    outputs: Vec<String>,
+
+    /// Only adjust the monitor whose EDID serial is SERIAL, whichever
+    /// port it is plugged into; can be repeated
+    #[arg(long = "match-edid", value_name = "SERIAL")]
+    edid_serials: Vec<String>,
+
+    /// Dim the screen further after SECONDS without keyboard or mouse input
+    #[arg(long, value_name = "SECONDS")]
+    dim_on_idle: Option<u64>,
+
+    /// Hold the last applied setting while the screen is locked, so fades
+    /// do not flicker on lock screens that fight gamma changes
+    #[arg(long)]
+    pause_when_locked: bool,
+
+    /// Enable the adjustment while the desktop prefers a dark color scheme
+    /// and disable it otherwise, following the XDG settings portal
+    #[arg(long)]
+    follow_color_scheme: bool,
+
+    /// If another instance is running in continual mode, ask it to exit
+    /// and take over instead of refusing to start
+    #[arg(long)]
+    replace: bool,
+
+    /// Accept toggle, enable, disable, reload and "temp N" commands
+    /// written to $XDG_RUNTIME_DIR/redshift.fifo
+    #[arg(long)]
+    control_fifo: bool,
+
+    /// Accept temporary overrides (see --override) on a socket in
+    /// $XDG_RUNTIME_DIR
+    #[arg(long)]
+    control_socket: bool,
+
+    /// Answer each connection to the Unix socket at PATH with the current
+    /// "TEMPERATURE BRIGHTNESS PERIOD" line, e.g. for desktop widgets
+    #[arg(long, value_name = "PATH")]
+    query_socket: Option<PathBuf>,
+
+    /// Serve Prometheus metrics over HTTP at ADDR/metrics in continual
+    /// mode, e.g. 127.0.0.1:9099
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<String>,
+
+    /// Print a completion script for SHELL (bash, zsh, fish or powershell)
+    /// and exit; meant for packagers
+    #[arg(long, value_name = "SHELL", hide = true)]
+    generate_completions: Option<Shell>,
 }
 
 impl Args {
@@ -96,153 +401,90 @@ impl Args {
 
         /* Brightness and gamma - these are new, so always use from INI if not in CLI */
         /* These will be handled separately when building the scheme */
-    }
-}
 
-fn parse_location(loc_str: &str) -> Result<Location, String> {
-    let parts: Vec<&str> = loc_str.split(':').collect();
-    if parts.len() != 2 {
-        return Err("Location must be in format LAT:LON".to_string());
-    }
-
-    let lat: f32 = parts[0]
-        .parse()
-        .map_err(|_| format!("Invalid latitude: {}", parts[0]))?;
-    let lon: f32 = parts[1]
-        .parse()
-        .map_err(|_| format!("Invalid longitude: {}", parts[1]))?;
+        if self.method.is_none() {
+            self.method = ini_config.adjustment_method.as_deref().and_then(|method| {
+                GammaMethodChoice::from_str(method, true)
+                    .map_err(|_| warn!("Unknown adjustment method: {}", method))
+                    .ok()
+            });
+        }
 
-    if lat < MIN_LAT || lat > MAX_LAT {
-        return Err(format!(
-            "Latitude must be between {} and {}",
-            MIN_LAT, MAX_LAT
-        ));
-    }
-    if lon < MIN_LON || lon > MAX_LON {
-        return Err(format!(
-            "Longitude must be between {} and {}",
-            MIN_LON, MAX_LON
-        ));
-    }
+        if self.geoclue_desktop_id.is_none() {
+            self.geoclue_desktop_id = ini_config.geoclue_desktop_id.clone();
+        }
 
-    Ok(Location { lat, lon })
-}
+        if self.location_provider.is_none() {
+            self.location_provider = ini_config.location_provider.clone();
+        }
 
-fn get_current_period(
-    location: &Location,
-    scheme: &TransitionScheme,
-) -> (Period, ColorSetting) {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs_f64();
-
-    let elevation = solar::solar_elevation(now, location.lat as f64, location.lon as f64);
-
-    if elevation >= scheme.high {
-        (Period::Daytime, scheme.day)
-    } else if elevation <= scheme.low {
-        (Period::Night, scheme.night)
-    } else {
-        (Period::Transition, interpolate_color_setting(
-            elevation,
-            scheme.low,
-            scheme.high,
-            &scheme.night,
-            &scheme.day,
-        ))
+        if self.intensity.is_none() {
+            self.intensity = ini_config.intensity;
+        }
     }
-}
 
-fn interpolate_color_setting(
-    elevation: f64,
-    low: f64,
-    high: f64,
-    night: &ColorSetting,
-    day: &ColorSetting,
-) -> ColorSetting {
-    let alpha = ((elevation - low) / (high - low)) as f32;
-    let alpha = alpha.max(0.0).min(1.0);
-
-    ColorSetting {
-        temperature: ((1.0 - alpha) * (night.temperature as f32) + alpha * (day.temperature as f32))
-            as i32,
-        gamma: [
-            (1.0 - alpha) * night.gamma[0] + alpha * day.gamma[0],
-            (1.0 - alpha) * night.gamma[1] + alpha * day.gamma[1],
-            (1.0 - alpha) * night.gamma[2] + alpha * day.gamma[2],
-        ],
-        brightness: (1.0 - alpha) * night.brightness + alpha * day.brightness,
+    /// Take settings from a scheme file that were not given on the
+    /// command line
+    fn merge_with_scheme_file(&mut self, scheme_file: &SchemeFile) {
+        if self.blue_cutoff_temp.is_none() {
+            self.blue_cutoff_temp = scheme_file.blue_cutoff_temp;
+        }
+        if self.quantization.is_none() {
+            self.quantization = scheme_file.quantization;
+        }
     }
-}
 
-/* Determine how far through the transition we are based on elevation.
-   Returns a value from 0.0 (night) to 1.0 (day). */
-fn get_transition_progress_from_elevation(scheme: &TransitionScheme, elevation: f64) -> f64 {
-    if elevation < scheme.low {
-        0.0
-    } else if elevation < scheme.high {
-        (scheme.low - elevation) / (scheme.low - scheme.high)
-    } else {
-        1.0
+    /// Gamma method from the command line, config file or environment
+    fn method(&self) -> GammaMethodChoice {
+        self.method.unwrap_or(GammaMethodChoice::Randr)
     }
-}
 
-/* Use transition progress to interpolate color settings.
-   Progress from 0.0 (night) to 1.0 (day). */
-fn interpolate_transition_scheme(
-    scheme: &TransitionScheme,
-    progress: f64,
-    result: &mut ColorSetting,
-) {
-    let alpha = progress.max(0.0).min(1.0);
-
-    result.temperature = ((1.0 - alpha) * (scheme.night.temperature as f64)
-        + alpha * (scheme.day.temperature as f64)) as i32;
-    result.brightness = ((1.0 - alpha) * (scheme.night.brightness as f64)
-        + alpha * (scheme.day.brightness as f64)) as f32;
-    result.gamma[0] = ((1.0 - alpha) * (scheme.night.gamma[0] as f64)
-        + alpha * (scheme.day.gamma[0] as f64)) as f32;
-    result.gamma[1] = ((1.0 - alpha) * (scheme.night.gamma[1] as f64)
-        + alpha * (scheme.day.gamma[1] as f64)) as f32;
-    result.gamma[2] = ((1.0 - alpha) * (scheme.night.gamma[2] as f64)
-        + alpha * (scheme.day.gamma[2] as f64)) as f32;
-}
+    /// Program mode selected on the command line.
+    /// Clap rejects conflicting mode flags, so at most one is set.
+    fn mode(&self) -> ProgramMode {
+        if self.print {
+            ProgramMode::Print
+        } else if self.one_shot {
+            ProgramMode::OneShot
+        } else if self.reset {
+            ProgramMode::Reset
+        } else if self.reset_all {
+            ProgramMode::ResetAll
+        } else if self.demo.is_some() {
+            ProgramMode::Demo
+        } else if self.stdin_control {
+            ProgramMode::StdinControl
+        } else {
+            ProgramMode::Continual
+        }
+    }
 
-/* Return true if color settings have major differences.
-   Used to determine if a fade should be applied in continual mode. */
-fn color_setting_diff_is_major(first: &ColorSetting, second: &ColorSetting) -> bool {
-    (first.temperature - second.temperature).abs() > 25
-        || (first.brightness - second.brightness).abs() > 0.1
-        || (first.gamma[0] - second.gamma[0]).abs() > 0.1
-        || (first.gamma[1] - second.gamma[1]).abs() > 0.1
-        || (first.gamma[2] - second.gamma[2]).abs() > 0.1
-}
+    /// Exit policy from --on-exit, or the mode's traditional behaviour
+    fn exit_policy(&self) -> ExitPolicy {
+        self.on_exit.unwrap_or(match self.mode() {
+            ProgramMode::Continual | ProgramMode::Demo | ProgramMode::StdinControl => {
+                ExitPolicy::Neutral
+            }
+            _ => ExitPolicy::Keep,
+        })
+    }
 
-/* Interpolate between two color settings using alpha (0.0 to 1.0). */
-fn interpolate_color_settings(
-    first: &ColorSetting,
-    second: &ColorSetting,
-    alpha: f64,
-    result: &mut ColorSetting,
-) {
-    let alpha = alpha.max(0.0).min(1.0);
-
-    result.temperature = ((1.0 - alpha) * (first.temperature as f64)
-        + alpha * (second.temperature as f64)) as i32;
-    result.brightness = ((1.0 - alpha) * (first.brightness as f64)
-        + alpha * (second.brightness as f64)) as f32;
-    result.gamma[0] = ((1.0 - alpha) * (first.gamma[0] as f64)
-        + alpha * (second.gamma[0] as f64)) as f32;
-    result.gamma[1] = ((1.0 - alpha) * (first.gamma[1] as f64)
-        + alpha * (second.gamma[1] as f64)) as f32;
-    result.gamma[2] = ((1.0 - alpha) * (first.gamma[2] as f64)
-        + alpha * (second.gamma[2] as f64)) as f32;
+    /// Desktop id to report to GeoClue2
+    fn geoclue_desktop_id(&self) -> &str {
+        self.geoclue_desktop_id
+            .as_deref()
+            .unwrap_or(DEFAULT_GEOCLUE_DESKTOP_ID)
+    }
 }
 
-/* Ease fade function - cubic interpolation for smooth transitions. */
-fn ease_fade(t: f64) -> f64 {
-    t * t * (3.0 - 2.0 * t)
+/// Temperatures accepted anywhere: the hard limits narrowed by temp-min and
+/// temp-max, clamped or rejected per --clamp-invalid
+fn temperature_bounds(
+    args: &Args,
+    ini_config: &config_ini::RedshiftConfig,
+) -> Result<TemperatureBounds, String> {
+    Ok(TemperatureBounds::new(ini_config.temp_min, ini_config.temp_max)?
+        .with_clamp(args.clamp_invalid))
 }
 
 /// Determine location using priority system (with INI config support)
@@ -254,14 +496,14 @@ fn determine_location_with_ini(
 
     // Priority 1: Command-line argument
     if let Some(loc_str) = &args.location {
-        let loc = parse_location(loc_str)?;
+        let loc = location::parse_location_arg(loc_str)?;
         info!("Using location from command-line: {:.4}, {:.4}", loc.lat, loc.lon);
 
         // Load config for other settings
         let mut config = Config::load().unwrap_or_default();
 
-        // Only ask to save if running in interactive mode (not print, not one-shot)
-        if !args.print && !args.one_shot {
+        // Only ask to save if running in continual mode (not print, not one-shot)
+        if args.mode() == ProgramMode::Continual && !args.period && args.export_vcgt.is_none() {
             use dialoguer::Confirm;
             let should_save = Confirm::new()
                 .with_prompt("Save this location for future use?")
@@ -271,8 +513,9 @@ fn determine_location_with_ini(
 
             if should_save {
                 config.set_location(loc, LocationSource::Manual, None);
-                config.save().ok(); // Ignore save errors
-                info!("Location saved to configuration file");
+                if save_location(&config) {
+                    info!("Location saved to configuration file");
+                }
             } else {
                 debug!("Location will not be saved (session only)");
             }
@@ -284,6 +527,25 @@ fn determine_location_with_ini(
     // Load or create config
     let mut config = Config::load().unwrap_or_default();
 
+    // Ignore the daily GeoClue2 timer so it is retried right away; the
+    // attempt saves the new check time as usual
+    if args.refresh_location {
+        config.reset_geoclue_check();
+        info!("Ignoring last GeoClue2 check, location will be refreshed");
+    }
+
+    // A GPS receiver shared by gpsd, when selected. Its position changes,
+    // so it is not saved.
+    if args.location_provider.as_deref() == Some("gpsd") {
+        match try_gpsd(ini_config) {
+            Ok(loc) => {
+                info!("Using location from gpsd: {:.4}, {:.4}", loc.lat, loc.lon);
+                return Ok((loc, config));
+            }
+            Err(e) => warn!("{}, falling back to other location sources", e),
+        }
+    }
+
     // Priority 2: INI config file manual location
     if let Some(ini_loc) = ini_config.get_manual_location() {
         info!("Using location from INI config: {:.4}, {:.4}", ini_loc.lat, ini_loc.lon);
@@ -294,19 +556,19 @@ fn determine_location_with_ini(
     if config.should_check_geoclue() {
         info!("Checking for automatic location via GeoClue2...");
 
-        if let Ok(loc) = try_geoclue2() {
+        if let Ok(loc) = try_geoclue2(args.geoclue_desktop_id(), ini_config) {
             info!("Got location from GeoClue2: {:.4}, {:.4}", loc.lat, loc.lon);
 
             config.set_location(loc, LocationSource::GeoClue2, None);
             config.update_geoclue_check();
-            config.save().ok();
+            save_location(&config);
 
             return Ok((loc, config));
         }
 
         // Mark that we checked, even though it failed
         config.update_geoclue_check();
-        config.save().ok();
+        config.save_or_warn();
     }
 
     // Priority 4: Use saved TOML configuration
@@ -330,32 +592,104 @@ fn determine_location_with_ini(
 
     // Priority 5: Interactive selection
     if args.no_auto_location {
-        eprintln!("Error: --no-auto-location requires -l LAT:LON or saved configuration");
-        std::process::exit(1);
+        return Err(error::Error::Location(
+            "--no-auto-location requires -l LAT:LON or saved configuration".to_string(),
+        )
+        .into());
     }
 
     eprintln!("\nNo location configured and automatic detection unavailable.");
-    let loc = interactive::select_location_interactive()?;
+    let loc = interactive::select_location_interactive().map_err(error::Error::Location)?;
 
     // Save for future use
     let city_name = format!("Selected city"); // Could be improved
     config.set_location(loc, LocationSource::Interactive, Some(city_name));
-    config.save().ok();
+    save_location(&config);
 
     Ok((loc, config))
 }
 
+/// Print a --status-interval sample of the applied `setting` to standard
+/// output, since it was asked for and should show without -v
+fn print_status(log_format: LogFormat, setting: &ColorSetting, period: Period, disabled: bool) {
+    match log_format {
+        LogFormat::Human => println!(
+            "Current setting: {}K, brightness {:.2}, {}",
+            setting.temperature,
+            setting.brightness,
+            if disabled { "disabled" } else { period.name() }
+        ),
+        LogFormat::Kv => println!(
+            "{}",
+            ContinualEvent::Status {
+                temperature: setting.temperature,
+                brightness: setting.brightness,
+                period,
+                enabled: !disabled,
+            }
+        ),
+    }
+}
+
+/// Save a config holding a newly chosen location. If it cannot be written
+/// the user is told, since they will otherwise be asked again next time.
+fn save_location(config: &Config) -> bool {
+    let saved = config.save_or_warn();
+    if !saved {
+        eprintln!("Your location could not be saved and will have to be chosen again next time.");
+    }
+    saved
+}
+
+/// Print the location providers and their options, for -l list
+fn print_location_providers() {
+    println!("Available location providers:");
+    for provider in location::location_providers() {
+        println!();
+        println!("{}:", provider.name());
+        provider.print_help();
+    }
+    println!("Specify options as -l PROVIDER:KEY=VALUE:KEY=VALUE, e.g. -l manual:lat=40.7:lon=-74.0");
+}
+
+/// Try to get location from gpsd, configured by the INI [gpsd] section
+fn try_gpsd(ini_config: &config_ini::RedshiftConfig) -> Result<Location, error::Error> {
+    let mut provider = GpsdLocationProvider::new();
+    if let Some(host) = &ini_config.gpsd_host {
+        provider.set_option("host", host)?;
+    }
+    if let Some(port) = ini_config.gpsd_port {
+        provider.set_option("port", &port.to_string())?;
+    }
+    provider.init()?;
+    provider.start()?;
+
+    debug!("Waiting for a fix from gpsd...");
+    provider.wait_for_location(Duration::from_secs(5))
+}
+
 /// Try to get location from GeoClue2
-fn try_geoclue2() -> Result<Location, String> {
-    let mut provider = GeoClue2LocationProvider::new();
+/// With geoclue-samples, the median of the first fixes received within
+/// geoclue-settle-time is used instead of the latest one.
+fn try_geoclue2(
+    desktop_id: &str,
+    ini_config: &config_ini::RedshiftConfig,
+) -> Result<Location, error::Error> {
+    let mut provider = GeoClue2LocationProvider::with_desktop_id(desktop_id);
+    debug!("Using GeoClue2 desktop id: {}", provider.desktop_id());
     provider.init()?;
     provider.start()?;
 
     // Wait for location
     debug!("Waiting for location from GeoClue2...");
-    std::thread::sleep(Duration::from_secs(5));
-
-    provider.get_location()
+    let settle_time = Duration::from_secs(ini_config.geoclue_settle_time.unwrap_or(5));
+    match ini_config.geoclue_samples {
+        Some(count) if count > 1 => provider.wait_for_samples(count, settle_time),
+        _ => {
+            std::thread::sleep(settle_time);
+            provider.get_location()
+        }
+    }
 }
 
 /// Build transition scheme from args and INI config
@@ -363,96 +697,326 @@ fn build_transition_scheme(
     args: &Args,
     ini_config: &config_ini::RedshiftConfig,
 ) -> Result<TransitionScheme, String> {
-    let mut scheme = TransitionScheme::default();
+    let defaults = TransitionScheme::default();
 
     /* Set temperatures from merged args */
-    scheme.day.temperature = args.temp_day;
-    scheme.night.temperature = args.temp_night;
+    let mut builder = TransitionSchemeBuilder::new()
+        .temperature_bounds(temperature_bounds(args, ini_config)?)
+        .day_temp(args.temp_day)
+        .night_temp(args.temp_night)
+        .allow_overdrive(args.allow_overdrive)
+        .interpolate_in_mireds(ini_config.interpolate_in_mireds.unwrap_or(false));
 
     /* Parse and apply brightness from CLI or INI */
     if let Some(ref brightness_str) = args.brightness {
-        let (day, night) = config_ini::parse_brightness_string(brightness_str)?;
-        scheme.day.brightness = day;
-        scheme.night.brightness = night;
-    } else {
-        if let Some(day) = ini_config.brightness_day {
-            scheme.day.brightness = day;
-        }
-        if let Some(night) = ini_config.brightness_night {
-            scheme.night.brightness = night;
+        let (day, night) = if args.clamp_invalid {
+            config_ini::parse_brightness_values(brightness_str)
+        } else {
+            let max = if args.allow_overdrive {
+                MAX_OVERDRIVE_BRIGHTNESS
+            } else {
+                MAX_BRIGHTNESS
+            };
+            config_ini::parse_brightness_in_range(brightness_str, max)
         }
+        .map_err(|e| format!("Invalid -b/--brightness {}: {}", brightness_str, e))?;
+        builder = builder.brightness(day, night);
+    } else {
+        builder = builder.brightness(
+            ini_config.brightness_day.unwrap_or(defaults.day.brightness),
+            ini_config.brightness_night.unwrap_or(defaults.night.brightness),
+        );
     }
 
     /* Parse and apply gamma from CLI or INI */
     if let Some(ref gamma_str) = args.gamma {
-        let gamma = config_ini::parse_gamma_string(gamma_str)?;
-        scheme.day.gamma = gamma;
-        scheme.night.gamma = gamma;
+        let gamma = if args.clamp_invalid {
+            config_ini::parse_gamma_values(gamma_str)?
+        } else {
+            config_ini::parse_gamma_string(gamma_str)?
+        };
+        builder = builder.gamma(gamma, gamma);
     } else {
-        if let Some(gamma) = ini_config.gamma_day {
-            scheme.day.gamma = gamma;
-        }
-        if let Some(gamma) = ini_config.gamma_night {
-            scheme.night.gamma = gamma;
-        }
+        builder = builder.gamma(
+            ini_config.gamma_day.unwrap_or(defaults.day.gamma),
+            ini_config.gamma_night.unwrap_or(defaults.night.gamma),
+        );
+    }
+
+    /* Apply contrast from INI */
+    if let Some(contrast) = ini_config.contrast {
+        builder = builder.contrast(contrast);
     }
 
     /* Apply elevation settings from INI */
     if let Some(high) = ini_config.elevation_high {
-        scheme.high = high;
+        builder = builder.elevation_high(high);
     }
     if let Some(low) = ini_config.elevation_low {
-        scheme.low = low;
+        builder = builder.elevation_low(low);
+    }
+
+    /* Apply multi-stage twilight if both stage temperatures are set.
+       Stage brightness and gamma default to the day values at civil
+       twilight and the night values at nautical twilight. */
+    match (ini_config.temp_civil, ini_config.temp_nautical) {
+        (Some(civil), Some(nautical)) => {
+            let day_gamma = ini_config.gamma_day.unwrap_or(defaults.day.gamma);
+            let night_gamma = ini_config.gamma_night.unwrap_or(defaults.night.gamma);
+            builder = builder.twilight(TwilightStages {
+                civil: ColorSetting {
+                    temperature: civil,
+                    brightness: ini_config
+                        .brightness_civil
+                        .or(ini_config.brightness_day)
+                        .unwrap_or(defaults.day.brightness),
+                    gamma: day_gamma,
+                    contrast: ini_config.contrast.unwrap_or(defaults.day.contrast),
+                },
+                nautical: ColorSetting {
+                    temperature: nautical,
+                    brightness: ini_config
+                        .brightness_nautical
+                        .or(ini_config.brightness_night)
+                        .unwrap_or(defaults.night.brightness),
+                    gamma: night_gamma,
+                    contrast: ini_config.contrast.unwrap_or(defaults.night.contrast),
+                },
+            });
+        }
+        (None, None) => {}
+        _ => warn!("Both temp-civil and temp-nautical are needed for twilight stages, ignoring"),
+    }
+
+    /* Warm the night further past the deep night elevation */
+    if let Some(temperature) = ini_config.temp_deep_night {
+        builder = match ini_config.elevation_deep_night {
+            Some(elevation) => builder.deep_night(temperature, elevation),
+            None => builder.deep_night_temp(temperature),
+        };
+    }
+
+    /* Apply temperature keyframes from the [schedule] section */
+    if !ini_config.schedule.is_empty() {
+        builder = builder.keyframes(ini_config.schedule.clone());
+    }
+
+    /* Scale the night effect towards neutral */
+    if let Some(intensity) = args.intensity {
+        builder = builder.intensity(intensity);
+    }
+
+    /* Clamp out-of-range brightness, gamma and contrast instead of failing */
+    builder = builder.clamp_invalid(args.clamp_invalid);
+
+    /* Only follow the schedule on some days of the week */
+    if let Some(days) = ini_config.active_days {
+        builder = builder.active_days(days);
     }
 
     /* Apply time-based transition if specified */
     if let Some(dawn) = ini_config.dawn_time {
-        scheme.use_time = true;
-        scheme.dawn = dawn;
+        builder = builder.dawn(dawn);
     }
     if let Some(dusk) = ini_config.dusk_time {
-        scheme.use_time = true;
-        scheme.dusk = dusk;
+        builder = builder.dusk(dusk);
+    }
+
+    /* Validate the whole scheme once */
+    builder.build()
+}
+
+/// Transition scheme from the scheme file if there is one, else from args
+/// and INI config. Every scheme goes through here, so none skips the
+/// legibility check.
+fn resolve_transition_scheme(
+    args: &Args,
+    ini_config: &config_ini::RedshiftConfig,
+    scheme_file: Option<&SchemeFile>,
+) -> Result<TransitionScheme, String> {
+    let scheme = match scheme_file {
+        Some(scheme_file) => scheme_file.scheme.clone(),
+        None => build_transition_scheme(args, ini_config)?,
+    };
+
+    for (name, setting) in [("Day", &scheme.day), ("Night", &scheme.night)] {
+        if setting.brightness > MAX_BRIGHTNESS {
+            warn!(
+                "{} brightness {} is above {}, highlights will clip",
+                name, setting.brightness, MAX_BRIGHTNESS
+            );
+        }
     }
 
-    /* Validate brightness bounds */
-    if scheme.day.brightness < MIN_BRIGHTNESS || scheme.day.brightness > MAX_BRIGHTNESS {
-        return Err(format!(
-            "Day brightness must be between {} and {}",
-            MIN_BRIGHTNESS, MAX_BRIGHTNESS
-        ));
+    /* Refuse settings too dark to read, since the user might then be
+       unable to see well enough to fix them */
+    for (name, setting) in [("Day", &scheme.day), ("Night", &scheme.night)] {
+        let mut applied = *setting;
+        if ini_config.perceptual_brightness == Some(true) {
+            applied.brightness = colorramp::perceptual_brightness(setting.brightness);
+        }
+        if let Err(e) = colorramp::check_legibility(&applied) {
+            if !args.force {
+                return Err(format!(
+                    "{} brightness and gamma are too dark: {}. Use --force to apply them anyway",
+                    name, e
+                ));
+            }
+            warn!("{} brightness and gamma are too dark: {}", name, e);
+        }
+    }
+
+    Ok(scheme)
+}
+
+/// Create the gamma method selected on the command line
+fn create_gamma_method(
+    args: &Args,
+    ini_config: &config_ini::RedshiftConfig,
+) -> Box<dyn GammaMethod> {
+    build_gamma_method(args.method(), args, ini_config)
+}
+
+/// How ramps are built from the color setting, from args and INI config
+fn ramp_options(args: &Args, ini_config: &config_ini::RedshiftConfig) -> RampOptions {
+    let mut options = RampOptions::default();
+    if ini_config.brightness_compensation == Some(true) {
+        debug!("Brightness compensation enabled");
+        options.brightness_compensation = true;
+    }
+    if ini_config.perceptual_brightness == Some(true) {
+        debug!("Perceptual brightness mapping enabled");
+        options.perceptual_brightness = true;
     }
-    if scheme.night.brightness < MIN_BRIGHTNESS || scheme.night.brightness > MAX_BRIGHTNESS {
-        return Err(format!(
-            "Night brightness must be between {} and {}",
-            MIN_BRIGHTNESS, MAX_BRIGHTNESS
-        ));
+    options.quantization = args.quantization.or(ini_config.quantization).unwrap_or_default();
+    debug!("Ramp quantization: {:?}", options.quantization);
+    options.blue_cutoff = args.blue_cutoff_temp.or(ini_config.blue_cutoff_temp);
+    if let Some(cutoff) = options.blue_cutoff {
+        debug!("Removing blue below {}K", cutoff);
     }
+    options.cvd_profile = args.cvd_profile;
+    if let Some(profile) = options.cvd_profile {
+        debug!("Correcting the white point for {:?}", profile);
+    }
+    if let Some([x, y]) = ini_config.white_point {
+        let white_point = get_white_point_xy(x, y);
+        debug!(
+            "Using fixed white point x={}, y={} (about {}K)",
+            x,
+            y,
+            estimate_temperature(white_point)
+        );
+        options.white_point = Some(white_point);
+    }
+    options
+}
 
-    /* Validate gamma bounds */
-    for &gamma in &scheme.day.gamma {
-        if gamma < MIN_GAMMA || gamma > MAX_GAMMA {
-            return Err(format!(
-                "Day gamma must be between {} and {}",
-                MIN_GAMMA, MAX_GAMMA
-            ));
+/// Create the gamma method `choice`, configured from args and INI config
+fn build_gamma_method(
+    choice: GammaMethodChoice,
+    args: &Args,
+    ini_config: &config_ini::RedshiftConfig,
+) -> Box<dyn GammaMethod> {
+    match choice {
+        GammaMethodChoice::Randr => {
+            let mut randr = RandrGammaMethod::new();
+            if args.probe_gamma {
+                debug!("Probing CRTCs for gamma support");
+                randr.set_probe_gamma(true);
+            }
+            if !args.outputs.is_empty() {
+                randr.set_outputs(args.outputs.clone());
+            }
+            if !args.edid_serials.is_empty() {
+                randr.set_edid_serials(args.edid_serials.clone());
+            }
+            randr.set_ramp_options(ramp_options(args, ini_config));
+            Box::new(randr)
         }
+        GammaMethodChoice::Dummy => {
+            let mut dummy = DummyGammaMethod::new();
+            dummy.set_quiet(args.quiet);
+            Box::new(dummy)
+        }
+    }
+}
+
+/// Initialize and start the first working method of AUTO_GAMMA_METHODS.
+/// With more than one to choose from, the one remembered from the last run
+/// is tried first, and the method that started is saved, or the memory
+/// cleared if none did, so a method that stopped working is not preferred
+/// again.
+fn detect_auto_gamma_method(
+    args: &Args,
+    ini_config: &config_ini::RedshiftConfig,
+) -> Result<gamma::DetectedMethod, error::Error> {
+    let candidates: Vec<_> = AUTO_GAMMA_METHODS
+        .iter()
+        .map(|&choice| build_gamma_method(choice, args, ini_config))
+        .collect();
+    if candidates.len() < 2 {
+        return gamma::detect_method(candidates, None);
+    }
+
+    /* Reload before saving so the location and other fields are kept; a
+       file that cannot be read is left alone */
+    let config = Config::load();
+    let remembered = config.as_ref().ok().and_then(|config| config.gamma_method.clone());
+    if let Some(name) = &remembered {
+        debug!("Trying remembered gamma method first: {}", name);
+    }
+
+    let result = gamma::detect_method(candidates, remembered.as_deref());
+    let working = result
+        .as_ref()
+        .ok()
+        .filter(|detected| detected.started.is_ok())
+        .map(|detected| detected.method.name().to_string());
+    if let Some(name) = &working {
+        info!("Using gamma method: {}", name);
     }
-    for &gamma in &scheme.night.gamma {
-        if gamma < MIN_GAMMA || gamma > MAX_GAMMA {
-            return Err(format!(
-                "Night gamma must be between {} and {}",
-                MIN_GAMMA, MAX_GAMMA
-            ));
+    if working != remembered {
+        match config {
+            Ok(mut config) => {
+                config.gamma_method = working;
+                config.save_or_warn();
+            }
+            Err(e) => warn!("Gamma method not remembered: {}", e),
         }
     }
+    result
+}
 
-    Ok(scheme)
+/* Exit status for an error from `run`. Errors of this crate say which
+   part failed (see `error::Error::exit_code`); anything else is a general
+   failure. */
+fn exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    error
+        .downcast_ref::<error::Error>()
+        .map_or(error::EXIT_FAILURE, error::Error::exit_code)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(exit_code(e.as_ref()));
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
     let mut args = Args::parse();
 
+    /* Completion scripts are written before anything else runs */
+    if let Some(shell) = args.generate_completions {
+        clap_complete::generate(
+            shell,
+            &mut Args::command(),
+            env!("CARGO_BIN_NAME"),
+            &mut std::io::stdout(),
+        );
+        return Ok(());
+    }
+
     /* Initialize logger based on verbosity level */
     let log_level = match args.verbose {
         0 => log::LevelFilter::Warn,
@@ -472,29 +1036,155 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     debug!("Logger initialized at level: {:?}", log_level);
 
+    if let Some(path) = &args.cities_file {
+        let count = cities::load_cities_file(path).map_err(error::Error::Config)?;
+        info!("Loaded {} cities from {}", count, path.display());
+    }
+
+    if let Some(path) = &args.whitepoint_table {
+        let count = colorramp::load_white_point_table(path).map_err(error::Error::Config)?;
+        info!("Loaded {} white points from {}", count, path.display());
+    }
+
+    /* The setup wizard runs before signal handlers are installed so that
+       Ctrl+C still aborts it */
+    if args.setup {
+        setup::run_setup()?;
+        return Ok(());
+    }
+
+    /* Like legacy redshift, -l list shows the location providers */
+    if args.location.as_deref() == Some(location::LIST_PROVIDERS_ARG) {
+        print_location_providers();
+        return Ok(());
+    }
+
+    /* Send a temporary override to the running instance */
+    if let (Some(temperature), Some(duration)) = (args.override_temp, args.override_for) {
+        let command = ControlCommand::SetTemporaryOverride {
+            temperature,
+            duration,
+        };
+        control::send_command(&control::socket_path()?, &command)?;
+        println!("Holding {}K for {} seconds", temperature, duration.as_secs());
+        return Ok(());
+    }
+
     /* Install signal handlers for graceful shutdown and mode toggling */
     signals::install_handlers()?;
 
     /* Load INI configuration file */
-    let ini_config = config_ini::RedshiftConfig::load().unwrap_or_default();
+    let ini_config = config_ini::RedshiftConfig::load()?;
+
+    /* Narrow the temperatures accepted anywhere, before anything is
+       validated against them */
+    let bounds = temperature_bounds(&args, &ini_config).map_err(error::Error::Config)?;
 
-    /* Merge INI config with CLI args (CLI takes priority) */
+    /* Merge INI config with CLI args (CLI takes priority), keeping the
+       plain CLI args for reloading the config later */
+    let cli_args = args.clone();
     args.merge_with_ini(&ini_config);
 
-    /* Validate temperature bounds */
-    if args.temp_day < MIN_TEMP || args.temp_day > MAX_TEMP {
-        eprintln!(
-            "Temperature must be between {} and {}",
-            MIN_TEMP, MAX_TEMP
-        );
-        std::process::exit(1);
+    /* A scheme file replaces the scheme built from the INI config */
+    let scheme_file = match &args.scheme_file {
+        Some(path) => {
+            info!("Loading transition scheme from {}", path.display());
+            Some(SchemeFile::load(path, bounds)?)
+        }
+        None => None,
+    };
+    if let Some(scheme_file) = &scheme_file {
+        args.merge_with_scheme_file(scheme_file);
+    }
+
+    /* Validate temperature bounds; with --clamp-invalid the scheme
+       builder clamps them instead */
+    for temperature in [args.temp_day, args.temp_night] {
+        if !bounds.contains(temperature) && !args.clamp_invalid {
+            return Err(error::Error::Config(format!(
+                "Temperature must be between {} and {}",
+                bounds.min, bounds.max
+            ))
+            .into());
+        }
+    }
+
+    let mode = args.mode();
+    debug!("Program mode: {:?}", mode);
+
+    if mode == ProgramMode::Reset {
+        /* Reset mode does not need a location, just restore neutral gamma */
+        let mut gamma_method = create_gamma_method(&args, &ini_config);
+        info!("Initializing gamma method: {}", gamma_method.name());
+        gamma_method.init()?;
+        gamma_method.start()?;
+
+        info!("Resetting color temperature to {}K", NEUTRAL_TEMP);
+        gamma_method.set_temperature(&ColorSetting::default(), false)?;
+        /* The reset is what stays on the screen, whatever the exit policy;
+           restoring would bring back the tint found at startup */
+        gamma_method.set_restore_on_drop(false);
+        return Ok(());
     }
-    if args.temp_night < MIN_TEMP || args.temp_night > MAX_TEMP {
-        eprintln!(
-            "Temperature must be between {} and {}",
-            MIN_TEMP, MAX_TEMP
+
+    if mode == ProgramMode::ResetAll {
+        /* Try every hardware method regardless of -m, so whatever left the
+           screen tinted is cleared */
+        let methods: Vec<Box<dyn GammaMethod>> = vec![Box::new(RandrGammaMethod::new())];
+        let mut any_reset = false;
+        for (name, result) in gamma::reset_all(methods) {
+            match result {
+                Ok(()) => {
+                    info!("Reset {} to {}K", name, NEUTRAL_TEMP);
+                    any_reset = true;
+                }
+                Err(e) => warn!("Could not reset {}: {}", name, e),
+            }
+        }
+        if !any_reset {
+            return Err("No gamma method could be reset".into());
+        }
+        return Ok(());
+    }
+
+    if mode == ProgramMode::StdinControl {
+        /* Live control does not follow the schedule, so needs no location */
+        let mut gamma_method = create_gamma_method(&args, &ini_config);
+        info!("Initializing gamma method: {}", gamma_method.name());
+        gamma_method.init()?;
+        gamma_method.start()?;
+
+        let mut gamma_guard = GammaRestoreGuard::with_policy(gamma_method.as_mut(), args.exit_policy());
+        if args.no_restore {
+            gamma_guard.disable_restore();
+        }
+        let commands = stdin_control::spawn_reader(std::io::BufReader::new(std::io::stdin()), bounds);
+        let fade_curve = args.fade_curve.or(ini_config.fade_curve).unwrap_or_default();
+        run_stdin_control(&mut gamma_guard, &commands, fade_curve)?;
+        return Ok(());
+    }
+
+    if let Some(query) = &args.preview_city {
+        /* Another city's schedule, leaving the saved location alone */
+        let city = cities::find_city(query).map_err(error::Error::Config)?;
+        let location = Location {
+            lat: city.latitude()?,
+            lon: city.longitude()?,
+        };
+        let scheme = resolve_transition_scheme(&args, &ini_config, scheme_file.as_ref())
+            .map_err(error::Error::Config)?;
+
+        let day_start = demo::local_midnight(RealClock.now());
+        print!(
+            "{}",
+            preview::format_preview(
+                &format!("{}, {}", city.display_name(), city.country),
+                &location,
+                &preview::day_events(&location, day_start),
+                &preview::day_timeline(&location, &scheme, day_start, preview::TIMELINE_STEP),
+            )
         );
-        std::process::exit(1);
+        return Ok(());
     }
 
     /* Determine location using priority system:
@@ -504,25 +1194,104 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
        4. GeoClue2 automatic detection (with daily retry)
        5. Interactive selection (country/city list)
     */
-    let (location, mut config) = determine_location_with_ini(&args, &ini_config)?;
+    let (location, config) = determine_location_with_ini(&args, &ini_config)?;
 
-    /* Set up gamma method */
-    let mut gamma_method: Box<dyn GammaMethod> = match args.method {
-        GammaMethodChoice::Randr => Box::new(RandrGammaMethod::new()),
-        GammaMethodChoice::Dummy => Box::new(DummyGammaMethod::new()),
+    /* Create transition scheme from the scheme file, or args and INI config */
+    let scheme = resolve_transition_scheme(&args, &ini_config, scheme_file.as_ref())
+        .map_err(error::Error::Config)?;
+
+    /* Get current period and color setting */
+    let clock = RealClock;
+    let (period, color_setting) = get_current_period(&location, &scheme, &clock);
+
+    if args.period {
+        /* Script-friendly period query, no gamma method needed */
+        println!("{}", period.name().to_lowercase());
+        return Ok(());
+    }
+
+    if let Some(path) = &args.export_vcgt {
+        /* Computed from the scheme alone, no gamma method needed */
+        vcgt::export_vcgt(path, &color_setting, &ramp_options(&args, &ini_config))?;
+        info!(
+            "Wrote {}K curves to {}",
+            color_setting.temperature,
+            path.display()
+        );
+        return Ok(());
+    }
+
+    /* Only one instance may adjust the display in continual mode. The
+       dummy method does not touch it, so needs no lock. */
+    let _instance_lock = if mode == ProgramMode::Continual
+        && !matches!(args.method(), GammaMethodChoice::Dummy)
+    {
+        let lock = instance_lock::lock_path()
+            .map_err(instance_lock::LockError::Io)
+            .and_then(|path| {
+                if args.replace {
+                    InstanceLock::acquire_replacing(
+                        &path,
+                        instance_lock::REPLACE_TIMEOUT,
+                        &instance_lock::terminate_process,
+                    )
+                } else {
+                    InstanceLock::acquire(&path)
+                }
+            });
+        match lock {
+            Ok(lock) => Some(lock),
+            Err(e @ instance_lock::LockError::Held(_)) => {
+                let hint = if args.replace { "" } else { ". Use --replace to take over" };
+                return Err(format!("{}{}", e, hint).into());
+            }
+            Err(e) => {
+                warn!("Could not take the instance lock: {}", e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
-    info!("Initializing gamma method: {}", gamma_method.name());
-    gamma_method.init()?;
-    gamma_method.start()?;
+    /* Give the compositor and other session startup a head start, so the
+       first adjustment is not undone by them */
+    if let Some(seconds) = args
+        .startup_delay
+        .filter(|_| !matches!(mode, ProgramMode::Print | ProgramMode::Demo))
+    {
+        info!("Waiting {}s before the first adjustment", seconds);
+        if !signals::sleep_unless_exiting(Duration::from_secs(seconds)) {
+            info!("Exit signal received during startup delay");
+            return Ok(());
+        }
+    }
 
-    /* Create transition scheme from args and INI config */
-    let scheme = build_transition_scheme(&args, &ini_config)?;
+    /* Set up gamma method */
+    let (mut gamma_method, started) = if args.method.is_some() {
+        let mut gamma_method = create_gamma_method(&args, &ini_config);
+        info!("Initializing gamma method: {}", gamma_method.name());
+        gamma_method.init()?;
+        let started = gamma_method.start();
+        (gamma_method, started)
+    } else {
+        let detected = detect_auto_gamma_method(&args, &ini_config)?;
+        (detected.method, detected.started)
+    };
 
-    /* Get current period and color setting */
-    let (period, color_setting) = get_current_period(&location, &scheme);
+    /* Keep running in continual mode when no output can be controlled,
+       so the schedule can still be followed in the log */
+    if let Err(e) = started {
+        if mode != ProgramMode::Continual {
+            return Err(e.into());
+        }
+        warn!("Failed to start {} method: {}", gamma_method.name(), e);
+        gamma_method = Box::new(NullApplyGammaMethod::new());
+        gamma_method.init()?;
+        gamma_method.start()?;
+    }
 
-    if args.print {
+    if mode == ProgramMode::Print {
         println!("Period: {}", period.name());
         println!("Color temperature: {}K", color_setting.temperature);
         println!(
@@ -534,18 +1303,44 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             color_setting.gamma[0], color_setting.gamma[1], color_setting.gamma[2]
         );
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-        let elevation = solar::solar_elevation(now, location.lat as f64, location.lon as f64);
+        let elevation = solar::solar_elevation(clock.now(), location.lat, location.lon);
         println!("Solar elevation: {:.2}°", elevation);
 
         return Ok(());
     }
 
+    /* Warn about other tools fighting over the gamma ramp */
+    if !matches!(args.method(), GammaMethodChoice::Dummy) {
+        for conflict in conflicts::detect_conflicts() {
+            warn!(
+                "{} is also running and may override the color adjustment",
+                conflict
+            );
+        }
+    }
+
     /* Create gamma restore guard to ensure cleanup on exit or panic */
-    let mut gamma_guard = GammaRestoreGuard::new(gamma_method.as_mut());
+    let mut gamma_guard = GammaRestoreGuard::with_policy(gamma_method.as_mut(), args.exit_policy());
+    if args.no_restore {
+        gamma_guard.disable_restore();
+    }
+    debug!("Exit policy: {:?}", gamma_guard.policy());
+
+    let fade_curve = args.fade_curve.or(ini_config.fade_curve).unwrap_or_default();
+    debug!("Fade curve: {:?}", fade_curve);
+
+    if let Some(seconds) = args.demo {
+        run_demo(
+            &location,
+            &scheme,
+            &mut gamma_guard,
+            Duration::from_secs(seconds),
+            ini_config.fade_thresholds(),
+            fade_curve,
+            &clock,
+        )?;
+        return Ok(());
+    }
 
     /* Apply color temperature */
     info!("Period: {}", period.name());
@@ -558,74 +1353,606 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         color_setting.gamma[2]
     );
 
+    /* Continual mode starts from what is applied here, so the target is
+       not faded in to a second time */
     gamma_guard.get_mut().set_temperature(&color_setting, false)?;
+    let applied = color_setting;
 
-    if args.one_shot {
-        /* For one-shot mode, don't restore gamma on exit */
-        gamma_guard.disable_restore();
+    if mode == ProgramMode::OneShot {
+        /* The guard applies the exit policy, keeping the setting by default */
         return Ok(());
     }
 
+    /* Optional dimming while the user is idle */
+    let idle_dimmer = args.dim_on_idle.and_then(|seconds| match X11IdleSource::connect() {
+        Ok(source) => {
+            info!("Dimming after {}s of inactivity", seconds);
+            Some(IdleDimmer::new(Box::new(source), Duration::from_secs(seconds)))
+        }
+        Err(e) => {
+            warn!("Idle dimming unavailable: {}", e);
+            None
+        }
+    });
+
+    /* Optional pause while the screen is locked */
+    let lock_watcher = if args.pause_when_locked {
+        let mut sources: Vec<Box<dyn LockSource>> = Vec::new();
+        match X11LockSource::connect() {
+            Ok(source) => sources.push(Box::new(source)),
+            Err(e) => debug!("xscreensaver lock detection unavailable: {}", e),
+        }
+        match LogindLockSource::connect() {
+            Ok(source) => sources.push(Box::new(source)),
+            Err(e) => debug!("logind lock detection unavailable: {}", e),
+        }
+        if sources.is_empty() {
+            warn!("Lock detection unavailable: neither xscreensaver nor logind could be reached");
+            None
+        } else {
+            Some(LockWatcher::new(Box::new(AnyLockSource::new(sources))))
+        }
+    } else {
+        None
+    };
+
+    /* Optional toggling with the desktop's dark mode */
+    let color_scheme_watcher = if args.follow_color_scheme {
+        match PortalColorSchemeSource::connect() {
+            Ok(source) => Some(ColorSchemeWatcher::new(Box::new(source))),
+            Err(e) => {
+                warn!("Color scheme following unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    /* Reapply after switching back from another VT, since some X servers
+       reset the ramps then */
+    let vt_watcher = match args.method() {
+        GammaMethodChoice::Randr => match vt::x_session_vt() {
+            Ok(session_vt) => {
+                debug!("X session runs on VT {}", session_vt);
+                Some(VtWatcher::new(Box::new(SysfsVtSource::new()), session_vt))
+            }
+            Err(e) => {
+                debug!("VT switches will not be detected: {}", e);
+                None
+            }
+        },
+        GammaMethodChoice::Dummy => None,
+    };
+
+    /* Control socket for temporary overrides; opt-in */
+    let control = if args.control_socket {
+        match control::socket_path().and_then(|path| ControlServer::bind(&path)) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                warn!("Control socket unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    /* Control FIFO for shell scripts; opt-in */
+    let fifo = if args.control_fifo {
+        match ControlFifo::create(&fifo::fifo_path()) {
+            Ok(fifo) => Some(fifo),
+            Err(e) => {
+                warn!("Control FIFO unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    /* Query socket for widgets; opt-in */
+    let query = args.query_socket.as_deref().and_then(|path| {
+        QueryServer::bind(path)
+            .map_err(|e| warn!("Query socket unavailable: {}", e))
+            .ok()
+    });
+
+    /* Metrics endpoint for scrapers; opt-in */
+    #[cfg(feature = "metrics")]
+    let metrics = args.metrics_addr.as_deref().and_then(|addr| {
+        MetricsServer::bind(addr)
+            .map_err(|e| warn!("Metrics endpoint unavailable: {}", e))
+            .ok()
+    });
+    #[cfg(feature = "metrics")]
+    if let Some(server) = &metrics {
+        info!("Serving metrics on http://{}/metrics", server.local_addr());
+    }
+
+    /* Re-read the INI config on request, with CLI args still taking priority */
+    let reload = move || -> Result<TransitionScheme, String> {
+        let ini_config = config_ini::RedshiftConfig::load()?;
+        if let Some(path) = &cli_args.scheme_file {
+            let bounds = temperature_bounds(&cli_args, &ini_config)?;
+            let scheme_file = SchemeFile::load(path, bounds)?;
+            return resolve_transition_scheme(&cli_args, &ini_config, Some(&scheme_file));
+        }
+        let mut args = cli_args.clone();
+        args.merge_with_ini(&ini_config);
+        resolve_transition_scheme(&args, &ini_config, None)
+    };
+
+    /* Presets cycled through with SIGUSR2, optionally resuming the one
+       selected in the last run */
+    let persist_cycle = ini_config.cycle_persist.unwrap_or(false);
+    let mut cycle = ini_config
+        .cycle_temps
+        .clone()
+        .map(|presets| {
+            presets
+                .into_iter()
+                .filter_map(|temperature| {
+                    bounds
+                        .resolve(temperature)
+                        .map_err(|e| warn!("Ignoring cycle temperature: {}", e))
+                        .ok()
+                })
+                .collect::<Vec<i32>>()
+        })
+        .map(TemperatureCycle::new)
+        .filter(|cycle| !cycle.is_empty())
+        .unwrap_or_default();
+    if persist_cycle {
+        cycle = cycle.with_position(config.cycle_position);
+    }
+
     /* Continual mode - continuously adjust color temperature */
-    run_continual_mode(&location, &scheme, &mut gamma_guard)?;
+    let options = ContinualOptions {
+        applied,
+        idle_dimmer,
+        lock_watcher,
+        color_scheme_watcher,
+        fade_thresholds: ini_config.fade_thresholds(),
+        fade_curve,
+        elevation_hysteresis: ini_config.elevation_hysteresis.unwrap_or(0.0),
+        instant: args.instant,
+        fast_exit: args.fast_exit || ini_config.fade == Some(false),
+        reapply_interval: args.reapply_interval.map(Duration::from_secs),
+        status_interval: args.status_interval.map(Duration::from_millis),
+        fade_thread: args.fade_thread,
+        on_display_loss: args.on_display_loss.unwrap_or_default(),
+        vt_watcher,
+        control,
+        fifo,
+        query,
+        #[cfg(feature = "metrics")]
+        metrics,
+        cycle,
+        persist_cycle,
+        log_format: args.log_format.unwrap_or_default(),
+    };
+    let stats = std::thread::scope(|scope| {
+        run_continual_mode(&location, scheme, &mut gamma_guard, options, &reload, &clock, scope)
+    })?;
+    stats.log("Shutting down after");
 
     Ok(())
 }
 
-/* Run continual mode loop.
-   This is the main loop of the continual mode which keeps track of the
-   current time and continuously updates the screen to the appropriate
-   color temperature. Also handles signals for toggling and clean exit. */
-fn run_continual_mode(
+/* Animate a simulated day from local midnight to midnight over `duration`,
+   using the same timeline and fades as continual mode. Stops early on an
+   exit signal; the guard restores the screen afterwards either way. */
+fn run_demo(
     location: &Location,
     scheme: &TransitionScheme,
     gamma_guard: &mut GammaRestoreGuard,
+    duration: Duration,
+    fade_thresholds: FadeThresholds,
+    fade_curve: FadeCurve,
+    clock: &dyn TimeSource,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let demo_clock = DemoClock::new(demo::local_midnight(clock.now()), duration);
+
+    /* Target color setting at a simulated time */
+    let target_at = |now: f64| {
+        let (_, transition_prog) = get_period_and_progress(location, scheme, now);
+        let mut interp = ColorSetting::default();
+        interpolate_transition_scheme(scheme, transition_prog, &mut interp);
+        apply_deep_night(location, scheme, now, &mut interp);
+        apply_schedule(scheme, now, &mut interp);
+        apply_active_days(scheme, now, &mut interp);
+        interp
+    };
+
+    /* Start at midnight right away instead of fading in from neutral */
+    let mut fade = Fade::with_thresholds(ColorSetting::default(), fade_thresholds);
+    fade.set_curve(fade_curve);
+    fade.set_interpolate_in_mireds(scheme.interpolate_in_mireds);
+    fade.jump(&target_at(demo_clock.simulated_time(Duration::ZERO)));
+
+    info!("Demo: simulating a day in {} seconds", duration.as_secs());
+    let started = Instant::now();
+    let mut prev_hour = -1;
+    loop {
+        if signals::is_exiting() {
+            info!("Exit signal received, stopping demo");
+            break;
+        }
+
+        let elapsed = started.elapsed();
+        let now = demo_clock.simulated_time(elapsed);
+
+        let hour = timeofday::local_seconds_since_midnight(now) / 3600;
+        if hour != prev_hour {
+            debug!("Demo time: {:02}:00", hour);
+            prev_hour = hour;
+        }
+
+        let interp = fade.update(&target_at(now), false);
+        gamma_guard.get_mut().set_temperature(&interp, false)?;
+
+        if demo_clock.is_finished(elapsed) && !fade.is_active() {
+            break;
+        }
+        std::thread::sleep(demo::DEMO_STEP);
+    }
+
+    Ok(())
+}
+
+/* Apply each setting received from standard input with a short fade,
+   until the input ends and the last fade completes or an exit signal
+   arrives. */
+fn run_stdin_control(
+    gamma_guard: &mut GammaRestoreGuard,
+    commands: &Receiver<StdinCommand>,
+    fade_curve: FadeCurve,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    /* Fade parameters */
-    let mut fade_length: i32 = 0;
-    let mut fade_time: i32 = 0;
-    let mut fade_start_interp = ColorSetting::default();
+    let mut fade = Fade::new(ColorSetting::default());
+    fade.set_curve(fade_curve);
+    let mut target = ColorSetting::default();
+    let mut input_open = true;
+
+    info!("Reading settings from standard input");
+    loop {
+        if signals::is_exiting() {
+            info!("Exit signal received, stopping");
+            break;
+        }
+
+        /* Wait for input between fade steps, or briefly when idle so exit
+           signals are still noticed */
+        let step = if fade.is_active() {
+            STDIN_FADE_STEP
+        } else {
+            Duration::from_millis(SLEEP_DURATION_SHORT)
+        };
+        let mut changed = false;
+        if input_open {
+            match commands.recv_timeout(step) {
+                Ok(command) => {
+                    debug!(
+                        "Standard input: {}K, brightness {:.2}",
+                        command.temperature, command.brightness
+                    );
+                    target = command.setting();
+                    changed = true;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => input_open = false,
+            }
+        } else if fade.is_active() {
+            std::thread::sleep(step);
+        } else {
+            break;
+        }
+
+        if changed || fade.is_active() {
+            let interp = fade.update(&target, changed);
+            gamma_guard.get_mut().set_temperature(&interp, false)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where the continual mode loop sends its settings
+enum Output<'a> {
+    /// Faded by the loop and applied as it goes
+    Direct(&'a mut dyn GammaMethod),
+    /// Pushed to a thread that fades and applies them (--fade-thread)
+    Thread(FadeApplier<'a>),
+}
+
+/// Optional behaviour of the continual mode loop
+struct ContinualOptions {
+    /// Setting on the screen when the loop starts
+    applied: ColorSetting,
+    idle_dimmer: Option<IdleDimmer>,
+    lock_watcher: Option<LockWatcher>,
+    color_scheme_watcher: Option<ColorSchemeWatcher>,
+    fade_thresholds: FadeThresholds,
+    fade_curve: FadeCurve,
+    elevation_hysteresis: f64,
+    instant: bool,
+    fast_exit: bool,
+    reapply_interval: Option<Duration>,
+    status_interval: Option<Duration>,
+    fade_thread: bool,
+    on_display_loss: DisplayLossPolicy,
+    vt_watcher: Option<VtWatcher>,
+    control: Option<ControlServer>,
+    fifo: Option<ControlFifo>,
+    query: Option<QueryServer>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<MetricsServer>,
+    cycle: TemperatureCycle,
+    persist_cycle: bool,
+    log_format: LogFormat,
+}
+
+/* Run continual mode loop.
+   This is the main loop of the continual mode which keeps track of the
+   current time and continuously updates the screen to the appropriate
+   color temperature. Also handles signals for toggling and clean exit.
+   `reload` rebuilds the scheme from the config file on request.
+   Returns diagnostic counters for the run. */
+fn run_continual_mode<'scope>(
+    location: &Location,
+    mut scheme: TransitionScheme,
+    gamma_guard: &'scope mut GammaRestoreGuard,
+    options: ContinualOptions,
+    reload: &dyn Fn() -> Result<TransitionScheme, String>,
+    clock: &dyn TimeSource,
+    scope: &'scope Scope<'scope, '_>,
+) -> Result<ContinualStats, Box<dyn std::error::Error>> {
+    let ContinualOptions {
+        applied,
+        mut idle_dimmer,
+        mut lock_watcher,
+        mut color_scheme_watcher,
+        fade_thresholds,
+        fade_curve,
+        elevation_hysteresis,
+        instant,
+        fast_exit,
+        reapply_interval,
+        status_interval,
+        fade_thread,
+        on_display_loss,
+        mut vt_watcher,
+        mut control,
+        mut fifo,
+        query,
+        #[cfg(feature = "metrics")]
+        metrics,
+        mut cycle,
+        persist_cycle,
+        log_format,
+    } = options;
+
+    /* Forced reapplication for drivers that reset the ramps */
+    let mut reapply_timer =
+        reapply_interval.map(|interval| ReapplyTimer::new(interval, Instant::now()));
+
+    /* Steady status samples, independent of fades and updates */
+    let mut status_timer =
+        status_interval.map(|interval| ReapplyTimer::new(interval, Instant::now()));
+
+    /* Fade state, starting from what is on the screen */
+    let mut fade = Fade::with_thresholds(applied, fade_thresholds);
+    fade.set_curve(fade_curve);
+    fade.set_interpolate_in_mireds(scheme.interpolate_in_mireds);
+
+    /* Settings go straight to the gamma method, or with --fade-thread to
+       a thread of their own that steps fades and applies them */
+    let exit_policy = gamma_guard.policy();
+    let mut output = if fade_thread {
+        debug!("Applying fades from a dedicated thread");
+        let step = Duration::from_millis(SLEEP_DURATION_SHORT);
+        Output::Thread(FadeApplier::spawn(
+            scope,
+            fade.clone(),
+            step,
+            gamma_guard.get_mut(),
+            on_display_loss,
+        ))
+    } else {
+        Output::Direct(gamma_guard.get_mut())
+    };
 
     /* Save previous parameters so we can avoid printing status updates if
        the values did not change. */
     let mut prev_period = Period::None;
     let mut prev_target_interp = ColorSetting::default();
-    let mut interp = ColorSetting::default();
+
+    /* Last setting that passed validation, applied instead of invalid ones */
+    let mut last_good_interp = applied;
+
+    /* Diagnostic counters, logged periodically at debug level */
+    let mut stats = ContinualStats::default();
+    let mut stats_logger = StatsLogger::new(STATS_LOG_INTERVAL);
 
     /* State for signal handling */
     let mut disabled = false;
     let mut prev_disabled = true; /* Start as true to trigger initial status print */
     let mut done = false; /* Set to true when starting shutdown fade */
+    let mut first_update = true;
+
+    /* Set by a toggle or other manual change and kept until the next fade
+       update, so an iteration skipped while the screen is locked does not
+       lose it and leave a fade heading for the old target */
+    let mut force_fade = false;
+
+    /* Temporary override requested over the control socket */
+    let mut overrides = OverrideState::new();
+
+    /* Temperature held by a "temp N" FIFO command or a SIGUSR2 preset
+       until enabled again */
+    let mut held_temperature: Option<i32> = cycle.temperature();
+    if let Some(temperature) = held_temperature {
+        info!("Holding preset {}K from the last run", temperature);
+    }
 
     debug!("Starting continual mode loop");
-    debug!("Initial color temperature: {}K, Brightness: {:.2}", interp.temperature, interp.brightness);
+    debug!(
+        "Initial color temperature: {}K, Brightness: {:.2}",
+        fade.current().temperature,
+        fade.current().brightness
+    );
 
     /* Continuously adjust color temperature */
     loop {
-        /* Check for toggle signal (SIGUSR1) */
-        if signals::check_toggle() && !done {
-            disabled = !disabled;
-            info!("Status: {}", if disabled { "Disabled" } else { "Enabled" });
-        }
-
         /* Check for exit signal (SIGINT/SIGTERM) */
         if signals::is_exiting() {
             if done {
                 /* Second signal during fade - stop immediately */
                 debug!("Second exit signal received, stopping immediately");
                 break;
+            } else if exit_policy == ExitPolicy::Keep {
+                /* Fading out would undo the setting that is to be kept */
+                info!("Exit signal received, keeping the current setting");
+                break;
             } else {
-                /* First signal - start shutdown fade */
-                info!("Exit signal received, starting shutdown fade");
+                /* First signal - start shutdown fade, or with fast exit
+                   apply neutral once and stop */
+                if fast_exit {
+                    info!("Exit signal received, restoring immediately");
+                } else {
+                    info!("Exit signal received, starting shutdown fade");
+                }
                 done = true;
                 disabled = true;
                 signals::clear_exiting();
             }
         }
 
+        /* Check for toggle signal (SIGUSR1), ignored once shutting down.
+           Toggling always fades, even when the difference would otherwise
+           be applied instantly. */
+        let mut toggled = false;
+        if signals::check_toggle() && !done {
+            disabled = !disabled;
+            toggled = true;
+        }
+
+        /* Check for cycle signal (SIGUSR2), ignored once shutting down.
+           Each preset holds until the next signal, and the one after the
+           last returns to the schedule. */
+        let cycle_position = cycle.position();
+        if signals::check_cycle() && !done {
+            held_temperature = cycle.advance();
+            disabled = false;
+            toggled = true;
+            match held_temperature {
+                Some(temperature) => info!(
+                    "Holding preset {}K ({} of {})",
+                    temperature,
+                    cycle.position().unwrap_or(0) + 1,
+                    cycle.len()
+                ),
+                None => info!("Returning to schedule"),
+            }
+        }
+
+        /* Handle control socket commands. Starting or ending an override
+           always fades, like toggling. */
+        let monotonic_now = Instant::now();
+        let mut override_changed = false;
+        if let Some(server) = control.as_mut() {
+            for command in server.poll(&scheme.temperature_bounds) {
+                match command {
+                    ControlCommand::SetTemporaryOverride {
+                        temperature,
+                        duration,
+                    } => {
+                        info!(
+                            "Holding {}K for {} seconds",
+                            temperature,
+                            duration.as_secs()
+                        );
+                        overrides.set(temperature, duration, monotonic_now);
+                        override_changed = true;
+                    }
+                }
+            }
+        }
+        if overrides.expire(monotonic_now) {
+            info!("Temporary override expired, returning to schedule");
+            override_changed = true;
+        }
+
+        /* Handle control FIFO commands, ignored once shutting down. Like
+           toggling, every change of state fades. */
+        let fifo_commands = match fifo.as_mut() {
+            Some(fifo) if !done => fifo.poll(&scheme.temperature_bounds),
+            _ => Vec::new(),
+        };
+        for command in fifo_commands {
+            debug!("Control FIFO command: {:?}", command);
+            match command {
+                FifoCommand::Toggle => disabled = !disabled,
+                FifoCommand::Enable => {
+                    disabled = false;
+                    held_temperature = None;
+                    cycle.reset();
+                }
+                FifoCommand::Disable => disabled = true,
+                FifoCommand::Reload => match reload() {
+                    Ok(new_scheme) => {
+                        info!("Reloaded configuration");
+                        fade.set_interpolate_in_mireds(new_scheme.interpolate_in_mireds);
+                        if let Output::Thread(applier) = &mut output {
+                            applier.send(ApplierCommand::InterpolateInMireds(
+                                new_scheme.interpolate_in_mireds,
+                            ));
+                        }
+                        scheme = new_scheme;
+                    }
+                    Err(e) => warn!("Failed to reload configuration: {}", e),
+                },
+                FifoCommand::Temperature(temperature) => {
+                    info!("Holding {}K", temperature);
+                    disabled = false;
+                    held_temperature = Some(temperature);
+                    cycle.reset();
+                }
+            }
+            toggled = true;
+        }
+
+        /* Follow the desktop's dark mode preference, ignored once shutting
+           down. A switch fades like toggling. */
+        if let Some(watcher) = color_scheme_watcher.as_mut().filter(|_| !done) {
+            if let Some(enabled) = watcher.poll() {
+                if disabled == enabled {
+                    disabled = !enabled;
+                    toggled = true;
+                }
+            }
+        }
+
+        /* Reload before saving so the location and other fields are kept;
+           a file that cannot be read is left alone */
+        if persist_cycle && cycle.position() != cycle_position {
+            match Config::load() {
+                Ok(mut config) => {
+                    config.cycle_position = cycle.position();
+                    config.save_or_warn();
+                }
+                Err(e) => warn!("Cycle position not saved: {}", e),
+            }
+        }
+
         /* Print status change */
         if disabled != prev_disabled {
-            info!("Status: {}", if disabled { "Disabled" } else { "Enabled" });
+            match log_format {
+                LogFormat::Human => {
+                    info!("Status: {}", if disabled { "Disabled" } else { "Enabled" })
+                }
+                LogFormat::Kv => info!("{}", ContinualEvent::Toggle { enabled: !disabled }),
+            }
         }
         prev_disabled = disabled;
 
@@ -636,107 +1963,275 @@ fn run_continual_mode(
                 temperature: 6500,
                 brightness: 1.0,
                 gamma: [1.0, 1.0, 1.0],
+                contrast: 1.0,
             }
         } else {
             /* Get current time */
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs_f64();
-
-            /* Current angular elevation of the sun */
-            let elevation = solar::solar_elevation(now, location.lat as f64, location.lon as f64);
-            trace!("Solar elevation: {:.2}°", elevation);
+            let now = clock.now();
 
             /* Determine period and transition progress */
-            let period = if elevation >= scheme.high {
-                Period::Daytime
-            } else if elevation <= scheme.low {
-                Period::Night
-            } else {
-                Period::Transition
-            };
-
-            let transition_prog = get_transition_progress_from_elevation(scheme, elevation);
+            let (period, transition_prog) = get_period_and_progress_held(
+                location,
+                &scheme,
+                now,
+                prev_period,
+                elevation_hysteresis,
+            );
 
             /* Use transition progress to get target color temperature */
             let mut temp_interp = ColorSetting::default();
-            interpolate_transition_scheme(scheme, transition_prog, &mut temp_interp);
+            interpolate_transition_scheme(&scheme, transition_prog, &mut temp_interp);
+            apply_deep_night(location, &scheme, now, &mut temp_interp);
+            apply_schedule(&scheme, now, &mut temp_interp);
+            apply_active_days(&scheme, now, &mut temp_interp);
 
             /* Print period if it changed during this update,
                or if we are in the transition period. In transition we
                print the progress, so we always print it in that case. */
-            if period != prev_period || period == Period::Transition {
+            if log_format == LogFormat::Kv {
+                if period != prev_period {
+                    info!(
+                        "{}",
+                        ContinualEvent::PeriodChange {
+                            from: prev_period,
+                            to: period,
+                            progress: transition_prog,
+                            temperature: temp_interp.temperature,
+                        }
+                    );
+                }
+            } else if period != prev_period || period == Period::Transition {
                 match period {
                     Period::Transition => {
                         info!("Period: Transition ({:.1}%)", transition_prog * 100.0);
-                        debug!("Transition progress: {:.3} (elevation: {:.2}°)", transition_prog, elevation);
+                        debug!("Transition progress: {:.3}", transition_prog);
                     }
                     _ => {
-                        info!("Period: {}", period.name());
+                        /* With an elevation schedule, estimate when the
+                           sun next rises or sets */
+                        let next = if scheme.use_time {
+                            None
+                        } else {
+                            solar::next_sun_transition(now, location.lat, location.lon)
+                        };
+                        match next {
+                            Some((event, time)) => info!(
+                                "Period: {} — {} in {}",
+                                period.name(),
+                                event.name().to_lowercase(),
+                                solar::format_time_until(time - now)
+                            ),
+                            None => info!("Period: {}", period.name()),
+                        }
                     }
                 }
             }
             prev_period = period;
 
+            if let Some(temperature) = held_temperature {
+                temp_interp.temperature = temperature;
+            }
+            if let Some(temperature) = overrides.temperature(monotonic_now) {
+                temp_interp.temperature = temperature;
+            }
+
             temp_interp
         };
 
-        /* Start fade if the parameter differences are too big to apply instantly. */
-        if (fade_length == 0 && color_setting_diff_is_major(&interp, &target_interp))
-            || (fade_length != 0 && color_setting_diff_is_major(&target_interp, &prev_target_interp))
-        {
-            debug!("Starting fade: {} steps", FADE_LENGTH);
-            fade_length = FADE_LENGTH;
-            fade_time = 0;
-            fade_start_interp = interp;
+        /* Dim further while the user is idle, fading on every change of
+           idle state so activity smoothly restores the brightness. */
+        let mut idle_changed = false;
+        if let Some(dimmer) = idle_dimmer.as_mut() {
+            idle_changed = dimmer.poll();
+            if !disabled {
+                dimmer.apply(&mut target_interp);
+            }
         }
 
-        /* Handle ongoing fade */
-        if fade_length != 0 {
-            fade_time += 1;
-            let frac = fade_time as f64 / fade_length as f64;
-            let alpha = ease_fade(frac).max(0.0).min(1.0);
-
-            interpolate_color_settings(&fade_start_interp, &target_interp, alpha, &mut interp);
-            trace!("Fade progress: {}/{} (alpha: {:.3})", fade_time, fade_length, alpha);
+        force_fade |= toggled || idle_changed || override_changed;
 
-            if fade_time > fade_length {
-                debug!("Fade complete");
-                fade_time = 0;
-                fade_length = 0;
+        /* Hold the last applied setting while the screen is locked. The
+           fade resumes from where it stopped once unlocked. */
+        if let Some(watcher) = lock_watcher.as_mut() {
+            watcher.poll();
+            if watcher.should_pause(done) {
+                if let Output::Thread(applier) = &mut output {
+                    applier.send(ApplierCommand::Hold);
+                }
+                wait_with_status(
+                    status_timer.as_mut(),
+                    Instant::now() + Duration::from_millis(SLEEP_DURATION),
+                    Instant::now,
+                    signals::sleep_unless_signalled,
+                    || print_status(log_format, &last_good_interp, prev_period, disabled),
+                );
+                continue;
             }
+        }
+
+        /* Start or continue a fade if the parameter differences are too
+           big to apply instantly. With --instant the first target is
+           applied directly and only later changes fade. */
+        let jump = if instant && first_update {
+            debug!("Applying initial target without fade");
+            true
         } else {
-            interp = target_interp;
+            done && fast_exit
+        };
+        let force = force_fade;
+        first_update = false;
+        force_fade = false;
+
+        let mut reapply = false;
+        if let Some(timer) = reapply_timer.as_mut() {
+            if timer.check(Instant::now()) {
+                trace!("Reapplying gamma ramps");
+                reapply = true;
+            }
+        }
+        if let Some(watcher) = vt_watcher.as_mut() {
+            if watcher.poll() {
+                info!("Switched back to the X session, reapplying gamma ramps");
+                reapply = true;
+            }
+        }
+
+        let fades_before = stats.fades_started;
+        let (applied_before, interp) = match &mut output {
+            Output::Direct(gamma) => {
+                let applied_before = fade.current().temperature;
+                let interp = if jump {
+                    fade.jump(&target_interp)
+                } else {
+                    fade.update(&target_interp, force)
+                };
+
+                /* Adjust temperature, never applying NaN or out-of-range
+                   values */
+                let interp = match interp.validate_within(&scheme.temperature_bounds) {
+                    Ok(()) => interp,
+                    Err(e) => {
+                        error!("Invalid color setting, keeping last good one: {}", e);
+                        stats.rejected += 1;
+                        last_good_interp
+                    }
+                };
+                if reapply {
+                    gamma.invalidate();
+                }
+                apply_recovering(
+                    *gamma,
+                    &interp,
+                    on_display_loss,
+                    RECONNECT_ATTEMPTS,
+                    RECONNECT_DELAY,
+                    &mut signals::sleep_unless_exiting,
+                )?;
+                stats.applies += 1;
+                stats.fades_started = fade.fades_started();
+                (applied_before, interp)
+            }
+            Output::Thread(applier) => {
+                /* The thread validates and applies, so report what it
+                   has done so far */
+                let applied_before = applier.state().current.temperature;
+                if reapply {
+                    applier.send(ApplierCommand::Reapply);
+                }
+                applier.send(if jump {
+                    ApplierCommand::Jump(target_interp)
+                } else {
+                    ApplierCommand::Target {
+                        setting: target_interp,
+                        force,
+                    }
+                });
+
+                let state = applier.state();
+                if let Some(e) = state.error {
+                    return Err(e.into());
+                }
+                if state.stopped {
+                    return Err("Fade thread stopped unexpectedly".into());
+                }
+                stats.applies = state.applies;
+                stats.rejected = state.rejected;
+                stats.fades_started = state.fades_started;
+                (applied_before, state.current)
+            }
+        };
+        let fading = match &output {
+            Output::Direct(_) => fade.is_active(),
+            Output::Thread(applier) => !applier.is_settled(),
+        };
+
+        if log_format == LogFormat::Kv && stats.fades_started != fades_before {
+            info!(
+                "{}",
+                ContinualEvent::FadeStart {
+                    from: applied_before,
+                    to: target_interp.temperature,
+                }
+            );
         }
 
-        if prev_target_interp.temperature != target_interp.temperature {
+        if log_format == LogFormat::Human
+            && prev_target_interp.temperature != target_interp.temperature
+        {
             info!("Color temperature: {}K", target_interp.temperature);
         }
         if prev_target_interp.brightness != target_interp.brightness {
             debug!("Brightness: {:.2}", target_interp.brightness);
         }
 
-        /* Adjust temperature */
-        gamma_guard.get_mut().set_temperature(&interp, false)?;
+        last_good_interp = interp;
+        if let Some(server) = query.as_ref() {
+            server.update(QuerySnapshot {
+                temperature: interp.temperature,
+                brightness: interp.brightness,
+                period: prev_period,
+            });
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(server) = metrics.as_ref() {
+            server.update(MetricsSnapshot {
+                temperature: interp.temperature,
+                brightness: interp.brightness,
+                enabled: !disabled,
+                solar_elevation: solar::solar_elevation(clock.now(), location.lat, location.lon),
+            });
+        }
+        stats_logger.maybe_log(&stats, Instant::now());
 
         /* Save target color setting as previous */
         prev_target_interp = target_interp;
 
         /* If shutdown was requested and fade is complete, exit */
-        if done && fade_length == 0 {
+        if done && !fading {
             break;
         }
 
         /* Sleep length depends on whether a fade is ongoing. */
-        let delay = if fade_length != 0 {
+        let mut delay = Duration::from_millis(if fading {
             SLEEP_DURATION_SHORT
         } else {
             SLEEP_DURATION
-        };
+        });
 
-        std::thread::sleep(Duration::from_millis(delay));
+        /* Wake in time for a reapply that falls due before the next update */
+        if let Some(timer) = reapply_timer.as_ref() {
+            delay = delay.min(timer.remaining(Instant::now()));
+        }
+
+        /* Sample the applied setting on its own cadence while waiting */
+        wait_with_status(
+            status_timer.as_mut(),
+            Instant::now() + delay,
+            Instant::now,
+            signals::sleep_unless_signalled,
+            || print_status(log_format, &last_good_interp, prev_period, disabled),
+        );
     }
 
-    Ok(())
+    Ok(stats)
 }
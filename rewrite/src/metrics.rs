@@ -0,0 +1,175 @@
+/// Prometheus metrics endpoint for continual mode
+/// A minimal HTTP server answering GET /metrics with gauges in the text
+/// exposition format. Only built with the `metrics` feature, and written
+/// against std alone so it adds no dependencies.
+
+use log::{debug, warn};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Values exported to scrapers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricsSnapshot {
+    pub temperature: i32,
+    pub brightness: f32,
+    pub enabled: bool,
+    pub solar_elevation: f64,
+}
+
+impl MetricsSnapshot {
+    /// Render as gauges in the Prometheus text exposition format
+    pub fn render_exposition(&self) -> String {
+        let gauges = [
+            (
+                "redshift_temperature_kelvin",
+                "Color temperature currently applied",
+                self.temperature.to_string(),
+            ),
+            (
+                "redshift_brightness",
+                "Brightness currently applied",
+                format!("{:.3}", self.brightness),
+            ),
+            (
+                "redshift_enabled",
+                "1 if the adjustment is enabled, 0 if toggled off",
+                (self.enabled as u8).to_string(),
+            ),
+            (
+                "redshift_solar_elevation_degrees",
+                "Elevation of the sun at the location",
+                format!("{:.3}", self.solar_elevation),
+            ),
+        ];
+
+        gauges
+            .iter()
+            .map(|(name, help, value)| {
+                format!("# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n")
+            })
+            .collect()
+    }
+}
+
+/// Listening end of the metrics endpoint
+pub struct MetricsServer {
+    snapshot: Arc<Mutex<Option<MetricsSnapshot>>>,
+    shutdown: Arc<AtomicBool>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+    addr: SocketAddr,
+}
+
+impl MetricsServer {
+    /// Listen on `addr`, e.g. 127.0.0.1:9099, and start answering scrapes
+    pub fn bind(addr: &str) -> Result<Self, String> {
+        let listener =
+            TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+
+        let snapshot = Arc::new(Mutex::new(None));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let snapshot = Arc::clone(&snapshot);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || serve(listener, snapshot, shutdown))
+        };
+
+        Ok(Self {
+            snapshot,
+            shutdown,
+            thread_handle: Some(handle),
+            addr,
+        })
+    }
+
+    /// Address actually listened on, with the port filled in if 0 was given
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Export `snapshot` from now on
+    pub fn update(&self, snapshot: MetricsSnapshot) {
+        *self.snapshot.lock().unwrap() = Some(snapshot);
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        // Wake the blocking accept so the thread sees the shutdown flag
+        self.shutdown.store(true, Ordering::SeqCst);
+        TcpStream::connect(self.addr).ok();
+
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Answer each scrape with the latest snapshot until shut down. Scrapes
+/// before the first update get 503, so they show up as failed rather than
+/// as made-up values.
+fn serve(
+    listener: TcpListener,
+    snapshot: Arc<Mutex<Option<MetricsSnapshot>>>,
+    shutdown: Arc<AtomicBool>,
+) {
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Metrics endpoint: {}", e);
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+
+        let snapshot = *snapshot.lock().unwrap();
+        if let Err(e) = respond(stream, snapshot) {
+            debug!("Metrics client went away: {}", e);
+        }
+    }
+}
+
+/// Read one request from `stream` and write the response
+fn respond(mut stream: TcpStream, snapshot: Option<MetricsSnapshot>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    /* Only the request line matters; read until the end of the headers */
+    let mut request = Vec::new();
+    let mut buf = [0u8; 512];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (status, body) = match (method, path, snapshot) {
+        ("GET", "/metrics", Some(snapshot)) => ("200 OK", snapshot.render_exposition()),
+        ("GET", "/metrics", None) => ("503 Service Unavailable", "No data yet\n".to_string()),
+        _ => ("404 Not Found", "Not found\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
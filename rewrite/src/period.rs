@@ -0,0 +1,158 @@
+/// Period and target color setting at a point in time
+/// Shared by continual, one-shot, print and demo mode, which differ only in
+/// where "now" comes from.
+
+use crate::clock::TimeSource;
+use crate::fade;
+use crate::solar;
+use crate::timeofday;
+use crate::types::{ColorSetting, Location, Period, TransitionScheme};
+use log::trace;
+
+/// Period and target color setting at the time read from `clock`
+pub fn get_current_period(
+    location: &Location,
+    scheme: &TransitionScheme,
+    clock: &dyn TimeSource,
+) -> (Period, ColorSetting) {
+    let now = clock.now();
+
+    let (period, transition_prog) = get_period_and_progress(location, scheme, now);
+
+    let mut color_setting = match period {
+        Period::Daytime => scheme.day,
+        Period::Night => scheme.night,
+        _ => {
+            let mut interp = ColorSetting::default();
+            interpolate_transition_scheme(scheme, transition_prog, &mut interp);
+            interp
+        }
+    };
+    apply_deep_night(location, scheme, now, &mut color_setting);
+    apply_schedule(scheme, now, &mut color_setting);
+    apply_active_days(scheme, now, &mut color_setting);
+
+    (period, color_setting)
+}
+
+/// Determine the period and transition progress (0.0 night to 1.0 day) at
+/// the given time. With dawn/dusk times configured this uses the local
+/// wall-clock time, otherwise the solar elevation at the location.
+pub fn get_period_and_progress(
+    location: &Location,
+    scheme: &TransitionScheme,
+    now: f64,
+) -> (Period, f64) {
+    get_period_and_progress_held(location, scheme, now, Period::None, 0.0)
+}
+
+/// Like get_period_and_progress, but keeps `previous` while the solar
+/// elevation is within `hysteresis` degrees of the threshold it crossed
+/// (see solar::apply_period_hysteresis).
+pub fn get_period_and_progress_held(
+    location: &Location,
+    scheme: &TransitionScheme,
+    now: f64,
+    previous: Period,
+    hysteresis: f64,
+) -> (Period, f64) {
+    if scheme.use_time {
+        let time_offset = timeofday::local_seconds_since_midnight(now);
+        trace!("Seconds since local midnight: {}", time_offset);
+
+        return (
+            timeofday::period_from_time(scheme, time_offset),
+            timeofday::transition_progress_from_time(scheme, time_offset),
+        );
+    }
+
+    /* Current angular elevation of the sun */
+    let elevation = solar::solar_elevation(now, location.lat, location.lon);
+    trace!("Solar elevation: {:.2}°", elevation);
+
+    let table = solar::solar_table_fill(now, location.lat, location.lon);
+    let period = solar::classify_period(scheme, elevation, &table);
+    let period = solar::apply_period_hysteresis(scheme, previous, period, elevation, hysteresis);
+
+    let transition_prog = match period {
+        Period::Daytime => 1.0,
+        Period::Night => 0.0,
+        _ => solar::transition_progress_from_elevation(scheme, elevation),
+    };
+
+    (period, transition_prog)
+}
+
+/// Warm the night towards the scheme's deep night temperature, if any,
+/// once the sun is below the low transition elevation
+pub fn apply_deep_night(
+    location: &Location,
+    scheme: &TransitionScheme,
+    now: f64,
+    setting: &mut ColorSetting,
+) {
+    if scheme.deep_night_temp.is_none() || scheme.use_time {
+        return;
+    }
+    let elevation = solar::solar_elevation(now, location.lat, location.lon);
+    if let Some(temperature) = solar::deep_night_temperature(scheme, elevation) {
+        trace!("Deep night temperature: {}K", temperature);
+        setting.temperature = temperature;
+    }
+}
+
+/// Take the temperature from the [schedule] keyframes, if any, at the
+/// given unix time. Brightness and gamma still follow the period.
+pub fn apply_schedule(scheme: &TransitionScheme, now: f64, setting: &mut ColorSetting) {
+    let time_offset = timeofday::local_seconds_since_midnight(now);
+    if let Some(temperature) = timeofday::temperature_from_keyframes(&scheme.keyframes, time_offset) {
+        trace!("Schedule temperature: {}K", temperature);
+        setting.temperature = temperature;
+    }
+}
+
+/// Hold the neutral setting when the local day at the given unix time is
+/// not one of the scheme's active days
+pub fn apply_active_days(scheme: &TransitionScheme, now: f64, setting: &mut ColorSetting) {
+    if let Some(days) = scheme.active_days {
+        if !days.contains_local(now) {
+            trace!("{} is not an active day", timeofday::local_weekday(now));
+            *setting = ColorSetting::default();
+        }
+    }
+}
+
+/// Use transition progress to interpolate color settings.
+/// Progress from 0.0 (night) to 1.0 (day).
+pub fn interpolate_transition_scheme(
+    scheme: &TransitionScheme,
+    progress: f64,
+    result: &mut ColorSetting,
+) {
+    let alpha = progress.clamp(0.0, 1.0);
+
+    /* Multi-stage schemes pass through the twilight breakpoints, so map
+       the progress back onto the elevation range */
+    if let (Some(stages), false) = (scheme.twilight, scheme.use_time) {
+        let elevation = scheme.low + alpha * (scheme.high - scheme.low);
+        *result = solar::twilight_color_setting(scheme, &stages, elevation);
+        return;
+    }
+
+    result.temperature = fade::interpolate_temperature(
+        scheme.night.temperature,
+        scheme.day.temperature,
+        alpha,
+        scheme.interpolate_in_mireds,
+    );
+    result.brightness = ((1.0 - alpha) * (scheme.night.brightness as f64)
+        + alpha * (scheme.day.brightness as f64)) as f32;
+    result.gamma[0] = ((1.0 - alpha) * (scheme.night.gamma[0] as f64)
+        + alpha * (scheme.day.gamma[0] as f64)) as f32;
+    result.gamma[1] = ((1.0 - alpha) * (scheme.night.gamma[1] as f64)
+        + alpha * (scheme.day.gamma[1] as f64)) as f32;
+    result.gamma[2] = ((1.0 - alpha) * (scheme.night.gamma[2] as f64)
+        + alpha * (scheme.day.gamma[2] as f64)) as f32;
+    result.contrast = ((1.0 - alpha) * (scheme.night.contrast as f64)
+        + alpha * (scheme.day.contrast as f64)) as f32;
+}
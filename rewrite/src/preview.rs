@@ -0,0 +1,98 @@
+/// Previewing the schedule at another location
+/// --preview-city prints a city's solar events and an hourly timeline of
+/// the target setting for today, computed as continual mode would at each
+/// hour, without touching the screen or saving the location. Times are on
+/// this machine's local clock and labelled so.
+
+use crate::clock::FixedClock;
+use crate::period::get_current_period;
+use crate::solar::{self, SolarTime};
+use crate::timeofday::{format_local_time, format_local_utc_offset};
+use crate::types::{ColorSetting, Location, Period, TransitionScheme};
+
+/// Interval between timeline entries in seconds
+pub const TIMELINE_STEP: f64 = 3600.0;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 3600.0;
+
+/// Period and target setting at one point of the timeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineEntry {
+    pub time: f64,
+    pub period: Period,
+    pub setting: ColorSetting,
+}
+
+/// Period and target setting every `step` seconds through the day starting
+/// at unix time `day_start`
+pub fn day_timeline(
+    location: &Location,
+    scheme: &TransitionScheme,
+    day_start: f64,
+    step: f64,
+) -> Vec<TimelineEntry> {
+    let steps = (SECONDS_PER_DAY / step).ceil() as usize;
+    (0..steps)
+        .map(|index| {
+            let time = day_start + index as f64 * step;
+            let (period, setting) = get_current_period(location, scheme, &FixedClock(time));
+            TimelineEntry {
+                time,
+                period,
+                setting,
+            }
+        })
+        .collect()
+}
+
+/// Solar events of the day starting at unix time `day_start`, in the order
+/// they happen; events the sun does not reach come last
+pub fn day_events(location: &Location, day_start: f64) -> Vec<(SolarTime, Option<f64>)> {
+    /* Solar tables cover a UTC day, so take the one around local noon */
+    let utc_day = ((day_start + SECONDS_PER_DAY / 2.0) / SECONDS_PER_DAY).floor() * SECONDS_PER_DAY;
+
+    let mut events = solar::events_today(utc_day, location.lat, location.lon);
+    events.sort_by(|a, b| match (a.1, b.1) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    events
+}
+
+/// Render the preview for `name` as printed by --preview-city. The city's
+/// time zone is not known, so times are labelled as this machine's.
+pub fn format_preview(
+    name: &str,
+    location: &Location,
+    events: &[(SolarTime, Option<f64>)],
+    timeline: &[TimelineEntry],
+) -> String {
+    let mut out = format!("{} ({:.4}, {:.4})\n", name, location.lat, location.lon);
+    if let Some(first) = timeline.first() {
+        out.push_str(&format!(
+            "Times are this machine's local time ({}), not the city's.\n",
+            format_local_utc_offset(first.time)
+        ));
+    }
+
+    out.push_str("\nSolar events (local time):\n");
+    for (event, time) in events {
+        let time = match time {
+            Some(time) => format_local_time(*time),
+            None => "not reached".to_string(),
+        };
+        out.push_str(&format!("  {:<18} {}\n", event.name(), time));
+    }
+
+    out.push_str("\nTimeline (local time):\n");
+    for entry in timeline {
+        out.push_str(&format!(
+            "  {}  {:>5}K  {:.2}  {}\n",
+            format_local_time(entry.time),
+            entry.setting.temperature,
+            entry.setting.brightness,
+            entry.period.name()
+        ));
+    }
+    out
+}
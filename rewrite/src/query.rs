@@ -0,0 +1,145 @@
+/// Query socket reporting the current setting of a continual mode instance
+/// Every connection gets one `TEMPERATURE BRIGHTNESS PERIOD` line, e.g.
+/// "4200 0.90 transition", and is closed, so widgets can poll it cheaply.
+/// Clients are served from a separate thread so they never wait for the
+/// main loop to wake up. The thread polls a nonblocking listener, so it
+/// stops on shutdown even when the socket file was removed.
+
+use crate::types::Period;
+use log::{debug, warn};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Setting reported to query clients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuerySnapshot {
+    pub temperature: i32,
+    pub brightness: f32,
+    pub period: Period,
+}
+
+impl QuerySnapshot {
+    /// Format as the line sent to clients
+    pub fn line(&self) -> String {
+        format!(
+            "{} {:.2} {}\n",
+            self.temperature,
+            self.brightness,
+            self.period.name().to_lowercase()
+        )
+    }
+}
+
+/// Wait between checks for new clients and for shutdown
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest a slow client may hold up the reply
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Listening end of the query socket
+pub struct QueryServer {
+    snapshot: Arc<Mutex<Option<QuerySnapshot>>>,
+    shutdown: Arc<AtomicBool>,
+    thread_handle: Option<thread::JoinHandle<()>>,
+    path: PathBuf,
+}
+
+impl QueryServer {
+    /// Bind the query socket at `path` and start answering clients.
+    /// A stale socket left by a crashed instance is replaced, but a socket
+    /// another running instance is listening on is not, and neither is a
+    /// file that is not a socket.
+    pub fn bind(path: &Path) -> Result<Self, String> {
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("{} exists and is not a socket", path.display()));
+            }
+            if UnixStream::connect(path).is_ok() {
+                return Err(format!("Another instance is listening on {}", path.display()));
+            }
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Failed to remove stale socket: {}", e))?;
+        }
+
+        let listener = UnixListener::bind(path)
+            .map_err(|e| format!("Failed to bind {}: {}", path.display(), e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure query socket: {}", e))?;
+
+        let snapshot = Arc::new(Mutex::new(None));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let snapshot = Arc::clone(&snapshot);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || serve(listener, snapshot, shutdown))
+        };
+
+        debug!("Answering queries on {}", path.display());
+        Ok(Self {
+            snapshot,
+            shutdown,
+            thread_handle: Some(handle),
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Report `snapshot` to clients from now on
+    pub fn update(&self, snapshot: QuerySnapshot) {
+        *self.snapshot.lock().unwrap() = Some(snapshot);
+    }
+}
+
+impl Drop for QueryServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Answer each client with the latest snapshot until shut down. Clients
+/// connecting before the first update get no reply.
+fn serve(
+    listener: UnixListener,
+    snapshot: Arc<Mutex<Option<QuerySnapshot>>>,
+    shutdown: Arc<AtomicBool>,
+) {
+    while !shutdown.load(Ordering::SeqCst) {
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_INTERVAL);
+                continue;
+            }
+            Err(e) => {
+                warn!("Query socket: {}", e);
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+
+        let line = snapshot.lock().unwrap().map(|snapshot| snapshot.line());
+        if let Some(line) = line {
+            if let Err(e) = stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+            {
+                debug!("Query client could not be configured: {}", e);
+                continue;
+            }
+            if let Err(e) = stream.write_all(line.as_bytes()) {
+                debug!("Query client went away: {}", e);
+            }
+        }
+    }
+}
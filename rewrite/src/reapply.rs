@@ -0,0 +1,37 @@
+/// Periodic reapplication of the gamma ramps
+/// Some drivers reset the ramps on their own, so with --reapply-interval
+/// the current setting is sent again even when nothing changed
+
+use std::time::{Duration, Instant};
+
+/// Tracks when the ramps are next due to be reapplied.
+/// Times are passed in so the schedule can be tested without waiting.
+#[derive(Debug, Clone, Copy)]
+pub struct ReapplyTimer {
+    interval: Duration,
+    last: Instant,
+}
+
+impl ReapplyTimer {
+    /// Start the timer with the ramps applied at `now`
+    pub fn new(interval: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            last: now,
+        }
+    }
+
+    /// Return true and restart the interval if a reapply is due at `now`
+    pub fn check(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last) < self.interval {
+            return false;
+        }
+        self.last = now;
+        true
+    }
+
+    /// Time left at `now` until the next reapply is due
+    pub fn remaining(&self, now: Instant) -> Duration {
+        (self.last + self.interval).saturating_duration_since(now)
+    }
+}
@@ -0,0 +1,52 @@
+/// Transition schemes read from a TOML file
+/// For schemes richer than the INI format expresses comfortably, e.g.
+/// keyframes or twilight stages. The file is deserialized directly into a
+/// scheme instead of going through the INI translation.
+
+use crate::colorramp::Quantization;
+use crate::error::{Error, Result};
+use crate::types::{TemperatureBounds, TransitionScheme, TransitionSchemeBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Contents of a scheme file: the scheme's fields at the top level plus
+/// settings that are not part of the scheme itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchemeFile {
+    #[serde(flatten)]
+    pub scheme: TransitionScheme,
+
+    /// Remove blue entirely below this temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blue_cutoff_temp: Option<i32>,
+
+    /// How ramp values are rounded to 16 bits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantization: Option<Quantization>,
+}
+
+impl SchemeFile {
+    /// Parse a scheme file and validate its scheme against `bounds`,
+    /// clamping out-of-range values instead if they say so
+    pub fn parse(contents: &str, bounds: TemperatureBounds) -> Result<Self> {
+        let mut file: SchemeFile = toml::from_str(contents)
+            .map_err(|e| Error::Config(format!("Failed to parse scheme file: {}", e)))?;
+
+        file.scheme = TransitionSchemeBuilder::from_scheme(file.scheme)
+            .temperature_bounds(bounds)
+            .clamp_invalid(bounds.clamp)
+            .build()
+            .map_err(|e| Error::Config(format!("Invalid scheme file: {}", e)))?;
+
+        Ok(file)
+    }
+
+    /// Read and parse the scheme file at `path`
+    pub fn load(path: &Path, bounds: TemperatureBounds) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            Error::Config(format!("Failed to read scheme file {}: {}", path.display(), e))
+        })?;
+        Self::parse(&contents, bounds)
+    }
+}
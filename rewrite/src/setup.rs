@@ -0,0 +1,143 @@
+/// First-run setup wizard
+/// Walks new users through location, temperatures and gamma method, then
+/// writes a starter redshift.conf and the saved location config
+
+use crate::cities;
+use crate::config::{Config, LocationSource};
+use crate::config_ini::RedshiftConfig;
+use crate::gamma::GammaMethod;
+use crate::gamma_randr::RandrGammaMethod;
+use crate::types::Location;
+use dialoguer::{Confirm, Select};
+use log::debug;
+use std::fs;
+
+/// Day/night temperature presets offered by the wizard
+pub const TEMPERATURE_PRESETS: &[(&str, i32, i32)] = &[
+    ("Subtle", 6500, 4500),
+    ("Balanced (default)", 6500, 3500),
+    ("Warm", 5500, 2700),
+    ("Very warm", 4500, 2000),
+];
+
+/// Choices made in the wizard
+#[derive(Debug, Clone)]
+pub struct SetupChoices {
+    pub location: Location,
+    pub city_name: String,
+    pub temp_day: i32,
+    pub temp_night: i32,
+    pub method: String,
+}
+
+/// Render the starter INI config for the given choices
+pub fn render_config(choices: &SetupChoices) -> String {
+    format!(
+        "; Generated by redshift --setup\n\
+         [redshift]\n\
+         temp-day={}\n\
+         temp-night={}\n\
+         adjustment-method={}\n\
+         location-provider=manual\n\
+         \n\
+         ; {}\n\
+         [manual]\n\
+         lat={:.4}\n\
+         lon={:.4}\n",
+        choices.temp_day,
+        choices.temp_night,
+        choices.method,
+        choices.city_name,
+        choices.location.lat,
+        choices.location.lon,
+    )
+}
+
+/// Return the gamma methods that can be initialized on this system
+fn probe_gamma_methods() -> Vec<&'static str> {
+    let mut methods = Vec::new();
+
+    let mut randr = RandrGammaMethod::new();
+    match randr.init() {
+        Ok(()) => methods.push("randr"),
+        Err(e) => debug!("RandR unavailable: {}", e),
+    }
+
+    /* Always available, for testing */
+    methods.push("dummy");
+    methods
+}
+
+/// Run the interactive setup wizard
+pub fn run_setup() -> Result<(), String> {
+    println!("\nRedshift setup\n");
+
+    let ini_path = RedshiftConfig::default_config_path()?;
+    if ini_path.exists() {
+        let overwrite = Confirm::new()
+            .with_prompt(format!("{} already exists. Overwrite it?", ini_path.display()))
+            .default(false)
+            .interact()
+            .map_err(|e| format!("Confirmation failed: {}", e))?;
+        if !overwrite {
+            println!("Setup cancelled, existing configuration kept.");
+            return Ok(());
+        }
+    }
+
+    /* Location */
+    let country = cities::select_country()
+        .map_err(|e| format!("Country selection failed: {}", e))?;
+    let city = cities::select_city(&country)?;
+    let location = Location {
+        lat: city.latitude().map_err(|e| format!("Invalid latitude: {}", e))?,
+        lon: city.longitude().map_err(|e| format!("Invalid longitude: {}", e))?,
+    };
+
+    /* Temperatures */
+    let preset_names: Vec<String> = TEMPERATURE_PRESETS
+        .iter()
+        .map(|(name, day, night)| format!("{} ({}K day, {}K night)", name, day, night))
+        .collect();
+    let preset = Select::new()
+        .with_prompt("Select a color temperature preset")
+        .items(&preset_names)
+        .default(1)
+        .interact()
+        .map_err(|e| format!("Selection failed: {}", e))?;
+    let (_, temp_day, temp_night) = TEMPERATURE_PRESETS[preset];
+
+    /* Gamma method */
+    let methods = probe_gamma_methods();
+    let method = Select::new()
+        .with_prompt("Select an adjustment method")
+        .items(&methods)
+        .default(0)
+        .interact()
+        .map_err(|e| format!("Selection failed: {}", e))?;
+
+    let choices = SetupChoices {
+        location,
+        city_name: city.display_name(),
+        temp_day,
+        temp_night,
+        method: methods[method].to_string(),
+    };
+
+    /* Write INI config */
+    if let Some(parent) = ini_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    fs::write(&ini_path, render_config(&choices))
+        .map_err(|e| format!("Failed to write {}: {}", ini_path.display(), e))?;
+    println!("\nWrote {}", ini_path.display());
+
+    /* Save location to the TOML config as well */
+    let mut config = Config::load().unwrap_or_default();
+    config.set_location(location, LocationSource::Interactive, Some(choices.city_name));
+    config.save()?;
+    println!("Saved location to {}", Config::config_path()?.display());
+
+    Ok(())
+}
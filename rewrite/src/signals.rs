@@ -3,17 +3,20 @@
  *
  * Signals handled:
  * - SIGUSR1: Toggle between enabled/disabled state (restores gamma when disabled)
+ * - SIGUSR2: Advance to the next preset temperature, or back to the schedule
  * - SIGINT/SIGTERM: Clean shutdown with gamma restoration
  */
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /* Global atomic flags for signal state.
  * These are safe to access from signal handlers and main thread. */
 lazy_static::lazy_static! {
     static ref EXITING: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
     static ref TOGGLE_REQUESTED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    static ref CYCLE_REQUESTED: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
 }
 
 /* Install signal handlers.
@@ -29,6 +32,9 @@ pub fn install_handlers() -> Result<(), Box<dyn std::error::Error>> {
     /* SIGUSR1 sets the toggle flag */
     flag::register(SIGUSR1, Arc::clone(&TOGGLE_REQUESTED))?;
 
+    /* SIGUSR2 sets the cycle flag */
+    flag::register(SIGUSR2, Arc::clone(&CYCLE_REQUESTED))?;
+
     Ok(())
 }
 
@@ -44,6 +50,13 @@ pub fn check_toggle() -> bool {
     TOGGLE_REQUESTED.swap(false, Ordering::SeqCst)
 }
 
+/* Check if a cycle signal (SIGUSR2) was received.
+ * This returns true only once per signal, then clears the flag. Signals
+ * arriving between two checks count as one. */
+pub fn check_cycle() -> bool {
+    CYCLE_REQUESTED.swap(false, Ordering::SeqCst)
+}
+
 /* Check if a toggle was requested without clearing the flag.
  * Used for testing/polling. */
 #[allow(dead_code)]
@@ -61,3 +74,38 @@ pub fn clear_toggle() {
 pub fn clear_exiting() {
     EXITING.store(false, Ordering::SeqCst);
 }
+
+/* Check if any signal is waiting to be handled, without clearing it. */
+pub fn is_signal_pending() -> bool {
+    is_exiting()
+        || TOGGLE_REQUESTED.load(Ordering::SeqCst)
+        || CYCLE_REQUESTED.load(Ordering::SeqCst)
+}
+
+/* Sleep for `duration`, waking early if an exit signal arrives.
+ * Returns false if the sleep was cut short by SIGINT or SIGTERM. */
+pub fn sleep_unless_exiting(duration: Duration) -> bool {
+    sleep_unless(duration, is_exiting)
+}
+
+/* Sleep for `duration`, waking early once any signal is waiting to be
+ * handled. Returns false if the sleep was cut short. */
+pub fn sleep_unless_signalled(duration: Duration) -> bool {
+    sleep_unless(duration, is_signal_pending)
+}
+
+fn sleep_unless(duration: Duration, interrupted: fn() -> bool) -> bool {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    let deadline = Instant::now() + duration;
+    loop {
+        if interrupted() {
+            return false;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        std::thread::sleep(remaining.min(POLL_INTERVAL));
+    }
+}
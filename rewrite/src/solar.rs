@@ -3,6 +3,8 @@
 /// Based on equations from "Astronomical Algorithms" by Jean Meeus
 /// Originally from U.S. Department of Commerce, NOAA
 
+use crate::fade::{interpolate_color_settings_with, interpolate_temperature};
+use crate::types::{ColorSetting, Period, TransitionScheme, TwilightStages};
 use std::f64::consts::PI;
 
 /// Model of atmospheric refraction near horizon (in degrees)
@@ -13,7 +15,7 @@ pub const SOLAR_NAUT_TWILIGHT_ELEV: f64 = -12.0;
 pub const SOLAR_CIVIL_TWILIGHT_ELEV: f64 = -6.0;
 pub const SOLAR_DAYTIME_ELEV: f64 = 0.0 - SOLAR_ATM_REFRAC;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SolarTime {
     Noon,
     Midnight,
@@ -28,6 +30,35 @@ pub enum SolarTime {
 }
 
 impl SolarTime {
+    /// Every event, in the order of the table from `solar_table_fill`
+    pub const ALL: [SolarTime; 10] = [
+        SolarTime::Noon,
+        SolarTime::Midnight,
+        SolarTime::AstroDawn,
+        SolarTime::NautDawn,
+        SolarTime::CivilDawn,
+        SolarTime::Sunrise,
+        SolarTime::Sunset,
+        SolarTime::CivilDusk,
+        SolarTime::NautDusk,
+        SolarTime::AstroDusk,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SolarTime::Noon => "Noon",
+            SolarTime::Midnight => "Midnight",
+            SolarTime::AstroDawn => "Astronomical dawn",
+            SolarTime::NautDawn => "Nautical dawn",
+            SolarTime::CivilDawn => "Civil dawn",
+            SolarTime::Sunrise => "Sunrise",
+            SolarTime::Sunset => "Sunset",
+            SolarTime::CivilDusk => "Civil dusk",
+            SolarTime::NautDusk => "Nautical dusk",
+            SolarTime::AstroDusk => "Astronomical dusk",
+        }
+    }
+
     fn angle(&self) -> f64 {
         let angle_deg = match self {
             SolarTime::Noon => 0.0,
@@ -255,3 +286,177 @@ pub fn solar_table_fill(date: f64, lat: f64, lon: f64) -> [f64; 10] {
 
     table
 }
+
+/// Solar events for the day, each with its unix timestamp or None if the
+/// sun does not reach that elevation (e.g. no sunset during midnight sun)
+/// date: Unix timestamp for the day
+/// lat: Latitude in degrees
+/// lon: Longitude in degrees
+pub fn events_today(date: f64, lat: f64, lon: f64) -> Vec<(SolarTime, Option<f64>)> {
+    let table = solar_table_fill(date, lat, lon);
+
+    SolarTime::ALL
+        .iter()
+        .map(|&event| {
+            let time = table[event as usize];
+            (event, (!time.is_nan()).then_some(time))
+        })
+        .collect()
+}
+
+/// Next sunrise or sunset after `now`, looking at today's and tomorrow's
+/// tables (days start at midnight UTC)
+/// Returns: The event and its unix timestamp, or None if the sun neither
+/// rises nor sets in that time (midnight sun or polar night)
+pub fn next_sun_transition(now: f64, lat: f64, lon: f64) -> Option<(SolarTime, f64)> {
+    let today = (now / 86400.0).floor() * 86400.0;
+
+    [today, today + 86400.0]
+        .iter()
+        .flat_map(|&day| events_today(day, lat, lon))
+        .filter_map(|(event, time)| match event {
+            SolarTime::Sunrise | SolarTime::Sunset => time.map(|time| (event, time)),
+            _ => None,
+        })
+        .filter(|&(_, time)| time > now)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Format a duration in seconds as hours and minutes, e.g. "2h13m" or "45m".
+/// Negative durations count as zero.
+pub fn format_time_until(seconds: f64) -> String {
+    let minutes = (seconds.max(0.0) / 60.0).round() as u64;
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h{:02}m", h, m),
+    }
+}
+
+/// Classify the period of day from the solar elevation and the day's table
+/// scheme: Transition scheme with the high/low elevation thresholds
+/// elevation: Current solar elevation in degrees
+/// table: Solar event table for the day as returned by `solar_table_fill`
+/// Returns: Daytime, Night or Transition
+///
+/// Near the poles the sun may stay above or below the horizon for the whole
+/// day, in which case sunrise and sunset are NaN in the table. The whole day
+/// is then treated as daytime (midnight sun) or night (polar night) instead
+/// of comparing the low-hanging sun against the elevation thresholds.
+pub fn classify_period(scheme: &TransitionScheme, elevation: f64, table: &[f64; 10]) -> Period {
+    let sunrise = table[SolarTime::Sunrise as usize];
+    let sunset = table[SolarTime::Sunset as usize];
+
+    if sunrise.is_nan() || sunset.is_nan() {
+        /* The sun never crosses the horizon today, so the side of the
+           horizon it is on now is the side it stays on all day. */
+        return if elevation > SOLAR_DAYTIME_ELEV {
+            Period::Daytime
+        } else {
+            Period::Night
+        };
+    }
+
+    if elevation >= scheme.high {
+        Period::Daytime
+    } else if elevation <= scheme.low {
+        Period::Night
+    } else {
+        Period::Transition
+    }
+}
+
+/// Keep the previous period while the sun hovers near a threshold.
+/// Once day has been reached, it stays day until the elevation drops
+/// `hysteresis` degrees below `high`; night likewise lasts until the
+/// elevation rises that far above `low`. A hysteresis of zero returns
+/// `period` unchanged.
+pub fn apply_period_hysteresis(
+    scheme: &TransitionScheme,
+    previous: Period,
+    period: Period,
+    elevation: f64,
+    hysteresis: f64,
+) -> Period {
+    match (previous, period) {
+        (Period::Daytime, Period::Transition) if elevation > scheme.high - hysteresis => {
+            Period::Daytime
+        }
+        (Period::Night, Period::Transition) if elevation < scheme.low + hysteresis => {
+            Period::Night
+        }
+        _ => period,
+    }
+}
+
+/// Determine how far through the transition we are based on elevation.
+/// Returns a value from 0.0 (night) to 1.0 (day). The thresholds are
+/// inclusive as in `classify_period`: `high` itself is day (1.0) and `low`
+/// itself night (0.0), so period and progress agree at both boundaries.
+/// A zero-length or reversed elevation range switches directly at `high`
+/// instead of dividing by zero.
+pub fn transition_progress_from_elevation(scheme: &TransitionScheme, elevation: f64) -> f64 {
+    if elevation >= scheme.high {
+        1.0
+    } else if elevation > scheme.low {
+        (scheme.low - elevation) / (scheme.low - scheme.high)
+    } else {
+        0.0
+    }
+}
+
+/// Color setting at `elevation` for a multi-stage transition.
+/// Interpolates linearly between the breakpoints: night at astronomical
+/// twilight, the nautical and civil stages at their boundaries and day
+/// at `scheme.high`.
+pub fn twilight_color_setting(
+    scheme: &TransitionScheme,
+    stages: &TwilightStages,
+    elevation: f64,
+) -> ColorSetting {
+    let breakpoints = [
+        (SOLAR_ASTRO_TWILIGHT_ELEV, scheme.night),
+        (SOLAR_NAUT_TWILIGHT_ELEV, stages.nautical),
+        (SOLAR_CIVIL_TWILIGHT_ELEV, stages.civil),
+        (scheme.high, scheme.day),
+    ];
+
+    if elevation <= breakpoints[0].0 {
+        return scheme.night;
+    }
+
+    for pair in breakpoints.windows(2) {
+        let (low, from) = pair[0];
+        let (high, to) = pair[1];
+        if elevation < high {
+            let mut result = from;
+            interpolate_color_settings_with(
+                &from,
+                &to,
+                (elevation - low) / (high - low),
+                scheme.interpolate_in_mireds,
+                &mut result,
+            );
+            return result;
+        }
+    }
+
+    scheme.day
+}
+
+/// Deep night temperature at `elevation`, if the scheme has one and the sun
+/// is below `scheme.low`. Interpolates from the night temperature at `low`
+/// to `deep_night_temp` at `deep_night_elevation`, and holds it below.
+pub fn deep_night_temperature(scheme: &TransitionScheme, elevation: f64) -> Option<i32> {
+    let deep = scheme.deep_night_temp?;
+    if scheme.use_time || elevation >= scheme.low {
+        return None;
+    }
+
+    let alpha = (scheme.low - elevation) / (scheme.low - scheme.deep_night_elevation);
+    Some(interpolate_temperature(
+        scheme.night.temperature,
+        deep,
+        alpha,
+        scheme.interpolate_in_mireds,
+    ))
+}
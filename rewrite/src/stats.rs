@@ -0,0 +1,54 @@
+/// Diagnostic counters for continual mode
+/// Kept locally and only ever logged, to help diagnose flicker reports
+
+use log::debug;
+use std::time::{Duration, Instant};
+
+/// How often the counters are logged while running
+pub const STATS_LOG_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Counts of what the continual mode loop did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContinualStats {
+    /// Fades started, including ones restarted mid-fade
+    pub fades_started: u64,
+    /// Color settings sent to the gamma method
+    pub applies: u64,
+    /// Invalid settings replaced by the last good one
+    pub rejected: u64,
+}
+
+impl ContinualStats {
+    pub fn log(&self, label: &str) {
+        debug!(
+            "{}: {} fades started, {} applies, {} rejected settings",
+            label, self.fades_started, self.applies, self.rejected
+        );
+    }
+}
+
+/// Logs stats at most once per interval
+pub struct StatsLogger {
+    interval: Duration,
+    last: Instant,
+}
+
+impl StatsLogger {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    /// Log `stats` if the interval has passed since the last log.
+    /// Returns true if it logged.
+    pub fn maybe_log(&mut self, stats: &ContinualStats, now: Instant) -> bool {
+        if now.duration_since(self.last) < self.interval {
+            return false;
+        }
+        stats.log("Continual mode stats");
+        self.last = now;
+        true
+    }
+}
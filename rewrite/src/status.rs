@@ -0,0 +1,40 @@
+/// Steady status samples for continual mode
+/// With --status-interval the current setting is printed at a fixed cadence
+/// even while nothing changes, e.g. for a live graph. Samples are taken
+/// while the loop waits for its next update, so they do not change how
+/// often the gamma ramps are applied.
+
+use crate::reapply::ReapplyTimer;
+use std::time::{Duration, Instant};
+
+/// Wait until `wake_at`, calling `emit` whenever a status sample falls due
+/// on the way. `sleep` returns false to stop waiting early, e.g. when a
+/// signal arrives. `now` and `sleep` are passed in so the cadence can be
+/// tested without waiting.
+pub fn wait_with_status(
+    mut timer: Option<&mut ReapplyTimer>,
+    wake_at: Instant,
+    now: impl Fn() -> Instant,
+    mut sleep: impl FnMut(Duration) -> bool,
+    mut emit: impl FnMut(),
+) {
+    loop {
+        let current = now();
+        if let Some(timer) = timer.as_deref_mut() {
+            if timer.check(current) {
+                emit();
+            }
+        }
+        if current >= wake_at {
+            break;
+        }
+
+        let mut step = wake_at - current;
+        if let Some(timer) = timer.as_deref() {
+            step = step.min(timer.remaining(current));
+        }
+        if !sleep(step) {
+            break;
+        }
+    }
+}
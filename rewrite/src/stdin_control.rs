@@ -0,0 +1,100 @@
+/// Live control from standard input, e.g. for a GUI slider
+/// Each line holds a temperature and optionally a brightness, such as
+/// `4200 0.8`. The solar schedule is ignored; input ends the mode at EOF.
+
+use crate::types::{resolve_temp, ColorSetting, TemperatureBounds, MAX_BRIGHTNESS, MIN_BRIGHTNESS};
+use log::{debug, warn};
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Interval between fade steps, short enough that a whole fade takes well
+/// under a second and the screen follows a slider closely
+pub const STDIN_FADE_STEP: Duration = Duration::from_millis(10);
+
+/// A line read from standard input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StdinCommand {
+    pub temperature: i32,
+    pub brightness: f32,
+}
+
+impl StdinCommand {
+    /// Parse "TEMP [BRIGHTNESS]"; brightness defaults to 1.0 and the
+    /// temperature is resolved against `bounds`
+    pub fn parse(line: &str, bounds: &TemperatureBounds) -> Result<Self, String> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+
+        let (temperature, brightness) = match parts.as_slice() {
+            [temperature] => (*temperature, None),
+            [temperature, brightness] => (*temperature, Some(*brightness)),
+            _ => return Err(format!("Expected TEMP [BRIGHTNESS], got: {}", line.trim())),
+        };
+
+        let temperature = bounds.resolve(resolve_temp(temperature)?)?;
+
+        let brightness = match brightness {
+            Some(brightness) => brightness
+                .parse()
+                .map_err(|_| format!("Invalid brightness: {}", brightness))?,
+            None => 1.0,
+        };
+        if !(MIN_BRIGHTNESS..=MAX_BRIGHTNESS).contains(&brightness) {
+            return Err(format!(
+                "Brightness must be between {} and {}",
+                MIN_BRIGHTNESS, MAX_BRIGHTNESS
+            ));
+        }
+
+        Ok(Self {
+            temperature,
+            brightness,
+        })
+    }
+
+    /// The color setting to apply, neutral apart from temperature and
+    /// brightness
+    pub fn setting(&self) -> ColorSetting {
+        ColorSetting {
+            temperature: self.temperature,
+            brightness: self.brightness,
+            ..ColorSetting::default()
+        }
+    }
+}
+
+/// Read commands from `input` on a background thread, with temperatures
+/// resolved against `bounds`. Empty lines are skipped and invalid ones
+/// logged. The channel closes at end of input.
+pub fn spawn_reader<R: BufRead + Send + 'static>(
+    input: R,
+    bounds: TemperatureBounds,
+) -> Receiver<StdinCommand> {
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("Failed to read standard input: {}", e);
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match StdinCommand::parse(&line, &bounds) {
+                Ok(command) => {
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!("Standard input: {}", e),
+            }
+        }
+        debug!("End of standard input");
+    });
+
+    receiver
+}
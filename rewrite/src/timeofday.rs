@@ -0,0 +1,153 @@
+/// Wall-clock time of day for time-based (dawn-time/dusk-time) transitions
+/// Ported from get_seconds_since_midnight() and the *_from_time() helpers
+/// in legacy/src/redshift.c
+
+use crate::types::{Period, TransitionScheme};
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_DAY: i32 = 24 * 3600;
+
+/// Seconds since midnight as shown on the wall clock of the given time.
+/// On daylight saving changes the wall clock jumps, so a spring-forward day
+/// never reaches the skipped hour and a fall-back day repeats one.
+pub fn seconds_since_midnight<Tz: TimeZone>(time: &DateTime<Tz>) -> i32 {
+    time.num_seconds_from_midnight() as i32
+}
+
+/// Seconds since local midnight for a unix timestamp, using the system
+/// timezone (including its daylight saving rules)
+pub fn local_seconds_since_midnight(timestamp: f64) -> i32 {
+    match Local.timestamp_opt(timestamp.floor() as i64, 0).single() {
+        Some(time) => seconds_since_midnight(&time),
+        None => 0,
+    }
+}
+
+/// Local wall-clock time of a unix timestamp as HH:MM
+pub fn format_local_time(timestamp: f64) -> String {
+    match Local.timestamp_opt(timestamp.floor() as i64, 0).single() {
+        Some(time) => time.format("%H:%M").to_string(),
+        None => "--:--".to_string(),
+    }
+}
+
+/// Offset of the local wall clock from UTC at a unix timestamp, e.g.
+/// "UTC+02:00"
+pub fn format_local_utc_offset(timestamp: f64) -> String {
+    match Local.timestamp_opt(timestamp.floor() as i64, 0).single() {
+        Some(time) => time.format("UTC%:z").to_string(),
+        None => "UTC".to_string(),
+    }
+}
+
+/// Day of the week shown on the local wall clock at a unix timestamp
+pub fn local_weekday(timestamp: f64) -> Weekday {
+    match Local.timestamp_opt(timestamp.floor() as i64, 0).single() {
+        Some(time) => time.weekday(),
+        None => Weekday::Mon,
+    }
+}
+
+/// Days of the week on which the schedule is active, e.g. "mon,tue,wed"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ActiveDays {
+    /// One bit per day, Monday first
+    days: u8,
+}
+
+impl ActiveDays {
+    /// Parse a comma-separated list of day names, abbreviated ("mon") or
+    /// in full ("monday"), in any case
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut days = 0;
+        for name in s.split(',').map(str::trim) {
+            let day: Weekday = name
+                .parse()
+                .map_err(|_| format!("Invalid day of the week: '{}'", name))?;
+            days |= 1 << day.num_days_from_monday();
+        }
+        Ok(Self { days })
+    }
+
+    pub fn contains(self, day: Weekday) -> bool {
+        self.days & (1 << day.num_days_from_monday()) != 0
+    }
+
+    /// Whether the local day at `timestamp` is one of these days
+    pub fn contains_local(self, timestamp: f64) -> bool {
+        self.contains(local_weekday(timestamp))
+    }
+}
+
+impl std::fmt::Display for ActiveDays {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let names: Vec<String> = (0u8..7)
+            .filter_map(|day| Weekday::try_from(day).ok())
+            .filter(|&day| self.contains(day))
+            .map(|day| day.to_string().to_lowercase())
+            .collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+impl TryFrom<String> for ActiveDays {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        Self::parse(&s)
+    }
+}
+
+impl From<ActiveDays> for String {
+    fn from(days: ActiveDays) -> String {
+        days.to_string()
+    }
+}
+
+/// Determine which period we are in based on the time of day
+pub fn period_from_time(scheme: &TransitionScheme, time_offset: i32) -> Period {
+    if time_offset < scheme.dawn.start || time_offset >= scheme.dusk.end {
+        Period::Night
+    } else if time_offset >= scheme.dawn.end && time_offset < scheme.dusk.start {
+        Period::Daytime
+    } else {
+        Period::Transition
+    }
+}
+
+/// Determine how far through the transition we are based on the time of day.
+/// Returns a value from 0.0 (night) to 1.0 (day).
+pub fn transition_progress_from_time(scheme: &TransitionScheme, time_offset: i32) -> f64 {
+    if time_offset < scheme.dawn.start || time_offset >= scheme.dusk.end {
+        0.0
+    } else if time_offset < scheme.dawn.end {
+        (scheme.dawn.start - time_offset) as f64 / (scheme.dawn.start - scheme.dawn.end) as f64
+    } else if time_offset > scheme.dusk.start {
+        (scheme.dusk.end - time_offset) as f64 / (scheme.dusk.end - scheme.dusk.start) as f64
+    } else {
+        1.0
+    }
+}
+
+/// Temperature at `time_offset` seconds since midnight from keyframes
+/// sorted by time, interpolating linearly between neighbours. After the
+/// last keyframe the temperature moves towards the first one, wrapping
+/// at midnight. Returns None without keyframes.
+pub fn temperature_from_keyframes(keyframes: &[(i32, i32)], time_offset: i32) -> Option<i32> {
+    let &(first_time, first_temp) = keyframes.first()?;
+    let &(last_time, last_temp) = keyframes.last()?;
+
+    /* Segment containing the time, wrapping from the last keyframe to the
+       first one on the next day */
+    let ((start, from), (end, to)) = keyframes
+        .windows(2)
+        .find(|pair| pair[0].0 <= time_offset && time_offset < pair[1].0)
+        .map(|pair| (pair[0], pair[1]))
+        .unwrap_or(((last_time, last_temp), (first_time + SECONDS_PER_DAY, first_temp)));
+
+    let elapsed = (time_offset - start).rem_euclid(SECONDS_PER_DAY);
+    let alpha = elapsed as f64 / (end - start) as f64;
+    Some(((1.0 - alpha) * from as f64 + alpha * to as f64).round() as i32)
+}
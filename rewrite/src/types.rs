@@ -1,26 +1,156 @@
 /// Core types for Redshift
 /// Ported from legacy/src/redshift.h
 
+use crate::fade::{interpolate_color_settings_with, interpolate_temperature};
+use crate::solar::{SOLAR_ASTRO_TWILIGHT_ELEV, SOLAR_CIVIL_TWILIGHT_ELEV};
+use crate::timeofday::ActiveDays;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
 /// The color temperature when no adjustment is applied
 pub const NEUTRAL_TEMP: i32 = 6500;
 
 /// Bounds for parameters
-pub const MIN_LAT: f32 = -90.0;
-pub const MAX_LAT: f32 = 90.0;
-pub const MIN_LON: f32 = -180.0;
-pub const MAX_LON: f32 = 180.0;
+pub const MIN_LAT: f64 = -90.0;
+pub const MAX_LAT: f64 = 90.0;
+pub const MIN_LON: f64 = -180.0;
+pub const MAX_LON: f64 = 180.0;
 pub const MIN_TEMP: i32 = 1000;
 pub const MAX_TEMP: i32 = 25000;
 pub const MIN_BRIGHTNESS: f32 = 0.1;
 pub const MAX_BRIGHTNESS: f32 = 1.0;
+/// Brightness ceiling with overdrive allowed (see `TransitionScheme::allow_overdrive`)
+pub const MAX_OVERDRIVE_BRIGHTNESS: f32 = 2.0;
 pub const MIN_GAMMA: f32 = 0.1;
 pub const MAX_GAMMA: f32 = 10.0;
+pub const MIN_CONTRAST: f32 = 0.5;
+pub const MAX_CONTRAST: f32 = 2.0;
+/// Names of the ramp channels, in the order gamma values are given
+pub const CHANNEL_NAMES: [&str; 3] = ["red", "green", "blue"];
+/// Smallest allowed gap in degrees between the high and low transition elevations
+pub const MIN_ELEVATION_RANGE: f64 = 0.1;
+
+/// Named color temperatures accepted wherever a temperature is expected
+pub const NAMED_TEMPERATURES: &[(&str, i32)] = &[
+    ("candle", 1900),
+    ("incandescent", 2700),
+    ("halogen", 3400),
+    ("warm-white", 4000),
+    ("daylight", 5600),
+    ("neutral", 6500),
+    ("overcast", 7000),
+];
+
+/// Resolve a temperature given either as a preset name (e.g. "candle")
+/// or as a number of Kelvin
+pub fn resolve_temp(s: &str) -> Result<i32, String> {
+    let s = s.trim();
+
+    if let Some(&(_, temp)) = NAMED_TEMPERATURES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(s))
+    {
+        return Ok(temp);
+    }
+
+    s.parse().map_err(|_| {
+        let names: Vec<&str> = NAMED_TEMPERATURES.iter().map(|(name, _)| *name).collect();
+        format!(
+            "Invalid temperature: {} (expected a number or one of {})",
+            s,
+            names.join(", ")
+        )
+    })
+}
+
+/// Range of temperatures accepted anywhere: for the scheme, in commands
+/// and when applying a setting. Defaults to `MIN_TEMP` to `MAX_TEMP`; the
+/// temp-min and temp-max config keys narrow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TemperatureBounds {
+    pub min: i32,
+    pub max: i32,
+    /// Clamp requested temperatures outside the range instead of
+    /// rejecting them (--clamp-invalid)
+    pub clamp: bool,
+}
+
+impl Default for TemperatureBounds {
+    fn default() -> Self {
+        Self {
+            min: MIN_TEMP,
+            max: MAX_TEMP,
+            clamp: false,
+        }
+    }
+}
+
+impl TemperatureBounds {
+    /// Narrow the hard limits to `min` and/or `max`. The range has to keep
+    /// `NEUTRAL_TEMP`, which is applied whenever the adjustment is off.
+    pub fn new(min: Option<i32>, max: Option<i32>) -> Result<Self, String> {
+        let bounds = Self {
+            min: min.unwrap_or(MIN_TEMP),
+            max: max.unwrap_or(MAX_TEMP),
+            clamp: false,
+        };
+        for temperature in [bounds.min, bounds.max] {
+            if !(MIN_TEMP..=MAX_TEMP).contains(&temperature) {
+                return Err(format!(
+                    "Temperature bound {}K is outside {}-{}K",
+                    temperature, MIN_TEMP, MAX_TEMP
+                ));
+            }
+        }
+        if !(bounds.min..=bounds.max).contains(&NEUTRAL_TEMP) {
+            return Err(format!(
+                "Temperature bounds {}-{}K must include the neutral {}K",
+                bounds.min, bounds.max, NEUTRAL_TEMP
+            ));
+        }
+        Ok(bounds)
+    }
+
+    /// Set whether out-of-range requests are clamped instead of rejected
+    pub fn with_clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    pub fn contains(&self, temperature: i32) -> bool {
+        (self.min..=self.max).contains(&temperature)
+    }
+
+    /// Error unless `temperature` is within the range
+    pub fn check(&self, temperature: i32) -> Result<(), String> {
+        if self.contains(temperature) {
+            return Ok(());
+        }
+        Err(format!(
+            "Temperature {}K is outside {}-{}K",
+            temperature, self.min, self.max
+        ))
+    }
+
+    /// Accept a requested temperature: unchanged if within the range,
+    /// otherwise clamped with a warning or rejected, per `clamp`
+    pub fn resolve(&self, temperature: i32) -> Result<i32, String> {
+        match self.check(temperature) {
+            Err(_) if self.clamp => {
+                let clamped = temperature.clamp(self.min, self.max);
+                warn!("Temperature {}K clamped to {}K", temperature, clamped);
+                Ok(clamped)
+            }
+            result => result.map(|()| temperature),
+        }
+    }
+}
 
 /// Geographic location
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Location {
-    pub lat: f32,
-    pub lon: f32,
+    pub lat: f64,
+    pub lon: f64,
 }
 
 /// Periods of day
@@ -44,11 +174,14 @@ impl Period {
 }
 
 /// Color setting with temperature, gamma, and brightness
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorSetting {
     pub temperature: i32,
     pub gamma: [f32; 3],
     pub brightness: f32,
+    /// Scale of the ramp around its midpoint; 1.0 leaves it unchanged
+    pub contrast: f32,
 }
 
 impl Default for ColorSetting {
@@ -57,7 +190,69 @@ impl Default for ColorSetting {
             temperature: NEUTRAL_TEMP,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
+        }
+    }
+}
+
+impl ColorSetting {
+    /// Check that all values are finite and within bounds, so that
+    /// garbage from upstream math is never applied to the display.
+    /// Brightness may go up to `MAX_OVERDRIVE_BRIGHTNESS`; whether overdrive
+    /// is allowed at all is up to the scheme. The temperature is checked
+    /// against the hard limits only; see `validate_within`.
+    pub fn validate(&self) -> Result<(), String> {
+        TemperatureBounds::default().check(self.temperature)?;
+        if !(MIN_BRIGHTNESS..=MAX_OVERDRIVE_BRIGHTNESS).contains(&self.brightness) {
+            return Err(format!(
+                "Brightness {} is outside {}-{}",
+                self.brightness, MIN_BRIGHTNESS, MAX_OVERDRIVE_BRIGHTNESS
+            ));
+        }
+        if let Some((channel, gamma)) = CHANNEL_NAMES
+            .iter()
+            .zip(self.gamma)
+            .find(|(_, gamma)| !(MIN_GAMMA..=MAX_GAMMA).contains(gamma))
+        {
+            return Err(format!(
+                "Gamma {} of the {} channel is outside {}-{}",
+                gamma, channel, MIN_GAMMA, MAX_GAMMA
+            ));
+        }
+        if !(MIN_CONTRAST..=MAX_CONTRAST).contains(&self.contrast) {
+            return Err(format!(
+                "Contrast {} is outside {}-{}",
+                self.contrast, MIN_CONTRAST, MAX_CONTRAST
+            ));
+        }
+        Ok(())
+    }
+
+    /// Like `validate`, with the temperature also within `bounds`
+    pub fn validate_within(&self, bounds: &TemperatureBounds) -> Result<(), String> {
+        self.validate()?;
+        bounds.check(self.temperature)
+    }
+
+    /// Clamp brightness, gamma and contrast to their nearest bound, with
+    /// NaN reset to neutral. Returns a description of each changed value,
+    /// e.g. "blue gamma 15 clamped to 10".
+    pub fn clamp_ramp_values(&mut self, max_brightness: f32) -> Vec<String> {
+        let mut changes = Vec::new();
+        let mut clamp = |name: String, value: &mut f32, min: f32, max: f32| {
+            let clamped = if value.is_nan() { 1.0 } else { value.clamp(min, max) };
+            if clamped != *value {
+                changes.push(format!("{} {} clamped to {}", name, value, clamped));
+                *value = clamped;
+            }
+        };
+
+        clamp("brightness".to_string(), &mut self.brightness, MIN_BRIGHTNESS, max_brightness);
+        for (channel, gamma) in CHANNEL_NAMES.iter().zip(self.gamma.iter_mut()) {
+            clamp(format!("{} gamma", channel), gamma, MIN_GAMMA, MAX_GAMMA);
         }
+        clamp("contrast".to_string(), &mut self.contrast, MIN_CONTRAST, MAX_CONTRAST);
+        changes
     }
 }
 
@@ -69,17 +264,29 @@ pub enum ProgramMode {
     Print,
     Reset,
     Manual,
+    Demo,
+    StdinControl,
+    ResetAll,
 }
 
 /// Time range in seconds from midnight
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct TimeRange {
     pub start: i32,
     pub end: i32,
 }
 
+/// Color settings reached at the civil and nautical twilight boundaries
+/// in a multi-stage transition
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TwilightStages {
+    pub civil: ColorSetting,
+    pub nautical: ColorSetting,
+}
+
 /// Transition scheme defining solar elevations and color settings
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TransitionScheme {
     pub high: f64,
     pub low: f64,
@@ -88,6 +295,38 @@ pub struct TransitionScheme {
     pub dusk: TimeRange,
     pub day: ColorSetting,
     pub night: ColorSetting,
+    /// Extra breakpoints for a multi-stage transition. When set, `low` is
+    /// astronomical twilight and the ramp passes through these settings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub twilight: Option<TwilightStages>,
+    /// Warmer temperature for late night. Below `low` the night temperature
+    /// moves towards it, reaching it at `deep_night_elevation`. None keeps
+    /// the night setting all night.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deep_night_temp: Option<i32>,
+    /// Solar elevation at which `deep_night_temp` is reached
+    pub deep_night_elevation: f64,
+    /// Temperature keyframes as (seconds since local midnight, Kelvin),
+    /// sorted by time. When non-empty they set the temperature instead of
+    /// the day/night endpoints.
+    pub keyframes: Vec<(i32, i32)>,
+    /// Allow brightness above `MAX_BRIGHTNESS`, up to
+    /// `MAX_OVERDRIVE_BRIGHTNESS`. Ramps are scaled past linear, so the
+    /// brightest tones clip to white.
+    #[serde(default)]
+    pub allow_overdrive: bool,
+    /// Interpolate temperatures linearly in mireds (1e6/K) rather than in
+    /// Kelvin, for transitions that look more even
+    #[serde(default)]
+    pub interpolate_in_mireds: bool,
+    /// Days of the week the scheme applies on; neutral is held on the
+    /// others. None means every day.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_days: Option<ActiveDays>,
+    /// Temperatures accepted in the scheme, and in commands while it is in
+    /// use. Taken from temp-min/temp-max rather than the scheme file.
+    #[serde(skip)]
+    pub temperature_bounds: TemperatureBounds,
 }
 
 impl Default for TransitionScheme {
@@ -103,7 +342,407 @@ impl Default for TransitionScheme {
                 temperature: 3500,
                 gamma: [1.0, 1.0, 1.0],
                 brightness: 1.0,
+                contrast: 1.0,
             },
+            twilight: None,
+            deep_night_temp: None,
+            deep_night_elevation: SOLAR_ASTRO_TWILIGHT_ELEV,
+            keyframes: Vec::new(),
+            allow_overdrive: false,
+            interpolate_in_mireds: false,
+            active_days: None,
+            temperature_bounds: TemperatureBounds::default(),
+        }
+    }
+}
+
+impl TransitionScheme {
+    /// Highest brightness the scheme accepts
+    pub fn max_brightness(&self) -> f32 {
+        if self.allow_overdrive {
+            MAX_OVERDRIVE_BRIGHTNESS
+        } else {
+            MAX_BRIGHTNESS
+        }
+    }
+
+    /// Check every bound the scheme has to satisfy: temperatures, brightness,
+    /// gamma and contrast of each period, twilight stages, keyframes and
+    /// elevation ordering
+    pub fn validate(&self) -> Result<(), String> {
+        /* Validate twilight stages */
+        if let Some(stages) = &self.twilight {
+            if self.use_time {
+                return Err(
+                    "Twilight stages cannot be combined with dawn-time/dusk-time".to_string(),
+                );
+            }
+            if self.high <= SOLAR_CIVIL_TWILIGHT_ELEV {
+                return Err(format!(
+                    "High transition elevation ({}) must be above civil twilight ({})",
+                    self.high, SOLAR_CIVIL_TWILIGHT_ELEV
+                ));
+            }
+            for (name, setting) in [("Civil", &stages.civil), ("Nautical", &stages.nautical)] {
+                setting
+                    .validate_within(&self.temperature_bounds)
+                    .map_err(|e| format!("{} twilight stage: {}", name, e))?;
+                if setting.brightness > self.max_brightness() {
+                    return Err(format!(
+                        "{} twilight stage: Brightness {} is above {}",
+                        name,
+                        setting.brightness,
+                        self.max_brightness()
+                    ));
+                }
+            }
+        }
+
+        /* Validate the deep night threshold */
+        if self.deep_night_temp.is_some() {
+            if self.use_time {
+                return Err(
+                    "Deep night cannot be combined with dawn-time/dusk-time".to_string(),
+                );
+            }
+            if self.deep_night_elevation >= self.low {
+                return Err(format!(
+                    "Deep night elevation ({}) must be below the low transition elevation ({})",
+                    self.deep_night_elevation, self.low
+                ));
+            }
+        }
+
+        /* Validate temperature bounds, narrowed by temp-min/temp-max */
+        let bounds = self.temperature_bounds;
+        let keyframe_temps = self.keyframes.iter().map(|&(_, temperature)| temperature);
+        for temperature in [self.day.temperature, self.night.temperature]
+            .into_iter()
+            .chain(self.deep_night_temp)
+            .chain(keyframe_temps)
+        {
+            if !bounds.contains(temperature) {
+                return Err(format!(
+                    "Temperature must be between {} and {}, got {}",
+                    bounds.min, bounds.max, temperature
+                ));
+            }
+        }
+
+        /* Each keyframe needs its own time of day */
+        if let Some(pair) = self.keyframes.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!(
+                "Duplicate schedule keyframe at {:02}:{:02}",
+                pair[0].0 / 3600,
+                pair[0].0 % 3600 / 60
+            ));
+        }
+
+        /* Validate elevation ordering, leaving room for a transition */
+        if self.high - self.low < MIN_ELEVATION_RANGE {
+            return Err(format!(
+                "High transition elevation ({}) must be at least {} degrees above the low transition elevation ({})",
+                self.high, MIN_ELEVATION_RANGE, self.low
+            ));
+        }
+
+        /* Validate brightness bounds */
+        for (name, setting) in [("Day", &self.day), ("Night", &self.night)] {
+            if !(MIN_BRIGHTNESS..=self.max_brightness()).contains(&setting.brightness) {
+                return Err(format!(
+                    "{} brightness must be between {} and {}",
+                    name,
+                    MIN_BRIGHTNESS,
+                    self.max_brightness()
+                ));
+            }
+        }
+
+        /* Validate gamma bounds, naming the offending channel */
+        for (name, setting) in [("Day", &self.day), ("Night", &self.night)] {
+            for (channel, gamma) in CHANNEL_NAMES.iter().zip(setting.gamma) {
+                if !(MIN_GAMMA..=MAX_GAMMA).contains(&gamma) {
+                    return Err(format!(
+                        "{} gamma must be between {} and {}, but the {} channel is {}",
+                        name, MIN_GAMMA, MAX_GAMMA, channel, gamma
+                    ));
+                }
+            }
         }
+
+        /* Validate contrast bounds */
+        for (name, setting) in [("Day", &self.day), ("Night", &self.night)] {
+            if !(MIN_CONTRAST..=MAX_CONTRAST).contains(&setting.contrast) {
+                return Err(format!(
+                    "{} contrast must be between {} and {}",
+                    name, MIN_CONTRAST, MAX_CONTRAST
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Elevation at which deep night is reached when none is given:
+/// astronomical twilight, kept at least 6 degrees below `low`
+pub fn default_deep_night_elevation(low: f64) -> f64 {
+    SOLAR_ASTRO_TWILIGHT_ELEV.min(low - 6.0)
+}
+
+/// Builder for a validated `TransitionScheme`
+///
+/// Unset values fall back to `TransitionScheme::default()`. Bounds are
+/// checked once in `build()` by `TransitionScheme::validate()`.
+#[derive(Debug, Clone, Default)]
+pub struct TransitionSchemeBuilder {
+    scheme: TransitionScheme,
+    /// Deep night elevation given explicitly; otherwise it is derived from
+    /// the final low transition elevation in `build()`
+    deep_night_elevation: Option<f64>,
+    /// Low transition elevation given explicitly, which twilight stages
+    /// cannot honour unless it is astronomical twilight
+    low: Option<f64>,
+    intensity: Option<u8>,
+    clamp_invalid: bool,
+}
+
+impl TransitionSchemeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from an existing scheme, e.g. one read from a file
+    pub fn from_scheme(scheme: TransitionScheme) -> Self {
+        let keyframes = scheme.keyframes.clone();
+        /* The file cannot tell an unset elevation from the default one */
+        let deep_night_elevation = Some(scheme.deep_night_elevation)
+            .filter(|&elevation| elevation != SOLAR_ASTRO_TWILIGHT_ELEV);
+        let low = Some(scheme.low).filter(|&low| low != TransitionScheme::default().low);
+        Self {
+            scheme,
+            deep_night_elevation,
+            low,
+            intensity: None,
+            clamp_invalid: false,
+        }
+        .keyframes(keyframes)
+    }
+
+    pub fn day_temp(mut self, temperature: i32) -> Self {
+        self.scheme.day.temperature = temperature;
+        self
+    }
+
+    pub fn night_temp(mut self, temperature: i32) -> Self {
+        self.scheme.night.temperature = temperature;
+        self
+    }
+
+    /// Solar elevation (in degrees) above which it is day
+    pub fn elevation_high(mut self, high: f64) -> Self {
+        self.scheme.high = high;
+        self
+    }
+
+    /// Solar elevation (in degrees) below which it is night. Twilight
+    /// stages always end at astronomical twilight, so any other value
+    /// cannot be combined with them.
+    pub fn elevation_low(mut self, low: f64) -> Self {
+        self.scheme.low = low;
+        self.low = Some(low);
+        self
+    }
+
+    pub fn brightness(mut self, day: f32, night: f32) -> Self {
+        self.scheme.day.brightness = day;
+        self.scheme.night.brightness = night;
+        self
+    }
+
+    /// Contrast for both day and night
+    pub fn contrast(mut self, contrast: f32) -> Self {
+        self.scheme.day.contrast = contrast;
+        self.scheme.night.contrast = contrast;
+        self
+    }
+
+    pub fn gamma(mut self, day: [f32; 3], night: [f32; 3]) -> Self {
+        self.scheme.day.gamma = day;
+        self.scheme.night.gamma = night;
+        self
+    }
+
+    /// Use a wall-clock dawn transition instead of solar elevation
+    pub fn dawn(mut self, dawn: TimeRange) -> Self {
+        self.scheme.use_time = true;
+        self.scheme.dawn = dawn;
+        self
+    }
+
+    /// Use a wall-clock dusk transition instead of solar elevation
+    pub fn dusk(mut self, dusk: TimeRange) -> Self {
+        self.scheme.use_time = true;
+        self.scheme.dusk = dusk;
+        self
+    }
+
+    /// Pass through `stages` at civil and nautical twilight, reaching the
+    /// night setting at astronomical twilight
+    pub fn twilight(mut self, stages: TwilightStages) -> Self {
+        self.scheme.twilight = Some(stages);
+        self
+    }
+
+    /// Move the night temperature towards `temperature` below the low
+    /// transition elevation, reaching it at `elevation`
+    pub fn deep_night(mut self, temperature: i32, elevation: f64) -> Self {
+        self.scheme.deep_night_temp = Some(temperature);
+        self.deep_night_elevation = Some(elevation);
+        self
+    }
+
+    /// Like `deep_night`, reaching `temperature` at astronomical twilight,
+    /// or 6 degrees below the low transition elevation if that is lower
+    pub fn deep_night_temp(mut self, temperature: i32) -> Self {
+        self.scheme.deep_night_temp = Some(temperature);
+        self.deep_night_elevation = None;
+        self
+    }
+
+    /// Follow temperature keyframes through the day, given as
+    /// (seconds since midnight, Kelvin) in any order
+    pub fn keyframes(mut self, mut keyframes: Vec<(i32, i32)>) -> Self {
+        keyframes.sort_by_key(|&(time, _)| time);
+        self.scheme.keyframes = keyframes;
+        self
+    }
+
+    /// Accept brightness above 1.0 (see `TransitionScheme::allow_overdrive`)
+    pub fn allow_overdrive(mut self, allow: bool) -> Self {
+        self.scheme.allow_overdrive = allow;
+        self
+    }
+
+    /// Interpolate temperatures in mireds instead of Kelvin
+    pub fn interpolate_in_mireds(mut self, in_mireds: bool) -> Self {
+        self.scheme.interpolate_in_mireds = in_mireds;
+        self
+    }
+
+    /// Only follow the scheme on `days`, holding neutral on the others
+    pub fn active_days(mut self, days: ActiveDays) -> Self {
+        self.scheme.active_days = Some(days);
+        self
+    }
+
+    /// Strength of the night setting in percent (0-100): 0 makes the night
+    /// and twilight stages neutral, 100 keeps them as configured, and values
+    /// in between move temperature, brightness and gamma towards neutral
+    /// together. Applied in `build()`, after all other settings.
+    pub fn intensity(mut self, percent: u8) -> Self {
+        self.intensity = Some(percent);
+        self
+    }
+
+    /// Accept only temperatures within `bounds` in the scheme
+    pub fn temperature_bounds(mut self, bounds: TemperatureBounds) -> Self {
+        self.scheme.temperature_bounds = bounds;
+        self
+    }
+
+    /// Clamp out-of-range brightness, gamma and contrast to the nearest
+    /// bound with a warning in `build()`, instead of failing
+    pub fn clamp_invalid(mut self, clamp: bool) -> Self {
+        self.clamp_invalid = clamp;
+        self
+    }
+
+    /// Apply twilight stage defaults, validate and return the scheme
+    pub fn build(self) -> Result<TransitionScheme, String> {
+        let mut scheme = self.scheme;
+
+        if self.clamp_invalid {
+            let max_brightness = scheme.max_brightness();
+            let mut settings = vec![("Day", &mut scheme.day), ("Night", &mut scheme.night)];
+            if let Some(stages) = scheme.twilight.as_mut() {
+                settings.push(("Civil twilight", &mut stages.civil));
+                settings.push(("Nautical twilight", &mut stages.nautical));
+            }
+            for (name, setting) in settings {
+                for change in setting.clamp_ramp_values(max_brightness) {
+                    warn!("{} {}", name, change);
+                }
+            }
+
+            let bounds = scheme.temperature_bounds;
+            let clamp_temperature = |name: &str, temperature: &mut i32| {
+                let clamped = (*temperature).clamp(bounds.min, bounds.max);
+                if clamped != *temperature {
+                    warn!("{} temperature {}K clamped to {}K", name, temperature, clamped);
+                    *temperature = clamped;
+                }
+            };
+            clamp_temperature("Day", &mut scheme.day.temperature);
+            clamp_temperature("Night", &mut scheme.night.temperature);
+            if let Some(stages) = scheme.twilight.as_mut() {
+                clamp_temperature("Civil twilight", &mut stages.civil.temperature);
+                clamp_temperature("Nautical twilight", &mut stages.nautical.temperature);
+            }
+            if let Some(temperature) = scheme.deep_night_temp.as_mut() {
+                clamp_temperature("Deep night", temperature);
+            }
+            for (_, temperature) in scheme.keyframes.iter_mut() {
+                clamp_temperature("Schedule", temperature);
+            }
+        }
+
+        if let Some(percent) = self.intensity {
+            if percent > 100 {
+                return Err(format!("Intensity {} is outside 0-100", percent));
+            }
+        }
+        /* Full intensity keeps the configured values exactly */
+        if let Some(percent) = self.intensity.filter(|&percent| percent < 100) {
+            let alpha = f64::from(percent) / 100.0;
+            let in_mireds = scheme.interpolate_in_mireds;
+            let scale = |setting: &mut ColorSetting| {
+                let configured = *setting;
+                interpolate_color_settings_with(
+                    &ColorSetting::default(),
+                    &configured,
+                    alpha,
+                    in_mireds,
+                    setting,
+                );
+            };
+            scale(&mut scheme.night);
+            if let Some(temperature) = scheme.deep_night_temp.as_mut() {
+                *temperature =
+                    interpolate_temperature(NEUTRAL_TEMP, *temperature, alpha, in_mireds);
+            }
+            if let Some(stages) = scheme.twilight.as_mut() {
+                scale(&mut stages.civil);
+                scale(&mut stages.nautical);
+            }
+        }
+
+        /* A multi-stage transition always ends at astronomical twilight */
+        if scheme.twilight.is_some() {
+            if let Some(low) = self.low.filter(|&low| low != SOLAR_ASTRO_TWILIGHT_ELEV) {
+                return Err(format!(
+                    "Low transition elevation ({}) cannot be used with twilight stages, \
+                     which reach night at astronomical twilight ({})",
+                    low, SOLAR_ASTRO_TWILIGHT_ELEV
+                ));
+            }
+            scheme.low = SOLAR_ASTRO_TWILIGHT_ELEV;
+        }
+        scheme.deep_night_elevation = self
+            .deep_night_elevation
+            .unwrap_or_else(|| default_deep_night_elevation(scheme.low));
+
+        scheme.validate()?;
+        Ok(scheme)
     }
 }
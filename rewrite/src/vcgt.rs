@@ -0,0 +1,64 @@
+/// Export of the adjustment as an ICC 'vcgt' (video card gamma table) tag
+/// The tag is written in its table form, as embedded in ICC profiles, so
+/// calibration tools can load or embed the same curves redshift applies.
+
+use crate::colorramp::{colorramp_fill_with_options, RampOptions};
+use crate::types::ColorSetting;
+use std::path::Path;
+
+/// Entries per channel in an exported table
+pub const VCGT_SIZE: usize = 256;
+
+/// Tag type signature
+pub const VCGT_SIGNATURE: &[u8; 4] = b"vcgt";
+
+/// Gamma type marking a table, as opposed to a formula
+const VCGT_TYPE_TABLE: u32 = 0;
+
+/// Bytes before the table data
+pub const VCGT_HEADER_LEN: usize = 18;
+
+/// Red, green and blue ramps for `setting` under `options` at the export
+/// size. Entry i starts from i/(size - 1) of full scale, so unlike the
+/// legacy ramps the table ends at 65535.
+pub fn vcgt_ramps(setting: &ColorSetting, options: &RampOptions) -> [Vec<u16>; 3] {
+    let last = VCGT_SIZE - 1;
+    let linear: Vec<u16> = (0..VCGT_SIZE).map(|i| (i * 65535 / last) as u16).collect();
+    let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear);
+    colorramp_fill_with_options(&mut r, &mut g, &mut b, setting, options);
+    [r, g, b]
+}
+
+/// Encode ramps of equal length as a vcgt tag: signature, reserved word,
+/// table type, channel count, entry count and entry size, followed by each
+/// channel's 16 bit entries, all big-endian
+pub fn encode_vcgt(ramps: &[Vec<u16>; 3]) -> Result<Vec<u8>, String> {
+    let size = ramps[0].len();
+    if ramps.iter().any(|ramp| ramp.len() != size) {
+        return Err("Channels must have the same number of entries".to_string());
+    }
+    let entries = u16::try_from(size)
+        .map_err(|_| format!("Too many entries for a vcgt table: {}", size))?;
+
+    let mut data = Vec::with_capacity(VCGT_HEADER_LEN + 3 * 2 * size);
+    data.extend_from_slice(VCGT_SIGNATURE);
+    data.extend_from_slice(&0u32.to_be_bytes());
+    data.extend_from_slice(&VCGT_TYPE_TABLE.to_be_bytes());
+    data.extend_from_slice(&3u16.to_be_bytes());
+    data.extend_from_slice(&entries.to_be_bytes());
+    data.extend_from_slice(&2u16.to_be_bytes());
+    for value in ramps.iter().flatten() {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    Ok(data)
+}
+
+/// Write the vcgt tag for `setting` under `options` to `path`
+pub fn export_vcgt(
+    path: &Path,
+    setting: &ColorSetting,
+    options: &RampOptions,
+) -> Result<(), String> {
+    let data = encode_vcgt(&vcgt_ramps(setting, options))?;
+    std::fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,122 @@
+/// Virtual terminal switch detection
+/// Some X servers reset the gamma ramps when switching back from another
+/// VT, so the current setting is reapplied when the X session's VT becomes
+/// active again
+
+use log::trace;
+use std::fs;
+use std::path::PathBuf;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{self, AtomEnum, ConnectionExt as _};
+use x11rb::rust_connection::RustConnection;
+
+/// Kernel file naming the active virtual terminal, e.g. "tty2"
+pub const ACTIVE_VT_PATH: &str = "/sys/class/tty/tty0/active";
+
+/// Root window property in which the X server records its VT number
+const X_VT_PROPERTY: &str = "XFree86_VT";
+
+/// Source of the number of the currently active virtual terminal
+pub trait VtSource {
+    fn active_vt(&mut self) -> Option<u32>;
+}
+
+/// Active VT read from sysfs
+pub struct SysfsVtSource {
+    path: PathBuf,
+}
+
+impl SysfsVtSource {
+    pub fn new() -> Self {
+        Self {
+            path: PathBuf::from(ACTIVE_VT_PATH),
+        }
+    }
+}
+
+impl Default for SysfsVtSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VtSource for SysfsVtSource {
+    fn active_vt(&mut self) -> Option<u32> {
+        parse_active_vt(&fs::read_to_string(&self.path).ok()?)
+    }
+}
+
+/// Parse the VT number from the contents of the active VT file
+pub fn parse_active_vt(contents: &str) -> Option<u32> {
+    contents.trim().strip_prefix("tty")?.parse().ok()
+}
+
+/// Ask the X server which VT it runs on
+pub fn x_session_vt() -> Result<u32, String> {
+    let (conn, screen_num) = RustConnection::connect(None)
+        .map_err(|e| format!("Failed to connect to X server: {}", e))?;
+
+    let root = conn
+        .setup()
+        .roots
+        .get(screen_num)
+        .ok_or_else(|| format!("Screen {} could not be found", screen_num))?
+        .root;
+
+    let atom = conn
+        .intern_atom(true, X_VT_PROPERTY.as_bytes())
+        .map_err(|e| format!("Failed to look up {}: {}", X_VT_PROPERTY, e))?
+        .reply()
+        .map_err(|e| format!("Failed to look up {}: {}", X_VT_PROPERTY, e))?
+        .atom;
+    if atom == u32::from(AtomEnum::NONE) {
+        return Err(format!("X server does not set {}", X_VT_PROPERTY));
+    }
+
+    let reply = conn
+        .get_property(false, root, atom, xproto::AtomEnum::CARDINAL, 0, 1)
+        .map_err(|e| format!("Failed to read {}: {}", X_VT_PROPERTY, e))?
+        .reply()
+        .map_err(|e| format!("Failed to read {}: {}", X_VT_PROPERTY, e))?;
+
+    reply
+        .value32()
+        .and_then(|mut values| values.next())
+        .ok_or_else(|| format!("{} is not set on the root window", X_VT_PROPERTY))
+}
+
+/// Watches for the session's VT becoming active again
+pub struct VtWatcher {
+    source: Box<dyn VtSource>,
+    session_vt: u32,
+    last_active: Option<u32>,
+}
+
+impl VtWatcher {
+    pub fn new(source: Box<dyn VtSource>, session_vt: u32) -> Self {
+        Self {
+            source,
+            session_vt,
+            last_active: None,
+        }
+    }
+
+    /// Read the active VT and return true if the session's VT has just
+    /// become active after another one was
+    pub fn poll(&mut self) -> bool {
+        let active = self.source.active_vt();
+        if active != self.last_active {
+            trace!("Active VT changed from {:?} to {:?}", self.last_active, active);
+        }
+
+        let returned = active == Some(self.session_vt)
+            && matches!(self.last_active, Some(previous) if previous != self.session_vt);
+
+        /* An unreadable VT keeps the last known one, so a failed read
+           does not look like a switch */
+        if active.is_some() {
+            self.last_active = active;
+        }
+        returned
+    }
+}
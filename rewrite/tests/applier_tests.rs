@@ -0,0 +1,342 @@
+/// Tests for applying fades on a dedicated thread
+/// The stepper is driven with made-up times; the thread tests check that
+/// every setting applied between two pushed targets is the interpolation
+/// the loop itself would have applied.
+
+use redshift_rebooted::applier::*;
+use redshift_rebooted::display_loss::DisplayLossPolicy;
+use redshift_rebooted::fade::{ease_fade, interpolate_color_settings_with, Fade, FADE_LENGTH};
+use redshift_rebooted::error::Error;
+use redshift_rebooted::gamma::GammaMethod;
+use redshift_rebooted::types::ColorSetting;
+use redshift_rebooted::Result;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const STEP: Duration = Duration::from_millis(100);
+
+fn setting(temperature: i32) -> ColorSetting {
+    ColorSetting {
+        temperature,
+        ..ColorSetting::default()
+    }
+}
+
+/* Settings applied by a fade from `from` to `to`, step by step */
+fn expected_fade(from: &ColorSetting, to: &ColorSetting) -> Vec<ColorSetting> {
+    (1..=FADE_LENGTH + 1)
+        .map(|step| {
+            let mut result = ColorSetting::default();
+            interpolate_color_settings_with(
+                from,
+                to,
+                ease_fade(step as f64 / FADE_LENGTH as f64),
+                false,
+                &mut result,
+            );
+            result
+        })
+        .collect()
+}
+
+/* Gamma method that records the settings applied, or fails to apply
+   them when `fail` is set */
+#[derive(Default)]
+struct RecordingGammaMethod {
+    applied: Vec<ColorSetting>,
+    invalidated: usize,
+    fail: bool,
+}
+
+impl GammaMethod for RecordingGammaMethod {
+    fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_temperature(&mut self, setting: &ColorSetting, _preserve: bool) -> Result<()> {
+        if self.fail {
+            return Err(Error::Gamma("Display went away".to_string()));
+        }
+        self.applied.push(*setting);
+        Ok(())
+    }
+
+    fn restore(&mut self) {}
+
+    fn invalidate(&mut self) {
+        self.invalidated += 1;
+    }
+
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    fn print_help(&self) {}
+}
+
+#[test]
+fn test_stepper_steps_on_deadlines() {
+    let start = Instant::now();
+    let mut stepper = FadeStepper::new(Fade::new(setting(6500)), STEP);
+    assert_eq!(stepper.deadline(), None, "Nothing to step before a target");
+
+    // A big change starts a fade and takes its first step right away
+    let first = stepper.handle(
+        ApplierCommand::Target {
+            setting: setting(3500),
+            force: false,
+        },
+        start,
+    );
+    let expected = expected_fade(&setting(6500), &setting(3500));
+    assert_eq!(first, Some(expected[0]));
+    assert_eq!(stepper.deadline(), Some(start + STEP));
+
+    // No step before the deadline, one at it
+    assert_eq!(stepper.tick(start + STEP / 2), None);
+    assert_eq!(stepper.tick(start + STEP), Some(expected[1]));
+    assert_eq!(stepper.deadline(), Some(start + 2 * STEP));
+}
+
+#[test]
+fn test_stepper_target_during_fade_adds_no_step() {
+    let start = Instant::now();
+    let mut stepper = FadeStepper::new(Fade::new(setting(6500)), STEP);
+    let target = ApplierCommand::Target {
+        setting: setting(3500),
+        force: false,
+    };
+    stepper.handle(target, start);
+
+    // The loop pushes its target again on every update
+    assert_eq!(stepper.handle(target, start + STEP / 2), None);
+    assert_eq!(stepper.deadline(), Some(start + STEP));
+}
+
+#[test]
+fn test_stepper_does_not_burst_after_falling_behind() {
+    let start = Instant::now();
+    let mut stepper = FadeStepper::new(Fade::new(setting(6500)), STEP);
+    stepper.handle(
+        ApplierCommand::Target {
+            setting: setting(3500),
+            force: false,
+        },
+        start,
+    );
+
+    // Woken well past the deadline: one step, then a full interval
+    let late = start + 5 * STEP;
+    assert!(stepper.tick(late).is_some());
+    assert_eq!(stepper.deadline(), Some(late + STEP));
+
+    // A slightly late step keeps the original cadence
+    let slightly_late = late + STEP + STEP / 10;
+    assert!(stepper.tick(slightly_late).is_some());
+    assert_eq!(stepper.deadline(), Some(late + 2 * STEP));
+}
+
+#[test]
+fn test_stepper_hold_pauses_until_next_target() {
+    let start = Instant::now();
+    let mut stepper = FadeStepper::new(Fade::new(setting(6500)), STEP);
+    let target = ApplierCommand::Target {
+        setting: setting(3500),
+        force: false,
+    };
+    stepper.handle(target, start);
+
+    assert_eq!(stepper.handle(ApplierCommand::Hold, start), None);
+    assert_eq!(stepper.deadline(), None);
+    assert_eq!(
+        stepper.tick(start + 10 * STEP),
+        None,
+        "Held fades do not step"
+    );
+    assert!(stepper.fade().is_active());
+
+    // The next target resumes from where the fade stopped
+    let resumed = stepper.handle(target, start + 20 * STEP);
+    assert_eq!(
+        resumed,
+        Some(expected_fade(&setting(6500), &setting(3500))[1])
+    );
+}
+
+#[test]
+fn test_stepper_jump_cancels_fade() {
+    let start = Instant::now();
+    let mut stepper = FadeStepper::new(Fade::new(setting(6500)), STEP);
+    stepper.handle(
+        ApplierCommand::Target {
+            setting: setting(3500),
+            force: false,
+        },
+        start,
+    );
+
+    assert_eq!(
+        stepper.handle(ApplierCommand::Jump(setting(6500)), start),
+        Some(setting(6500))
+    );
+    assert_eq!(stepper.deadline(), None);
+    assert!(!stepper.fade().is_active());
+}
+
+/* Run the applier over `commands` on a thread with a fake clock that
+   moves one step on every reading, until it has handled them all and
+   finished fading. Returns the gamma method and final state. */
+fn run_to_completion(commands: &[ApplierCommand]) -> (RecordingGammaMethod, AppliedState) {
+    let mut gamma = RecordingGammaMethod::default();
+    let state = Mutex::new(AppliedState::default());
+    let clock = Mutex::new(Instant::now());
+    let now = || {
+        let mut clock = clock.lock().unwrap();
+        *clock += STEP;
+        *clock
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    for command in commands {
+        sender.send(*command).unwrap();
+    }
+
+    thread::scope(|scope| {
+        let stepper = FadeStepper::new(Fade::new(ColorSetting::default()), STEP);
+        scope.spawn(|| {
+            run_applier(
+                stepper,
+                receiver,
+                &mut gamma,
+                &state,
+                DisplayLossPolicy::Exit,
+                now,
+            )
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let state = state.lock().unwrap().clone();
+            if state.handled == commands.len() as u64 && !state.fading {
+                break;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "Applier did not settle: {:?}",
+                state
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        drop(sender);
+    });
+
+    let state = state.into_inner().unwrap();
+    (gamma, state)
+}
+
+#[test]
+fn test_applier_thread_interpolates_between_targets() {
+    let day = setting(6500);
+    let night = ColorSetting {
+        temperature: 3500,
+        brightness: 0.8,
+        ..ColorSetting::default()
+    };
+    let (gamma, state) = run_to_completion(&[
+        ApplierCommand::Jump(day),
+        ApplierCommand::Target {
+            setting: night,
+            force: false,
+        },
+    ]);
+
+    let mut expected = vec![day];
+    expected.extend(expected_fade(&day, &night));
+    assert_eq!(gamma.applied, expected);
+    assert_eq!(state.current, night);
+    assert_eq!(state.fades_started, 1);
+    assert_eq!(state.applies, expected.len() as u64);
+}
+
+#[test]
+fn test_applier_thread_keeps_last_good_setting() {
+    let invalid = ColorSetting {
+        brightness: f32::NAN,
+        ..setting(4000)
+    };
+    let (gamma, state) = run_to_completion(&[
+        ApplierCommand::Jump(setting(5000)),
+        ApplierCommand::Jump(invalid),
+        ApplierCommand::Reapply,
+    ]);
+
+    assert_eq!(gamma.applied, vec![setting(5000), setting(5000)]);
+    assert_eq!(gamma.invalidated, 1);
+    assert_eq!(state.rejected, 1);
+    assert_eq!(state.current, setting(5000));
+}
+
+#[test]
+fn test_fade_applier_settles_on_target() {
+    let mut gamma = RecordingGammaMethod::default();
+    thread::scope(|scope| {
+        let fade = Fade::new(ColorSetting::default());
+        let mut applier = FadeApplier::spawn(
+            scope,
+            fade,
+            Duration::from_millis(1),
+            &mut gamma,
+            DisplayLossPolicy::Exit,
+        );
+        applier.send(ApplierCommand::Target {
+            setting: setting(3500),
+            force: false,
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !applier.is_settled() {
+            assert!(Instant::now() < deadline, "Fade did not finish");
+            thread::sleep(Duration::from_millis(1));
+        }
+        let state = applier.state();
+        assert_eq!(state.current, setting(3500));
+        assert_eq!(state.fades_started, 1);
+        assert_eq!(state.error, None);
+    });
+
+    assert_eq!(gamma.applied.len(), FADE_LENGTH as usize + 1);
+    assert_eq!(gamma.applied.last(), Some(&setting(3500)));
+}
+
+#[test]
+fn test_fade_applier_reports_gamma_errors() {
+    let mut gamma = RecordingGammaMethod {
+        fail: true,
+        ..RecordingGammaMethod::default()
+    };
+    thread::scope(|scope| {
+        let fade = Fade::new(ColorSetting::default());
+        let mut applier =
+            FadeApplier::spawn(scope, fade, STEP, &mut gamma, DisplayLossPolicy::Exit);
+        applier.send(ApplierCommand::Jump(setting(3500)));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let error = loop {
+            if let Some(error) = applier.state().error {
+                break error;
+            }
+            assert!(Instant::now() < deadline, "Error was not reported");
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(error, Error::Gamma("Display went away".to_string()));
+
+        // Later commands are dropped rather than blocking
+        applier.send(ApplierCommand::Jump(setting(6500)));
+    });
+}
@@ -294,3 +294,176 @@ mod data_integrity_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod country_filter_tests {
+    use redshift_rebooted::cities::*;
+
+    fn sample_countries() -> Vec<String> {
+        ["Algeria", "Germany", "Niger", "Nigeria", "Spain"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_empty_query_returns_all() {
+        let countries = sample_countries();
+        assert_eq!(filter_countries(&countries, ""), countries);
+        assert_eq!(filter_countries(&countries, "   "), countries);
+    }
+
+    #[test]
+    fn test_filter_is_case_insensitive() {
+        let countries = sample_countries();
+        assert_eq!(filter_countries(&countries, "ger"), vec!["Germany", "Algeria", "Niger", "Nigeria"]);
+        assert_eq!(filter_countries(&countries, "GERM"), vec!["Germany"]);
+    }
+
+    #[test]
+    fn test_filter_prefix_matches_first() {
+        let countries = sample_countries();
+        let matches = filter_countries(&countries, "ni");
+        assert_eq!(matches, vec!["Niger", "Nigeria"]);
+
+        let matches = filter_countries(&countries, "ria");
+        assert_eq!(matches, vec!["Algeria", "Nigeria"]);
+    }
+
+    #[test]
+    fn test_filter_no_matches() {
+        let countries = sample_countries();
+        assert!(filter_countries(&countries, "xyz").is_empty());
+    }
+
+    #[test]
+    fn test_filter_real_country_list() {
+        let countries = get_countries();
+        let matches = filter_countries(&countries, "Germany");
+        assert_eq!(matches, vec!["Germany"]);
+    }
+}
+
+#[cfg(test)]
+mod cities_file_tests {
+    use redshift_rebooted::cities::*;
+    use std::collections::HashMap;
+
+    fn city_json(city: &str, country: &str, lat: &str, lng: &str) -> String {
+        format!(
+            r#"{{"city": "{city}", "city_ascii": "{city}", "lat": "{lat}", "lng": "{lng}",
+                "country": "{country}", "admin_name": "", "population": "", "id": "1",
+                "isCityCountry": false}}"#
+        )
+    }
+
+    #[test]
+    fn test_parse_cities_accepts_bundled_schema() {
+        let json = format!("[{}]", city_json("Smallville", "United States", "39.78", "-98.56"));
+        let cities = parse_cities(&json).unwrap();
+
+        assert_eq!(cities.len(), 1);
+        assert_eq!(cities[0].city_ascii, "Smallville");
+        assert_eq!(cities[0].latitude().unwrap(), 39.78);
+    }
+
+    #[test]
+    fn test_parse_cities_rejects_malformed_entries() {
+        // Not the bundled schema
+        assert!(parse_cities("{\"city\": \"Nowhere\"}").is_err());
+        assert!(parse_cities("[{\"city\": \"Nowhere\"}]").is_err());
+
+        // Coordinates that do not parse or are out of range
+        for (lat, lng) in [("north", "0"), ("91", "0"), ("0", "-180.5")] {
+            let json = format!(
+                "[{}, {}]",
+                city_json("Fine", "Chile", "-33.4", "-70.6"),
+                city_json("Broken", "Chile", lat, lng)
+            );
+            let err = parse_cities(&json).unwrap_err();
+            assert!(err.contains("City 2 (Broken)"), "Got: {}", err);
+        }
+
+        // Missing names
+        let json = format!("[{}]", city_json("Somewhere", "", "0", "0"));
+        assert!(parse_cities(&json).is_err());
+    }
+
+    #[test]
+    fn test_merge_cities_adds_and_replaces() {
+        let bundled = format!(
+            "[{}, {}]",
+            city_json("Springfield", "United States", "39.80", "-89.64"),
+            city_json("Lyon", "France", "45.76", "4.84")
+        );
+        let mut map = HashMap::new();
+        assert_eq!(merge_cities(&mut map, parse_cities(&bundled).unwrap()), (2, 0));
+
+        let extra = format!(
+            "[{}, {}, {}]",
+            city_json("springfield", "United States", "37.21", "-93.29"),
+            city_json("Smallville", "United States", "39.78", "-98.56"),
+            city_json("Vaduz", "Liechtenstein", "47.14", "9.52")
+        );
+        assert_eq!(merge_cities(&mut map, parse_cities(&extra).unwrap()), (2, 1));
+
+        let us = &map["United States"];
+        assert_eq!(us.len(), 2);
+        assert_eq!(us[0].lat, "37.21", "Same name replaces the bundled entry");
+        assert_eq!(us[1].city_ascii, "Smallville");
+        assert_eq!(map["Liechtenstein"].len(), 1, "New countries are added");
+        assert_eq!(map["France"].len(), 1);
+    }
+
+    #[test]
+    fn test_malformed_cities_file_fails_startup() {
+        use std::process::Command;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("cities.json");
+        std::fs::write(&path, format!("[{}]", city_json("Atlantis", "Greece", "95", "0"))).unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+            .args(["--cities-file", path.to_str().unwrap(), "-l", "40:-74", "-m", "dummy", "-p"])
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(output.status.code(), Some(2), "Got: {}", stderr);
+        assert!(stderr.contains("Latitude must be between"), "Got: {}", stderr);
+    }
+}
+
+#[cfg(test)]
+mod city_lookup_tests {
+    use redshift_rebooted::cities::*;
+
+    #[test]
+    fn test_find_city_ignores_case_and_accents() {
+        for query in ["Reykjavik", "reykjavik", "Reykjavík", "  REYKJAVIK "] {
+            let city = find_city(query).unwrap();
+            assert_eq!(city.country, "Iceland", "{}", query);
+            assert_eq!(city.city_ascii, "Reykjavik");
+        }
+    }
+
+    #[test]
+    fn test_find_city_prefers_most_populous() {
+        let city = find_city("San Jose").unwrap();
+        assert_eq!(city.country, "United States");
+    }
+
+    #[test]
+    fn test_find_city_narrowed_to_country() {
+        let city = find_city("San Jose, costa rica").unwrap();
+        assert_eq!(city.country, "Costa Rica");
+
+        assert!(find_city("Reykjavik, Norway").is_err());
+    }
+
+    #[test]
+    fn test_find_unknown_city() {
+        let err = find_city("Atlantis").unwrap_err();
+        assert_eq!(err, "Unknown city: Atlantis");
+    }
+}
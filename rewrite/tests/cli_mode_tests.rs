@@ -0,0 +1,381 @@
+/// Tests for program mode selection on the command line
+/// Conflicting mode flags must be rejected with a usage error
+
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+fn run_redshift(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(args)
+        .output()
+        .expect("Failed to execute redshift")
+}
+
+fn assert_mode_conflict(args: &[&str]) {
+    let output = run_redshift(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        !output.status.success(),
+        "Conflicting flags {:?} should fail",
+        args
+    );
+    assert!(
+        stderr.contains("cannot be used with"),
+        "Conflicting flags {:?} should explain the conflict, got: {}",
+        args,
+        stderr
+    );
+}
+
+#[test]
+fn test_print_and_one_shot_conflict() {
+    assert_mode_conflict(&["-l", "40:-74", "-m", "dummy", "-p", "-o"]);
+}
+
+#[test]
+fn test_print_and_continual_conflict() {
+    assert_mode_conflict(&["-l", "40:-74", "-m", "dummy", "--print", "--continual"]);
+}
+
+#[test]
+fn test_one_shot_and_reset_conflict() {
+    assert_mode_conflict(&["-m", "dummy", "--one-shot", "--reset"]);
+}
+
+#[test]
+fn test_reset_and_continual_conflict() {
+    assert_mode_conflict(&["-m", "dummy", "-x", "--continual"]);
+}
+
+#[test]
+fn test_reset_mode_applies_neutral_temperature() {
+    let output = run_redshift(&["-m", "dummy", "-x"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Reset mode should succeed");
+    assert!(
+        stdout.contains("Temperature: 6500"),
+        "Reset mode should apply neutral temperature, got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_single_mode_flag_is_accepted() {
+    let output = run_redshift(&["-l", "40:-74", "-m", "dummy", "-p"]);
+    assert!(output.status.success(), "A single mode flag should be accepted");
+}
+
+#[test]
+fn test_period_prints_lowercase_period_name() {
+    let output = run_redshift(&["-l", "40:-74", "--period"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "--period should exit 0");
+    assert!(
+        ["daytime\n", "night\n", "transition\n"].contains(&stdout.as_ref()),
+        "--period should print only the period name, got: {:?}",
+        stdout
+    );
+}
+
+#[test]
+fn test_period_does_not_initialize_gamma_method() {
+    // RandR is the default method; --period must not need an X server
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "40:-74", "--period", "-v"])
+        .env_remove("DISPLAY")
+        .output()
+        .expect("Failed to execute redshift");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "--period should work without X");
+    assert!(!stderr.contains("Initializing gamma method"));
+}
+
+#[test]
+fn test_period_and_print_conflict() {
+    assert_mode_conflict(&["-l", "40:-74", "--period", "-p"]);
+}
+
+#[test]
+fn test_setup_and_print_conflict() {
+    assert_mode_conflict(&["-l", "40:-74", "-m", "dummy", "--setup", "-p"]);
+}
+
+#[test]
+fn test_temperature_preset_name_on_command_line() {
+    let output = run_redshift(&[
+        "-l", "40:-74", "-m", "dummy", "-o", "-t", "candle", "--temp-night", "candle",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Preset names should be accepted");
+    assert!(stdout.contains("Temperature: 1900"), "Should apply 1900K, got: {}", stdout);
+}
+
+#[test]
+fn test_unknown_temperature_preset_rejected() {
+    let output = run_redshift(&["-l", "40:-74", "-m", "dummy", "-p", "--temp-night", "sunset"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("sunset"), "Should explain the bad value, got: {}", stderr);
+}
+
+#[test]
+fn test_quiet_omits_dummy_warning_and_temperature() {
+    let output = run_redshift(&["-m", "dummy", "-x", "--quiet"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Quiet reset should succeed");
+    assert!(!stderr.contains("WARNING"), "Quiet mode should omit the warning, got: {}", stderr);
+    assert!(!stdout.contains("Temperature:"), "Quiet mode should omit temperatures, got: {}", stdout);
+}
+
+#[test]
+fn test_dummy_warns_without_quiet() {
+    let output = run_redshift(&["-m", "dummy", "-x"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("WARNING: Using dummy gamma method!"), "got: {}", stderr);
+}
+
+#[test]
+fn test_scheme_file_replaces_ini_scheme() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("scheme.toml");
+    std::fs::write(&path, "[day]\ntemperature = 4200\n\n[night]\ntemperature = 4200\n")
+        .expect("Failed to write scheme file");
+
+    let output = run_redshift(&[
+        "-l", "40:-74", "-m", "dummy", "-p", "--scheme-file", path.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Print with a scheme file should succeed");
+    assert!(
+        stdout.contains("Color temperature: 4200K"),
+        "Should use the scheme file temperatures, got: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_invalid_scheme_file_is_rejected() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("scheme.toml");
+    std::fs::write(&path, "high = -20.0\n").expect("Failed to write scheme file");
+
+    let output = run_redshift(&[
+        "-l", "40:-74", "-m", "dummy", "-p", "--scheme-file", path.to_str().unwrap(),
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "An invalid scheme file should fail");
+    assert!(stderr.contains("Invalid scheme file"), "Should explain the problem, got: {}", stderr);
+}
+
+#[test]
+fn test_illegible_scheme_file_is_rejected() {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let path = dir.path().join("scheme.toml");
+    std::fs::write(&path, "[night]\ntemperature = 4500\nbrightness = 0.2\ngamma = [0.5, 0.5, 0.5]\n")
+        .expect("Failed to write scheme file");
+    let args = ["-l", "40:-74", "-m", "dummy", "-p", "--scheme-file", path.to_str().unwrap()];
+
+    let output = run_redshift(&args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "A scheme too dark to read should be refused");
+    assert!(stderr.contains("Night brightness and gamma are too dark"), "Should explain the problem, got: {}", stderr);
+
+    let output = run_redshift(&[&args[..], &["--force"]].concat());
+    assert!(output.status.success(), "--force should apply the scheme anyway");
+}
+
+#[test]
+fn test_out_of_range_location_is_usage_error() {
+    let output = run_redshift(&["-l", "95:0", "-m", "dummy", "-p"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "Got: {}", stderr);
+    assert!(stderr.contains("Latitude must be between"), "Got: {}", stderr);
+}
+
+#[test]
+fn test_generate_completions() {
+    for (shell, marker) in [
+        ("bash", "complete -F"),
+        ("zsh", "#compdef redshift-rebooted"),
+        ("fish", "complete -c redshift-rebooted"),
+        ("powershell", "Register-ArgumentCompleter"),
+    ] {
+        let output = run_redshift(&["--generate-completions", shell]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(output.status.success(), "Generating {} completions should succeed", shell);
+        assert!(stdout.contains(marker), "Unexpected {} completions: {}", shell, stdout);
+        assert!(stdout.contains("temp-day"), "{} completions should list options", shell);
+    }
+}
+
+#[test]
+fn test_generate_completions_is_hidden() {
+    let output = run_redshift(&["--help"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!stdout.contains("generate-completions"), "Should not be listed in --help");
+}
+
+#[test]
+fn test_generate_completions_rejects_unknown_shell() {
+    let output = run_redshift(&["--generate-completions", "tcsh"]);
+    assert!(!output.status.success(), "Unknown shells should be rejected");
+}
+
+#[test]
+fn test_location_list_prints_providers() {
+    let output = run_redshift(&["-l", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "-l list should succeed");
+    assert!(stdout.contains("geoclue2"), "Should list GeoClue2, got: {}", stdout);
+    assert!(stdout.contains("manual"), "Should list the manual provider, got: {}", stdout);
+    assert!(!stdout.contains("Period"), "Should not run print mode, got: {}", stdout);
+}
+
+#[test]
+fn test_location_manual_provider_options() {
+    let output = run_redshift(&["-l", "manual:lat=40:lon=-74", "-m", "dummy", "--period"]);
+    assert!(output.status.success(), "-l manual:lat=N:lon=N should be accepted");
+
+    let output = run_redshift(&["-l", "manual:lat=40", "-m", "dummy", "--period"]);
+    assert!(!output.status.success(), "A missing longitude should be rejected");
+}
+
+/* Temperatures printed by the dummy method, in order */
+fn dummy_temperatures(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("Temperature: "))
+        .map(str::to_string)
+        .collect()
+}
+
+/* Run continual mode until its loop has made the first update, then stop
+   it with SIGTERM. Returns stdout and the log. */
+fn run_continual_first_update(args: &[&str]) -> (Output, String) {
+    let dir = TempDir::new().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(args)
+        .arg("-vv")
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("XDG_RUNTIME_DIR", dir.path())
+        .env("HOME", dir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute redshift");
+
+    /* The status is logged in the first iteration, which finishes its
+       update before the loop looks at signals again */
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut log = String::new();
+    let mut line = String::new();
+    while stderr.read_line(&mut line).unwrap() > 0 {
+        log.push_str(&line);
+        let started = line.contains("Status: Enabled");
+        line.clear();
+        if started {
+            break;
+        }
+    }
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGTERM);
+    }
+    stderr.read_to_string(&mut log).unwrap();
+    (child.wait_with_output().unwrap(), log)
+}
+
+#[test]
+fn test_continual_mode_applies_startup_target_once() {
+    // Day and night alike, so the target is the same all day; kept at
+    // exit, so no exit fade is started either
+    let base = [
+        "-l", "0:0", "-m", "dummy", "-t", "3000", "--temp-night", "3000", "--on-exit", "keep",
+    ];
+
+    for args in [&base[..], &[&base[..], &["--instant"]].concat()] {
+        let (output, log) = run_continual_first_update(args);
+        let temperatures = dummy_temperatures(&output);
+        assert!(!temperatures.is_empty(), "Nothing applied, log:\n{}", log);
+        assert!(
+            temperatures.iter().all(|temperature| temperature == "3000"),
+            "Expected the startup setting only, got {:?}",
+            temperatures
+        );
+        assert!(log.contains("Shutting down after: 0 fades started"), "Log:\n{}", log);
+    }
+}
+
+#[test]
+fn test_one_shot_on_exit_policies() {
+    let base = ["-l", "0:0", "-m", "dummy", "-o", "-t", "4000", "--temp-night", "4000"];
+
+    let output = run_redshift(&base);
+    assert!(output.status.success());
+    assert_eq!(dummy_temperatures(&output), ["4000"], "One-shot keeps the setting by default");
+
+    let output = run_redshift(&[&base[..], &["--on-exit", "neutral"]].concat());
+    assert!(output.status.success());
+    assert_eq!(dummy_temperatures(&output), ["4000", "6500"]);
+
+    let output = run_redshift(&[&base[..], &["--on-exit", "keep"]].concat());
+    assert_eq!(dummy_temperatures(&output), ["4000"]);
+
+    let output = run_redshift(&[&base[..], &["--on-exit", "bogus"]].concat());
+    assert!(!output.status.success(), "Unknown policies should be rejected");
+}
+
+#[test]
+fn test_gamma_zero_on_command_line_is_rejected() {
+    for (gamma, message) in [
+        ("0", "Gamma 0 is outside"),
+        ("1:0:1", "Gamma 0 of the green channel is outside"),
+    ] {
+        let output = run_redshift(&["-l", "40:-74", "-m", "dummy", "-p", "-g", gamma]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success(), "Gamma {} should be rejected", gamma);
+        assert!(stderr.contains(message), "Unexpected error: {}", stderr);
+    }
+}
+
+#[test]
+fn test_brightness_out_of_range_on_command_line_is_rejected() {
+    for (brightness, message) in [
+        ("1.5:0.5", "Invalid -b/--brightness 1.5:0.5: Day brightness 1.5 is outside 0.1-1"),
+        ("0.8:0.05", "Invalid -b/--brightness 0.8:0.05: Night brightness 0.05 is outside 0.1-1"),
+    ] {
+        let output = run_redshift(&["-l", "40:-74", "-m", "dummy", "-p", "-b", brightness]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success(), "Brightness {} should be rejected", brightness);
+        assert!(stderr.contains(message), "Unexpected error: {}", stderr);
+    }
+
+    // Overdrive allows brightness above 1
+    let output = run_redshift(&["-l", "40:-74", "-m", "dummy", "-p", "-b", "1.5:0.5", "--allow-overdrive"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_clamp_invalid_clamps_command_line_gamma() {
+    let output = run_redshift(&["-l", "40:-74", "-m", "dummy", "-p", "-g", "1:1:15", "--clamp-invalid"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Clamping should succeed: {}", stderr);
+    assert!(stderr.contains("blue gamma 15 clamped to 10"), "Missing warning: {}", stderr);
+}
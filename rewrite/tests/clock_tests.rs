@@ -0,0 +1,140 @@
+/// Tests for time sources and the period at a given instant
+
+use chrono::Weekday;
+use redshift_rebooted::clock::*;
+use redshift_rebooted::period::get_current_period;
+use redshift_rebooted::timeofday::{local_weekday, ActiveDays};
+use redshift_rebooted::types::{ColorSetting, Location, Period, TransitionScheme};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/* 2024-03-20 00:00 UTC, the March equinox */
+const EQUINOX_MIDNIGHT: f64 = 1710892800.0;
+
+/* On the equator at Greenwich local solar time is close to UTC */
+const EQUATOR: Location = Location { lat: 0.0, lon: 0.0 };
+
+#[test]
+fn test_fixed_clock_is_stopped() {
+    let clock = FixedClock(EQUINOX_MIDNIGHT);
+    assert_eq!(clock.now(), EQUINOX_MIDNIGHT);
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(clock.now(), EQUINOX_MIDNIGHT);
+}
+
+#[test]
+fn test_real_clock_follows_system_time() {
+    let system = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64();
+    assert!((RealClock.now() - system).abs() < 5.0);
+}
+
+#[test]
+fn test_period_at_midnight_is_night() {
+    let scheme = TransitionScheme::default();
+    let (period, setting) = get_current_period(&EQUATOR, &scheme, &FixedClock(EQUINOX_MIDNIGHT));
+    assert_eq!(period, Period::Night);
+    assert_eq!(setting, scheme.night);
+}
+
+#[test]
+fn test_period_at_noon_is_daytime() {
+    let scheme = TransitionScheme::default();
+    let noon = FixedClock(EQUINOX_MIDNIGHT + 12.0 * 3600.0);
+    let (period, setting) = get_current_period(&EQUATOR, &scheme, &noon);
+    assert_eq!(period, Period::Daytime);
+    assert_eq!(setting, scheme.day);
+}
+
+#[test]
+fn test_period_at_sunrise_is_transition() {
+    let scheme = TransitionScheme::default();
+    let sunrise = FixedClock(EQUINOX_MIDNIGHT + 6.0 * 3600.0);
+    let (period, setting) = get_current_period(&EQUATOR, &scheme, &sunrise);
+    assert_eq!(period, Period::Transition);
+    assert!(
+        setting.temperature > scheme.night.temperature
+            && setting.temperature < scheme.day.temperature,
+        "Expected an intermediate temperature, got {}K",
+        setting.temperature
+    );
+}
+
+#[test]
+fn test_temperature_rises_through_dawn() {
+    let scheme = TransitionScheme::default();
+    let temperatures: Vec<i32> = (0..=8)
+        .map(|step| {
+            let clock = FixedClock(EQUINOX_MIDNIGHT + 5.5 * 3600.0 + step as f64 * 300.0);
+            get_current_period(&EQUATOR, &scheme, &clock).1.temperature
+        })
+        .collect();
+
+    assert!(
+        temperatures.windows(2).all(|pair| pair[0] <= pair[1]),
+        "Temperature should not fall at dawn: {:?}",
+        temperatures
+    );
+    assert!(temperatures[0] < temperatures[8], "{:?}", temperatures);
+}
+
+#[test]
+fn test_deep_night_applies_at_midnight() {
+    let scheme = TransitionScheme {
+        deep_night_temp: Some(2000),
+        ..TransitionScheme::default()
+    };
+    let (period, setting) = get_current_period(&EQUATOR, &scheme, &FixedClock(EQUINOX_MIDNIGHT));
+    assert_eq!(period, Period::Night);
+    assert_eq!(setting.temperature, 2000);
+    assert_eq!(setting.brightness, scheme.night.brightness);
+
+    // Daytime is unaffected
+    let noon = FixedClock(EQUINOX_MIDNIGHT + 12.0 * 3600.0);
+    assert_eq!(get_current_period(&EQUATOR, &scheme, &noon).1, scheme.day);
+}
+
+/* Active days containing or excluding the local day at `timestamp` */
+fn days_around(timestamp: f64, include: bool) -> ActiveDays {
+    let today = local_weekday(timestamp);
+    let names: Vec<String> = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+    .iter()
+    .filter(|&&day| (day == today) == include)
+    .map(|day| day.to_string())
+    .collect();
+    ActiveDays::parse(&names.join(",")).unwrap()
+}
+
+#[test]
+fn test_schedule_is_neutral_on_excluded_day() {
+    let clock = FixedClock(EQUINOX_MIDNIGHT);
+    let scheme = TransitionScheme {
+        active_days: Some(days_around(clock.now(), false)),
+        ..TransitionScheme::default()
+    };
+
+    let (period, setting) = get_current_period(&EQUATOR, &scheme, &clock);
+    assert_eq!(period, Period::Night, "The period is still reported");
+    assert_eq!(setting, ColorSetting::default());
+}
+
+#[test]
+fn test_schedule_is_active_on_included_day() {
+    let clock = FixedClock(EQUINOX_MIDNIGHT);
+    let scheme = TransitionScheme {
+        active_days: Some(days_around(clock.now(), true)),
+        ..TransitionScheme::default()
+    };
+
+    let (_, setting) = get_current_period(&EQUATOR, &scheme, &clock);
+    assert_eq!(setting, scheme.night);
+}
@@ -0,0 +1,85 @@
+/// Tests for following the desktop's dark mode preference
+/// Uses a scripted color scheme source so no portal is needed
+
+use redshift_rebooted::color_scheme::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Color scheme source returning whatever the test sets
+struct FakeColorSchemeSource {
+    scheme: Rc<RefCell<Result<ColorScheme, String>>>,
+}
+
+impl ColorSchemeSource for FakeColorSchemeSource {
+    fn color_scheme(&mut self) -> Result<ColorScheme, String> {
+        self.scheme.borrow().clone()
+    }
+}
+
+fn fake_watcher(
+    initial: ColorScheme,
+) -> (ColorSchemeWatcher, Rc<RefCell<Result<ColorScheme, String>>>) {
+    let scheme = Rc::new(RefCell::new(Ok(initial)));
+    let source = FakeColorSchemeSource {
+        scheme: Rc::clone(&scheme),
+    };
+    (ColorSchemeWatcher::new(Box::new(source)), scheme)
+}
+
+#[test]
+fn test_portal_values() {
+    assert_eq!(ColorScheme::from_portal(0), ColorScheme::NoPreference);
+    assert_eq!(ColorScheme::from_portal(1), ColorScheme::Dark);
+    assert_eq!(ColorScheme::from_portal(2), ColorScheme::Light);
+    assert_eq!(ColorScheme::from_portal(7), ColorScheme::NoPreference);
+}
+
+#[test]
+fn test_only_dark_enables() {
+    assert!(ColorScheme::Dark.wants_enabled());
+    assert!(!ColorScheme::Light.wants_enabled());
+    assert!(!ColorScheme::NoPreference.wants_enabled());
+}
+
+#[test]
+fn test_first_poll_reports_initial_state() {
+    let (mut watcher, _) = fake_watcher(ColorScheme::Light);
+
+    assert_eq!(watcher.poll(), Some(false));
+    assert_eq!(watcher.poll(), None, "An unchanged preference is not reported");
+}
+
+#[test]
+fn test_switching_dark_mode_toggles() {
+    let (mut watcher, scheme) = fake_watcher(ColorScheme::Dark);
+    assert_eq!(watcher.poll(), Some(true));
+
+    *scheme.borrow_mut() = Ok(ColorScheme::Light);
+    assert_eq!(watcher.poll(), Some(false));
+
+    *scheme.borrow_mut() = Ok(ColorScheme::Dark);
+    assert_eq!(watcher.poll(), Some(true));
+    assert_eq!(watcher.poll(), None);
+}
+
+#[test]
+fn test_light_to_no_preference_is_reported_but_stays_disabled() {
+    let (mut watcher, scheme) = fake_watcher(ColorScheme::Light);
+    watcher.poll();
+
+    *scheme.borrow_mut() = Ok(ColorScheme::NoPreference);
+    assert_eq!(watcher.poll(), Some(false));
+}
+
+#[test]
+fn test_query_errors_keep_state() {
+    let (mut watcher, scheme) = fake_watcher(ColorScheme::Dark);
+    watcher.poll();
+
+    *scheme.borrow_mut() = Err("Portal went away".to_string());
+    assert_eq!(watcher.poll(), None);
+
+    // Coming back with the same preference is not a change
+    *scheme.borrow_mut() = Ok(ColorScheme::Dark);
+    assert_eq!(watcher.poll(), None);
+}
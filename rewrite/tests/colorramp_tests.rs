@@ -164,6 +164,7 @@ fn test_colorramp_fill_warm_reduces_blue() {
         temperature: 3500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     colorramp_fill(&mut gamma_r, &mut gamma_g, &mut gamma_b, &setting);
@@ -201,6 +202,7 @@ fn test_colorramp_fill_brightness() {
         temperature: 6500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 0.5, // Half brightness
+        contrast: 1.0,
     };
 
     colorramp_fill(&mut gamma_r, &mut gamma_g, &mut gamma_b, &setting);
@@ -219,6 +221,38 @@ fn test_colorramp_fill_brightness() {
     }
 }
 
+#[test]
+fn test_colorramp_fill_overdrive_scales_and_clamps() {
+    let size = 256;
+    let linear = linear_ramp(size);
+    let (mut gamma_r, mut gamma_g, mut gamma_b) = (linear.clone(), linear.clone(), linear.clone());
+
+    let setting = ColorSetting {
+        brightness: 1.5,
+        ..ColorSetting::default()
+    };
+    colorramp_fill(&mut gamma_r, &mut gamma_g, &mut gamma_b, &setting);
+
+    // Below the clipping point values grow by half
+    for i in 1..size / 2 {
+        let expected = linear[i] as f64 * 1.5;
+        assert!(
+            (gamma_r[i] as f64 - expected).abs() < 2.0,
+            "Index {} should be scaled by 1.5: {} vs {}",
+            i,
+            gamma_r[i],
+            expected
+        );
+    }
+
+    // Above 1/1.5 of full intensity every value clips to the top
+    for i in (size * 2 / 3 + 1)..size {
+        assert_eq!(gamma_r[i], u16::MAX, "Index {} should clamp", i);
+        assert_eq!(gamma_g[i], u16::MAX);
+        assert_eq!(gamma_b[i], u16::MAX);
+    }
+}
+
 #[test]
 fn test_colorramp_fill_gamma() {
     // Test gamma adjustment
@@ -240,6 +274,7 @@ fn test_colorramp_fill_gamma() {
         temperature: 6500,
         gamma: [2.0, 1.0, 1.0], // Higher gamma for red
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     colorramp_fill(&mut gamma_r, &mut gamma_g, &mut gamma_b, &setting);
@@ -307,6 +342,7 @@ fn test_colorramp_fill_float_warm() {
         temperature: 3500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     colorramp_fill_float(&mut gamma_r, &mut gamma_g, &mut gamma_b, &setting);
@@ -347,6 +383,7 @@ fn test_color_setting_cloning() {
         temperature: 5000,
         gamma: [0.9, 1.0, 1.1],
         brightness: 0.8,
+        contrast: 1.0,
     };
 
     let cloned = setting;
@@ -355,3 +392,627 @@ fn test_color_setting_cloning() {
     assert_eq!(setting.gamma, cloned.gamma);
     assert_eq!(setting.brightness, cloned.brightness);
 }
+
+#[test]
+fn test_compensated_temperature_unchanged_at_full_brightness() {
+    assert_eq!(compensated_temperature(3500, 1.0), 3500);
+    assert_eq!(compensated_temperature(3500, 0.8), 3500);
+    assert_eq!(compensated_temperature(6500, 0.9), 6500);
+}
+
+#[test]
+fn test_compensated_temperature_warmer_when_dim() {
+    let temp = compensated_temperature(3500, 0.3);
+    assert!(temp < 3500, "Dimmed temperature should be warmer, got {}", temp);
+    assert!(temp > 3000, "Compensation should be a small nudge, got {}", temp);
+
+    // Dimmer means more compensation
+    assert!(compensated_temperature(3500, 0.1) < temp);
+}
+
+#[test]
+fn test_colorramp_fill_brightness_compensation() {
+    let size = 256;
+    let linear: Vec<u16> = (0..size).map(|i| ((i * 65535) / (size - 1)) as u16).collect();
+
+    let setting = ColorSetting {
+        temperature: 4500,
+        gamma: [1.0, 1.0, 1.0],
+        brightness: 0.3,
+        contrast: 1.0,
+    };
+
+    let (mut plain_r, mut plain_g, mut plain_b) = (linear.clone(), linear.clone(), linear.clone());
+    colorramp_fill(&mut plain_r, &mut plain_g, &mut plain_b, &setting);
+
+    let (mut comp_r, mut comp_g, mut comp_b) = (linear.clone(), linear.clone(), linear.clone());
+    colorramp_fill_with_compensation(&mut comp_r, &mut comp_g, &mut comp_b, &setting, true);
+
+    // Red is saturated below 6500K, so only green and blue are reduced
+    assert_eq!(comp_r, plain_r);
+    for i in size / 2..size {
+        assert!(comp_b[i] < plain_b[i], "Compensated blue should be lower at index {}", i);
+        assert!(comp_g[i] <= plain_g[i], "Compensated green should not be higher at index {}", i);
+    }
+
+    // Without compensation the result matches colorramp_fill exactly
+    let (mut off_r, mut off_g, mut off_b) = (linear.clone(), linear.clone(), linear);
+    colorramp_fill_with_compensation(&mut off_r, &mut off_g, &mut off_b, &setting, false);
+    assert_eq!(off_b, plain_b);
+}
+
+#[test]
+fn test_white_point_xy_d65_is_neutral() {
+    // D65 is the sRGB reference white, so no channel should be scaled
+    let white_point = get_white_point_xy(0.3127, 0.3290);
+
+    for (i, channel) in white_point.iter().enumerate() {
+        assert!(
+            (channel - 1.0).abs() < EPSILON,
+            "Channel {} for D65 should be ~1.0, got {}",
+            i,
+            channel
+        );
+    }
+}
+
+#[test]
+fn test_white_point_xy_d50_is_warm() {
+    // D50 is warmer than D65: full red, reduced blue
+    let white_point = get_white_point_xy(0.3457, 0.3585);
+
+    assert!((white_point[0] - 1.0).abs() < EPSILON, "Red should be the max channel");
+    assert!(white_point[2] < white_point[1], "Blue should be reduced most");
+    assert!(white_point[2] < 0.9, "Blue should be noticeably reduced, got {}", white_point[2]);
+}
+
+#[test]
+fn test_colorramp_fill_with_white_point_overrides_temperature() {
+    let size = 256;
+    let linear: Vec<u16> = (0..size).map(|i| ((i as f64 / size as f64) * 65536.0) as u16).collect();
+    let mut r = linear.clone();
+    let mut g = linear.clone();
+    let mut b = linear.clone();
+
+    // Warm temperature, but the D65 white point should leave the ramps neutral
+    let setting = ColorSetting {
+        temperature: 3500,
+        ..ColorSetting::default()
+    };
+    colorramp_fill_with_white_point(&mut r, &mut g, &mut b, &setting, &get_white_point_xy(0.3127, 0.3290));
+
+    let last = size - 1;
+    let tolerance = (65536.0 * EPSILON) as i32;
+    assert!((r[last] as i32 - linear[last] as i32).abs() < tolerance);
+    assert!((b[last] as i32 - linear[last] as i32).abs() < tolerance);
+}
+
+/// Fill a linear ramp with `quantization` and return it with the ideal
+/// unquantized values for the red channel
+fn quantized_ramp(quantization: Quantization) -> (Vec<u16>, Vec<f64>) {
+    let size = 1024;
+    let linear: Vec<u16> = (0..size).map(|i| ((i as f64 / size as f64) * 65536.0) as u16).collect();
+    let mut r = linear.clone();
+    let mut g = linear.clone();
+    let mut b = linear.clone();
+
+    let setting = ColorSetting {
+        temperature: 4500,
+        gamma: [0.9, 1.0, 1.0],
+        brightness: 0.85,
+        contrast: 1.0,
+    };
+    let white_point = get_white_point(setting.temperature);
+    colorramp_fill_quantized(&mut r, &mut g, &mut b, &setting, &white_point, quantization);
+
+    let ideal = linear
+        .iter()
+        .map(|&v| {
+            ((v as f64 / 65536.0) * setting.brightness as f64 * white_point[0] as f64)
+                .powf(1.0 / setting.gamma[0] as f64)
+                * 65536.0
+        })
+        .collect();
+    (r, ideal)
+}
+
+#[test]
+fn test_round_is_closer_to_ideal_than_truncate() {
+    let (rounded, ideal) = quantized_ramp(Quantization::Round);
+    let (truncated, _) = quantized_ramp(Quantization::Truncate);
+
+    let error = |ramp: &[u16]| -> f64 {
+        ramp.iter().zip(&ideal).map(|(&v, &x)| (v as f64 - x).abs()).sum()
+    };
+    assert!(
+        error(&rounded) < error(&truncated),
+        "Rounding error {} should be below truncation error {}",
+        error(&rounded),
+        error(&truncated)
+    );
+
+    for (i, (&v, &x)) in rounded.iter().zip(&ideal).enumerate() {
+        assert!((v as f64 - x).abs() <= 0.5, "Rounded value {} at {} is off from {}", v, i, x);
+    }
+    for (&v, &x) in truncated.iter().zip(&ideal) {
+        assert!(v as f64 <= x, "Truncation never rounds up");
+    }
+}
+
+#[test]
+fn test_dither_stays_within_one_step_without_bias() {
+    let (dithered, ideal) = quantized_ramp(Quantization::Dither);
+
+    let mut total_error = 0.0;
+    for (&v, &x) in dithered.iter().zip(&ideal) {
+        assert!((v as f64 - x).abs() < 1.0, "Dithered value {} is off from {}", v, x);
+        total_error += v as f64 - x;
+    }
+    let mean_error = total_error / dithered.len() as f64;
+    assert!(mean_error.abs() < 0.1, "Dither should not bias the ramp, mean error {}", mean_error);
+}
+
+#[test]
+fn test_quantize_clamps_to_u16() {
+    for quantization in [Quantization::Truncate, Quantization::Round, Quantization::Dither] {
+        assert_eq!(quantization.quantize(65536.0, 15), u16::MAX);
+        assert_eq!(quantization.quantize(-3.0, 0), 0);
+    }
+}
+
+#[test]
+fn test_parse_quantization() {
+    assert_eq!(Quantization::parse("round"), Ok(Quantization::Round));
+    assert_eq!(Quantization::parse(" Truncate "), Ok(Quantization::Truncate));
+    assert_eq!(Quantization::parse("dither"), Ok(Quantization::Dither));
+    assert!(Quantization::parse("floor").is_err());
+    assert_eq!(Quantization::default(), Quantization::Round);
+}
+
+/// Fill linear ramps for `setting` with the blue cutoff applied
+fn fill_with_blue_cutoff(setting: &ColorSetting, cutoff: Option<i32>) -> [Vec<u16>; 3] {
+    let size = 256;
+    let linear: Vec<u16> = (0..size).map(|i| ((i as f64 / size as f64) * 65536.0) as u16).collect();
+    let mut ramps = [linear.clone(), linear.clone(), linear];
+
+    let white_point = apply_blue_cutoff(get_white_point(setting.temperature), setting.temperature, cutoff);
+    let [r, g, b] = &mut ramps;
+    colorramp_fill_quantized(r, g, b, setting, &white_point, Quantization::Round);
+    ramps
+}
+
+#[test]
+fn test_blue_cutoff_zeroes_blue_below_cutoff() {
+    let setting = ColorSetting {
+        temperature: 2000,
+        ..ColorSetting::default()
+    };
+
+    let cut = fill_with_blue_cutoff(&setting, Some(2500));
+    let plain = fill_with_blue_cutoff(&setting, None);
+
+    assert!(cut[2].iter().all(|&v| v == 0), "Blue ramp should be all zeros below the cutoff");
+    assert!(plain[2].iter().any(|&v| v > 0), "Blackbody blue is reduced, not removed");
+    assert_eq!(cut[0], plain[0], "Red should be unaffected");
+    assert_eq!(cut[1], plain[1], "Green should be unaffected");
+}
+
+#[test]
+fn test_blue_cutoff_unchanged_at_and_above_cutoff() {
+    for temperature in [2500, 4000, 6500] {
+        let setting = ColorSetting {
+            temperature,
+            ..ColorSetting::default()
+        };
+        assert_eq!(
+            fill_with_blue_cutoff(&setting, Some(2500)),
+            fill_with_blue_cutoff(&setting, None),
+            "Ramps at {}K should be unchanged",
+            temperature
+        );
+    }
+}
+
+#[test]
+fn test_parse_cvd_profile() {
+    assert_eq!(CvdProfile::parse("protanopia"), Ok(CvdProfile::Protanopia));
+    assert_eq!(CvdProfile::parse(" Deuteranopia "), Ok(CvdProfile::Deuteranopia));
+    assert_eq!(CvdProfile::parse("tritanopia"), Ok(CvdProfile::Tritanopia));
+    assert!(CvdProfile::parse("achromatopsia").is_err());
+}
+
+/// Fill linear ramps for `setting` with the CVD profile applied
+fn fill_with_cvd_profile(setting: &ColorSetting, profile: Option<CvdProfile>) -> [Vec<u16>; 3] {
+    let linear = linear_ramp(256);
+    let mut ramps = [linear.clone(), linear.clone(), linear];
+
+    let white_point = apply_cvd_profile(get_white_point(setting.temperature), profile);
+    let [r, g, b] = &mut ramps;
+    colorramp_fill_quantized(r, g, b, setting, &white_point, Quantization::Round);
+    ramps
+}
+
+/* Ratio of the top green ramp value to the top red one */
+fn green_to_red(ramps: &[Vec<u16>; 3]) -> f64 {
+    ramps[1][255] as f64 / ramps[0][255] as f64
+}
+
+#[test]
+fn test_cvd_profiles_leave_neutral_unchanged() {
+    let setting = ColorSetting::default();
+    let plain = fill_with_cvd_profile(&setting, None);
+    for profile in [CvdProfile::Protanopia, CvdProfile::Deuteranopia, CvdProfile::Tritanopia] {
+        assert_eq!(fill_with_cvd_profile(&setting, Some(profile)), plain, "{:?}", profile);
+
+        // Each row of the matrix sums to 1
+        for row in profile.matrix() {
+            assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-5, "{:?}", profile);
+        }
+    }
+}
+
+#[test]
+fn test_cvd_profiles_keep_more_green_in_warm_shifts() {
+    let setting = ColorSetting {
+        temperature: 3500,
+        ..ColorSetting::default()
+    };
+    let plain = fill_with_cvd_profile(&setting, None);
+
+    for profile in [CvdProfile::Protanopia, CvdProfile::Deuteranopia, CvdProfile::Tritanopia] {
+        let corrected = fill_with_cvd_profile(&setting, Some(profile));
+        assert_eq!(corrected[0], plain[0], "{:?}: red stays the brightest channel", profile);
+        assert!(
+            green_to_red(&corrected) > green_to_red(&plain),
+            "{:?}: green should rise relative to red ({} vs {})",
+            profile,
+            green_to_red(&corrected),
+            green_to_red(&plain)
+        );
+    }
+
+    // Protanopes lose the most red, so their profile keeps the most green
+    let protanopia = fill_with_cvd_profile(&setting, Some(CvdProfile::Protanopia));
+    let deuteranopia = fill_with_cvd_profile(&setting, Some(CvdProfile::Deuteranopia));
+    assert!(green_to_red(&protanopia) > green_to_red(&deuteranopia));
+
+    // Tritanopes get blue back instead of losing it to the warm shift
+    let tritanopia = fill_with_cvd_profile(&setting, Some(CvdProfile::Tritanopia));
+    assert!(tritanopia[2][255] > plain[2][255]);
+}
+
+#[test]
+fn test_contrast_one_is_noop() {
+    let size = 256;
+    let linear = linear_ramp(size);
+    let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear.clone());
+
+    let setting = ColorSetting {
+        temperature: 4500,
+        brightness: 0.8,
+        ..ColorSetting::default()
+    };
+    assert_eq!(setting.contrast, 1.0);
+    colorramp_fill(&mut r, &mut g, &mut b, &setting);
+
+    // Identical to the ramp without any contrast step
+    let white_point = get_white_point(setting.temperature);
+    for (channel, ramp) in [&r, &g, &b].into_iter().enumerate() {
+        for (i, &value) in ramp.iter().enumerate() {
+            let expected = ((linear[i] as f64 / 65536.0)
+                * setting.brightness as f64
+                * white_point[channel] as f64
+                * 65536.0)
+                .round() as u16;
+            assert_eq!(value, expected, "Channel {} differs at {}", channel, i);
+        }
+    }
+
+    for i in 0..=100 {
+        let value = i as f64 / 100.0;
+        assert_eq!(apply_contrast(value, 1.0), value);
+    }
+}
+
+#[test]
+fn test_contrast_steepens_ramp_around_midpoint() {
+    let size = 256;
+    let linear = linear_ramp(size);
+    let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear.clone());
+
+    let setting = ColorSetting {
+        contrast: 1.5,
+        ..ColorSetting::default()
+    };
+    colorramp_fill(&mut r, &mut g, &mut b, &setting);
+
+    let mid = size / 2;
+    // The midpoint stays put
+    assert_eq!(r[mid], linear[mid]);
+    // Values move away from the midpoint: darker below, brighter above
+    assert!(r[mid / 2] < linear[mid / 2]);
+    assert!(r[mid + mid / 2] > linear[mid + mid / 2]);
+    // The slope around the midpoint is 1.5 times the original
+    let slope = (r[mid + 8] - r[mid - 8]) as f64 / (linear[mid + 8] - linear[mid - 8]) as f64;
+    assert!((slope - 1.5).abs() < 0.01, "Slope {} should be 1.5", slope);
+    // The ends clip instead of wrapping
+    assert_eq!(r[0], 0);
+    assert_eq!(r[size - 1], u16::MAX);
+}
+
+#[test]
+fn test_apply_contrast_clamps() {
+    assert_eq!(apply_contrast(0.0, 2.0), 0.0);
+    assert_eq!(apply_contrast(1.0, 2.0), 1.0);
+    assert_eq!(apply_contrast(0.5, 0.5), 0.5);
+    assert_eq!(apply_contrast(1.0, 0.5), 0.75);
+}
+
+#[test]
+fn test_mid_gray_luminance_neutral() {
+    // Without adjustment mid-gray stays at half luminance
+    let luminance = mid_gray_luminance(&ColorSetting::default());
+    assert!((luminance - 0.5).abs() < 0.01, "Got {}", luminance);
+}
+
+#[test]
+fn test_mid_gray_luminance_darkens_with_brightness_and_gamma() {
+    let dim = ColorSetting {
+        brightness: 0.5,
+        ..ColorSetting::default()
+    };
+    let dim_low_gamma = ColorSetting {
+        gamma: [0.5, 0.5, 0.5],
+        ..dim
+    };
+
+    assert!(mid_gray_luminance(&dim) < mid_gray_luminance(&ColorSetting::default()));
+    assert!(mid_gray_luminance(&dim_low_gamma) < mid_gray_luminance(&dim));
+}
+
+#[test]
+fn test_legibility_rejects_low_brightness_with_low_gamma() {
+    let setting = ColorSetting {
+        temperature: 3500,
+        brightness: 0.2,
+        gamma: [0.5, 0.5, 0.5],
+        contrast: 1.0,
+    };
+
+    let err = check_legibility(&setting).unwrap_err();
+    assert!(err.contains("luminance"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_legibility_accepts_usual_night_settings() {
+    for (temperature, brightness) in [(3500, 1.0), (2700, 0.7), (1900, 0.5), (1000, 0.1)] {
+        let setting = ColorSetting {
+            temperature,
+            brightness,
+            ..ColorSetting::default()
+        };
+        assert!(
+            check_legibility(&setting).is_ok(),
+            "{}K at brightness {} should be legible",
+            temperature,
+            brightness
+        );
+    }
+}
+
+#[test]
+fn test_estimate_temperature_round_trip() {
+    for temperature in (MIN_TEMP..=MAX_TEMP).step_by(137).chain([MAX_TEMP, 6500, 3400]) {
+        let estimate = estimate_temperature(get_white_point(temperature));
+        assert!(
+            (estimate - temperature).abs() <= 2,
+            "{}K estimated as {}K",
+            temperature,
+            estimate
+        );
+    }
+}
+
+#[test]
+fn test_estimate_temperature_ignores_brightness() {
+    let dimmed = get_white_point(3500).map(|c| c * 0.5);
+    assert!((estimate_temperature(dimmed) - 3500).abs() <= 2);
+}
+
+#[test]
+fn test_estimate_temperature_off_curve() {
+    // Neutral white sits between the 6500K and 6600K table entries
+    let estimate = estimate_temperature([1.0, 1.0, 1.0]);
+    assert!((6400..=6700).contains(&estimate), "Got {}K", estimate);
+
+    // Pure red is warmer than anything in the table
+    assert_eq!(estimate_temperature([1.0, 0.0, 0.0]), MIN_TEMP);
+}
+
+#[test]
+fn test_estimate_temperature_degenerate_input() {
+    assert_eq!(estimate_temperature([0.0, 0.0, 0.0]), NEUTRAL_TEMP);
+    assert_eq!(estimate_temperature([f32::NAN, 1.0, 1.0]), NEUTRAL_TEMP);
+}
+
+#[test]
+fn test_linear_ramp_tiny_sizes_span_full_range() {
+    assert_eq!(linear_ramp(2), vec![0, 65535]);
+    assert_eq!(linear_ramp(3), vec![0, 32768, 65535]);
+    assert_eq!(linear_ramp(1), vec![0]);
+    assert!(linear_ramp(0).is_empty());
+}
+
+#[test]
+fn test_linear_ramp_regular_sizes_match_legacy() {
+    let ramp = linear_ramp(256);
+    assert_eq!(ramp[0], 0);
+    assert_eq!(ramp[128], 32768);
+    assert_eq!(ramp[255], 65280);
+}
+
+#[test]
+fn test_colorramp_fill_tiny_ramps() {
+    for size in [2, 3] {
+        let linear = linear_ramp(size);
+        let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear.clone());
+        colorramp_fill(&mut r, &mut g, &mut b, &ColorSetting::default());
+
+        // Neutral keeps the endpoints and the midpoint
+        for ramp in [&r, &g, &b] {
+            assert_eq!(ramp[0], 0, "size {}", size);
+            assert!(ramp[size - 1] >= 65500, "size {}: {:?}", size, ramp);
+        }
+        if size == 3 {
+            assert!((r[1] as i32 - 32768).abs() < 500, "Middle entry {}", r[1]);
+        }
+
+        let warm = ColorSetting {
+            temperature: 3000,
+            ..ColorSetting::default()
+        };
+        let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear);
+        colorramp_fill(&mut r, &mut g, &mut b, &warm);
+
+        // Black stays black and the warm shift shows up at the top
+        assert_eq!([r[0], g[0], b[0]], [0, 0, 0]);
+        assert_eq!(r[size - 1], 65535);
+        assert!(b[size - 1] < g[size - 1] && g[size - 1] < r[size - 1]);
+        if size == 3 {
+            assert!(r[0] < r[1] && r[1] < r[2]);
+            assert!(b[0] < b[1] && b[1] < b[2]);
+        }
+    }
+}
+
+#[test]
+fn test_perceptual_brightness_mapping() {
+    assert_eq!(perceptual_brightness(1.0), 1.0);
+    assert_eq!(perceptual_brightness(0.0), 0.0);
+    // CIE L* 50 is about 18% luminance
+    assert!((perceptual_brightness(0.5) - 0.1842).abs() < 1e-3);
+    // Monotonic, including across the linear segment near black
+    let values: Vec<f32> = (0..=100).map(|i| perceptual_brightness(i as f32 / 100.0)).collect();
+    for pair in values.windows(2) {
+        assert!(pair[1] > pair[0], "Mapping should increase: {:?}", pair);
+    }
+}
+
+#[test]
+fn test_perceptual_brightness_passes_overdrive_through() {
+    assert_eq!(perceptual_brightness(1.5), 1.5);
+    assert_eq!(perceptual_brightness(2.0), 2.0);
+    // Continuous where the L* mapping ends
+    assert!(perceptual_brightness(0.999) < 1.0);
+    assert!((perceptual_brightness(0.999) - 1.0).abs() < 1e-2);
+}
+
+#[test]
+fn test_colorramp_scale_at_half_brightness_with_perceptual_mapping() {
+    let setting = ColorSetting {
+        temperature: NEUTRAL_TEMP,
+        gamma: [1.0, 1.0, 1.0],
+        brightness: 0.5,
+        contrast: 1.0,
+    };
+    let perceptual = ColorSetting {
+        brightness: perceptual_brightness(setting.brightness),
+        ..setting
+    };
+
+    let full = vec![u16::MAX; 1];
+    let (mut linear_r, mut linear_g, mut linear_b) = (full.clone(), full.clone(), full.clone());
+    colorramp_fill(&mut linear_r, &mut linear_g, &mut linear_b, &setting);
+    let (mut mapped_r, mut mapped_g, mut mapped_b) = (full.clone(), full.clone(), full);
+    colorramp_fill(&mut mapped_r, &mut mapped_g, &mut mapped_b, &perceptual);
+
+    let linear_scale = linear_g[0] as f64 / 65536.0;
+    let mapped_scale = mapped_g[0] as f64 / 65536.0;
+    assert!((linear_scale - 0.5).abs() < 1e-3, "Linear scale: {}", linear_scale);
+    assert!((mapped_scale - 0.184).abs() < 1e-3, "Perceptual scale: {}", mapped_scale);
+}
+
+const CUSTOM_TABLE: &str = "\
+# Measured on a warm-tinted panel
+temp,r,g,b
+2000, 1.0, 0.40, 0.10
+4000, 1.0, 0.70, 0.50
+
+6500, 0.95, 1.0, 1.0
+";
+
+#[test]
+fn test_parse_white_point_table() {
+    let table = parse_white_point_table(CUSTOM_TABLE).unwrap();
+    assert_eq!(
+        table,
+        vec![
+            WhitePointEntry { temperature: 2000, rgb: [1.0, 0.4, 0.1] },
+            WhitePointEntry { temperature: 4000, rgb: [1.0, 0.7, 0.5] },
+            WhitePointEntry { temperature: 6500, rgb: [0.95, 1.0, 1.0] },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_white_point_table_rejects_invalid_rows() {
+    let cases = [
+        ("2000,1,0.4,0.1\n2000,1,0.5,0.2\n", "Line 2"),
+        ("3000,1,0.5,0.2\n2000,1,0.4,0.1\n", "must increase"),
+        ("2000,1,0.4\n4000,1,0.7,0.5\n", "Line 1"),
+        ("2000,1,0.4,1.5\n4000,1,0.7,0.5\n", "1.5"),
+        ("2000,1,0.4,0.1\n-4000,1,0.7,0.5\n", "-4000"),
+        ("2000,1,0.4,0.1\n", "at least two"),
+        ("", "at least two"),
+    ];
+
+    for (csv, expected) in cases {
+        let err = parse_white_point_table(csv).unwrap_err();
+        assert!(err.contains(expected), "Unexpected error for {:?}: {}", csv, err);
+    }
+}
+
+#[test]
+fn test_interpolate_white_point_table() {
+    let table = parse_white_point_table(CUSTOM_TABLE).unwrap();
+
+    assert_eq!(interpolate_white_point_table(&table, 4000), [1.0, 0.7, 0.5]);
+
+    let between = interpolate_white_point_table(&table, 3000);
+    assert!((between[0] - 1.0).abs() < 1e-6);
+    assert!((between[1] - 0.55).abs() < 1e-6, "Green: {}", between[1]);
+    assert!((between[2] - 0.3).abs() < 1e-6, "Blue: {}", between[2]);
+
+    let between = interpolate_white_point_table(&table, 5250);
+    assert!((between[0] - 0.975).abs() < 1e-6, "Red: {}", between[0]);
+
+    // Outside the table the end values hold
+    assert_eq!(interpolate_white_point_table(&table, 1000), [1.0, 0.4, 0.1]);
+    assert_eq!(interpolate_white_point_table(&table, 25000), [0.95, 1.0, 1.0]);
+}
+
+#[test]
+fn test_colorramp_fill_guards_invalid_gamma() {
+    let ramp = |gamma: [f32; 3]| {
+        let setting = ColorSetting {
+            temperature: 4500,
+            gamma,
+            brightness: 1.0,
+            contrast: 1.0,
+        };
+        let linear = linear_ramp(256);
+        let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear);
+        colorramp_fill(&mut r, &mut g, &mut b, &setting);
+        (r, g, b)
+    };
+
+    // Zero and negative gamma clamp to the minimum instead of dividing by zero
+    let (r, g, b) = ramp([0.0, -1.0, MIN_GAMMA]);
+    let (min_r, min_g, min_b) = ramp([MIN_GAMMA; 3]);
+    assert_eq!((r, g, b), (min_r, min_g, min_b));
+
+    // Non-finite gamma leaves the channel unchanged by gamma
+    let (r, _, _) = ramp([f32::NAN, 1.0, 1.0]);
+    let (neutral_r, _, _) = ramp([1.0; 3]);
+    assert_eq!(r, neutral_r);
+}
@@ -1,26 +1,50 @@
 use redshift_rebooted::config_ini::*;
+use redshift_rebooted::types::{MAX_BRIGHTNESS, MAX_OVERDRIVE_BRIGHTNESS};
 use std::fs;
 use std::io::Write;
 use tempfile::TempDir;
 
 #[test]
 fn test_parse_brightness_single_value() {
-    let (day, night) = parse_brightness_string("0.9").unwrap();
+    let (day, night) = parse_brightness_in_range("0.9", MAX_BRIGHTNESS).unwrap();
     assert_eq!(day, 0.9);
     assert_eq!(night, 0.9);
 }
 
 #[test]
 fn test_parse_brightness_separate_values() {
-    let (day, night) = parse_brightness_string("0.8:0.5").unwrap();
+    let (day, night) = parse_brightness_in_range("0.8:0.5", MAX_BRIGHTNESS).unwrap();
     assert_eq!(day, 0.8);
     assert_eq!(night, 0.5);
 }
 
 #[test]
 fn test_parse_brightness_invalid() {
-    assert!(parse_brightness_string("0.8:0.5:0.3").is_err());
-    assert!(parse_brightness_string("invalid").is_err());
+    assert!(parse_brightness_in_range("0.8:0.5:0.3", MAX_BRIGHTNESS).is_err());
+    assert!(parse_brightness_in_range("invalid", MAX_BRIGHTNESS).is_err());
+}
+
+#[test]
+fn test_parse_brightness_out_of_range() {
+    assert_eq!(
+        parse_brightness_in_range("1.2:0.5", MAX_BRIGHTNESS),
+        Err("Day brightness 1.2 is outside 0.1-1".to_string())
+    );
+    assert_eq!(
+        parse_brightness_in_range("0.8:0.05", MAX_BRIGHTNESS),
+        Err("Night brightness 0.05 is outside 0.1-1".to_string())
+    );
+    assert!(parse_brightness_in_range("0", MAX_BRIGHTNESS).is_err());
+    assert!(parse_brightness_in_range("NaN:0.5", MAX_BRIGHTNESS).is_err());
+    assert_eq!(parse_brightness_in_range("0.1:1", MAX_BRIGHTNESS).unwrap(), (0.1, 1.0));
+
+    // Overdrive raises the ceiling, and the unchecked parse has none
+    assert_eq!(
+        parse_brightness_in_range("1.5:0.8", MAX_OVERDRIVE_BRIGHTNESS).unwrap(),
+        (1.5, 0.8)
+    );
+    assert!(parse_brightness_in_range("2.5", MAX_OVERDRIVE_BRIGHTNESS).is_err());
+    assert_eq!(parse_brightness_values("1.2:0.05").unwrap(), (1.2, 0.05));
 }
 
 #[test]
@@ -41,6 +65,15 @@ fn test_parse_gamma_invalid() {
     assert!(parse_gamma_string("invalid").is_err());
 }
 
+#[test]
+fn test_parse_gamma_out_of_range() {
+    for value in ["0", "-1", "0:1:1", "1:1:0.05", "11", "NaN"] {
+        assert!(parse_gamma_string(value).is_err(), "{} should be rejected", value);
+    }
+    assert_eq!(parse_gamma_string("0.1").unwrap(), [0.1, 0.1, 0.1]);
+    assert_eq!(parse_gamma_string("10").unwrap(), [10.0, 10.0, 10.0]);
+}
+
 #[test]
 fn test_load_full_config() {
     let temp_dir = TempDir::new().unwrap();
@@ -158,6 +191,24 @@ brightness=0.8
     assert_eq!(config.brightness_night, Some(0.8));
 }
 
+#[test]
+fn test_brightness_compensation_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+brightness-compensation=1
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.brightness_compensation, Some(true));
+}
+
 #[test]
 fn test_gamma_separate_day_night() {
     let temp_dir = TempDir::new().unwrap();
@@ -178,6 +229,64 @@ gamma-night=0.6
     assert_eq!(config.gamma_night, Some([0.6, 0.6, 0.6]));
 }
 
+#[test]
+fn test_out_of_range_gamma_is_left_to_scheme_validation() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    /* Range checks happen when the scheme is built, where --clamp-invalid
+       can clamp them; only malformed values are ignored here */
+    for (content, day, night) in [
+        ("gamma=0\n", Some([0.0; 3]), Some([0.0; 3])),
+        ("gamma=0:1:1\n", Some([0.0, 1.0, 1.0]), Some([0.0, 1.0, 1.0])),
+        ("gamma-day=0\ngamma-night=0.6\n", Some([0.0; 3]), Some([0.6; 3])),
+        ("gamma-day=0.8\ngamma-night=15\n", Some([0.8; 3]), Some([15.0; 3])),
+        ("gamma=0.9\ngamma-day=bogus\n", Some([0.9; 3]), Some([0.9; 3])),
+    ] {
+        fs::write(&config_path, format!("[redshift]\n{}", content)).unwrap();
+        let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+        assert_eq!(config.gamma_day, day, "gamma-day for {:?}", content);
+        assert_eq!(config.gamma_night, night, "gamma-night for {:?}", content);
+    }
+}
+
+#[test]
+fn test_geoclue_desktop_id_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+geoclue-desktop-id=redshift-gtk
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.geoclue_desktop_id, Some("redshift-gtk".to_string()));
+}
+
+#[test]
+fn test_geoclue_desktop_id_empty_is_ignored() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+geoclue-desktop-id=
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.geoclue_desktop_id, None);
+}
+
 #[test]
 fn test_get_manual_location() {
     let mut config = RedshiftConfig::default();
@@ -212,3 +321,507 @@ fn test_nonexistent_config() {
     let result = RedshiftConfig::load_from_file(&config_path);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_fade_thresholds_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+fade-threshold-temp=10
+fade-threshold-brightness=0.05
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    let thresholds = config.fade_thresholds();
+
+    assert_eq!(thresholds.temperature, 10);
+    assert_eq!(thresholds.brightness, 0.05);
+    assert_eq!(thresholds.gamma, 0.1, "Unset gamma threshold keeps the default");
+}
+
+#[test]
+fn test_fade_thresholds_default() {
+    let config = RedshiftConfig::default();
+    let thresholds = config.fade_thresholds();
+
+    assert_eq!(thresholds.temperature, 25);
+    assert_eq!(thresholds.brightness, 0.1);
+    assert_eq!(thresholds.gamma, 0.1);
+}
+
+#[test]
+fn test_geoclue2_section_desktop_id() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+temp-day=5700
+
+[geoclue2]
+desktop-id=redshift-gtk
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.geoclue_desktop_id, Some("redshift-gtk".to_string()));
+}
+
+#[test]
+fn test_redshift_section_desktop_id_takes_precedence() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+geoclue-desktop-id=my-app
+
+[geoclue2]
+desktop-id=redshift-gtk
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.geoclue_desktop_id, Some("my-app".to_string()));
+}
+
+#[test]
+fn test_unknown_sections_are_ignored() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    // Sections copied from an upstream redshift config
+    let config_content = r#"
+[redshift]
+temp-day=5700
+temp-night=3500
+location-provider=manual
+adjustment-method=vidmode
+
+[manual]
+lat=48.1
+lon=11.6
+
+[vidmode]
+screen=0
+
+[drm]
+card=0
+crtc=1
+
+[randr]
+screen=0
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.temp_day, Some(5700));
+    assert_eq!(config.temp_night, Some(3500));
+    assert_eq!(config.randr_screen, Some(0));
+    assert!(config.get_manual_location().is_some());
+}
+
+#[test]
+fn test_temperature_preset_names_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let config_content = r#"
+[redshift]
+temp-day=daylight
+temp-night=candle
+"#;
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.temp_day, Some(5600));
+    assert_eq!(config.temp_night, Some(1900));
+}
+
+#[test]
+fn test_config_with_bom_and_crlf() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    // As saved by a Windows editor: UTF-8 BOM and CRLF line endings
+    let config_content = "\u{feff}[redshift]\r\ntemp-day=5700\r\ntemp-night=3500\r\ngamma=0.8:0.7:0.8\r\n\r\n[manual]\r\nlat=48.1\r\nlon=11.6\r\n";
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    file.write_all(config_content.as_bytes()).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.temp_day, Some(5700), "First key after the BOM should parse");
+    assert_eq!(config.temp_night, Some(3500));
+    assert_eq!(config.gamma_day, Some([0.8, 0.7, 0.8]));
+    assert_eq!(config.manual_lat, Some(48.1));
+    assert_eq!(config.manual_lon, Some(11.6), "Last key before EOF should parse");
+}
+
+#[test]
+fn test_normalize_ini_text() {
+    assert_eq!(normalize_ini_text("\u{feff}[redshift]\r\na=1\r\n"), "[redshift]\na=1\n");
+    assert_eq!(normalize_ini_text("[redshift]\na=1\n"), "[redshift]\na=1\n");
+    // Only a leading BOM is removed
+    assert_eq!(normalize_ini_text("a=\u{feff}"), "a=\u{feff}");
+}
+
+#[test]
+fn test_strip_inline_comment() {
+    assert_eq!(strip_inline_comment("temp-day=5700 ; daytime"), "temp-day=5700");
+    assert_eq!(strip_inline_comment("temp-day=5700\t# daytime"), "temp-day=5700");
+    // rust-ini also accepts ':' as the separator
+    assert_eq!(strip_inline_comment("temp-day: 5700 ; daytime"), "temp-day: 5700");
+    // Without whitespace before it the character is part of the value
+    assert_eq!(strip_inline_comment("id=a;b#c"), "id=a;b#c");
+    // Quoted values keep their comment characters
+    assert_eq!(strip_inline_comment("id=\"my # app\" ; note"), "id=\"my # app\"");
+    assert_eq!(strip_inline_comment("id='a ; b'"), "id='a ; b'");
+    // Lines without a value are left alone
+    assert_eq!(strip_inline_comment("[redshift]"), "[redshift]");
+    assert_eq!(strip_inline_comment("; temp-day=5700"), "; temp-day=5700");
+}
+
+#[test]
+fn test_config_inline_comments() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    writeln!(file, "[redshift]").unwrap();
+    writeln!(file, "temp-day=5700 ; daytime").unwrap();
+    writeln!(file, "temp-night=3200   # bedtime").unwrap();
+    writeln!(file, "brightness-day: 0.9 ; dim a little").unwrap();
+    writeln!(file, "adjustment-method=dummy # for testing").unwrap();
+    writeln!(file, "geoclue-desktop-id=\"my # app\" ; quoted").unwrap();
+    drop(file);
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.temp_day, Some(5700));
+    assert_eq!(config.temp_night, Some(3200));
+    assert_eq!(config.brightness_day, Some(0.9));
+    assert_eq!(config.adjustment_method.as_deref(), Some("dummy"));
+    assert_eq!(config.geoclue_desktop_id.as_deref(), Some("my # app"));
+}
+
+#[test]
+fn test_config_deep_night() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\ntemp-deep-night=candle\nelevation-deep-night=-15\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.temp_deep_night, Some(1900));
+    assert_eq!(config.elevation_deep_night, Some(-15.0));
+
+    fs::write(&config_path, "[redshift]\ntemp-night=3500\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.temp_deep_night, None);
+    assert_eq!(config.elevation_deep_night, None);
+}
+
+#[test]
+fn test_config_twilight_stages() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    writeln!(file, "[redshift]").unwrap();
+    writeln!(file, "temp-civil=4500").unwrap();
+    writeln!(file, "temp-nautical=incandescent").unwrap();
+    writeln!(file, "brightness-civil=0.9").unwrap();
+    writeln!(file, "brightness-nautical=0.6").unwrap();
+    drop(file);
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.temp_civil, Some(4500));
+    assert_eq!(config.temp_nautical, Some(2700));
+    assert_eq!(config.brightness_civil, Some(0.9));
+    assert_eq!(config.brightness_nautical, Some(0.6));
+}
+
+#[test]
+fn test_parse_schedule_entry() {
+    // The INI parser splits "07:30=7000K" at the first ':'
+    assert_eq!(parse_schedule_entry("07", "30=7000K"), Ok((27000, 7000)));
+    assert_eq!(parse_schedule_entry("21", "00 = 2700"), Ok((75600, 2700)));
+    assert_eq!(parse_schedule_entry("22", "15=candle"), Ok((80100, 1900)));
+    assert!(parse_schedule_entry("25", "00=3000K").is_err());
+    assert!(parse_schedule_entry("12", "00").is_err());
+    assert!(parse_schedule_entry("12", "00=warm").is_err());
+}
+
+#[test]
+fn test_config_schedule_section() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    let mut file = fs::File::create(&config_path).unwrap();
+    writeln!(file, "[schedule]").unwrap();
+    writeln!(file, "20:00=3500K").unwrap();
+    writeln!(file, "07:00=7000K").unwrap();
+    writeln!(file, "12:00=6500K").unwrap();
+    writeln!(file, "bogus=1").unwrap();
+    drop(file);
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(
+        config.schedule,
+        vec![(25200, 7000), (43200, 6500), (72000, 3500)],
+        "Keyframes should be sorted and invalid entries skipped"
+    );
+}
+
+#[test]
+fn test_config_quantization() {
+    use redshift_rebooted::colorramp::Quantization;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+    fs::write(&config_path, "[redshift]\nquantization=truncate\n").unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.quantization, Some(Quantization::Truncate));
+
+    fs::write(&config_path, "[redshift]\nquantization=nearest\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.quantization, None, "Invalid values should be ignored");
+}
+
+#[test]
+fn test_config_blue_cutoff_temp() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\nblue-cutoff-temp=2500\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.blue_cutoff_temp, Some(2500));
+
+    fs::write(&config_path, "[redshift]\nblue-cutoff-temp=incandescent\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.blue_cutoff_temp, Some(2700));
+}
+
+#[test]
+fn test_config_contrast() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+    fs::write(&config_path, "[redshift]\ncontrast=1.25\n").unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.contrast, Some(1.25));
+}
+
+#[test]
+fn test_find_duplicate_keys() {
+    let text = "top=1\ntop=2\n[redshift]\ntemp-day=5000\n; temp-day=4000\ntemp-night=3000\n\
+                temp-day=5800\n[manual]\nlat=1\n[redshift]\ntemp-day=6000\n";
+
+    assert_eq!(
+        find_duplicate_keys(text),
+        vec![
+            DuplicateKey {
+                section: None,
+                key: "top".to_string(),
+                lines: vec![1, 2],
+            },
+            DuplicateKey {
+                section: Some("redshift".to_string()),
+                key: "temp-day".to_string(),
+                lines: vec![4, 7, 11],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_find_duplicate_keys_same_key_in_different_sections() {
+    assert!(find_duplicate_keys("[redshift]\nfade=1\n[randr]\nfade=1\n").is_empty());
+    assert!(find_duplicate_keys("[redshift]\ntemp-day=5000\ntemp-night=3000\n").is_empty());
+}
+
+#[test]
+fn test_find_duplicate_keys_colon_separator() {
+    let text = "[manual]\nlat=48.1\nlat: 52.5\n[redshift]\nbrightness=0.7:0.4\nbrightness:0.9\n";
+
+    assert_eq!(
+        find_duplicate_keys(text),
+        vec![
+            DuplicateKey {
+                section: Some("manual".to_string()),
+                key: "lat".to_string(),
+                lines: vec![2, 3],
+            },
+            DuplicateKey {
+                section: Some("redshift".to_string()),
+                key: "brightness".to_string(),
+                lines: vec![5, 6],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_duplicate_key_with_colon_uses_first_value() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+    fs::write(&config_path, "[redshift]\ntemp-day:5000\ntemp-day=5800\n").unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.temp_day, Some(5000));
+}
+
+#[test]
+fn test_duplicate_key_uses_first_value() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+    fs::write(&config_path, "[redshift]\ntemp-day=5000\ntemp-day=5800\n").unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.temp_day, Some(5000));
+}
+
+#[test]
+fn test_config_fade_curve() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\nfade-curve=ease-in-out-cubic\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.fade_curve, Some(redshift_rebooted::fade::FadeCurve::EaseInOutCubic));
+
+    fs::write(&config_path, "[redshift]\nfade-curve=bounce\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.fade_curve, None, "Unknown curves are ignored");
+}
+
+#[test]
+fn test_config_interpolate_in_mireds() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\ninterpolate-in-mireds=1\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.interpolate_in_mireds, Some(true));
+
+    fs::write(&config_path, "[redshift]\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.interpolate_in_mireds, None);
+}
+
+#[test]
+fn test_config_cycle_temps() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\ncycle-temps=6500, 4000,candle\ncycle-persist=1\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.cycle_temps, Some(vec![6500, 4000, 1900]));
+    assert_eq!(config.cycle_persist, Some(true));
+
+    fs::write(&config_path, "[redshift]\ncycle-temps=6500,400\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.cycle_temps, None, "Lists with an invalid entry are ignored");
+}
+
+#[test]
+fn test_config_gpsd_section() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(
+        &config_path,
+        "[redshift]\nlocation-provider=gpsd\n\n[gpsd]\nhost=gps.local\nport=2948\n",
+    )
+    .unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.location_provider.as_deref(), Some("gpsd"));
+    assert_eq!(config.gpsd_host.as_deref(), Some("gps.local"));
+    assert_eq!(config.gpsd_port, Some(2948));
+
+    fs::write(&config_path, "[gpsd]\nport=99999\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.gpsd_host, None);
+    assert_eq!(config.gpsd_port, None);
+}
+
+#[test]
+fn test_config_geoclue_samples() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\ngeoclue-samples=5\ngeoclue-settle-time=20\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.geoclue_samples, Some(5));
+    assert_eq!(config.geoclue_settle_time, Some(20));
+
+    fs::write(&config_path, "[redshift]\ngeoclue-samples=0\ngeoclue-settle-time=-3\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.geoclue_samples, None);
+    assert_eq!(config.geoclue_settle_time, None);
+}
+
+#[test]
+fn test_config_intensity() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\nintensity=40\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.intensity, Some(40));
+
+    fs::write(&config_path, "[redshift]\nintensity=150\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.intensity, None);
+}
+
+#[test]
+fn test_config_perceptual_brightness() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\nperceptual-brightness=1\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.perceptual_brightness, Some(true));
+
+    fs::write(&config_path, "[redshift]\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.perceptual_brightness, None);
+}
+
+#[test]
+fn test_active_days_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+
+    fs::write(&config_path, "[redshift]\nactive-days=mon,tue,wed,thu,fri\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(
+        config.active_days.map(|days| days.to_string()),
+        Some("mon,tue,wed,thu,fri".to_string())
+    );
+
+    fs::write(&config_path, "[redshift]\nactive-days=mon,holiday\n").unwrap();
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.active_days, None, "Invalid day lists are ignored");
+}
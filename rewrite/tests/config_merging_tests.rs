@@ -75,6 +75,7 @@ fn test_config_loads_elevation_settings() {
 [redshift]
 elevation-high=3
 elevation-low=-6
+elevation-hysteresis=0.5
 "#;
 
     let mut file = fs::File::create(&config_path).unwrap();
@@ -84,6 +85,17 @@ elevation-low=-6
 
     assert_eq!(config.elevation_high, Some(3.0));
     assert_eq!(config.elevation_low, Some(-6.0));
+    assert_eq!(config.elevation_hysteresis, Some(0.5));
+}
+
+#[test]
+fn test_negative_elevation_hysteresis_ignored() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+    fs::write(&config_path, "[redshift]\nelevation-hysteresis=-1\n").unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+    assert_eq!(config.elevation_hysteresis, None);
 }
 
 #[test]
@@ -166,3 +178,81 @@ screen=0
     // Randr section
     assert_eq!(config.randr_screen, Some(0));
 }
+
+/* Run print mode with the given redshift.conf and return the output */
+fn run_with_config(config_content: &str) -> std::process::Output {
+    run_with_config_and_args(config_content, &[])
+}
+
+/* Like run_with_config, passing extra command-line arguments */
+fn run_with_config_and_args(config_content: &str, extra_args: &[&str]) -> std::process::Output {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path().join("redshift");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("redshift.conf"), config_content).unwrap();
+
+    std::process::Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "40:-74", "-m", "dummy", "-p"])
+        .args(extra_args)
+        .env("XDG_CONFIG_HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute redshift")
+}
+
+#[test]
+fn test_equal_elevations_rejected() {
+    let output = run_with_config("[redshift]\nelevation-high=-6\nelevation-low=-6\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "Equal elevations should be rejected");
+    assert!(stderr.contains("elevation"), "Should explain the problem, got: {}", stderr);
+}
+
+#[test]
+fn test_reversed_elevations_rejected() {
+    let output = run_with_config("[redshift]\nelevation-high=-6\nelevation-low=3\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "Reversed elevations should be rejected");
+    assert!(stderr.contains("elevation"), "Should explain the problem, got: {}", stderr);
+}
+
+#[test]
+fn test_valid_elevations_accepted() {
+    let output = run_with_config("[redshift]\nelevation-high=5\nelevation-low=-8\n");
+    assert!(output.status.success(), "Valid elevations should be accepted");
+}
+
+const ILLEGIBLE_NIGHT: &str = "[redshift]\nbrightness-night=0.2\ngamma-night=0.5\n";
+
+#[test]
+fn test_illegible_night_setting_rejected() {
+    let output = run_with_config(ILLEGIBLE_NIGHT);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "An unreadable night setting should be rejected");
+    assert!(stderr.contains("Night brightness and gamma are too dark"), "Should explain the problem, got: {}", stderr);
+    assert!(stderr.contains("--force"), "Should mention the override, got: {}", stderr);
+}
+
+#[test]
+fn test_illegible_night_setting_forced() {
+    let output = run_with_config_and_args(ILLEGIBLE_NIGHT, &["--force"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "--force should apply the setting anyway, got: {}", stderr);
+    assert!(stderr.contains("too dark"), "Should still warn, got: {}", stderr);
+}
+
+#[test]
+fn test_duplicate_key_warns_with_value_used() {
+    let output = run_with_config("[redshift]\ntemp-day=5000\ntemp-night=3000\ntemp-day=5800\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "Duplicate keys should not be fatal");
+    assert!(
+        stderr.contains("Duplicate key temp-day in [redshift] on lines 2, 4, using 5000"),
+        "Should warn about the duplicate, got: {}",
+        stderr
+    );
+}
@@ -159,3 +159,224 @@ fn test_saved_location_without_city_name() {
     assert_eq!(saved_loc.source, LocationSource::Interactive);
     assert!(saved_loc.city_name.is_none());
 }
+
+#[test]
+fn test_refresh_location_flag_retries_geoclue() {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let config_dir = dir.path().join("redshift");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(dir.path().join("redshift.conf"), "[redshift]\ngeoclue-settle-time=1\n").unwrap();
+
+    // A recent check would normally suppress GeoClue2 for a day
+    let mut config = Config::default();
+    config.set_location(Location { lat: 40.0, lon: -74.0 }, LocationSource::Manual, None);
+    config.update_geoclue_check();
+    config.last_geoclue_check = config.last_geoclue_check.map(|time| time - 60);
+    assert!(!config.should_check_geoclue());
+    let contents = toml::to_string(&config).unwrap();
+    std::fs::write(config_dir.join("config.toml"), &contents).unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+            .args(args)
+            .args(["-m", "dummy", "-p", "-v", "--no-auto-location"])
+            .env("XDG_CONFIG_HOME", dir.path())
+            .env("HOME", dir.path())
+            .output()
+            .expect("Failed to execute redshift");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stderr).contains("Checking for automatic location")
+    };
+    let saved = || std::fs::read_to_string(config_dir.join("config.toml")).unwrap();
+
+    assert!(!run(&[]), "GeoClue2 is not asked again within a day");
+
+    // The flag alone does not touch the saved configuration
+    assert!(!run(&["--refresh-location", "-l", "40:-74"]));
+    assert_eq!(saved(), contents);
+
+    // Without a location given, GeoClue2 is tried and the attempt recorded
+    assert!(run(&["--refresh-location"]));
+    let reloaded: Config = toml::from_str(&saved()).unwrap();
+    assert!(reloaded.last_geoclue_check > config.last_geoclue_check);
+    assert_eq!(reloaded.get_location(), config.get_location());
+}
+
+#[test]
+fn test_config_save_load_preserves_full_precision() {
+    // Tromsø, where a small latitude error shifts the transition noticeably
+    let location = Location { lat: 69.649_620_812_345_67, lon: 18.955_324_798_765_43 };
+    assert_ne!(location.lat as f32 as f64, location.lat, "Coordinate should not fit in f32");
+
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    std::env::set_var("XDG_CONFIG_HOME", dir.path());
+
+    let mut config = Config::default();
+    config.set_location(location, LocationSource::Manual, None);
+    config.save().expect("Should save config");
+    let loaded = Config::load().expect("Should load config").get_location().unwrap();
+
+    std::env::remove_var("XDG_CONFIG_HOME");
+
+    assert_eq!(loaded.lat, location.lat, "Latitude should survive without f32 rounding");
+    assert_eq!(loaded.lon, location.lon, "Longitude should survive without f32 rounding");
+}
+
+#[test]
+fn test_unwritable_config_dir_warns_instead_of_failing() {
+    use std::process::Command;
+
+    // A file where the config directory should be makes saving fail even
+    // for root, unlike permission bits
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    std::fs::write(dir.path().join("redshift"), "").unwrap();
+    std::fs::write(dir.path().join("redshift.conf"), "[redshift]\ngeoclue-settle-time=1\n").unwrap();
+
+    // Recording the GeoClue2 attempt fails, and only running out of
+    // location sources stops the program
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-m", "dummy", "-p", "--no-auto-location"])
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HOME", dir.path())
+        .output()
+        .expect("Failed to execute redshift");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(3), "A failed save should not stop the program");
+    assert!(stderr.contains("Configuration not saved"), "Got: {}", stderr);
+    assert!(stderr.contains("Failed to create config directory"), "Got: {}", stderr);
+}
+
+#[test]
+fn test_fallback_config_dir_prefers_xdg_config_home() {
+    use redshift_rebooted::config::fallback_config_dir;
+    use std::ffi::OsStr;
+    use std::path::{Path, PathBuf};
+
+    let temp = tempfile::tempdir().unwrap();
+
+    assert_eq!(
+        fallback_config_dir(Some(OsStr::new("/srv/kiosk/config")), temp.path()),
+        Some(PathBuf::from("/srv/kiosk/config"))
+    );
+
+    // An empty XDG_CONFIG_HOME counts as unset
+    let dir = fallback_config_dir(Some(OsStr::new("")), temp.path()).unwrap();
+    assert!(dir.starts_with(temp.path()), "Got {}", dir.display());
+
+    // Nowhere left to persist to
+    assert_eq!(fallback_config_dir(None, Path::new("/nonexistent/tmp")), None);
+}
+
+#[test]
+fn test_fallback_config_dir_is_private() {
+    use redshift_rebooted::config::fallback_config_dir;
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().unwrap();
+    let dir = fallback_config_dir(None, temp.path()).unwrap();
+    let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700, "Got {:o}", mode);
+
+    // A directory someone else could write to is not used
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o777)).unwrap();
+    assert_eq!(fallback_config_dir(None, temp.path()), None);
+
+    // Neither is a symlink planted under the predictable name
+    let other = tempfile::tempdir().unwrap();
+    std::fs::remove_dir(&dir).unwrap();
+    std::os::unix::fs::symlink(other.path(), &dir).unwrap();
+    assert_eq!(fallback_config_dir(None, temp.path()), None);
+}
+
+#[test]
+fn test_runs_without_home_or_xdg_config_home() {
+    use std::process::Command;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "0:0", "-m", "dummy", "-o", "-t", "4000", "--temp-night", "4000"])
+        .env_remove("HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .output()
+        .expect("Failed to execute redshift");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Temperature: 4000"), "Got: {}", stdout);
+}
+
+#[test]
+fn test_config_remembers_gamma_method() {
+    // Configs written before the method was remembered still load
+    let config: Config = toml::from_str("last_geoclue_check = 1700000000\n").unwrap();
+    assert_eq!(config.gamma_method, None);
+    assert!(!toml::to_string(&config).unwrap().contains("gamma_method"));
+
+    let config = Config {
+        gamma_method: Some("randr".to_string()),
+        ..Config::default()
+    };
+    let reloaded: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+    assert_eq!(reloaded.gamma_method.as_deref(), Some("randr"));
+}
+
+/* Run redshift with `config` as its INI file and nothing saved */
+fn run_with_ini(config: &str, args: &[&str]) -> std::process::Output {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    std::fs::create_dir_all(dir.path().join("redshift")).unwrap();
+    std::fs::write(dir.path().join("redshift").join("redshift.conf"), config).unwrap();
+
+    std::process::Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(args)
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HOME", dir.path())
+        .env_remove("DISPLAY")
+        .output()
+        .expect("Failed to execute redshift")
+}
+
+#[test]
+fn test_exit_codes_tell_failures_apart() {
+    // A bad config file is a usage error
+    let output = run_with_ini("[redshift]\ntemp-day=100\n", &["-l", "40:-74", "-m", "dummy", "-p"]);
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Temperature must be between"));
+
+    let output = run_with_ini(
+        "[redshift]\nbrightness-night=5\n",
+        &["-l", "40:-74", "-m", "dummy", "-p"],
+    );
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // So is one that cannot be parsed at all
+    let output = run_with_ini("[redshift\ntemp-day=5000\n", &["-l", "40:-74", "-m", "dummy", "-p"]);
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to load INI file"));
+
+    // No location and not allowed to ask for one
+    let output = run_with_ini("", &["-m", "dummy", "-p", "--no-auto-location"]);
+    assert_eq!(output.status.code(), Some(3), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // No X server to adjust
+    let output = run_with_ini("", &["-l", "40:-74", "-m", "randr", "-o"]);
+    assert_eq!(output.status.code(), Some(4), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = run_with_ini("", &["-l", "40:-74", "-m", "dummy", "-p"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_legibility_is_checked_after_perceptual_mapping() {
+    let args = ["-l", "40:-74", "-m", "dummy", "-p"];
+
+    // Brightness 0.1 is legible as linear luminance, but as L* 10 it is
+    // about 1% luminance, leaving mid-gray too dark
+    let output = run_with_ini("[redshift]\nbrightness-night=0.1\n", &args);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = run_with_ini("[redshift]\nbrightness-night=0.1\nperceptual-brightness=1\n", &args);
+    assert_eq!(output.status.code(), Some(2), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("too dark"));
+}
@@ -0,0 +1,69 @@
+/// Tests for detection of conflicting gamma tools
+
+use redshift_rebooted::conflicts::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn process(pid: u32, name: &str) -> ProcessInfo {
+    ProcessInfo {
+        pid,
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn test_find_conflicts_names_known_tools() {
+    let processes = vec![
+        process(1, "systemd"),
+        process(200, "gammastep"),
+        process(300, "bash"),
+        process(400, "gsd-color"),
+    ];
+
+    let conflicts = find_conflicts(&processes, 999);
+
+    assert_eq!(conflicts.len(), 2);
+    assert_eq!(conflicts[0], "Gammastep (gammastep, pid 200)");
+    assert_eq!(conflicts[1], "GNOME Night Light (gsd-color, pid 400)");
+}
+
+#[test]
+fn test_find_conflicts_ignores_own_process() {
+    // comm is truncated to 15 bytes by the kernel
+    let processes = vec![process(42, "redshift-reboot"), process(43, "redshift-reboot")];
+
+    let conflicts = find_conflicts(&processes, 42);
+
+    assert_eq!(conflicts, vec!["another redshift-rebooted instance (redshift-rebooted, pid 43)"]);
+}
+
+#[test]
+fn test_find_conflicts_requires_exact_names() {
+    let processes = vec![process(10, "redshift-helper"), process(11, "xfluxd"), process(12, "sctl")];
+
+    assert!(find_conflicts(&processes, 1).is_empty());
+}
+
+#[test]
+fn test_scan_processes_reads_comm_files() {
+    let proc_root = TempDir::new().unwrap();
+    for (pid, name) in [("1", "init\n"), ("1234", "wlsunset\n")] {
+        let dir = proc_root.path().join(pid);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("comm"), name).unwrap();
+    }
+    // Non-process entries and vanished processes are skipped
+    fs::create_dir(proc_root.path().join("self")).unwrap();
+    fs::create_dir(proc_root.path().join("5678")).unwrap();
+
+    let mut processes = scan_processes(proc_root.path());
+    processes.sort_by_key(|p| p.pid);
+
+    assert_eq!(processes, vec![process(1, "init"), process(1234, "wlsunset")]);
+    assert_eq!(find_conflicts(&processes, 1), vec!["wlsunset (wlsunset, pid 1234)"]);
+}
+
+#[test]
+fn test_scan_processes_missing_root() {
+    assert!(scan_processes(std::path::Path::new("/nonexistent/proc")).is_empty());
+}
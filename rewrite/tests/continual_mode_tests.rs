@@ -1,82 +1,19 @@
 /// Tests for continual mode functionality
 /// These tests verify the main event loop logic without actually running the infinite loop
 
+use redshift_rebooted::fade::{
+    color_setting_diff_is_major, ease_fade, interpolate_color_settings_with, FadeThresholds,
+};
+use redshift_rebooted::period::interpolate_transition_scheme;
+use redshift_rebooted::solar::transition_progress_from_elevation;
 use redshift_rebooted::types::{ColorSetting, TransitionScheme, NEUTRAL_TEMP};
 
-/* Helper function to calculate transition progress from elevation.
-   This is the same logic used in main.rs */
-fn get_transition_progress_from_elevation(scheme: &TransitionScheme, elevation: f64) -> f64 {
-    if elevation < scheme.low {
-        0.0
-    } else if elevation < scheme.high {
-        (scheme.low - elevation) / (scheme.low - scheme.high)
-    } else {
-        1.0
-    }
-}
-
-/* Helper function to interpolate transition scheme.
-   This is the same logic used in main.rs */
-fn interpolate_transition_scheme(
-    scheme: &TransitionScheme,
-    progress: f64,
-    result: &mut ColorSetting,
-) {
-    let alpha = progress.max(0.0).min(1.0);
-
-    result.temperature = ((1.0 - alpha) * (scheme.night.temperature as f64)
-        + alpha * (scheme.day.temperature as f64)) as i32;
-    result.brightness = ((1.0 - alpha) * (scheme.night.brightness as f64)
-        + alpha * (scheme.day.brightness as f64)) as f32;
-    result.gamma[0] = ((1.0 - alpha) * (scheme.night.gamma[0] as f64)
-        + alpha * (scheme.day.gamma[0] as f64)) as f32;
-    result.gamma[1] = ((1.0 - alpha) * (scheme.night.gamma[1] as f64)
-        + alpha * (scheme.day.gamma[1] as f64)) as f32;
-    result.gamma[2] = ((1.0 - alpha) * (scheme.night.gamma[2] as f64)
-        + alpha * (scheme.day.gamma[2] as f64)) as f32;
-}
-
-/* Helper function to check if color settings differ significantly */
-fn color_setting_diff_is_major(first: &ColorSetting, second: &ColorSetting) -> bool {
-    (first.temperature - second.temperature).abs() > 25
-        || (first.brightness - second.brightness).abs() > 0.1
-        || (first.gamma[0] - second.gamma[0]).abs() > 0.1
-        || (first.gamma[1] - second.gamma[1]).abs() > 0.1
-        || (first.gamma[2] - second.gamma[2]).abs() > 0.1
-}
-
-/* Helper function to interpolate between color settings */
-fn interpolate_color_settings(
-    first: &ColorSetting,
-    second: &ColorSetting,
-    alpha: f64,
-    result: &mut ColorSetting,
-) {
-    let alpha = alpha.max(0.0).min(1.0);
-
-    result.temperature = ((1.0 - alpha) * (first.temperature as f64)
-        + alpha * (second.temperature as f64)) as i32;
-    result.brightness = ((1.0 - alpha) * (first.brightness as f64)
-        + alpha * (second.brightness as f64)) as f32;
-    result.gamma[0] = ((1.0 - alpha) * (first.gamma[0] as f64)
-        + alpha * (second.gamma[0] as f64)) as f32;
-    result.gamma[1] = ((1.0 - alpha) * (first.gamma[1] as f64)
-        + alpha * (second.gamma[1] as f64)) as f32;
-    result.gamma[2] = ((1.0 - alpha) * (first.gamma[2] as f64)
-        + alpha * (second.gamma[2] as f64)) as f32;
-}
-
-/* Helper function for cubic easing */
-fn ease_fade(t: f64) -> f64 {
-    t * t * (3.0 - 2.0 * t)
-}
-
 #[test]
 fn test_transition_progress_at_night() {
     let scheme = TransitionScheme::default();
     // Elevation well below low threshold (-6.0)
     let elevation = -20.0;
-    let progress = get_transition_progress_from_elevation(&scheme, elevation);
+    let progress = transition_progress_from_elevation(&scheme, elevation);
     assert_eq!(progress, 0.0, "Should return 0.0 for night period");
 }
 
@@ -85,7 +22,7 @@ fn test_transition_progress_at_day() {
     let scheme = TransitionScheme::default();
     // Elevation well above high threshold (3.0)
     let elevation = 10.0;
-    let progress = get_transition_progress_from_elevation(&scheme, elevation);
+    let progress = transition_progress_from_elevation(&scheme, elevation);
     assert_eq!(progress, 1.0, "Should return 1.0 for day period");
 }
 
@@ -94,7 +31,7 @@ fn test_transition_progress_at_midpoint() {
     let scheme = TransitionScheme::default();
     // Elevation at exact midpoint between low (-6.0) and high (3.0)
     let elevation = -1.5;
-    let progress = get_transition_progress_from_elevation(&scheme, elevation);
+    let progress = transition_progress_from_elevation(&scheme, elevation);
     assert!((progress - 0.5).abs() < 0.01, "Should return ~0.5 at midpoint");
 }
 
@@ -103,11 +40,11 @@ fn test_transition_progress_at_boundaries() {
     let scheme = TransitionScheme::default();
 
     // At low boundary
-    let progress_low = get_transition_progress_from_elevation(&scheme, scheme.low);
+    let progress_low = transition_progress_from_elevation(&scheme, scheme.low);
     assert_eq!(progress_low, 0.0, "Should return 0.0 at low boundary");
 
     // At high boundary
-    let progress_high = get_transition_progress_from_elevation(&scheme, scheme.high);
+    let progress_high = transition_progress_from_elevation(&scheme, scheme.high);
     assert_eq!(progress_high, 1.0, "Should return 1.0 at high boundary");
 }
 
@@ -115,9 +52,9 @@ fn test_transition_progress_at_boundaries() {
 fn test_transition_progress_increases_with_elevation() {
     let scheme = TransitionScheme::default();
 
-    let prog1 = get_transition_progress_from_elevation(&scheme, -5.0);
-    let prog2 = get_transition_progress_from_elevation(&scheme, -3.0);
-    let prog3 = get_transition_progress_from_elevation(&scheme, -1.0);
+    let prog1 = transition_progress_from_elevation(&scheme, -5.0);
+    let prog2 = transition_progress_from_elevation(&scheme, -3.0);
+    let prog3 = transition_progress_from_elevation(&scheme, -1.0);
 
     assert!(prog1 < prog2, "Progress should increase with elevation");
     assert!(prog2 < prog3, "Progress should increase with elevation");
@@ -181,15 +118,17 @@ fn test_color_diff_major_temperature() {
         temperature: 6500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let setting2 = ColorSetting {
         temperature: 6400,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Difference is 100K, which is > 25K threshold
-    assert!(color_setting_diff_is_major(&setting1, &setting2));
+    assert!(color_setting_diff_is_major(&setting1, &setting2, &FadeThresholds::default()));
 }
 
 #[test]
@@ -198,15 +137,17 @@ fn test_color_diff_minor_temperature() {
         temperature: 6500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let setting2 = ColorSetting {
         temperature: 6490,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Difference is 10K, which is < 25K threshold
-    assert!(!color_setting_diff_is_major(&setting1, &setting2));
+    assert!(!color_setting_diff_is_major(&setting1, &setting2, &FadeThresholds::default()));
 }
 
 #[test]
@@ -215,15 +156,17 @@ fn test_color_diff_major_brightness() {
         temperature: 6500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let setting2 = ColorSetting {
         temperature: 6500,
         brightness: 0.8,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Difference is 0.2, which is > 0.1 threshold
-    assert!(color_setting_diff_is_major(&setting1, &setting2));
+    assert!(color_setting_diff_is_major(&setting1, &setting2, &FadeThresholds::default()));
 }
 
 #[test]
@@ -232,15 +175,17 @@ fn test_color_diff_major_gamma() {
         temperature: 6500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let setting2 = ColorSetting {
         temperature: 6500,
         brightness: 1.0,
         gamma: [0.8, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Gamma R difference is 0.2, which is > 0.1 threshold
-    assert!(color_setting_diff_is_major(&setting1, &setting2));
+    assert!(color_setting_diff_is_major(&setting1, &setting2, &FadeThresholds::default()));
 }
 
 #[test]
@@ -249,15 +194,17 @@ fn test_interpolate_settings_at_start() {
         temperature: 3000,
         brightness: 0.8,
         gamma: [0.9, 0.9, 0.9],
+        contrast: 1.0,
     };
     let second = ColorSetting {
         temperature: 6000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let mut result = ColorSetting::default();
 
-    interpolate_color_settings(&first, &second, 0.0, &mut result);
+    interpolate_color_settings_with(&first, &second, 0.0, false, &mut result);
 
     assert_eq!(result.temperature, first.temperature);
     assert_eq!(result.brightness, first.brightness);
@@ -270,15 +217,17 @@ fn test_interpolate_settings_at_end() {
         temperature: 3000,
         brightness: 0.8,
         gamma: [0.9, 0.9, 0.9],
+        contrast: 1.0,
     };
     let second = ColorSetting {
         temperature: 6000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let mut result = ColorSetting::default();
 
-    interpolate_color_settings(&first, &second, 1.0, &mut result);
+    interpolate_color_settings_with(&first, &second, 1.0, false, &mut result);
 
     assert_eq!(result.temperature, second.temperature);
     assert_eq!(result.brightness, second.brightness);
@@ -291,15 +240,17 @@ fn test_interpolate_settings_at_midpoint() {
         temperature: 4000,
         brightness: 0.8,
         gamma: [0.8, 0.8, 0.8],
+        contrast: 1.0,
     };
     let second = ColorSetting {
         temperature: 6000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let mut result = ColorSetting::default();
 
-    interpolate_color_settings(&first, &second, 0.5, &mut result);
+    interpolate_color_settings_with(&first, &second, 0.5, false, &mut result);
 
     assert_eq!(result.temperature, 5000);
     assert!((result.brightness - 0.9).abs() < 0.01);
@@ -312,19 +263,21 @@ fn test_interpolate_settings_clamps_alpha() {
         temperature: 3000,
         brightness: 0.8,
         gamma: [0.9, 0.9, 0.9],
+        contrast: 1.0,
     };
     let second = ColorSetting {
         temperature: 6000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     let mut result_below = ColorSetting::default();
-    interpolate_color_settings(&first, &second, -0.5, &mut result_below);
+    interpolate_color_settings_with(&first, &second, -0.5, false, &mut result_below);
     assert_eq!(result_below.temperature, first.temperature);
 
     let mut result_above = ColorSetting::default();
-    interpolate_color_settings(&first, &second, 1.5, &mut result_above);
+    interpolate_color_settings_with(&first, &second, 1.5, false, &mut result_above);
     assert_eq!(result_above.temperature, second.temperature);
 }
 
@@ -374,11 +327,13 @@ fn test_fade_animation_sequence() {
         temperature: NEUTRAL_TEMP,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let target = ColorSetting {
         temperature: 3500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     let fade_length = 40;
@@ -388,7 +343,7 @@ fn test_fade_animation_sequence() {
         let frac = i as f64 / fade_length as f64;
         let alpha = ease_fade(frac);
         let mut current = ColorSetting::default();
-        interpolate_color_settings(&start, &target, alpha, &mut current);
+        interpolate_color_settings_with(&start, &target, alpha, false, &mut current);
         temps.push(current.temperature);
     }
 
@@ -411,15 +366,17 @@ fn test_major_diff_triggers_fade() {
         temperature: NEUTRAL_TEMP,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let target = ColorSetting {
         temperature: 3500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Temperature difference is 3000K, which should trigger fade
-    assert!(color_setting_diff_is_major(&current, &target));
+    assert!(color_setting_diff_is_major(&current, &target, &FadeThresholds::default()));
 }
 
 #[test]
@@ -428,13 +385,15 @@ fn test_minor_diff_no_fade() {
         temperature: 6500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let target = ColorSetting {
         temperature: 6510,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Temperature difference is only 10K, should not trigger fade
-    assert!(!color_setting_diff_is_major(&current, &target));
+    assert!(!color_setting_diff_is_major(&current, &target, &FadeThresholds::default()));
 }
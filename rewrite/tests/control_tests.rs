@@ -0,0 +1,219 @@
+/// Tests for the control socket and temporary overrides
+/// Expiry is driven with explicit instants instead of sleeping
+
+use redshift_rebooted::control::*;
+use redshift_rebooted::types::TemperatureBounds;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+/* Parse with the hard temperature limits */
+fn parse(line: &str) -> Result<ControlCommand, String> {
+    ControlCommand::parse(line, &TemperatureBounds::default())
+}
+
+#[test]
+fn test_override_holds_until_expiry() {
+    let start = Instant::now();
+    let mut state = OverrideState::new();
+    assert_eq!(state.temperature(start), None);
+
+    state.set(3000, Duration::from_secs(1800), start);
+
+    assert_eq!(state.temperature(start), Some(3000));
+    assert_eq!(state.temperature(start + Duration::from_secs(1799)), Some(3000));
+    assert!(!state.expire(start + Duration::from_secs(1799)));
+}
+
+#[test]
+fn test_override_reverts_after_expiry() {
+    let start = Instant::now();
+    let mut state = OverrideState::new();
+    state.set(3000, Duration::from_secs(1800), start);
+
+    let later = start + Duration::from_secs(1800);
+    assert_eq!(state.temperature(later), None, "Schedule applies again at expiry");
+    assert!(state.expire(later), "Expiry is reported once");
+    assert!(!state.expire(later + Duration::from_secs(5)));
+}
+
+#[test]
+fn test_override_replaced_by_new_request() {
+    let start = Instant::now();
+    let mut state = OverrideState::new();
+    state.set(3000, Duration::from_secs(60), start);
+
+    let later = start + Duration::from_secs(30);
+    state.set(2500, Duration::from_secs(60), later);
+
+    assert_eq!(state.temperature(start + Duration::from_secs(75)), Some(2500));
+    assert_eq!(state.temperature(later + Duration::from_secs(60)), None);
+}
+
+#[test]
+fn test_parse_command() {
+    assert_eq!(
+        parse("SetTemporaryOverride 3000 1800\n").unwrap(),
+        ControlCommand::SetTemporaryOverride {
+            temperature: 3000,
+            duration: Duration::from_secs(1800),
+        }
+    );
+    assert!(parse("SetTemporaryOverride 3000").is_err());
+    assert!(parse("SetTemporaryOverride 500 60").is_err());
+    assert!(parse("Reboot").is_err());
+}
+
+#[test]
+fn test_command_line_round_trips() {
+    let command = ControlCommand::SetTemporaryOverride {
+        temperature: 2700,
+        duration: Duration::from_secs(90),
+    };
+    assert_eq!(parse(&command.line()).unwrap(), command);
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+    assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+    assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+    assert!(parse_duration("0m").is_err());
+    assert!(parse_duration("m").is_err());
+    assert!(parse_duration("soon").is_err());
+}
+
+#[test]
+fn test_huge_durations_are_rejected() {
+    assert_eq!(parse_duration("24h").unwrap(), MAX_OVERRIDE_DURATION);
+    assert!(parse_duration("25h").is_err());
+    assert!(parse_duration("18446744073709551615h").is_err(), "Must not overflow");
+    assert!(parse("SetTemporaryOverride 3000 18446744073709551615").is_err());
+    assert!(parse("SetTemporaryOverride 3000 30m").is_err());
+}
+
+#[test]
+fn test_override_duration_is_capped() {
+    let start = Instant::now();
+    let mut state = OverrideState::new();
+    state.set(3000, Duration::MAX, start);
+
+    assert_eq!(state.temperature(start + MAX_OVERRIDE_DURATION - Duration::from_secs(1)), Some(3000));
+    assert_eq!(state.temperature(start + MAX_OVERRIDE_DURATION), None);
+}
+
+#[test]
+fn test_socket_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(SOCKET_NAME);
+    let mut server = ControlServer::bind(&path).unwrap();
+
+    let client_path = path.clone();
+    let client = thread::spawn(move || {
+        send_command(
+            &client_path,
+            &ControlCommand::SetTemporaryOverride {
+                temperature: 3000,
+                duration: Duration::from_secs(60),
+            },
+        )
+    });
+
+    let mut received = Vec::new();
+    for _ in 0..100 {
+        received.extend(server.poll(&TemperatureBounds::default()));
+        if !received.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert!(client.join().unwrap().is_ok(), "Client should get OK");
+    assert_eq!(
+        received,
+        vec![ControlCommand::SetTemporaryOverride {
+            temperature: 3000,
+            duration: Duration::from_secs(60),
+        }]
+    );
+}
+
+#[test]
+fn test_bind_refuses_live_socket() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(SOCKET_NAME);
+
+    let _server = ControlServer::bind(&path).unwrap();
+    assert!(ControlServer::bind(&path).is_err(), "Live socket must not be taken over");
+}
+
+#[test]
+fn test_bind_replaces_stale_socket() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(SOCKET_NAME);
+
+    // A socket file left behind without a listener, as after a crash
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    assert!(ControlServer::bind(&path).is_ok(), "Stale socket should be replaced");
+}
+
+#[test]
+fn test_bind_refuses_regular_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(SOCKET_NAME);
+    std::fs::write(&path, "keep me").unwrap();
+
+    let err = ControlServer::bind(&path).err().unwrap();
+    assert!(err.contains("not a socket"), "Unexpected error: {}", err);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+}
+
+#[test]
+fn test_silent_client_does_not_block_poll() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(SOCKET_NAME);
+    let mut server = ControlServer::bind(&path).unwrap();
+
+    let _silent = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    let start = Instant::now();
+    assert!(server.poll(&TemperatureBounds::default()).is_empty());
+    assert!(start.elapsed() < Duration::from_millis(500), "poll waited for the client");
+
+    let client_path = path.clone();
+    let client = thread::spawn(move || {
+        send_command(
+            &client_path,
+            &ControlCommand::SetTemporaryOverride {
+                temperature: 3000,
+                duration: Duration::from_secs(60),
+            },
+        )
+    });
+    let mut received = Vec::new();
+    for _ in 0..100 {
+        received.extend(server.poll(&TemperatureBounds::default()));
+        if !received.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(client.join().unwrap().is_ok());
+    assert_eq!(received.len(), 1, "A silent client must not hold up others");
+}
+
+#[test]
+fn test_send_without_daemon_fails() {
+    let dir = TempDir::new().unwrap();
+    let err = send_command(
+        &dir.path().join(SOCKET_NAME),
+        &ControlCommand::SetTemporaryOverride {
+            temperature: 3000,
+            duration: Duration::from_secs(60),
+        },
+    )
+    .unwrap_err();
+    assert!(err.contains("running redshift"), "Unexpected error: {}", err);
+}
@@ -0,0 +1,44 @@
+/// Tests for cycling through preset temperatures with SIGUSR2
+
+use redshift_rebooted::cycle::*;
+
+#[test]
+fn test_cycle_goes_through_presets_and_back_to_auto() {
+    let mut cycle = TemperatureCycle::new(vec![6500, 4000, 2700]);
+    assert_eq!(cycle.temperature(), None, "Starts on the schedule");
+
+    assert_eq!(cycle.advance(), Some(6500));
+    assert_eq!(cycle.advance(), Some(4000));
+    assert_eq!(cycle.advance(), Some(2700));
+    assert_eq!(cycle.advance(), None, "After the last preset comes the schedule");
+    assert_eq!(cycle.advance(), Some(6500), "And then the first preset again");
+}
+
+#[test]
+fn test_cycle_reset_returns_to_schedule() {
+    let mut cycle = TemperatureCycle::default();
+    cycle.advance();
+    cycle.advance();
+
+    cycle.reset();
+    assert_eq!(cycle.position(), None);
+    assert_eq!(cycle.advance(), Some(DEFAULT_CYCLE_TEMPS[0]));
+}
+
+#[test]
+fn test_cycle_resumes_saved_position() {
+    let cycle = TemperatureCycle::new(vec![5000, 3000]).with_position(Some(1));
+    assert_eq!(cycle.temperature(), Some(3000));
+
+    // A list shortened since the position was saved falls back to auto
+    let cycle = TemperatureCycle::new(vec![5000]).with_position(Some(1));
+    assert_eq!(cycle.temperature(), None);
+}
+
+#[test]
+fn test_empty_cycle_stays_on_schedule() {
+    let mut cycle = TemperatureCycle::new(Vec::new());
+    assert!(cycle.is_empty());
+    assert_eq!(cycle.advance(), None);
+    assert_eq!(cycle.advance(), None);
+}
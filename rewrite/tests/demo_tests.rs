@@ -0,0 +1,65 @@
+/* Tests for the demo mode clock and the --demo flag */
+
+use redshift_rebooted::demo::{local_midnight, DemoClock};
+use redshift_rebooted::timeofday::local_seconds_since_midnight;
+use std::process::Command;
+use std::time::Duration;
+
+#[test]
+fn test_demo_clock_spans_one_day() {
+    let clock = DemoClock::new(1_000_000.0, Duration::from_secs(10));
+
+    assert_eq!(clock.simulated_time(Duration::ZERO), 1_000_000.0);
+    assert_eq!(clock.simulated_time(Duration::from_secs(5)), 1_000_000.0 + 12.0 * 3600.0);
+    assert_eq!(clock.simulated_time(Duration::from_secs(10)), 1_000_000.0 + 24.0 * 3600.0);
+}
+
+#[test]
+fn test_demo_clock_stops_at_end_of_day() {
+    let clock = DemoClock::new(0.0, Duration::from_secs(2));
+
+    assert!(!clock.is_finished(Duration::from_millis(1999)));
+    assert!(clock.is_finished(Duration::from_secs(2)));
+    assert_eq!(clock.simulated_time(Duration::from_secs(60)), 24.0 * 3600.0);
+}
+
+#[test]
+fn test_local_midnight_starts_the_day() {
+    let now = 1_700_000_000.5;
+    let midnight = local_midnight(now);
+
+    assert!(midnight <= now && now - midnight < 24.0 * 3600.0);
+    assert_eq!(local_seconds_since_midnight(midnight), 0);
+}
+
+#[test]
+fn test_demo_visits_night_and_day_temperatures() {
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["--demo", "3", "-m", "dummy", "-l", "0:0", "-t", "6200", "--temp-night", "3300"])
+        .env("TZ", "UTC")
+        .output()
+        .expect("Failed to execute redshift");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "Demo should succeed");
+    let temperatures: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Temperature: "))
+        .collect();
+
+    assert_eq!(temperatures.first(), Some(&"3300"), "Demo should start at night");
+    assert!(temperatures.contains(&"6200"), "Demo should reach the day temperature");
+    let last_night = temperatures.iter().rposition(|&t| t == "3300");
+    let first_day = temperatures.iter().position(|&t| t == "6200");
+    assert!(first_day < last_night, "Demo should return to night after the day");
+    assert_eq!(temperatures.last(), Some(&"6500"), "Screen should be restored afterwards");
+}
+
+#[test]
+fn test_demo_conflicts_with_other_modes() {
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["--demo", "-p", "-l", "0:0"])
+        .output()
+        .expect("Failed to execute redshift");
+    assert!(!output.status.success(), "--demo and -p should conflict");
+}
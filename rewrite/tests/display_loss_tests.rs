@@ -0,0 +1,199 @@
+/// Tests for handling a lost connection to the display
+
+use redshift_rebooted::display_loss::*;
+use redshift_rebooted::error::Error;
+use redshift_rebooted::gamma::GammaMethod;
+use redshift_rebooted::types::ColorSetting;
+use redshift_rebooted::Result;
+use std::time::Duration;
+
+/* Gamma method whose display comes back after `failing_connects` failed
+   reconnection attempts, and that loses it on the first apply when `lose`
+   is set */
+#[derive(Default)]
+struct FlakyGammaMethod {
+    failing_connects: u32,
+    connects: u32,
+    invalidated: bool,
+    lose: bool,
+    applied: Vec<ColorSetting>,
+}
+
+impl GammaMethod for FlakyGammaMethod {
+    fn init(&mut self) -> Result<()> {
+        self.connects += 1;
+        if self.connects <= self.failing_connects {
+            return Err(Error::Gamma("Failed to connect to X server".to_string()));
+        }
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_temperature(&mut self, setting: &ColorSetting, _preserve: bool) -> Result<()> {
+        if std::mem::take(&mut self.lose) {
+            return Err(lost());
+        }
+        self.applied.push(*setting);
+        Ok(())
+    }
+
+    fn restore(&mut self) {}
+
+    fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    fn name(&self) -> &str {
+        "flaky"
+    }
+
+    fn print_help(&self) {}
+}
+
+fn lost() -> Error {
+    Error::DisplayLost("Lost the connection to the X server: Broken pipe".to_string())
+}
+
+#[test]
+fn test_parse_display_loss_policy() {
+    assert_eq!(DisplayLossPolicy::parse("reconnect"), Ok(DisplayLossPolicy::Reconnect));
+    assert_eq!(DisplayLossPolicy::parse(" EXIT "), Ok(DisplayLossPolicy::Exit));
+    assert!(DisplayLossPolicy::parse("retry").is_err());
+    assert_eq!(DisplayLossPolicy::default(), DisplayLossPolicy::Exit);
+}
+
+#[test]
+fn test_exit_policy_does_not_reconnect() {
+    let mut gamma = FlakyGammaMethod::default();
+    let result = recover_display(
+        &mut gamma,
+        DisplayLossPolicy::Exit,
+        lost(),
+        3,
+        Duration::ZERO,
+        &mut |_| true,
+    );
+
+    match result {
+        Err(Error::DisplayLost(message)) => assert!(message.ends_with("exiting"), "{}", message),
+        other => panic!("Expected the display loss, got {:?}", other),
+    }
+    assert_eq!(gamma.connects, 0);
+}
+
+#[test]
+fn test_reconnect_retries_until_display_is_back() {
+    let mut gamma = FlakyGammaMethod {
+        failing_connects: 2,
+        ..FlakyGammaMethod::default()
+    };
+    let mut waits = Vec::new();
+    let result = recover_display(
+        &mut gamma,
+        DisplayLossPolicy::Reconnect,
+        lost(),
+        5,
+        Duration::from_secs(3),
+        &mut |delay| {
+            waits.push(delay);
+            true
+        },
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(gamma.connects, 3);
+    assert_eq!(waits, vec![Duration::from_secs(3); 3], "Waits before every attempt");
+    assert!(gamma.invalidated, "Ramps are sent again to the new connection");
+}
+
+#[test]
+fn test_reconnect_gives_up_after_attempts() {
+    let mut gamma = FlakyGammaMethod {
+        failing_connects: u32::MAX,
+        ..FlakyGammaMethod::default()
+    };
+    let result = recover_display(
+        &mut gamma,
+        DisplayLossPolicy::Reconnect,
+        lost(),
+        4,
+        Duration::ZERO,
+        &mut |_| true,
+    );
+
+    assert!(matches!(result, Err(Error::DisplayLost(_))), "{:?}", result);
+    assert_eq!(gamma.connects, 4);
+
+    // An exit signal during the wait stops trying at once
+    let mut gamma = FlakyGammaMethod::default();
+    let result = recover_display(
+        &mut gamma,
+        DisplayLossPolicy::Reconnect,
+        lost(),
+        4,
+        Duration::ZERO,
+        &mut |_| false,
+    );
+    assert!(result.is_err());
+    assert_eq!(gamma.connects, 0);
+}
+
+#[test]
+fn test_other_errors_are_not_recovered() {
+    let mut gamma = FlakyGammaMethod::default();
+    let error = Error::Gamma("CRTC 3 does not exist".to_string());
+    let result = recover_display(
+        &mut gamma,
+        DisplayLossPolicy::Reconnect,
+        error.clone(),
+        3,
+        Duration::ZERO,
+        &mut |_| true,
+    );
+
+    assert_eq!(result, Err(error));
+    assert_eq!(gamma.connects, 0);
+}
+
+#[test]
+fn test_setting_is_applied_again_after_reconnecting() {
+    let setting = ColorSetting {
+        temperature: 3500,
+        ..ColorSetting::default()
+    };
+    let mut gamma = FlakyGammaMethod {
+        lose: true,
+        ..FlakyGammaMethod::default()
+    };
+    let result = apply_recovering(
+        &mut gamma,
+        &setting,
+        DisplayLossPolicy::Reconnect,
+        3,
+        Duration::ZERO,
+        &mut |_| true,
+    );
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(gamma.connects, 1);
+    assert_eq!(gamma.applied, vec![setting], "Not left until the next update");
+
+    // Under the exit policy nothing is applied
+    let mut gamma = FlakyGammaMethod {
+        lose: true,
+        ..FlakyGammaMethod::default()
+    };
+    let result = apply_recovering(
+        &mut gamma,
+        &setting,
+        DisplayLossPolicy::Exit,
+        3,
+        Duration::ZERO,
+        &mut |_| true,
+    );
+    assert!(matches!(result, Err(Error::DisplayLost(_))), "{:?}", result);
+    assert!(gamma.applied.is_empty());
+}
@@ -0,0 +1,105 @@
+/// Tests for EDID parsing and matching monitors by serial
+
+use redshift_rebooted::edid::*;
+
+/* Base block of a Dell monitor with both a numeric serial and a serial
+   number string descriptor */
+const SAMPLE_EDID: [&str; 8] = [
+    "00ffffffffffff0010acc2a04c4c3833",
+    "1a1e0104b53c22783b00000000000000",
+    "00000000000000000000000000000000",
+    "0000000000004dd000a0f0703e803020",
+    "3500544f2100001a000000ff00434e30",
+    "58344b324d313233340a000000fc0044",
+    "454c4c205532373230510a20000000fd",
+    "00184b1e8c36010a20202020202001d6",
+];
+
+fn sample_edid() -> Vec<u8> {
+    let hex = SAMPLE_EDID.concat();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn edid(serial_number: u32, serial_string: Option<&str>) -> EdidInfo {
+    EdidInfo {
+        manufacturer: "DEL".to_string(),
+        product_code: 0xa0c2,
+        serial_number,
+        serial_string: serial_string.map(str::to_string),
+    }
+}
+
+#[test]
+fn test_parse_sample_edid() {
+    let info = parse_edid(&sample_edid()).unwrap();
+    assert_eq!(info.manufacturer, "DEL");
+    assert_eq!(info.product_code, 0xa0c2);
+    assert_eq!(info.serial_number, 0x3338_4c4c);
+    assert_eq!(info.serial_string.as_deref(), Some("CN0X4K2M1234"));
+    assert_eq!(info.serial(), "CN0X4K2M1234");
+}
+
+#[test]
+fn test_parse_edid_without_serial_string() {
+    let mut data = sample_edid();
+    // Turn the serial descriptor into a dummy descriptor
+    data[75] = 0x10;
+    let info = parse_edid(&data).unwrap();
+    assert_eq!(info.serial_string, None);
+    assert_eq!(info.serial(), "859327564");
+}
+
+#[test]
+fn test_parse_edid_rejects_invalid_data() {
+    let data = sample_edid();
+    assert!(parse_edid(&data[..100]).is_err());
+    assert!(parse_edid(&[]).is_err());
+
+    let mut data = data;
+    data[0] = 0x01;
+    assert!(parse_edid(&data).is_err());
+}
+
+#[test]
+fn test_edid_matches_serial() {
+    let info = parse_edid(&sample_edid()).unwrap();
+    assert!(info.matches_serial("CN0X4K2M1234"));
+    assert!(info.matches_serial(" cn0x4k2m1234 "));
+    assert!(info.matches_serial("859327564"));
+    assert!(!info.matches_serial("CN0X4K2M"));
+    assert!(!info.matches_serial(""));
+
+    // An unset numeric serial matches nothing
+    assert!(!edid(0, None).matches_serial("0"));
+}
+
+#[test]
+fn test_match_edid_serials() {
+    let discovered = vec![
+        Some(edid(1234, None)),
+        None,
+        Some(edid(0, Some("ABC987"))),
+        // Mirrored outputs of the same monitor
+        Some(edid(0, Some("ABC987"))),
+    ];
+
+    assert_eq!(match_edid_serials(&[], &discovered).unwrap(), Vec::<usize>::new());
+    assert_eq!(
+        match_edid_serials(&["abc987".to_string()], &discovered).unwrap(),
+        vec![2, 3]
+    );
+    assert_eq!(
+        match_edid_serials(&["1234".to_string(), "ABC987".to_string()], &discovered).unwrap(),
+        vec![0, 2, 3]
+    );
+
+    let err = match_edid_serials(&["XYZ".to_string()], &discovered).unwrap_err();
+    assert!(err.contains("XYZ"), "Unexpected error: {}", err);
+    assert!(err.contains("DEL 1234") && err.contains("DEL ABC987"), "Unexpected error: {}", err);
+
+    let err = match_edid_serials(&["XYZ".to_string()], &[None]).unwrap_err();
+    assert!(err.contains("(none)"), "Unexpected error: {}", err);
+}
@@ -0,0 +1,122 @@
+/// Tests for the REDSHIFT_* environment variables
+/// They sit between the built-in defaults and the config file: the config
+/// file and command line both override them
+
+use redshift_rebooted::config_ini::{
+    RedshiftConfig, ENV_METHOD, ENV_TEMP_DAY, ENV_TEMP_NIGHT,
+};
+use std::collections::HashMap;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn test_env_fills_unset_settings() {
+    let mut config = RedshiftConfig::default();
+    config.apply_env_from(lookup(&[
+        (ENV_METHOD, "dummy"),
+        (ENV_TEMP_DAY, "5500"),
+        (ENV_TEMP_NIGHT, "candle"),
+    ]));
+
+    assert_eq!(config.adjustment_method.as_deref(), Some("dummy"));
+    assert_eq!(config.temp_day, Some(5500));
+    assert_eq!(config.temp_night, Some(1900));
+}
+
+#[test]
+fn test_config_file_overrides_env() {
+    let mut config = RedshiftConfig {
+        adjustment_method: Some("randr".to_string()),
+        temp_day: Some(6000),
+        ..RedshiftConfig::default()
+    };
+    config.apply_env_from(lookup(&[
+        (ENV_METHOD, "dummy"),
+        (ENV_TEMP_DAY, "5500"),
+        (ENV_TEMP_NIGHT, "3000"),
+    ]));
+
+    assert_eq!(config.adjustment_method.as_deref(), Some("randr"));
+    assert_eq!(config.temp_day, Some(6000));
+    assert_eq!(config.temp_night, Some(3000), "Unset keys still come from the environment");
+}
+
+#[test]
+fn test_invalid_env_values_ignored() {
+    let mut config = RedshiftConfig::default();
+    config.apply_env_from(lookup(&[(ENV_TEMP_DAY, "warm"), (ENV_METHOD, "")]));
+
+    assert_eq!(config.temp_day, None);
+    assert_eq!(config.adjustment_method, None);
+}
+
+/* Run one-shot mode with only `vars` and an optional config file */
+fn run_one_shot(vars: &[(&str, &str)], config: Option<&str>, args: &[&str]) -> Output {
+    let dir = TempDir::new().unwrap();
+    if let Some(config) = config {
+        let config_dir = dir.path().join("redshift");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(config_dir.join("redshift.conf"), config).unwrap();
+    }
+
+    Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "0:0", "-o"])
+        .args(args)
+        .env_remove(ENV_METHOD)
+        .env_remove(ENV_TEMP_DAY)
+        .env_remove(ENV_TEMP_NIGHT)
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HOME", dir.path())
+        .envs(vars.iter().copied())
+        .output()
+        .expect("Failed to execute redshift")
+}
+
+fn dummy_temperatures(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("Temperature: "))
+        .map(str::to_string)
+        .collect()
+}
+
+const ENV_DUMMY_5000: [(&str, &str); 3] = [
+    (ENV_METHOD, "dummy"),
+    (ENV_TEMP_DAY, "5000"),
+    (ENV_TEMP_NIGHT, "5000"),
+];
+
+#[test]
+fn test_env_overrides_defaults() {
+    let output = run_one_shot(&ENV_DUMMY_5000, None, &[]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(dummy_temperatures(&output), ["5000"]);
+}
+
+#[test]
+fn test_cli_overrides_env() {
+    let output = run_one_shot(&ENV_DUMMY_5000, None, &["-t", "4000", "--temp-night", "4000"]);
+    assert!(output.status.success());
+    assert_eq!(dummy_temperatures(&output), ["4000"]);
+
+    // An explicit -m randr wins over REDSHIFT_METHOD=dummy, so nothing is
+    // printed by the dummy method
+    let output = run_one_shot(&ENV_DUMMY_5000, None, &["-m", "randr"]);
+    assert!(dummy_temperatures(&output).is_empty());
+}
+
+#[test]
+fn test_ini_overrides_env() {
+    let config = "[redshift]\ntemp-day=4500\ntemp-night=4500\n";
+    let output = run_one_shot(&ENV_DUMMY_5000, Some(config), &[]);
+    assert!(output.status.success());
+    assert_eq!(dummy_temperatures(&output), ["4500"]);
+}
@@ -0,0 +1,85 @@
+/* Tests for the crate-wide error type */
+
+use redshift_rebooted::config_ini::RedshiftConfig;
+use redshift_rebooted::gamma::{DummyGammaMethod, GammaMethod};
+use redshift_rebooted::location::{LocationProvider, ManualLocationProvider};
+use redshift_rebooted::types::ColorSetting;
+use redshift_rebooted::{Error, Result};
+use std::path::PathBuf;
+
+#[test]
+fn test_display_is_the_message() {
+    let error = Error::Gamma("No usable CRTCs found".to_string());
+    assert_eq!(error.to_string(), "No usable CRTCs found");
+}
+
+#[test]
+fn test_into_string() {
+    let message: String = Error::Config("Failed to read config file".to_string()).into();
+    assert_eq!(message, "Failed to read config file");
+}
+
+#[test]
+fn test_question_mark_into_string() {
+    fn load() -> std::result::Result<(), String> {
+        Err(Error::Location("Location not set".to_string()))?;
+        Ok(())
+    }
+
+    assert_eq!(load().unwrap_err(), "Location not set");
+}
+
+#[test]
+fn test_into_boxed_error() {
+    fn run() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let result: Result<()> = Err(Error::Solar("Invalid elevation".to_string()));
+        result?;
+        Ok(())
+    }
+
+    let error = run().unwrap_err();
+    assert_eq!(error.to_string(), "Invalid elevation");
+    assert_eq!(
+        error.downcast_ref::<Error>(),
+        Some(&Error::Solar("Invalid elevation".to_string()))
+    );
+}
+
+#[test]
+fn test_config_errors() {
+    let result = RedshiftConfig::load_from_file(&PathBuf::from("/nonexistent/redshift.conf"));
+    assert!(matches!(result, Err(Error::Config(_))), "Got {:?}", result.err());
+}
+
+#[test]
+fn test_location_errors() {
+    let mut provider = ManualLocationProvider::new();
+    assert!(matches!(provider.start(), Err(Error::Location(_))));
+    assert!(matches!(provider.set_option("lat", "north"), Err(Error::Location(_))));
+}
+
+#[test]
+fn test_gamma_errors() {
+    let mut method = DummyGammaMethod::new();
+    let invalid = ColorSetting {
+        temperature: 100,
+        ..ColorSetting::default()
+    };
+
+    match method.set_temperature(&invalid, false) {
+        Err(Error::Gamma(message)) => assert!(message.contains("Temperature"), "{}", message),
+        other => panic!("Expected a gamma error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_exit_codes() {
+    use redshift_rebooted::error::{EXIT_FAILURE, EXIT_NO_GAMMA, EXIT_NO_LOCATION, EXIT_USAGE};
+
+    assert_eq!(Error::Config("Invalid brightness".to_string()).exit_code(), EXIT_USAGE);
+    assert_eq!(Error::Location("No location".to_string()).exit_code(), EXIT_NO_LOCATION);
+    assert_eq!(Error::Gamma("No usable CRTCs found".to_string()).exit_code(), EXIT_NO_GAMMA);
+    assert_eq!(Error::DisplayLost("Broken pipe".to_string()).exit_code(), EXIT_NO_GAMMA);
+    assert_eq!(Error::Solar("Invalid elevation".to_string()).exit_code(), EXIT_FAILURE);
+    assert_eq!([EXIT_FAILURE, EXIT_USAGE, EXIT_NO_LOCATION, EXIT_NO_GAMMA], [1, 2, 3, 4]);
+}
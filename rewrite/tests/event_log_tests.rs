@@ -0,0 +1,59 @@
+use redshift_rebooted::event_log::*;
+use redshift_rebooted::types::Period;
+
+#[test]
+fn test_period_change_kv_line() {
+    let event = ContinualEvent::PeriodChange {
+        from: Period::Daytime,
+        to: Period::Transition,
+        progress: 0.5812,
+        temperature: 4200,
+    };
+    assert_eq!(
+        event.to_string(),
+        "event=period_change from=Daytime to=Transition progress=0.58 temp=4200"
+    );
+}
+
+#[test]
+fn test_kv_lines_are_key_value_pairs() {
+    let events = [
+        ContinualEvent::PeriodChange {
+            from: Period::None,
+            to: Period::Night,
+            progress: 0.0,
+            temperature: 3500,
+        },
+        ContinualEvent::FadeStart { from: 6500, to: 3500 },
+        ContinualEvent::Toggle { enabled: false },
+        ContinualEvent::Status {
+            temperature: 4200,
+            brightness: 0.9,
+            period: Period::Transition,
+            enabled: true,
+        },
+    ];
+
+    for event in events {
+        let line = event.to_string();
+        assert!(line.starts_with("event="), "Unexpected line: {}", line);
+        for field in line.split(' ') {
+            let (key, value) = field.split_once('=').unwrap_or_else(|| panic!("Not key=value: {}", field));
+            assert!(!key.is_empty() && !value.is_empty(), "Empty key or value in: {}", line);
+        }
+    }
+    assert_eq!(events[1].to_string(), "event=fade_start from=6500 to=3500");
+    assert_eq!(events[2].to_string(), "event=toggle enabled=false");
+    assert_eq!(
+        events[3].to_string(),
+        "event=status temp=4200 brightness=0.90 period=Transition enabled=true"
+    );
+}
+
+#[test]
+fn test_log_format_parse() {
+    assert_eq!(LogFormat::parse("kv"), Ok(LogFormat::Kv));
+    assert_eq!(LogFormat::parse(" Human "), Ok(LogFormat::Human));
+    assert_eq!(LogFormat::default(), LogFormat::Human);
+    assert!(LogFormat::parse("json").is_err());
+}
@@ -0,0 +1,370 @@
+/// Tests for the continual mode fade state machine
+/// Each call to update() is one tick of the loop, so the tests drive the
+/// fade step by step instead of sleeping.
+
+use redshift_rebooted::fade::*;
+use redshift_rebooted::types::ColorSetting;
+
+fn setting(temperature: i32) -> ColorSetting {
+    ColorSetting {
+        temperature,
+        ..ColorSetting::default()
+    }
+}
+
+#[test]
+fn test_small_change_applied_instantly() {
+    let mut fade = Fade::new(setting(6500));
+
+    let applied = fade.update(&setting(6490), false);
+
+    assert_eq!(applied.temperature, 6490);
+    assert!(!fade.is_active());
+}
+
+#[test]
+fn test_major_change_fades() {
+    let mut fade = Fade::new(setting(6500));
+
+    let first = fade.update(&setting(3500), false);
+
+    assert!(fade.is_active());
+    assert!(first.temperature < 6500 && first.temperature > 3500);
+}
+
+#[test]
+fn test_forced_fade_for_small_change() {
+    // A toggle between settings closer than the major-diff threshold
+    // still goes through the fade instead of jumping
+    let mut fade = Fade::new(setting(6500));
+    let target = setting(6480);
+
+    let mut steps = Vec::new();
+    steps.push(fade.update(&target, true).temperature);
+    while fade.is_active() {
+        steps.push(fade.update(&target, false).temperature);
+    }
+
+    assert!(steps.len() > 2, "Toggle should take multiple steps, got {:?}", steps);
+    assert!(steps[0] > 6480, "First step should not jump to the target");
+    assert_eq!(*steps.last().unwrap(), 6480);
+}
+
+#[test]
+fn test_toggle_produces_multi_step_fade() {
+    // Start at a warm night setting, then toggle to disabled (neutral)
+    let mut fade = Fade::new(setting(3500));
+    fade.update(&setting(3500), false);
+
+    let neutral = ColorSetting::default();
+    let mut temperatures = vec![fade.update(&neutral, true).temperature];
+    while fade.is_active() {
+        temperatures.push(fade.update(&neutral, false).temperature);
+    }
+
+    assert_eq!(temperatures.len() as i32, FADE_LENGTH + 1);
+    assert!(temperatures[0] < 6500, "First step should be intermediate");
+    for pair in temperatures.windows(2) {
+        assert!(pair[1] >= pair[0], "Fade should be monotonic: {:?}", temperatures);
+    }
+    assert_eq!(*temperatures.last().unwrap(), 6500);
+}
+
+#[test]
+fn test_retarget_during_fade_restarts_from_current() {
+    let mut fade = Fade::new(setting(6500));
+    fade.update(&setting(3500), false);
+    for _ in 0..10 {
+        fade.update(&setting(3500), false);
+    }
+    let midway = fade.current().temperature;
+
+    // Toggle back mid-fade: new fade starts from where we are now
+    let next = fade.update(&setting(6500), true);
+    assert!(next.temperature >= midway);
+    assert!(next.temperature < 6500);
+}
+
+#[test]
+fn test_toggle_mid_fade_redirects_to_toggled_target() {
+    // Fading into night when the user toggles back to neutral
+    let mut fade = Fade::new(setting(6500));
+    for _ in 0..FADE_LENGTH / 2 {
+        fade.update(&setting(3500), false);
+    }
+    let midway = fade.current().temperature;
+    let started = fade.fades_started();
+
+    let neutral = setting(6500);
+    let mut temperatures = vec![fade.update(&neutral, true).temperature];
+    assert_eq!(fade.fades_started(), started + 1, "The toggle starts a fresh fade");
+    while fade.is_active() {
+        temperatures.push(fade.update(&neutral, false).temperature);
+    }
+
+    // A full fade from where the toggle caught it, never heading back to
+    // the stale night target
+    assert_eq!(temperatures.len() as i32, FADE_LENGTH + 1);
+    assert!(temperatures[0] >= midway);
+    for pair in temperatures.windows(2) {
+        assert!(pair[1] >= pair[0], "Fade should head for the new target: {:?}", temperatures);
+    }
+    assert_eq!(*temperatures.last().unwrap(), 6500);
+}
+
+#[test]
+fn test_forced_update_at_new_target_stops_fade() {
+    let mut fade = Fade::new(setting(6500));
+    for _ in 0..10 {
+        fade.update(&setting(3500), false);
+    }
+    let midway = fade.current();
+    assert!(fade.is_active());
+
+    // The toggled target happens to be where the fade is now
+    let applied = fade.update(&midway, true);
+    assert_eq!(applied, midway);
+    assert!(!fade.is_active(), "No stale fade left running");
+}
+
+#[test]
+fn test_diff_is_major_thresholds() {
+    let defaults = FadeThresholds::default();
+    assert!(!color_setting_diff_is_major(&setting(6500), &setting(6475), &defaults));
+    assert!(color_setting_diff_is_major(&setting(6500), &setting(6474), &defaults));
+
+    let dim = ColorSetting {
+        brightness: 0.85,
+        ..ColorSetting::default()
+    };
+    assert!(color_setting_diff_is_major(&ColorSetting::default(), &dim, &defaults));
+}
+
+#[test]
+fn test_ease_fade_endpoints() {
+    assert_eq!(ease_fade(0.0), 0.0);
+    assert_eq!(ease_fade(0.5), 0.5);
+    assert_eq!(ease_fade(1.0), 1.0);
+    assert_eq!(ease_fade(1.025), 1.0);
+    assert_eq!(ease_fade(-0.5), 0.0);
+}
+
+#[test]
+fn test_forced_fade_skipped_when_already_at_target() {
+    let mut fade = Fade::new(setting(6500));
+
+    let applied = fade.update(&setting(6500), true);
+
+    assert_eq!(applied.temperature, 6500);
+    assert!(!fade.is_active(), "No fade needed when nothing changes");
+}
+
+#[test]
+fn test_diff_is_major_custom_thresholds() {
+    let fine = FadeThresholds {
+        temperature: 5,
+        brightness: 0.01,
+        gamma: 0.01,
+    };
+    assert!(color_setting_diff_is_major(&setting(6500), &setting(6490), &fine));
+    assert!(!color_setting_diff_is_major(&setting(6500), &setting(6495), &fine));
+
+    let coarse = FadeThresholds {
+        temperature: 500,
+        brightness: 0.3,
+        gamma: 0.3,
+    };
+    assert!(!color_setting_diff_is_major(&setting(6500), &setting(6100), &coarse));
+    assert!(color_setting_diff_is_major(&setting(6500), &setting(5999), &coarse));
+
+    let dim = ColorSetting {
+        brightness: 0.8,
+        ..ColorSetting::default()
+    };
+    assert!(color_setting_diff_is_major(&ColorSetting::default(), &dim, &fine));
+    assert!(!color_setting_diff_is_major(&ColorSetting::default(), &dim, &coarse));
+
+    let tinted = ColorSetting {
+        gamma: [1.0, 1.0, 0.95],
+        ..ColorSetting::default()
+    };
+    assert!(color_setting_diff_is_major(&ColorSetting::default(), &tinted, &fine));
+    assert!(!color_setting_diff_is_major(&ColorSetting::default(), &tinted, &FadeThresholds::default()));
+}
+
+#[test]
+fn test_fade_uses_custom_thresholds() {
+    // A 200K change fades by default but is applied instantly when coarse
+    let coarse = FadeThresholds {
+        temperature: 500,
+        ..FadeThresholds::default()
+    };
+    let mut fade = Fade::with_thresholds(setting(6500), coarse);
+    assert_eq!(fade.update(&setting(6300), false).temperature, 6300);
+    assert!(!fade.is_active());
+
+    let mut fade = Fade::new(setting(6500));
+    fade.update(&setting(6300), false);
+    assert!(fade.is_active());
+
+    // A 10K change is applied instantly by default but fades when fine
+    let fine = FadeThresholds {
+        temperature: 5,
+        ..FadeThresholds::default()
+    };
+    let mut fade = Fade::with_thresholds(setting(6500), fine);
+    fade.update(&setting(6490), false);
+    assert!(fade.is_active());
+}
+
+#[test]
+fn test_jump_applies_target_without_fade() {
+    let mut fade = Fade::new(ColorSetting::default());
+
+    assert_eq!(fade.jump(&setting(3500)).temperature, 3500);
+    assert!(!fade.is_active());
+
+    // Later changes still fade as usual
+    fade.update(&setting(6500), false);
+    assert!(fade.is_active());
+}
+
+#[test]
+fn test_jump_cancels_ongoing_fade() {
+    let mut fade = Fade::new(setting(6500));
+    fade.update(&setting(3500), false);
+    assert!(fade.is_active());
+
+    fade.jump(&setting(4000));
+    assert!(!fade.is_active());
+    assert_eq!(fade.update(&setting(4000), false).temperature, 4000);
+}
+
+#[test]
+fn test_fades_started_counter() {
+    let mut fade = Fade::new(setting(6500));
+    assert_eq!(fade.fades_started(), 0);
+
+    // Small change is applied instantly and not counted
+    fade.update(&setting(6490), false);
+    assert_eq!(fade.fades_started(), 0);
+
+    fade.update(&setting(3500), false);
+    assert_eq!(fade.fades_started(), 1);
+
+    // Continuing the same fade does not count again
+    fade.update(&setting(3500), false);
+    assert_eq!(fade.fades_started(), 1);
+
+    // Retargeting mid-fade restarts it
+    fade.update(&setting(6500), false);
+    assert_eq!(fade.fades_started(), 2);
+
+    // Jumping is not a fade
+    fade.jump(&setting(4000));
+    assert_eq!(fade.fades_started(), 2);
+}
+
+#[test]
+fn test_fade_curve_linear() {
+    assert_eq!(FadeCurve::Linear.apply(0.0), 0.0);
+    assert_eq!(FadeCurve::Linear.apply(0.5), 0.5);
+    assert_eq!(FadeCurve::Linear.apply(1.0), 1.0);
+    assert_eq!(FadeCurve::Linear.apply(0.25), 0.25);
+}
+
+#[test]
+fn test_fade_curve_smoothstep() {
+    assert_eq!(FadeCurve::Smoothstep.apply(0.0), 0.0);
+    assert_eq!(FadeCurve::Smoothstep.apply(0.5), 0.5);
+    assert_eq!(FadeCurve::Smoothstep.apply(1.0), 1.0);
+    assert_eq!(FadeCurve::Smoothstep.apply(0.25), ease_fade(0.25));
+}
+
+#[test]
+fn test_fade_curve_ease_in_out_cubic() {
+    assert_eq!(FadeCurve::EaseInOutCubic.apply(0.0), 0.0);
+    assert_eq!(FadeCurve::EaseInOutCubic.apply(0.5), 0.5);
+    assert_eq!(FadeCurve::EaseInOutCubic.apply(1.0), 1.0);
+    // Sharper than smoothstep away from the middle
+    assert!(FadeCurve::EaseInOutCubic.apply(0.25) < FadeCurve::Smoothstep.apply(0.25));
+    assert!(FadeCurve::EaseInOutCubic.apply(0.75) > FadeCurve::Smoothstep.apply(0.75));
+}
+
+#[test]
+fn test_fade_curves_clamp_overshoot() {
+    for curve in [FadeCurve::Linear, FadeCurve::Smoothstep, FadeCurve::EaseInOutCubic] {
+        assert_eq!(curve.apply(1.025), 1.0, "{:?}", curve);
+        assert_eq!(curve.apply(-0.5), 0.0, "{:?}", curve);
+    }
+}
+
+#[test]
+fn test_fade_curve_parse() {
+    assert_eq!(FadeCurve::parse("linear"), Ok(FadeCurve::Linear));
+    assert_eq!(FadeCurve::parse(" Smoothstep "), Ok(FadeCurve::Smoothstep));
+    assert_eq!(FadeCurve::parse("ease-in-out-cubic"), Ok(FadeCurve::EaseInOutCubic));
+    assert!(FadeCurve::parse("bounce").is_err());
+    assert_eq!(FadeCurve::default(), FadeCurve::Smoothstep);
+}
+
+#[test]
+fn test_fade_uses_selected_curve() {
+    let mut smooth = Fade::new(setting(6500));
+    let mut linear = Fade::new(setting(6500));
+    linear.set_curve(FadeCurve::Linear);
+
+    // Ten of the 40 steps in, linear has covered a quarter of the way
+    let (mut a, mut b) = (setting(6500), setting(6500));
+    for _ in 0..10 {
+        a = smooth.update(&setting(2500), false);
+        b = linear.update(&setting(2500), false);
+    }
+    assert_eq!(b.temperature, 5500);
+    assert!(a.temperature > b.temperature, "Smoothstep starts slower");
+}
+
+#[test]
+fn test_mired_interpolation_midpoint() {
+    // 6500K is ~153.8 mireds and 2500K is 400, so the mired midpoint of
+    // ~276.9 mireds is ~3611K, well below the Kelvin midpoint of 4500K
+    assert_eq!(interpolate_temperature(6500, 2500, 0.5, false), 4500);
+    let midpoint = interpolate_temperature(6500, 2500, 0.5, true);
+    assert!((3605..=3615).contains(&midpoint), "Mired midpoint was {}K", midpoint);
+
+    // Both agree at the endpoints
+    assert_eq!(interpolate_temperature(6500, 2500, 0.0, true), 6500);
+    assert_eq!(interpolate_temperature(6500, 2500, 1.0, true), 2500);
+}
+
+#[test]
+fn test_mired_interpolation_lands_on_endpoints() {
+    // The round trip through mireds is not exact, so truncating would
+    // stop some fades a Kelvin short of the target
+    for first in (1000..=25000).step_by(100) {
+        for second in [1000, 2700, 3400, 4100, 6500, 25000] {
+            assert_eq!(interpolate_temperature(first, second, 0.0, true), first);
+            assert_eq!(interpolate_temperature(first, second, 1.0, true), second);
+        }
+    }
+}
+
+#[test]
+fn test_fade_interpolates_in_mireds_when_set() {
+    let mut kelvin = Fade::new(setting(6500));
+    let mut mireds = Fade::new(setting(6500));
+    mireds.set_interpolate_in_mireds(true);
+
+    for _ in 0..FADE_LENGTH / 2 {
+        kelvin.update(&setting(2500), false);
+        mireds.update(&setting(2500), false);
+    }
+
+    // Halfway through, the mired fade is already further towards warm
+    assert!(
+        mireds.current().temperature < kelvin.current().temperature,
+        "Mired fade at {}K, Kelvin fade at {}K",
+        mireds.current().temperature,
+        kelvin.current().temperature
+    );
+}
@@ -0,0 +1,118 @@
+/// Tests for the control FIFO command parser and lifecycle
+
+use redshift_rebooted::fifo::*;
+use redshift_rebooted::types::TemperatureBounds;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::FileTypeExt;
+use tempfile::TempDir;
+
+/* Parse with the hard temperature limits */
+fn parse(line: &str) -> Result<FifoCommand, String> {
+    FifoCommand::parse(line, &TemperatureBounds::default())
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(parse("toggle"), Ok(FifoCommand::Toggle));
+    assert_eq!(parse("enable\n"), Ok(FifoCommand::Enable));
+    assert_eq!(parse("  disable  "), Ok(FifoCommand::Disable));
+    assert_eq!(parse("reload"), Ok(FifoCommand::Reload));
+    assert_eq!(parse("temp 3000"), Ok(FifoCommand::Temperature(3000)));
+    assert_eq!(parse("temp candle"), Ok(FifoCommand::Temperature(1900)));
+}
+
+#[test]
+fn test_parse_rejects_invalid_commands() {
+    assert!(parse("").is_err());
+    assert!(parse("pause").is_err());
+    assert!(parse("disable now").is_err());
+    assert!(parse("temp").is_err());
+    assert!(parse("temp 500").is_err());
+    assert!(parse("temp hot").is_err());
+}
+
+#[test]
+fn test_drain_commands_keeps_partial_line() {
+    let mut pending = "disable\n\nbogus\ntemp 30".to_string();
+
+    assert_eq!(drain_commands(&mut pending, &TemperatureBounds::default()), vec![FifoCommand::Disable]);
+    assert_eq!(pending, "temp 30");
+
+    pending.push_str("00\n");
+    assert_eq!(drain_commands(&mut pending, &TemperatureBounds::default()), vec![FifoCommand::Temperature(3000)]);
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn test_fifo_receives_commands() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(FIFO_NAME);
+
+    let mut fifo = ControlFifo::create(&path).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().file_type().is_fifo());
+    assert!(fifo.poll(&TemperatureBounds::default()).is_empty(), "Polling an idle FIFO should not block");
+
+    let mut writer = OpenOptions::new().write(true).open(&path).unwrap();
+    writer.write_all(b"disable\ntemp 3000\n").unwrap();
+    drop(writer);
+
+    assert_eq!(
+        fifo.poll(&TemperatureBounds::default()),
+        vec![FifoCommand::Disable, FifoCommand::Temperature(3000)]
+    );
+    // A closed writer does not produce spurious reads
+    assert!(fifo.poll(&TemperatureBounds::default()).is_empty());
+
+    drop(fifo);
+    assert!(!path.exists(), "FIFO should be removed on drop");
+}
+
+#[test]
+fn test_fifo_discards_overlong_line() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(FIFO_NAME);
+    let mut fifo = ControlFifo::create(&path).unwrap();
+    let mut writer = OpenOptions::new().write(true).open(&path).unwrap();
+
+    writer.write_all(&[b'x'; 4096]).unwrap();
+    assert!(fifo.poll(&TemperatureBounds::default()).is_empty());
+
+    // The end of the overlong line is dropped too, later lines still parse
+    writer.write_all(b"disable\ntemp 3000\n").unwrap();
+    assert_eq!(
+        fifo.poll(&TemperatureBounds::default()),
+        vec![FifoCommand::Temperature(3000)]
+    );
+}
+
+#[test]
+fn test_fifo_in_use_is_not_replaced() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(FIFO_NAME);
+
+    let _first = ControlFifo::create(&path).unwrap();
+    assert!(ControlFifo::create(&path).is_err());
+}
+
+#[test]
+fn test_stale_fifo_is_replaced() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(FIFO_NAME);
+
+    // Leave a FIFO behind without a reader, as a crashed instance would
+    let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+    assert!(ControlFifo::create(&path).is_ok());
+}
+
+#[test]
+fn test_regular_file_is_not_replaced() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(FIFO_NAME);
+    std::fs::write(&path, "keep me").unwrap();
+
+    assert!(ControlFifo::create(&path).is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+}
@@ -27,6 +27,7 @@ fn test_dummy_gamma_method_with_preserve_flag() {
         temperature: 3500,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     // Test with preserve = true
@@ -49,6 +50,7 @@ fn test_dummy_gamma_method_extreme_temperatures() {
         temperature: 1000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let result = method.set_temperature(&cool_setting, false);
     assert!(result.is_ok(), "Very cool temperature should succeed");
@@ -58,6 +60,7 @@ fn test_dummy_gamma_method_extreme_temperatures() {
         temperature: 25000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
     let result = method.set_temperature(&warm_setting, false);
     assert!(result.is_ok(), "Very warm temperature should succeed");
@@ -76,6 +79,7 @@ fn test_dummy_gamma_method_various_brightness() {
             temperature: 6500,
             brightness,
             gamma: [1.0, 1.0, 1.0],
+            contrast: 1.0,
         };
         let result = method.set_temperature(&setting, false);
         assert!(result.is_ok(), "Brightness {} should succeed", brightness);
@@ -101,6 +105,7 @@ fn test_dummy_gamma_method_various_gamma_values() {
             temperature: 6500,
             brightness: 1.0,
             gamma,
+            contrast: 1.0,
         };
         let result = method.set_temperature(&setting, false);
         assert!(result.is_ok(), "Gamma {:?} should succeed", gamma);
@@ -150,13 +155,13 @@ fn test_dummy_gamma_method_sequence_of_different_settings() {
 
     // Sequence of different settings simulating a day cycle
     let settings = [
-        ColorSetting { temperature: 6500, brightness: 0.5, gamma: [1.0, 1.0, 1.0] },
-        ColorSetting { temperature: 5000, brightness: 0.7, gamma: [1.0, 1.0, 1.0] },
-        ColorSetting { temperature: 4000, brightness: 0.9, gamma: [1.0, 1.0, 1.0] },
-        ColorSetting { temperature: 3500, brightness: 1.0, gamma: [1.0, 1.0, 1.0] },
-        ColorSetting { temperature: 4000, brightness: 0.9, gamma: [1.0, 1.0, 1.0] },
-        ColorSetting { temperature: 5000, brightness: 0.7, gamma: [1.0, 1.0, 1.0] },
-        ColorSetting { temperature: 6500, brightness: 0.5, gamma: [1.0, 1.0, 1.0] },
+        ColorSetting { temperature: 6500, brightness: 0.5, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
+        ColorSetting { temperature: 5000, brightness: 0.7, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
+        ColorSetting { temperature: 4000, brightness: 0.9, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
+        ColorSetting { temperature: 3500, brightness: 1.0, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
+        ColorSetting { temperature: 4000, brightness: 0.9, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
+        ColorSetting { temperature: 5000, brightness: 0.7, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
+        ColorSetting { temperature: 6500, brightness: 0.5, gamma: [1.0, 1.0, 1.0], contrast: 1.0 },
     ];
 
     for setting in &settings {
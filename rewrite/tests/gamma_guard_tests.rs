@@ -1,8 +1,10 @@
 /* Unit tests for GammaRestoreGuard functionality */
 
 use redshift_rebooted::gamma::{DummyGammaMethod, GammaMethod};
-use redshift_rebooted::gamma_guard::GammaRestoreGuard;
+use redshift_rebooted::gamma_guard::{ExitPolicy, GammaRestoreGuard};
 use redshift_rebooted::types::ColorSetting;
+use redshift_rebooted::Result;
+use std::sync::{Arc, Mutex};
 
 #[test]
 fn test_gamma_guard_restores_on_drop() {
@@ -16,12 +18,13 @@ fn test_gamma_guard_restores_on_drop() {
         temperature: 3500,
         brightness: 0.9,
         gamma: [1.0, 0.8, 0.7],
+        contrast: 1.0,
     };
     gamma.set_temperature(&custom_setting, false).expect("Set temp failed");
 
     /* Create guard - this should restore gamma when dropped */
     {
-        let _guard = GammaRestoreGuard::new(&mut gamma);
+        let _guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
         /* Guard goes out of scope here and should restore */
     }
 
@@ -41,12 +44,13 @@ fn test_gamma_guard_can_be_disabled() {
         temperature: 3500,
         brightness: 0.9,
         gamma: [1.0, 0.8, 0.7],
+        contrast: 1.0,
     };
     gamma.set_temperature(&custom_setting, false).expect("Set temp failed");
 
     /* Create guard and disable restoration */
     {
-        let mut guard = GammaRestoreGuard::new(&mut gamma);
+        let mut guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
         guard.disable_restore();
         /* Guard goes out of scope but should NOT restore */
     }
@@ -62,13 +66,14 @@ fn test_gamma_guard_get_mut() {
     gamma.start().expect("Start failed");
 
     /* Create guard */
-    let mut guard = GammaRestoreGuard::new(&mut gamma);
+    let mut guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
 
     /* Use guard to set temperature */
     let setting = ColorSetting {
         temperature: 4000,
         brightness: 1.0,
         gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
     };
 
     /* Should be able to get mutable reference and use it */
@@ -88,11 +93,12 @@ fn test_gamma_guard_restores_on_panic() {
         temperature: 3500,
         brightness: 0.9,
         gamma: [1.0, 0.8, 0.7],
+        contrast: 1.0,
     };
     gamma.set_temperature(&custom_setting, false).expect("Set temp failed");
 
     /* Create guard */
-    let _guard = GammaRestoreGuard::new(&mut gamma);
+    let _guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
 
     /* Panic - guard should still restore gamma */
     panic!("panic test");
@@ -107,22 +113,24 @@ fn test_multiple_guards_sequential() {
 
     /* First guard */
     {
-        let mut guard = GammaRestoreGuard::new(&mut gamma);
+        let mut guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
         let setting = ColorSetting {
             temperature: 3000,
             brightness: 0.8,
             gamma: [1.0, 0.9, 0.8],
+            contrast: 1.0,
         };
         guard.get_mut().set_temperature(&setting, false).expect("Failed");
     } /* Restores here */
 
     /* Second guard */
     {
-        let mut guard = GammaRestoreGuard::new(&mut gamma);
+        let mut guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
         let setting = ColorSetting {
             temperature: 5000,
             brightness: 0.95,
             gamma: [1.0, 1.0, 0.9],
+            contrast: 1.0,
         };
         guard.get_mut().set_temperature(&setting, false).expect("Failed");
     } /* Restores here too */
@@ -144,15 +152,185 @@ fn test_guard_restores_neutral_values() {
         temperature: 2000,
         brightness: 0.5,
         gamma: [0.5, 0.6, 0.7],
+        contrast: 1.0,
     };
     gamma.set_temperature(&extreme_setting, false).expect("Set temp failed");
 
     /* Create and drop guard */
     {
-        let _guard = GammaRestoreGuard::new(&mut gamma);
+        let _guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
     }
 
     /* Guard should have called set_temperature with neutral values */
     /* Note: With DummyGammaMethod we can't verify the exact call,
        but in real usage with RandrGammaMethod, the display would be reset */
 }
+
+/* Calls made to a gamma method, in order */
+#[derive(Debug, Clone, PartialEq)]
+enum Call {
+    Set(ColorSetting),
+    Restore,
+}
+
+/* Gamma method that records every call for inspection. Like RandR, it
+   restores when dropped unless told not to. */
+struct RecordingGammaMethod {
+    calls: Arc<Mutex<Vec<Call>>>,
+    restore_on_drop: bool,
+}
+
+impl RecordingGammaMethod {
+    fn new(calls: &Arc<Mutex<Vec<Call>>>) -> Self {
+        Self {
+            calls: Arc::clone(calls),
+            restore_on_drop: true,
+        }
+    }
+}
+
+impl Drop for RecordingGammaMethod {
+    fn drop(&mut self) {
+        if self.restore_on_drop {
+            self.restore();
+        }
+    }
+}
+
+impl GammaMethod for RecordingGammaMethod {
+    fn init(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_temperature(&mut self, setting: &ColorSetting, _preserve: bool) -> Result<()> {
+        self.calls.lock().unwrap().push(Call::Set(*setting));
+        Ok(())
+    }
+
+    fn restore(&mut self) {
+        self.calls.lock().unwrap().push(Call::Restore);
+    }
+
+    fn set_restore_on_drop(&mut self, restore: bool) {
+        self.restore_on_drop = restore;
+    }
+
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    fn print_help(&self) {}
+}
+
+/* Apply a warm setting through a guard with `policy`, returning all calls
+   up to and including dropping the method */
+fn calls_with_policy(policy: ExitPolicy) -> Vec<Call> {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let warm = ColorSetting {
+        temperature: 3500,
+        ..ColorSetting::default()
+    };
+
+    {
+        let mut gamma = RecordingGammaMethod::new(&calls);
+        let mut guard = GammaRestoreGuard::with_policy(&mut gamma, policy);
+        assert_eq!(guard.policy(), policy);
+        guard.get_mut().set_temperature(&warm, false).unwrap();
+    }
+
+    let calls = calls.lock().unwrap().clone();
+    assert_eq!(calls[0], Call::Set(warm));
+    calls
+}
+
+#[test]
+fn test_exit_policy_neutral_applies_6500k() {
+    let calls = calls_with_policy(ExitPolicy::Neutral);
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1], Call::Set(ColorSetting::default()));
+    assert_eq!(ColorSetting::default().temperature, 6500);
+}
+
+#[test]
+fn test_exit_policy_restore_restores_saved_ramps() {
+    let calls = calls_with_policy(ExitPolicy::Restore);
+    assert_eq!(calls, vec![calls[0].clone(), Call::Restore]);
+}
+
+#[test]
+fn test_exit_policy_keep_leaves_setting() {
+    let calls = calls_with_policy(ExitPolicy::Keep);
+    assert_eq!(calls.len(), 1, "Nothing should be applied at exit");
+}
+
+#[test]
+fn test_exit_policy_stops_randr_restoring_on_drop() {
+    use redshift_rebooted::gamma_randr::RandrGammaMethod;
+
+    for policy in [ExitPolicy::Keep, ExitPolicy::Neutral] {
+        let mut gamma = RandrGammaMethod::new();
+        assert!(gamma.restores_on_drop());
+        drop(GammaRestoreGuard::with_policy(&mut gamma, policy));
+        assert!(
+            !gamma.restores_on_drop(),
+            "Dropping RandR should not put the saved ramps back with {:?}",
+            policy
+        );
+    }
+}
+
+#[test]
+fn test_guard_defaults_to_neutral_and_disable_keeps() {
+    let mut gamma = DummyGammaMethod::new();
+    let mut guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
+    assert_eq!(guard.policy(), ExitPolicy::Neutral);
+
+    guard.disable_restore();
+    assert_eq!(guard.policy(), ExitPolicy::Keep);
+}
+
+#[test]
+fn test_disable_restore_overrides_restoring_policies() {
+    for policy in [ExitPolicy::Neutral, ExitPolicy::Restore] {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let mut gamma = RecordingGammaMethod::new(&calls);
+            let mut guard = GammaRestoreGuard::with_policy(&mut gamma, policy);
+            guard.disable_restore();
+            guard.get_mut().set_temperature(&ColorSetting::default(), false).unwrap();
+        }
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![Call::Set(ColorSetting::default())],
+            "Nothing should be applied or restored at exit with {:?}",
+            policy
+        );
+    }
+}
+
+#[test]
+fn test_disable_restore_leaves_randr_ramps() {
+    use redshift_rebooted::gamma_randr::RandrGammaMethod;
+
+    // --no-restore, e.g. on SIGTERM in continual mode
+    let mut gamma = RandrGammaMethod::new();
+    {
+        let mut guard = GammaRestoreGuard::with_policy(&mut gamma, ExitPolicy::Neutral);
+        guard.disable_restore();
+    }
+    assert!(!gamma.restores_on_drop(), "The applied ramps should stay on the screen");
+}
+
+#[test]
+fn test_exit_policy_parse() {
+    assert_eq!(ExitPolicy::parse("restore"), Ok(ExitPolicy::Restore));
+    assert_eq!(ExitPolicy::parse("KEEP"), Ok(ExitPolicy::Keep));
+    assert_eq!(ExitPolicy::parse(" neutral "), Ok(ExitPolicy::Neutral));
+    assert!(ExitPolicy::parse("reset").is_err());
+}
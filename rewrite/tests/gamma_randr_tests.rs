@@ -1,5 +1,5 @@
 use redshift_rebooted::gamma::GammaMethod;
-use redshift_rebooted::gamma_randr::RandrGammaMethod;
+use redshift_rebooted::gamma_randr::{RampError, RandrGammaMethod};
 use redshift_rebooted::types::*;
 
 #[test]
@@ -82,6 +82,7 @@ fn test_randr_gamma_method_full_lifecycle_x11() {
         temperature: 5000,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     if let Err(e) = method.set_temperature(&setting, false) {
@@ -107,6 +108,7 @@ fn test_randr_gamma_method_preserve_flag_x11() {
         temperature: 4000,
         gamma: [1.0, 1.0, 1.0],
         brightness: 0.9,
+        contrast: 1.0,
     };
 
     // Test without preserve
@@ -140,6 +142,7 @@ fn test_randr_gamma_method_multiple_changes_x11() {
             temperature: temp,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         if let Err(e) = method.set_temperature(&setting, false) {
@@ -166,6 +169,7 @@ fn test_randr_gamma_method_extreme_temperatures_x11() {
         temperature: MIN_TEMP,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     if let Err(e) = method.set_temperature(&min_setting, false) {
@@ -177,6 +181,7 @@ fn test_randr_gamma_method_extreme_temperatures_x11() {
         temperature: MAX_TEMP,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     if let Err(e) = method.set_temperature(&max_setting, false) {
@@ -188,6 +193,7 @@ fn test_randr_gamma_method_extreme_temperatures_x11() {
         temperature: NEUTRAL_TEMP,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     if let Err(e) = method.set_temperature(&neutral_setting, false) {
@@ -220,6 +226,7 @@ fn test_randr_gamma_method_gamma_values_x11() {
             temperature: 6500,
             gamma,
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         if let Err(e) = method.set_temperature(&setting, false) {
@@ -248,6 +255,7 @@ fn test_randr_gamma_method_brightness_values_x11() {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness,
+            contrast: 1.0,
         };
 
         if let Err(e) = method.set_temperature(&setting, false) {
@@ -274,3 +282,304 @@ fn test_randr_gamma_method_drop() {
     }
     // If we got here, Drop didn't panic
 }
+
+fn ramps_for(setting: &ColorSetting) -> Vec<u16> {
+    use redshift_rebooted::colorramp::colorramp_fill;
+
+    let size = 256;
+    let linear: Vec<u16> = (0..size).map(|i| ((i as f64 / size as f64) * 65536.0) as u16).collect();
+    let (mut r, mut g, mut b) = (linear.clone(), linear.clone(), linear);
+    colorramp_fill(&mut r, &mut g, &mut b, setting);
+    [r, g, b].concat()
+}
+
+#[test]
+fn test_applied_ramps_detect_repeated_settings() {
+    use redshift_rebooted::gamma_randr::AppliedRamps;
+
+    let warm = ColorSetting {
+        temperature: 3500,
+        ..ColorSetting::default()
+    };
+    let mut applied = AppliedRamps::new();
+
+    assert!(!applied.is_applied(0, &ramps_for(&warm)), "Nothing applied yet");
+    applied.store(0, ramps_for(&warm));
+
+    // Repeating the identical setting produces identical ramps
+    for _ in 0..3 {
+        assert!(applied.is_applied(0, &ramps_for(&warm)));
+    }
+
+    // A different setting, or another CRTC, needs a real apply
+    let warmer = ColorSetting {
+        temperature: 3400,
+        ..ColorSetting::default()
+    };
+    assert!(!applied.is_applied(0, &ramps_for(&warmer)));
+    assert!(!applied.is_applied(1, &ramps_for(&warm)));
+}
+
+#[test]
+fn test_applied_ramps_per_crtc_and_clear() {
+    use redshift_rebooted::gamma_randr::AppliedRamps;
+
+    let setting = ColorSetting::default();
+    let mut applied = AppliedRamps::new();
+
+    applied.store(2, ramps_for(&setting));
+    assert!(applied.is_applied(2, &ramps_for(&setting)));
+    assert!(!applied.is_applied(0, &ramps_for(&setting)));
+
+    applied.clear();
+    assert!(!applied.is_applied(2, &ramps_for(&setting)), "Clear should forget applied ramps");
+}
+
+#[test]
+fn test_randr_last_apply_changed_before_apply() {
+    let method = RandrGammaMethod::new();
+    assert!(!method.last_apply_changed(), "Nothing has been applied yet");
+}
+
+/* Records the order of requests instead of talking to an X server */
+struct RecordingConnection {
+    events: std::cell::RefCell<Vec<String>>,
+    failing_crtc: Option<u32>,
+}
+
+impl RecordingConnection {
+    fn new(failing_crtc: Option<u32>) -> Self {
+        Self {
+            events: std::cell::RefCell::new(Vec::new()),
+            failing_crtc,
+        }
+    }
+}
+
+impl redshift_rebooted::gamma_randr::RampConnection for RecordingConnection {
+    type Cookie<'a> = u32;
+
+    fn send_ramps(&self, crtc: u32, ramps: &[u16]) -> Result<u32, RampError> {
+        assert_eq!(ramps.len() % 3, 0, "Ramps should be R, G and B concatenated");
+        self.events.borrow_mut().push(format!("send {}", crtc));
+        Ok(crtc)
+    }
+
+    fn check_ramps(&self, crtc: u32) -> Result<(), RampError> {
+        self.events.borrow_mut().push(format!("check {}", crtc));
+        if self.failing_crtc == Some(crtc) {
+            return Err(RampError::Request(format!("CRTC {} rejected the ramps", crtc)));
+        }
+        Ok(())
+    }
+
+    fn read_ramps(&self, crtc: u32) -> Result<Vec<u16>, RampError> {
+        self.events.borrow_mut().push(format!("read {}", crtc));
+        Err(RampError::Request("Nothing to read".to_string()))
+    }
+}
+
+#[test]
+fn test_batched_ramps_queue_all_before_checking() {
+    use redshift_rebooted::gamma_randr::send_ramps_batched;
+
+    let conn = RecordingConnection::new(None);
+    let ramps = ramps_for(&ColorSetting::default());
+    let requests: Vec<(u32, &[u16])> = (60..64).map(|crtc| (crtc, ramps.as_slice())).collect();
+
+    let results = send_ramps_batched(&conn, &requests);
+
+    assert!(results.iter().all(|result| result.is_ok()));
+    assert_eq!(
+        *conn.events.borrow(),
+        vec![
+            "send 60", "send 61", "send 62", "send 63", "check 60", "check 61", "check 62",
+            "check 63",
+        ]
+    );
+}
+
+#[test]
+fn test_batched_ramps_report_each_result() {
+    use redshift_rebooted::gamma_randr::send_ramps_batched;
+
+    let conn = RecordingConnection::new(Some(61));
+    let ramps = ramps_for(&ColorSetting::default());
+    let requests: Vec<(u32, &[u16])> = (60..63).map(|crtc| (crtc, ramps.as_slice())).collect();
+
+    let results = send_ramps_batched(&conn, &requests);
+
+    // A rejected CRTC does not stop the others from being checked
+    assert!(results[0].is_ok());
+    assert_eq!(
+        results[1],
+        Err(RampError::Request("CRTC 61 rejected the ramps".to_string()))
+    );
+    assert!(results[2].is_ok());
+}
+
+#[test]
+fn test_restore_saved_ramps_sends_each_crtc() {
+    use redshift_rebooted::gamma_randr::restore_saved_ramps;
+
+    let conn = RecordingConnection::new(Some(60));
+    let ramps = ramps_for(&ColorSetting::default());
+    restore_saved_ramps(&conn, &[(60, ramps.as_slice()), (61, ramps.as_slice())]);
+
+    // A failed CRTC does not stop the others from being restored
+    assert_eq!(*conn.events.borrow(), vec!["send 60", "send 61", "check 60", "check 61"]);
+}
+
+/* How a fake CRTC treats the ramps set on it */
+#[derive(Clone, Copy)]
+enum CrtcBehavior {
+    Applies,
+    /// Rounds to 8 bits per entry, like many LUTs
+    Quantizes,
+    /// Accepts set requests but keeps the old ramps
+    Ignores,
+}
+
+/* A single CRTC holding ramps in memory */
+struct FakeCrtcConnection {
+    behavior: CrtcBehavior,
+    ramps: std::cell::RefCell<Vec<u16>>,
+    sets: std::cell::Cell<usize>,
+}
+
+impl FakeCrtcConnection {
+    fn new(behavior: CrtcBehavior, ramps: Vec<u16>) -> Self {
+        Self {
+            behavior,
+            ramps: std::cell::RefCell::new(ramps),
+            sets: std::cell::Cell::new(0),
+        }
+    }
+}
+
+impl redshift_rebooted::gamma_randr::RampConnection for FakeCrtcConnection {
+    type Cookie<'a> = ();
+
+    fn send_ramps(&self, _crtc: u32, ramps: &[u16]) -> Result<(), RampError> {
+        self.sets.set(self.sets.get() + 1);
+        match self.behavior {
+            CrtcBehavior::Applies => *self.ramps.borrow_mut() = ramps.to_vec(),
+            CrtcBehavior::Quantizes => {
+                *self.ramps.borrow_mut() = ramps.iter().map(|value| (value >> 8) * 0x0101).collect()
+            }
+            CrtcBehavior::Ignores => {}
+        }
+        Ok(())
+    }
+
+    fn check_ramps(&self, _cookie: ()) -> Result<(), RampError> {
+        Ok(())
+    }
+
+    fn read_ramps(&self, _crtc: u32) -> Result<Vec<u16>, RampError> {
+        Ok(self.ramps.borrow().clone())
+    }
+}
+
+#[test]
+fn test_probe_ramps_differ_from_saved() {
+    use redshift_rebooted::gamma_randr::probe_ramps;
+
+    let saved = ramps_for(&ColorSetting::default());
+    let probe = probe_ramps(&saved);
+    assert_eq!(probe.len(), saved.len());
+    assert!(probe.iter().zip(&saved).all(|(probe, saved)| probe != saved));
+
+    // Works at both ends of the range
+    assert_eq!(probe_ramps(&[0, u16::MAX]), vec![0x0400, u16::MAX - 0x0400]);
+}
+
+#[test]
+fn test_verify_gamma_support_accepts_working_crtc() {
+    use redshift_rebooted::gamma_randr::verify_gamma_support;
+
+    let saved = ramps_for(&ColorSetting::default());
+    for behavior in [CrtcBehavior::Applies, CrtcBehavior::Quantizes] {
+        let conn = FakeCrtcConnection::new(behavior, saved.clone());
+        assert_eq!(verify_gamma_support(&conn, 63, &saved), Ok(true));
+        assert_eq!(conn.sets.get(), 2, "Probe, then restore");
+    }
+
+    // The saved ramps are back afterwards
+    let conn = FakeCrtcConnection::new(CrtcBehavior::Applies, saved.clone());
+    verify_gamma_support(&conn, 63, &saved).unwrap();
+    assert_eq!(*conn.ramps.borrow(), saved);
+}
+
+#[test]
+fn test_verify_gamma_support_rejects_ignoring_crtc() {
+    use redshift_rebooted::gamma_randr::verify_gamma_support;
+
+    let saved = ramps_for(&ColorSetting::default());
+    let conn = FakeCrtcConnection::new(CrtcBehavior::Ignores, saved.clone());
+    assert_eq!(verify_gamma_support(&conn, 63, &saved), Ok(false));
+}
+
+#[test]
+fn test_verify_gamma_support_restores_when_read_fails() {
+    use redshift_rebooted::gamma_randr::verify_gamma_support;
+
+    let conn = RecordingConnection::new(None);
+    let saved = ramps_for(&ColorSetting::default());
+    assert!(verify_gamma_support(&conn, 60, &saved).is_err());
+    assert_eq!(*conn.events.borrow(), vec!["send 60", "check 60", "read 60", "send 60", "check 60"]);
+}
+
+#[test]
+fn test_crtcs_are_probed_once() {
+    use redshift_rebooted::gamma_randr::crtc_applies_ramps;
+    use std::collections::HashMap;
+
+    let saved = ramps_for(&ColorSetting::default());
+    let mut probed = HashMap::new();
+    let conn = FakeCrtcConnection::new(CrtcBehavior::Ignores, saved.clone());
+    assert!(!crtc_applies_ramps(&conn, 63, &saved, &mut probed));
+    assert_eq!(conn.sets.get(), 2);
+
+    // Starting again, e.g. after reconnecting, keeps the first answer
+    assert!(!crtc_applies_ramps(&conn, 63, &saved, &mut probed));
+    assert_eq!(conn.sets.get(), 2, "No second probe");
+
+    let conn = FakeCrtcConnection::new(CrtcBehavior::Applies, saved.clone());
+    assert!(crtc_applies_ramps(&conn, 64, &saved, &mut probed));
+    assert_eq!(probed, HashMap::from([(63, false), (64, true)]));
+}
+
+#[test]
+fn test_ramp_error_classifies_connection_loss() {
+    use redshift_rebooted::error::Error;
+    use x11rb::errors::{ConnectionError, ReplyError};
+    use x11rb::protocol::ErrorKind;
+    use x11rb::x11_utils::X11Error;
+
+    // The server going away breaks the connection itself
+    let broken_pipe = std::io::Error::from(std::io::ErrorKind::BrokenPipe);
+    let lost = RampError::from_reply(
+        "Set CRTC Gamma",
+        ReplyError::ConnectionError(ConnectionError::IoError(broken_pipe)),
+    );
+    assert!(lost.is_connection_lost(), "{:?}", lost);
+    assert!(matches!(Error::from(lost), Error::DisplayLost(_)));
+
+    // An error reply only fails that one request
+    let rejected = RampError::from_reply(
+        "Set CRTC Gamma",
+        ReplyError::X11Error(X11Error {
+            error_kind: ErrorKind::Value,
+            error_code: 2,
+            sequence: 7,
+            bad_value: 61,
+            minor_opcode: 24,
+            major_opcode: 140,
+            extension_name: Some("RANDR".to_string()),
+            request_name: Some("SetCrtcGamma"),
+        }),
+    );
+    assert!(!rejected.is_connection_lost(), "{:?}", rejected);
+    assert!(matches!(Error::from(rejected), Error::Gamma(_)));
+}
@@ -27,6 +27,7 @@ fn test_dummy_gamma_method_set_temperature() {
         temperature: 3500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     // Set temperature should succeed (even though it's a no-op)
@@ -66,6 +67,7 @@ fn test_dummy_gamma_method_various_temperatures() {
             temperature: temp,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         assert!(
@@ -95,6 +97,7 @@ fn test_dummy_gamma_method_various_gamma_values() {
             temperature: 6500,
             gamma,
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         assert!(
@@ -119,6 +122,7 @@ fn test_dummy_gamma_method_various_brightness_values() {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness,
+            contrast: 1.0,
         };
 
         assert!(
@@ -140,12 +144,14 @@ fn test_dummy_gamma_method_multiple_calls() {
         temperature: 6500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     let setting2 = ColorSetting {
         temperature: 3500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     assert!(method.set_temperature(&setting1, false).is_ok());
@@ -166,6 +172,7 @@ fn test_gamma_method_trait_object() {
         temperature: 4500,
         gamma: [1.0, 1.0, 1.0],
         brightness: 1.0,
+        contrast: 1.0,
     };
 
     assert!(method.set_temperature(&setting, false).is_ok());
@@ -197,3 +204,313 @@ fn test_dummy_gamma_method_display_trait() {
     let display_string = format!("{}", method);
     assert_eq!(display_string, "Dummy", "DummyGammaMethod should display as 'Dummy'");
 }
+
+fn outputs(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn test_match_output_names() {
+    let discovered = outputs(&["eDP-1", "DP-1", "DP-2", "HDMI-1"]);
+
+    assert_eq!(match_output_names(&outputs(&["DP-2"]), &discovered).unwrap(), vec![2]);
+    assert_eq!(
+        match_output_names(&outputs(&["HDMI-1", "eDP-1"]), &discovered).unwrap(),
+        vec![3, 0],
+        "Matches should follow the requested order"
+    );
+    assert_eq!(
+        match_output_names(&outputs(&["DP-1", "DP-1"]), &discovered).unwrap(),
+        vec![1],
+        "Duplicate names should match once"
+    );
+    assert!(match_output_names(&[], &discovered).unwrap().is_empty());
+}
+
+#[test]
+fn test_match_output_names_is_exact() {
+    // "DP-1" must not match "DP-10" or be matched case-insensitively
+    let discovered = outputs(&["DP-10", "dp-1"]);
+    assert!(match_output_names(&outputs(&["DP-1"]), &discovered).is_err());
+}
+
+#[test]
+fn test_match_output_names_unknown_lists_outputs() {
+    let discovered = outputs(&["eDP-1", "DP-2"]);
+
+    let err = match_output_names(&outputs(&["DP-3"]), &discovered).unwrap_err();
+    assert!(err.contains("DP-3"), "Error should name the missing output: {}", err);
+    assert!(err.contains("eDP-1, DP-2"), "Error should list discovered outputs: {}", err);
+
+    let err = match_output_names(&outputs(&["DP-3"]), &[]).unwrap_err();
+    assert!(err.contains("(none)"), "Error should say nothing was found: {}", err);
+}
+
+#[test]
+fn test_dummy_gamma_method_rejects_nan_setting() {
+    let mut method = DummyGammaMethod::new();
+    method.init().unwrap();
+
+    let setting = ColorSetting {
+        brightness: f32::NAN,
+        ..ColorSetting::default()
+    };
+    assert!(method.set_temperature(&setting, false).is_err());
+}
+
+#[test]
+fn test_null_apply_gamma_method_lifecycle() {
+    let mut method = NullApplyGammaMethod::new();
+
+    assert!(method.init().is_ok(), "NullApplyGammaMethod init should succeed");
+    assert!(method.start().is_ok(), "NullApplyGammaMethod start should succeed");
+    assert_eq!(method.name(), "null");
+
+    let setting = ColorSetting {
+        temperature: 3500,
+        gamma: [1.0, 1.0, 1.0],
+        brightness: 0.8,
+        contrast: 1.0,
+    };
+    assert!(method.set_temperature(&setting, false).is_ok());
+    assert!(method.set_temperature(&setting, false).is_ok(), "Repeating a setting is fine");
+
+    method.restore();
+    assert!(method.set_temperature(&setting, false).is_ok(), "Still usable after restore");
+}
+
+#[test]
+fn test_null_apply_gamma_method_rejects_invalid_setting() {
+    let mut method = NullApplyGammaMethod::new();
+    method.init().unwrap();
+    method.start().unwrap();
+
+    let valid = ColorSetting::default();
+    method.set_temperature(&valid, false).unwrap();
+
+    let invalid = ColorSetting {
+        temperature: 3500,
+        gamma: [1.0, 1.0, 1.0],
+        brightness: f32::NAN,
+        contrast: 1.0,
+    };
+    assert!(method.set_temperature(&invalid, false).is_err());
+    assert!(method.set_temperature(&valid, false).is_ok());
+}
+
+#[test]
+fn test_null_apply_gamma_method_as_trait_object() {
+    let mut method: Box<dyn GammaMethod> = Box::new(NullApplyGammaMethod::new());
+    method.init().unwrap();
+    method.start().unwrap();
+    assert!(method.set_temperature(&ColorSetting::default(), false).is_ok());
+    method.restore();
+}
+
+/* Backend that records initialization and applied temperatures and fails
+   at a chosen step */
+struct MockBackend {
+    name: &'static str,
+    fail_at: Option<&'static str>,
+    initialized: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+    applied: std::sync::Arc<std::sync::Mutex<Vec<(&'static str, i32)>>>,
+    restored_on_drop: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+    restore_on_drop: bool,
+}
+
+/* What the mock backends of one test did */
+#[derive(Default)]
+struct MockLog {
+    initialized: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+    applied: std::sync::Arc<std::sync::Mutex<Vec<(&'static str, i32)>>>,
+    restored_on_drop: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+}
+
+impl MockLog {
+    fn backend(&self, name: &'static str, fail_at: Option<&'static str>) -> Box<dyn GammaMethod> {
+        Box::new(MockBackend {
+            name,
+            fail_at,
+            initialized: self.initialized.clone(),
+            applied: self.applied.clone(),
+            restored_on_drop: self.restored_on_drop.clone(),
+            restore_on_drop: true,
+        })
+    }
+}
+
+impl MockBackend {
+    fn step(&self, step: &'static str) -> redshift_rebooted::Result<()> {
+        match self.fail_at {
+            Some(failing) if failing == step => Err(redshift_rebooted::error::Error::Gamma(
+                format!("{} failed", step),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl GammaMethod for MockBackend {
+    fn init(&mut self) -> redshift_rebooted::Result<()> {
+        self.initialized.lock().unwrap().push(self.name);
+        self.step("init")
+    }
+
+    fn start(&mut self) -> redshift_rebooted::Result<()> {
+        self.step("start")
+    }
+
+    fn set_temperature(&mut self, setting: &ColorSetting, _preserve: bool) -> redshift_rebooted::Result<()> {
+        self.step("set")?;
+        self.applied.lock().unwrap().push((self.name, setting.temperature));
+        Ok(())
+    }
+
+    fn restore(&mut self) {}
+
+    fn set_restore_on_drop(&mut self, restore: bool) {
+        self.restore_on_drop = restore;
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn print_help(&self) {}
+}
+
+/* Like RandR, put back the startup ramps when dropped unless told not to */
+impl Drop for MockBackend {
+    fn drop(&mut self) {
+        if self.restore_on_drop {
+            self.restored_on_drop.lock().unwrap().push(self.name);
+        }
+    }
+}
+
+#[test]
+fn test_reset_all_continues_past_failing_backends() {
+    let log = MockLog::default();
+
+    let results = reset_all(vec![
+        log.backend("first", Some("init")),
+        log.backend("second", None),
+        log.backend("third", Some("start")),
+        log.backend("fourth", Some("set")),
+        log.backend("fifth", None),
+    ]);
+
+    let outcomes: Vec<(&str, bool)> = results
+        .iter()
+        .map(|(name, result)| (name.as_str(), result.is_ok()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![("first", false), ("second", true), ("third", false), ("fourth", false), ("fifth", true)]
+    );
+    assert_eq!(results[2].1.as_ref().unwrap_err().to_string(), "start failed");
+
+    // Only the backends that started were set, each to neutral
+    assert_eq!(*log.applied.lock().unwrap(), vec![("second", NEUTRAL_TEMP), ("fifth", NEUTRAL_TEMP)]);
+}
+
+#[test]
+fn test_reset_all_is_not_undone_on_drop() {
+    let log = MockLog::default();
+
+    let results = reset_all(vec![log.backend("first", None), log.backend("second", Some("set"))]);
+
+    assert!(results[0].1.is_ok());
+    assert_eq!(*log.applied.lock().unwrap(), vec![("first", NEUTRAL_TEMP)]);
+    assert!(
+        log.restored_on_drop.lock().unwrap().is_empty(),
+        "Restoring would bring back the tint being cleared"
+    );
+}
+
+#[test]
+fn test_reset_all_with_no_backends() {
+    assert!(reset_all(Vec::new()).is_empty());
+}
+
+#[test]
+fn test_detect_method_tries_remembered_method_first() {
+    let log = MockLog::default();
+
+    let detected = detect_method(
+        vec![log.backend("first", None), log.backend("second", None)],
+        Some("second"),
+    )
+    .unwrap();
+    assert_eq!(detected.method.name(), "second");
+    assert!(detected.started.is_ok());
+    assert_eq!(*log.initialized.lock().unwrap(), vec!["second"], "Other methods are not probed");
+}
+
+#[test]
+fn test_detect_method_redetects_when_remembered_method_fails() {
+    let log = MockLog::default();
+
+    let detected = detect_method(
+        vec![
+            log.backend("first", Some("init")),
+            log.backend("second", None),
+            log.backend("third", Some("init")),
+        ],
+        Some("third"),
+    )
+    .unwrap();
+    assert_eq!(detected.method.name(), "second");
+    assert_eq!(*log.initialized.lock().unwrap(), vec!["third", "first", "second"]);
+}
+
+#[test]
+fn test_detect_method_redetects_when_remembered_method_fails_to_start() {
+    let log = MockLog::default();
+
+    let detected = detect_method(
+        vec![log.backend("first", None), log.backend("second", Some("start"))],
+        Some("second"),
+    )
+    .unwrap();
+    assert_eq!(detected.method.name(), "first");
+    assert!(detected.started.is_ok());
+    assert_eq!(*log.initialized.lock().unwrap(), vec!["second", "first"]);
+}
+
+#[test]
+fn test_detect_method_reports_start_failure_when_none_starts() {
+    let log = MockLog::default();
+
+    // The first method that initialized is kept, to be handled like a
+    // selected method that failed to start
+    let detected = detect_method(
+        vec![
+            log.backend("first", Some("init")),
+            log.backend("second", Some("start")),
+            log.backend("third", Some("start")),
+        ],
+        None,
+    )
+    .unwrap();
+    assert_eq!(detected.method.name(), "second");
+    assert_eq!(detected.started.unwrap_err().to_string(), "start failed");
+}
+
+#[test]
+fn test_detect_method_without_memory_or_working_method() {
+    let log = MockLog::default();
+
+    // A remembered name that is no longer a candidate is ignored
+    let detected = detect_method(vec![log.backend("first", None)], Some("gone")).unwrap();
+    assert_eq!(detected.method.name(), "first");
+
+    let err = detect_method(
+        vec![log.backend("first", Some("init")), log.backend("second", Some("init"))],
+        None,
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.to_string(), "init failed");
+    assert!(detect_method(Vec::new(), None).is_err());
+}
@@ -0,0 +1,132 @@
+/// Tests for idle dimming
+/// Uses a scripted idle source so no X server is needed
+
+use redshift_rebooted::fade::Fade;
+use redshift_rebooted::idle::*;
+use redshift_rebooted::types::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Idle source returning whatever idle time the test sets
+struct FakeIdleSource {
+    idle_time: Rc<RefCell<Result<Duration, String>>>,
+}
+
+impl IdleSource for FakeIdleSource {
+    fn idle_time(&mut self) -> Result<Duration, String> {
+        self.idle_time.borrow().clone()
+    }
+}
+
+fn fake_dimmer(threshold_secs: u64) -> (IdleDimmer, Rc<RefCell<Result<Duration, String>>>) {
+    let idle_time = Rc::new(RefCell::new(Ok(Duration::ZERO)));
+    let source = FakeIdleSource {
+        idle_time: Rc::clone(&idle_time),
+    };
+    (
+        IdleDimmer::new(Box::new(source), Duration::from_secs(threshold_secs)),
+        idle_time,
+    )
+}
+
+#[test]
+fn test_not_dimmed_while_active() {
+    let (mut dimmer, _) = fake_dimmer(60);
+
+    assert!(!dimmer.poll());
+
+    let mut setting = ColorSetting::default();
+    dimmer.apply(&mut setting);
+    assert_eq!(setting.brightness, 1.0);
+}
+
+#[test]
+fn test_dimmed_after_threshold() {
+    let (mut dimmer, idle_time) = fake_dimmer(60);
+
+    *idle_time.borrow_mut() = Ok(Duration::from_secs(59));
+    assert!(!dimmer.poll());
+
+    *idle_time.borrow_mut() = Ok(Duration::from_secs(60));
+    assert!(dimmer.poll(), "Crossing the threshold should report a change");
+    assert!(!dimmer.poll(), "Staying idle is not a change");
+
+    let mut setting = ColorSetting {
+        brightness: 0.8,
+        ..ColorSetting::default()
+    };
+    dimmer.apply(&mut setting);
+    assert!((setting.brightness - 0.8 * IDLE_DIM_FACTOR).abs() < 1e-6);
+}
+
+#[test]
+fn test_activity_restores_brightness() {
+    let (mut dimmer, idle_time) = fake_dimmer(60);
+
+    *idle_time.borrow_mut() = Ok(Duration::from_secs(120));
+    dimmer.poll();
+    *idle_time.borrow_mut() = Ok(Duration::from_millis(500));
+    assert!(dimmer.poll());
+
+    let mut setting = ColorSetting::default();
+    dimmer.apply(&mut setting);
+    assert_eq!(setting.brightness, 1.0);
+}
+
+#[test]
+fn test_dimming_respects_minimum_brightness() {
+    let (mut dimmer, idle_time) = fake_dimmer(1);
+    *idle_time.borrow_mut() = Ok(Duration::from_secs(5));
+    dimmer.poll();
+
+    let mut setting = ColorSetting {
+        brightness: MIN_BRIGHTNESS,
+        ..ColorSetting::default()
+    };
+    dimmer.apply(&mut setting);
+    assert_eq!(setting.brightness, MIN_BRIGHTNESS);
+}
+
+#[test]
+fn test_query_error_counts_as_active() {
+    let (mut dimmer, idle_time) = fake_dimmer(1);
+    *idle_time.borrow_mut() = Ok(Duration::from_secs(5));
+    dimmer.poll();
+
+    *idle_time.borrow_mut() = Err("connection lost".to_string());
+    assert!(dimmer.poll());
+
+    let mut setting = ColorSetting::default();
+    dimmer.apply(&mut setting);
+    assert_eq!(setting.brightness, 1.0, "Should not stay dimmed");
+}
+
+#[test]
+fn test_activity_fades_back() {
+    // Idle dimming followed by activity goes through a multi-step fade
+    let (mut dimmer, idle_time) = fake_dimmer(1);
+    let mut fade = Fade::new(ColorSetting::default());
+    *idle_time.borrow_mut() = Ok(Duration::from_secs(5));
+
+    let mut target = ColorSetting::default();
+    let changed = dimmer.poll();
+    dimmer.apply(&mut target);
+    fade.update(&target, changed);
+    while fade.is_active() {
+        fade.update(&target, false);
+    }
+    assert!((fade.current().brightness - IDLE_DIM_FACTOR).abs() < 1e-6);
+
+    *idle_time.borrow_mut() = Ok(Duration::ZERO);
+    let target = ColorSetting::default();
+    let changed = dimmer.poll();
+    let mut brightness = vec![fade.update(&target, changed).brightness];
+    while fade.is_active() {
+        brightness.push(fade.update(&target, false).brightness);
+    }
+
+    assert!(brightness.len() > 2, "Should fade over several steps: {:?}", brightness);
+    assert!(brightness[0] < 1.0);
+    assert_eq!(*brightness.last().unwrap(), 1.0);
+}
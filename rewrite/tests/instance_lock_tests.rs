@@ -0,0 +1,113 @@
+use redshift_rebooted::instance_lock::*;
+use std::cell::RefCell;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_lock_records_pid() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(LOCK_NAME);
+
+    let _lock = InstanceLock::acquire(&path).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.trim(), std::process::id().to_string());
+}
+
+#[test]
+fn test_second_acquire_reports_holder() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(LOCK_NAME);
+
+    let lock = InstanceLock::acquire(&path).unwrap();
+    let err = InstanceLock::acquire(&path).unwrap_err();
+    assert_eq!(err, LockError::Held(Some(std::process::id())));
+    assert!(err.to_string().contains("already running"));
+
+    // Released when the holder goes away
+    drop(lock);
+    assert!(InstanceLock::acquire(&path).is_ok());
+}
+
+#[test]
+fn test_stale_lock_file_is_reused() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(LOCK_NAME);
+
+    // A crashed instance leaves the file behind but not the lock
+    std::fs::write(&path, "99999999\n").unwrap();
+    let _lock = InstanceLock::acquire(&path).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.trim(), std::process::id().to_string());
+}
+
+#[test]
+fn test_replace_terminates_holder_and_takes_over() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(LOCK_NAME);
+
+    let holder = RefCell::new(Some(InstanceLock::acquire(&path).unwrap()));
+    let signalled = RefCell::new(Vec::new());
+    let terminate = |pid: u32| {
+        signalled.borrow_mut().push(pid);
+        holder.borrow_mut().take();
+        Ok(())
+    };
+
+    let lock = InstanceLock::acquire_replacing(&path, Duration::from_secs(5), &terminate);
+    assert!(lock.is_ok(), "Should take over: {:?}", lock);
+    assert_eq!(*signalled.borrow(), vec![std::process::id()]);
+}
+
+#[test]
+fn test_replace_gives_up_when_holder_stays() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(LOCK_NAME);
+
+    let _holder = InstanceLock::acquire(&path).unwrap();
+    let err = InstanceLock::acquire_replacing(&path, Duration::from_millis(300), &|_| Ok(()))
+        .unwrap_err();
+    assert!(matches!(err, LockError::Held(_)), "Unexpected error: {:?}", err);
+}
+
+#[test]
+fn test_replace_without_holder_does_not_signal() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(LOCK_NAME);
+
+    let lock = InstanceLock::acquire_replacing(&path, Duration::from_secs(1), &|pid| {
+        Err(format!("Should not signal {}", pid))
+    });
+    assert!(lock.is_ok());
+}
+
+#[test]
+fn test_unwritable_lock_path_is_io_error() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("missing").join(LOCK_NAME);
+
+    assert!(matches!(InstanceLock::acquire(&path), Err(LockError::Io(_))));
+}
+
+#[test]
+fn test_symlinked_lock_file_is_refused() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("victim");
+    std::fs::write(&target, "keep me").unwrap();
+    let path = dir.path().join(LOCK_NAME);
+    std::os::unix::fs::symlink(&target, &path).unwrap();
+
+    assert!(matches!(InstanceLock::acquire(&path), Err(LockError::Io(_))));
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "keep me", "Target must not be truncated");
+}
+
+#[test]
+fn test_only_other_instances_are_terminated() {
+    // Neither this process nor an unrelated one counts as an instance
+    assert!(!same_program(std::process::id()));
+    let mut other = std::process::Command::new("sleep").arg("5").spawn().unwrap();
+    assert!(!same_program(other.id()));
+    assert!(terminate_process(other.id()).is_err());
+    assert!(other.try_wait().unwrap().is_none(), "Unrelated process must not be signalled");
+    other.kill().unwrap();
+    other.wait().unwrap();
+}
@@ -35,6 +35,30 @@ fn test_geoclue2_location_provider_set_option_returns_error() {
     );
 }
 
+#[test]
+fn test_geoclue2_default_desktop_id() {
+    let provider = GeoClue2LocationProvider::new();
+    assert_eq!(provider.desktop_id(), DEFAULT_GEOCLUE_DESKTOP_ID);
+    assert_eq!(provider.desktop_id(), "redshift");
+}
+
+#[test]
+fn test_geoclue2_with_desktop_id() {
+    let provider = GeoClue2LocationProvider::with_desktop_id("org.example.redshift");
+    assert_eq!(provider.desktop_id(), "org.example.redshift");
+}
+
+#[test]
+fn test_geoclue2_set_desktop_id_option() {
+    let mut provider = GeoClue2LocationProvider::new();
+    assert!(provider.set_option("desktop-id", "redshift-gtk").is_ok());
+    assert_eq!(provider.desktop_id(), "redshift-gtk");
+
+    // Empty desktop id is rejected and leaves the previous value
+    assert!(provider.set_option("desktop-id", "").is_err());
+    assert_eq!(provider.desktop_id(), "redshift-gtk");
+}
+
 #[test]
 fn test_geoclue2_location_provider_trait_object() {
     let provider: Box<dyn LocationProvider> = Box::new(GeoClue2LocationProvider::new());
@@ -87,6 +87,7 @@ fn test_manual_location_provider_start_without_location() {
     );
     assert!(result
         .unwrap_err()
+        .to_string()
         .contains("Latitude and longitude must be set"));
 }
 
@@ -115,7 +116,7 @@ fn test_manual_location_provider_invalid_option() {
 
     let result = provider.set_option("invalid", "42.0");
     assert!(result.is_err(), "Invalid option should return error");
-    assert!(result.unwrap_err().contains("Unknown method parameter"));
+    assert!(result.unwrap_err().to_string().contains("Unknown method parameter"));
 }
 
 #[test]
@@ -126,7 +127,7 @@ fn test_manual_location_provider_invalid_value() {
 
     let result = provider.set_option("lat", "not_a_number");
     assert!(result.is_err(), "Invalid value should return error");
-    assert!(result.unwrap_err().contains("Malformed argument"));
+    assert!(result.unwrap_err().to_string().contains("Malformed argument"));
 }
 
 #[test]
@@ -281,3 +282,219 @@ fn test_manual_location_default() {
 
     assert_eq!(provider1.name(), provider2.name());
 }
+
+#[test]
+fn test_parse_location_arg_coordinates() {
+    let loc = parse_location_arg("40.7:-74.0").unwrap();
+    assert_eq!(loc.lat, 40.7);
+    assert_eq!(loc.lon, -74.0);
+}
+
+#[test]
+fn test_parse_location_arg_manual_provider() {
+    let loc = parse_location_arg("manual:lat=51.5:lon=-0.1").unwrap();
+    assert_eq!(loc.lat, 51.5);
+    assert_eq!(loc.lon, -0.1);
+
+    // Option order and key case do not matter
+    let loc = parse_location_arg("manual:LON=151.2:lat=-33.9").unwrap();
+    assert_eq!(loc.lat, -33.9);
+    assert_eq!(loc.lon, 151.2);
+}
+
+#[test]
+fn test_parse_location_arg_manual_needs_both_coordinates() {
+    for arg in ["manual:lat=51.5", "manual:lon=-0.1", "manual:"] {
+        let err = parse_location_arg(arg).unwrap_err().to_string();
+        assert!(err.contains("Latitude and longitude must be set"), "{}: {}", arg, err);
+    }
+}
+
+#[test]
+fn test_parse_location_arg_manual_rejects_bad_options() {
+    assert!(parse_location_arg("manual:lat=north:lon=0").is_err());
+    assert!(parse_location_arg("manual:lat=10:height=3").is_err());
+    assert!(parse_location_arg("manual:lat:lon").is_err());
+    assert!(parse_location_arg("manual:lat=95:lon=0").is_err(), "Out of range latitude");
+}
+
+#[test]
+fn test_parse_location_arg_bad_value_is_usage_error() {
+    for arg in ["95:0", "0:200", "north:0", "1:2:3", "manual:lat=95:lon=0", "manual:lat=x:lon=0"] {
+        let err = parse_location_arg(arg).unwrap_err();
+        assert!(matches!(err, redshift_rebooted::error::Error::Config(_)), "{}: {:?}", arg, err);
+    }
+}
+
+#[test]
+fn test_parse_location_arg_list_is_not_a_location() {
+    assert_eq!(LIST_PROVIDERS_ARG, "list");
+    assert!(parse_location_arg(LIST_PROVIDERS_ARG).is_err());
+}
+
+#[test]
+fn test_location_providers_listed() {
+    let names: Vec<String> = location_providers()
+        .iter()
+        .map(|provider| provider.name().to_string())
+        .collect();
+    assert_eq!(names, vec!["geoclue2", "gpsd", "manual"]);
+}
+
+#[test]
+fn test_parse_location_arg_keeps_f64_precision() {
+    let loc = parse_location_arg("69.64962081234567:18.95532479876543").unwrap();
+    assert_eq!(loc.lat, 69.649_620_812_345_67);
+    assert_eq!(loc.lon, 18.955_324_798_765_43);
+}
+
+#[test]
+fn test_parse_tpv_with_fix() {
+    let line = r#"{"class":"TPV","device":"/dev/ttyUSB0","mode":3,"time":"2026-06-01T12:00:00.000Z","lat":47.3769,"lon":8.5417,"alt":408.0}"#;
+    assert_eq!(parse_tpv(line), Some(Location { lat: 47.3769, lon: 8.5417 }));
+
+    // A 2D fix is enough for the sun's position
+    let line = r#"{"class":"TPV","mode":2,"lat":-33.86,"lon":151.21}"#;
+    assert_eq!(parse_tpv(line), Some(Location { lat: -33.86, lon: 151.21 }));
+}
+
+#[test]
+fn test_parse_tpv_without_fix() {
+    // No fix yet: mode 1 and no coordinates
+    assert_eq!(parse_tpv(r#"{"class":"TPV","device":"/dev/ttyUSB0","mode":1}"#), None);
+    // Coordinates left over from an earlier fix do not count without one
+    assert_eq!(parse_tpv(r#"{"class":"TPV","mode":1,"lat":1.0,"lon":2.0}"#), None);
+    assert_eq!(parse_tpv(r#"{"class":"TPV","mode":3,"lat":1.0}"#), None);
+}
+
+#[test]
+fn test_parse_tpv_ignores_other_reports() {
+    assert_eq!(parse_tpv(r#"{"class":"VERSION","release":"3.25","proto_major":3}"#), None);
+    assert_eq!(parse_tpv(r#"{"class":"SKY","mode":3,"lat":1.0,"lon":2.0}"#), None);
+    assert_eq!(parse_tpv("not json"), None);
+    assert_eq!(parse_tpv(""), None);
+}
+
+#[test]
+fn test_parse_tpv_rejects_out_of_range() {
+    assert_eq!(parse_tpv(r#"{"class":"TPV","mode":3,"lat":91.0,"lon":0.0}"#), None);
+    assert_eq!(parse_tpv(r#"{"class":"TPV","mode":3,"lat":0.0,"lon":-181.0}"#), None);
+}
+
+#[test]
+fn test_gpsd_provider_options() {
+    let mut provider = GpsdLocationProvider::new();
+    assert_eq!(provider.name(), "gpsd");
+    assert!(provider.set_option("host", "gps.local").is_ok());
+    assert!(provider.set_option("PORT", "2948").is_ok());
+    assert!(provider.set_option("port", "not-a-port").is_err());
+    assert!(provider.set_option("host", "").is_err());
+    assert!(provider.set_option("baud", "9600").is_err());
+}
+
+#[test]
+fn test_gpsd_provider_reads_fix_from_daemon() {
+    use std::io::{BufRead, BufReader, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut watch = String::new();
+        BufReader::new(stream.try_clone().unwrap()).read_line(&mut watch).unwrap();
+        stream
+            .write_all(
+                concat!(
+                    r#"{"class":"VERSION","release":"3.25"}"#,
+                    "\n",
+                    r#"{"class":"TPV","mode":1}"#,
+                    "\n",
+                    r#"{"class":"TPV","mode":3,"lat":52.52,"lon":13.405}"#,
+                    "\n"
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+        // Keep the connection open until the provider has read the fix
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        watch
+    });
+
+    let mut provider = GpsdLocationProvider::new();
+    provider.set_option("host", "127.0.0.1").unwrap();
+    provider.set_option("port", &port.to_string()).unwrap();
+    provider.init().unwrap();
+    provider.start().unwrap();
+
+    let location = provider.wait_for_location(std::time::Duration::from_secs(5)).unwrap();
+    assert_eq!(location, Location { lat: 52.52, lon: 13.405 });
+
+    let watch = server.join().unwrap();
+    assert!(watch.starts_with("?WATCH="), "Unexpected command: {}", watch);
+}
+
+#[test]
+fn test_gpsd_provider_without_daemon_fails_gracefully() {
+    // Bind and drop a listener to find a port nothing listens on
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let mut provider = GpsdLocationProvider::new();
+    provider.set_option("host", "127.0.0.1").unwrap();
+    provider.set_option("port", &port.to_string()).unwrap();
+    provider.start().unwrap();
+
+    let err = provider
+        .wait_for_location(std::time::Duration::from_secs(5))
+        .unwrap_err();
+    assert!(err.to_string().contains("gpsd unavailable"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_median_location_of_samples() {
+    // A coarse IP-based first fix followed by GPS-refined ones
+    let samples = [
+        Location { lat: 48.0, lon: 11.0 },
+        Location { lat: 48.137, lon: 11.575 },
+        Location { lat: 48.139, lon: 11.578 },
+        Location { lat: 48.138, lon: 11.576 },
+        Location { lat: 48.140, lon: 11.574 },
+    ];
+    let median = median_location(&samples).unwrap();
+    assert_eq!(median, Location { lat: 48.138, lon: 11.575 });
+}
+
+#[test]
+fn test_median_location_even_count_and_empty() {
+    let samples = [
+        Location { lat: 10.0, lon: -20.0 },
+        Location { lat: 30.0, lon: 40.0 },
+    ];
+    assert_eq!(median_location(&samples), Some(Location { lat: 20.0, lon: 10.0 }));
+    assert_eq!(median_location(&samples[..1]), Some(samples[0]));
+    assert_eq!(median_location(&[]), None);
+}
+
+#[test]
+fn test_median_location_across_antimeridian() {
+    // Fiji straddles 180°, so fixes come with both signs
+    let samples = [
+        Location { lat: -17.8, lon: 179.9 },
+        Location { lat: -17.7, lon: -179.9 },
+        Location { lat: -17.9, lon: 179.8 },
+    ];
+    let median = median_location(&samples).unwrap();
+    assert_eq!(median.lat, -17.8);
+    assert!((median.lon - 179.9).abs() < 1e-9, "Got {}", median.lon);
+
+    // The midpoint of an even count can land on the far side
+    let median = median_location(&[samples[1], samples[2]]).unwrap();
+    assert!((median.lon - 179.95).abs() < 1e-9, "Got {}", median.lon);
+    let median = median_location(&[samples[1], Location { lat: -17.7, lon: 179.7 }]).unwrap();
+    assert!((median.lon - 179.9).abs() < 1e-9, "Got {}", median.lon);
+    let median = median_location(&[samples[1], Location { lat: -17.7, lon: -179.5 }]).unwrap();
+    assert!((median.lon + 179.7).abs() < 1e-9, "Got {}", median.lon);
+}
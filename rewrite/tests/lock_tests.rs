@@ -0,0 +1,123 @@
+/// Tests for pausing updates while the screen is locked
+/// Uses a scripted lock source so no X server is needed
+
+use redshift_rebooted::fade::Fade;
+use redshift_rebooted::lock::*;
+use redshift_rebooted::types::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Lock source returning whatever state the test sets
+struct FakeLockSource {
+    locked: Rc<RefCell<Result<bool, String>>>,
+}
+
+impl LockSource for FakeLockSource {
+    fn is_locked(&mut self) -> Result<bool, String> {
+        self.locked.borrow().clone()
+    }
+}
+
+fn fake_watcher() -> (LockWatcher, Rc<RefCell<Result<bool, String>>>) {
+    let locked = Rc::new(RefCell::new(Ok(false)));
+    let source = FakeLockSource {
+        locked: Rc::clone(&locked),
+    };
+    (LockWatcher::new(Box::new(source)), locked)
+}
+
+#[test]
+fn test_poll_reports_lock_changes() {
+    let (mut watcher, locked) = fake_watcher();
+
+    assert!(!watcher.poll());
+    assert!(!watcher.should_pause(false));
+
+    *locked.borrow_mut() = Ok(true);
+    assert!(watcher.poll(), "Locking is a change");
+    assert!(watcher.should_pause(false));
+    assert!(!watcher.poll(), "Staying locked is not");
+
+    *locked.borrow_mut() = Ok(false);
+    assert!(watcher.poll());
+    assert!(!watcher.should_pause(false));
+}
+
+#[test]
+fn test_query_errors_count_as_unlocked() {
+    let (mut watcher, locked) = fake_watcher();
+
+    *locked.borrow_mut() = Ok(true);
+    watcher.poll();
+    *locked.borrow_mut() = Err("X server went away".to_string());
+    watcher.poll();
+
+    assert!(!watcher.should_pause(false), "Updates must not stay paused");
+}
+
+#[test]
+fn test_shutdown_is_never_paused() {
+    let (mut watcher, locked) = fake_watcher();
+    *locked.borrow_mut() = Ok(true);
+    watcher.poll();
+
+    assert!(watcher.should_pause(false));
+    assert!(!watcher.should_pause(true));
+}
+
+#[test]
+fn test_fade_holds_while_locked_and_resumes() {
+    let (mut watcher, locked) = fake_watcher();
+    let mut fade = Fade::new(ColorSetting::default());
+    let night = ColorSetting {
+        temperature: 3000,
+        ..ColorSetting::default()
+    };
+
+    /* One loop iteration: the setting applied, or None while paused */
+    let mut step = |watcher: &mut LockWatcher| {
+        watcher.poll();
+        (!watcher.should_pause(false)).then(|| fade.update(&night, false))
+    };
+
+    let mut applied = Vec::new();
+    for _ in 0..5 {
+        applied.extend(step(&mut watcher));
+    }
+    let before_lock = *applied.last().unwrap();
+    assert!(before_lock.temperature < 6500 && before_lock.temperature > 3000, "Mid-fade");
+
+    *locked.borrow_mut() = Ok(true);
+    for _ in 0..50 {
+        assert_eq!(step(&mut watcher), None, "Nothing is applied while locked");
+    }
+
+    *locked.borrow_mut() = Ok(false);
+    let after_unlock = step(&mut watcher).unwrap();
+    assert!(
+        after_unlock.temperature < before_lock.temperature
+            && after_unlock.temperature > 3000,
+        "The fade continues from where it stopped, got {}K after {}K",
+        after_unlock.temperature,
+        before_lock.temperature
+    );
+}
+
+#[test]
+fn test_any_source_locked_counts() {
+    let unlocked = Rc::new(RefCell::new(Ok(false)));
+    let failing = Rc::new(RefCell::new(Err("logind went away".to_string())));
+    let mut any = AnyLockSource::new(vec![
+        Box::new(FakeLockSource { locked: Rc::clone(&unlocked) }),
+        Box::new(FakeLockSource { locked: Rc::clone(&failing) }),
+    ]);
+
+    assert_eq!(any.is_locked(), Ok(false), "One readable source is enough");
+
+    *failing.borrow_mut() = Ok(true);
+    assert_eq!(any.is_locked(), Ok(true), "Any locked source locks");
+
+    *unlocked.borrow_mut() = Err("X server went away".to_string());
+    *failing.borrow_mut() = Err("logind went away".to_string());
+    assert!(any.is_locked().is_err(), "No readable source is an error");
+}
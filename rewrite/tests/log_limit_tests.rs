@@ -0,0 +1,66 @@
+/// Tests for rate-limited warnings
+
+use redshift_rebooted::log_limit::LogLimiter;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(10);
+
+#[test]
+fn test_repeats_within_window_emit_once() {
+    let mut limiter = LogLimiter::new(WINDOW);
+    let start = Instant::now();
+
+    assert_eq!(
+        limiter.check("Failed to restore CRTC gamma", start),
+        Some("Failed to restore CRTC gamma".to_string())
+    );
+    for tick in 1..10 {
+        let now = start + Duration::from_secs(tick);
+        assert_eq!(limiter.check("Failed to restore CRTC gamma", now), None);
+    }
+}
+
+#[test]
+fn test_repeat_after_window_reports_count() {
+    let mut limiter = LogLimiter::new(WINDOW);
+    let start = Instant::now();
+
+    limiter.check("CRTC gone", start);
+    for tick in 1..=4 {
+        limiter.check("CRTC gone", start + Duration::from_secs(tick));
+    }
+
+    assert_eq!(
+        limiter.check("CRTC gone", start + WINDOW),
+        Some("CRTC gone (repeated 4 times)".to_string())
+    );
+
+    // The count starts over once reported
+    assert_eq!(limiter.check("CRTC gone", start + WINDOW + Duration::from_secs(1)), None);
+    assert_eq!(
+        limiter.check("CRTC gone", start + WINDOW * 2),
+        Some("CRTC gone (repeated 1 times)".to_string())
+    );
+}
+
+#[test]
+fn test_repeat_after_quiet_window_has_no_summary() {
+    let mut limiter = LogLimiter::new(WINDOW);
+    let start = Instant::now();
+
+    limiter.check("CRTC gone", start);
+    assert_eq!(
+        limiter.check("CRTC gone", start + WINDOW * 3),
+        Some("CRTC gone".to_string())
+    );
+}
+
+#[test]
+fn test_distinct_messages_are_limited_separately() {
+    let mut limiter = LogLimiter::new(WINDOW);
+    let now = Instant::now();
+
+    assert!(limiter.check("CRTC 0 gone", now).is_some());
+    assert!(limiter.check("CRTC 1 gone", now).is_some());
+    assert!(limiter.check("CRTC 0 gone", now).is_none());
+}
@@ -1,35 +1,14 @@
 /// Tests for main.rs location parsing and determination logic
 
+use redshift_rebooted::fade::{color_setting_diff_is_major, ease_fade, FadeThresholds};
+use redshift_rebooted::location::parse_location_arg;
+use redshift_rebooted::period::interpolate_transition_scheme;
+use redshift_rebooted::solar::transition_progress_from_elevation;
 use redshift_rebooted::types::*;
 
-// Helper to parse location string (mimics main.rs parse_location)
+// Parse a location string the way main.rs does for -l
 fn parse_location(loc_str: &str) -> Result<Location, String> {
-    let parts: Vec<&str> = loc_str.split(':').collect();
-    if parts.len() != 2 {
-        return Err("Location must be in format LAT:LON".to_string());
-    }
-
-    let lat: f32 = parts[0]
-        .parse()
-        .map_err(|_| format!("Invalid latitude: {}", parts[0]))?;
-    let lon: f32 = parts[1]
-        .parse()
-        .map_err(|_| format!("Invalid longitude: {}", parts[1]))?;
-
-    if lat < MIN_LAT || lat > MAX_LAT {
-        return Err(format!(
-            "Latitude must be between {} and {}",
-            MIN_LAT, MAX_LAT
-        ));
-    }
-    if lon < MIN_LON || lon > MAX_LON {
-        return Err(format!(
-            "Longitude must be between {} and {}",
-            MIN_LON, MAX_LON
-        ));
-    }
-
-    Ok(Location { lat, lon })
+    parse_location_arg(loc_str).map_err(String::from)
 }
 
 #[cfg(test)]
@@ -163,6 +142,8 @@ mod parse_location_tests {
 mod interpolation_tests {
     use super::*;
 
+    /* Setting at `elevation` for a scheme between `night` and `day`, as
+       continual mode computes it */
     fn interpolate_color_setting(
         elevation: f64,
         low: f64,
@@ -170,19 +151,20 @@ mod interpolation_tests {
         night: &ColorSetting,
         day: &ColorSetting,
     ) -> ColorSetting {
-        let alpha = ((elevation - low) / (high - low)) as f32;
-        let alpha = alpha.max(0.0).min(1.0);
-
-        ColorSetting {
-            temperature: ((1.0 - alpha) * (night.temperature as f32) + alpha * (day.temperature as f32))
-                as i32,
-            gamma: [
-                (1.0 - alpha) * night.gamma[0] + alpha * day.gamma[0],
-                (1.0 - alpha) * night.gamma[1] + alpha * day.gamma[1],
-                (1.0 - alpha) * night.gamma[2] + alpha * day.gamma[2],
-            ],
-            brightness: (1.0 - alpha) * night.brightness + alpha * day.brightness,
-        }
+        let scheme = TransitionScheme {
+            low,
+            high,
+            night: *night,
+            day: *day,
+            ..TransitionScheme::default()
+        };
+        let mut result = ColorSetting::default();
+        interpolate_transition_scheme(
+            &scheme,
+            transition_progress_from_elevation(&scheme, elevation),
+            &mut result,
+        );
+        result
     }
 
     #[test]
@@ -191,11 +173,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         let result = interpolate_color_setting(-6.0, -6.0, 3.0, &night, &day);
@@ -208,11 +192,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         let result = interpolate_color_setting(3.0, -6.0, 3.0, &night, &day);
@@ -225,11 +211,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         let result = interpolate_color_setting(-1.5, -6.0, 3.0, &night, &day);
@@ -243,11 +231,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 0.5,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         let result = interpolate_color_setting(-1.5, -6.0, 3.0, &night, &day);
@@ -260,11 +250,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [0.8, 0.8, 0.8],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         let result = interpolate_color_setting(-1.5, -6.0, 3.0, &night, &day);
@@ -279,11 +271,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         // Elevation below low should clamp to night
@@ -297,11 +291,13 @@ mod interpolation_tests {
             temperature: 3500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let day = ColorSetting {
             temperature: 6500,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
         // Elevation above high should clamp to day
@@ -314,23 +310,16 @@ mod interpolation_tests {
 mod color_setting_tests {
     use super::*;
 
-    fn color_setting_diff_is_major(first: &ColorSetting, second: &ColorSetting) -> bool {
-        (first.temperature - second.temperature).abs() > 25
-            || (first.brightness - second.brightness).abs() > 0.1
-            || (first.gamma[0] - second.gamma[0]).abs() > 0.1
-            || (first.gamma[1] - second.gamma[1]).abs() > 0.1
-            || (first.gamma[2] - second.gamma[2]).abs() > 0.1
-    }
-
     #[test]
     fn test_identical_settings_not_major() {
         let setting = ColorSetting {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
-        assert!(!color_setting_diff_is_major(&setting, &setting));
+        assert!(!color_setting_diff_is_major(&setting, &setting, &FadeThresholds::default()));
     }
 
     #[test]
@@ -339,14 +328,16 @@ mod color_setting_tests {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let second = ColorSetting {
             temperature: 5020,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
-        assert!(!color_setting_diff_is_major(&first, &second));
+        assert!(!color_setting_diff_is_major(&first, &second, &FadeThresholds::default()));
     }
 
     #[test]
@@ -355,14 +346,16 @@ mod color_setting_tests {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let second = ColorSetting {
             temperature: 5100,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
-        assert!(color_setting_diff_is_major(&first, &second));
+        assert!(color_setting_diff_is_major(&first, &second, &FadeThresholds::default()));
     }
 
     #[test]
@@ -371,14 +364,16 @@ mod color_setting_tests {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let second = ColorSetting {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 0.8,
+            contrast: 1.0,
         };
 
-        assert!(color_setting_diff_is_major(&first, &second));
+        assert!(color_setting_diff_is_major(&first, &second, &FadeThresholds::default()));
     }
 
     #[test]
@@ -387,14 +382,16 @@ mod color_setting_tests {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let second = ColorSetting {
             temperature: 5000,
             gamma: [0.85, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
-        assert!(color_setting_diff_is_major(&first, &second));
+        assert!(color_setting_diff_is_major(&first, &second, &FadeThresholds::default()));
     }
 
     #[test]
@@ -403,14 +400,16 @@ mod color_setting_tests {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let second = ColorSetting {
             temperature: 5025,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
-        assert!(!color_setting_diff_is_major(&first, &second));
+        assert!(!color_setting_diff_is_major(&first, &second, &FadeThresholds::default()));
     }
 
     #[test]
@@ -419,22 +418,22 @@ mod color_setting_tests {
             temperature: 5000,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
         let second = ColorSetting {
             temperature: 5026,
             gamma: [1.0, 1.0, 1.0],
             brightness: 1.0,
+            contrast: 1.0,
         };
 
-        assert!(color_setting_diff_is_major(&first, &second));
+        assert!(color_setting_diff_is_major(&first, &second, &FadeThresholds::default()));
     }
 }
 
 #[cfg(test)]
 mod ease_fade_tests {
-    fn ease_fade(t: f64) -> f64 {
-        t * t * (3.0 - 2.0 * t)
-    }
+    use super::*;
 
     #[test]
     fn test_ease_fade_at_zero() {
@@ -0,0 +1,120 @@
+#![cfg(feature = "metrics")]
+/// Tests for the Prometheus metrics endpoint
+/// Run with `cargo test --features metrics`.
+
+use redshift_rebooted::metrics::*;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+const EXPECTED_METRICS: [&str; 4] = [
+    "redshift_temperature_kelvin",
+    "redshift_brightness",
+    "redshift_enabled",
+    "redshift_solar_elevation_degrees",
+];
+
+fn snapshot() -> MetricsSnapshot {
+    MetricsSnapshot {
+        temperature: 4200,
+        brightness: 0.9,
+        enabled: true,
+        solar_elevation: -2.5,
+    }
+}
+
+/* Parse the text exposition format into sample values by name, checking
+   that every sample is announced as a gauge first */
+fn parse_exposition(text: &str) -> HashMap<String, f64> {
+    let mut types = HashMap::new();
+    let mut samples = HashMap::new();
+
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["#", "HELP", _name, ..] => {}
+            ["#", "TYPE", name, kind] => {
+                types.insert(name.to_string(), kind.to_string());
+            }
+            [name, value] => {
+                assert_eq!(types.get(*name).map(String::as_str), Some("gauge"), "{}", line);
+                let value: f64 = value.parse().unwrap_or_else(|_| panic!("Bad value: {}", line));
+                samples.insert(name.to_string(), value);
+            }
+            _ => panic!("Unexpected line: {:?}", line),
+        }
+    }
+    samples
+}
+
+/* Send a GET request and return the status line and body */
+fn get(addr: SocketAddr, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").expect("No header end");
+    (head.lines().next().unwrap().to_string(), body.to_string())
+}
+
+#[test]
+fn test_exposition_parses_with_expected_metrics() {
+    let samples = parse_exposition(&snapshot().render_exposition());
+
+    for name in EXPECTED_METRICS {
+        assert!(samples.contains_key(name), "Missing {}", name);
+    }
+    assert_eq!(samples["redshift_temperature_kelvin"], 4200.0);
+    assert!((samples["redshift_brightness"] - 0.9).abs() < 1e-6);
+    assert_eq!(samples["redshift_enabled"], 1.0);
+    assert_eq!(samples["redshift_solar_elevation_degrees"], -2.5);
+}
+
+#[test]
+fn test_exposition_reports_disabled() {
+    let disabled = MetricsSnapshot {
+        enabled: false,
+        ..snapshot()
+    };
+    assert_eq!(parse_exposition(&disabled.render_exposition())["redshift_enabled"], 0.0);
+}
+
+#[test]
+fn test_metrics_endpoint_serves_latest_snapshot() {
+    let server = MetricsServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr();
+
+    let (status, _) = get(addr, "/metrics");
+    assert!(status.contains("503"), "No data before the first update: {}", status);
+
+    server.update(snapshot());
+    let (status, body) = get(addr, "/metrics");
+    assert!(status.contains("200"), "Unexpected status: {}", status);
+    let samples = parse_exposition(&body);
+    assert_eq!(samples.len(), EXPECTED_METRICS.len());
+
+    server.update(MetricsSnapshot {
+        temperature: 6500,
+        ..snapshot()
+    });
+    let (_, body) = get(addr, "/metrics");
+    assert_eq!(parse_exposition(&body)["redshift_temperature_kelvin"], 6500.0);
+}
+
+#[test]
+fn test_metrics_endpoint_unknown_path() {
+    let server = MetricsServer::bind("127.0.0.1:0").unwrap();
+    server.update(snapshot());
+
+    let (status, _) = get(server.local_addr(), "/");
+    assert!(status.contains("404"), "Unexpected status: {}", status);
+}
+
+#[test]
+fn test_metrics_bind_failure() {
+    let server = MetricsServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().to_string();
+    let err = MetricsServer::bind(&addr).err().expect("Port should be in use");
+    assert!(err.contains(&addr), "Unexpected error: {}", err);
+}
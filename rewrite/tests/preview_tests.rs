@@ -0,0 +1,117 @@
+/// Tests for previewing another city's schedule with --preview-city
+
+use redshift_rebooted::cities::find_city;
+use redshift_rebooted::preview::*;
+use redshift_rebooted::solar::SolarTime;
+use redshift_rebooted::types::{Location, Period, TransitionScheme};
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/* 2024-03-20 00:00 UTC, the March equinox */
+const EQUINOX_MIDNIGHT: f64 = 1710892800.0;
+
+/* 2024-06-21 00:00 UTC, near the June solstice */
+const SOLSTICE_MIDNIGHT: f64 = 1718928000.0;
+
+fn city_location(query: &str) -> Location {
+    let city = find_city(query).unwrap();
+    Location {
+        lat: city.latitude().unwrap(),
+        lon: city.longitude().unwrap(),
+    }
+}
+
+#[test]
+fn test_timeline_covers_the_day_hourly() {
+    let location = city_location("Reykjavik");
+    let scheme = TransitionScheme::default();
+    let timeline = day_timeline(&location, &scheme, EQUINOX_MIDNIGHT, TIMELINE_STEP);
+
+    assert_eq!(timeline.len(), 24);
+    for (hour, entry) in timeline.iter().enumerate() {
+        assert_eq!(entry.time, EQUINOX_MIDNIGHT + hour as f64 * 3600.0);
+    }
+
+    // Night at midnight, day at noon, warm and neutral at the ends
+    assert_eq!(timeline[0].period, Period::Night);
+    assert_eq!(timeline[0].setting, scheme.night);
+    assert_eq!(timeline[12].period, Period::Daytime);
+    assert_eq!(timeline[12].setting, scheme.day);
+    assert!(timeline.iter().any(|entry| entry.period == Period::Transition));
+}
+
+#[test]
+fn test_events_in_order_with_unreached_last() {
+    // No astronomical night in Reykjavik around midsummer
+    let location = city_location("Reykjavik");
+    let events = day_events(&location, SOLSTICE_MIDNIGHT);
+
+    assert_eq!(events.len(), SolarTime::ALL.len());
+    let reached: Vec<f64> = events.iter().filter_map(|&(_, time)| time).collect();
+    assert!(reached.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", events);
+    assert_eq!(events.last(), Some(&(SolarTime::AstroDusk, None)));
+    assert!(events.contains(&(SolarTime::AstroDawn, None)));
+}
+
+#[test]
+fn test_format_preview_lists_events_and_timeline() {
+    let location = city_location("Reykjavik");
+    let events = day_events(&location, EQUINOX_MIDNIGHT);
+    let timeline = day_timeline(&location, &TransitionScheme::default(), EQUINOX_MIDNIGHT, TIMELINE_STEP);
+    let text = format_preview("Reykjavik, Iceland", &location, &events, &timeline);
+
+    assert!(text.starts_with("Reykjavik, Iceland (64.1"), "{}", text);
+    assert!(text.contains("this machine's local time (UTC"), "{}", text);
+    assert!(text.contains("Solar events (local time):"), "{}", text);
+    assert!(text.contains("  Sunrise"), "{}", text);
+    assert!(text.contains("3500K  1.00  Night"), "{}", text);
+    assert!(text.contains("6500K  1.00  Daytime"), "{}", text);
+}
+
+/* Run the binary with the dummy method and an empty config directory,
+   returning its output and the config directory */
+fn run_preview(args: &[&str]) -> (Output, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-m", "dummy"])
+        .args(args)
+        .env_remove("REDSHIFT_METHOD")
+        .env_remove("REDSHIFT_TEMP_DAY")
+        .env_remove("REDSHIFT_TEMP_NIGHT")
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HOME", dir.path())
+        .env("TZ", "JST-9")
+        .output()
+        .expect("Failed to execute redshift");
+    (output, dir)
+}
+
+#[test]
+fn test_preview_city_prints_schedule_without_applying() {
+    let (output, dir) = run_preview(&["--preview-city", "Reykjavik", "--temp-night", "3000"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert!(stdout.starts_with("Reykjavik, Iceland"), "{}", stdout);
+    assert!(stdout.contains("local time (UTC+09:00), not the city's"), "{}", stdout);
+    assert!(stdout.contains("Sunrise"), "{}", stdout);
+    let timeline: Vec<&str> = stdout
+        .lines()
+        .skip_while(|line| *line != "Timeline (local time):")
+        .skip(1)
+        .collect();
+    assert_eq!(timeline.len(), 24, "{}", stdout);
+    assert!(timeline.iter().any(|line| line.contains("3000K")), "Uses the configured scheme");
+
+    // Neither the gamma method nor the saved location is touched
+    assert!(!stdout.contains("Temperature:"), "{}", stdout);
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0, "Nothing saved");
+}
+
+#[test]
+fn test_preview_unknown_city_fails() {
+    let (output, _dir) = run_preview(&["--preview-city", "Atlantis"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr);
+    assert!(stderr.contains("Unknown city: Atlantis"), "{}", stderr);
+}
@@ -0,0 +1,117 @@
+/// Tests for the query socket
+
+use redshift_rebooted::query::*;
+use redshift_rebooted::types::Period;
+use std::io::Read;
+use std::os::unix::net::UnixStream;
+use tempfile::TempDir;
+
+fn query(path: &std::path::Path) -> String {
+    let mut stream = UnixStream::connect(path).unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+    reply
+}
+
+#[test]
+fn test_snapshot_line() {
+    let snapshot = QuerySnapshot {
+        temperature: 4200,
+        brightness: 0.9,
+        period: Period::Transition,
+    };
+    assert_eq!(snapshot.line(), "4200 0.90 transition\n");
+}
+
+#[test]
+fn test_query_reports_latest_snapshot() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("query.sock");
+    let server = QueryServer::bind(&path).unwrap();
+
+    assert_eq!(query(&path), "", "No reply before the first update");
+
+    server.update(QuerySnapshot {
+        temperature: 3500,
+        brightness: 0.8,
+        period: Period::Night,
+    });
+    let reply = query(&path);
+    let fields: Vec<&str> = reply.split_whitespace().collect();
+    assert!(reply.ends_with('\n'));
+    assert_eq!(fields, vec!["3500", "0.80", "night"]);
+
+    server.update(QuerySnapshot {
+        temperature: 6500,
+        brightness: 1.0,
+        period: Period::Daytime,
+    });
+    assert_eq!(query(&path), "6500 1.00 daytime\n");
+}
+
+#[test]
+fn test_query_socket_removed_on_drop() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("query.sock");
+
+    let server = QueryServer::bind(&path).unwrap();
+    assert!(path.exists());
+    drop(server);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_query_socket_in_use_is_not_replaced() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("query.sock");
+
+    let _server = QueryServer::bind(&path).unwrap();
+    assert!(QueryServer::bind(&path).is_err());
+}
+
+#[test]
+fn test_stale_query_socket_is_replaced() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("query.sock");
+
+    // A socket file nobody listens on, as left behind by a crash
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let server = QueryServer::bind(&path).unwrap();
+    server.update(QuerySnapshot {
+        temperature: 5000,
+        brightness: 1.0,
+        period: Period::Daytime,
+    });
+    assert_eq!(query(&path), "5000 1.00 daytime\n");
+}
+
+#[test]
+fn test_query_socket_refuses_regular_file() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("query.sock");
+    std::fs::write(&path, "keep me").unwrap();
+
+    let err = QueryServer::bind(&path).err().unwrap();
+    assert!(err.contains("not a socket"), "Unexpected error: {}", err);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+}
+
+#[test]
+fn test_drop_returns_when_socket_file_is_gone() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("query.sock");
+    let server = QueryServer::bind(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let (done, finished) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        drop(server);
+        done.send(()).unwrap();
+    });
+    assert!(
+        finished.recv_timeout(std::time::Duration::from_secs(5)).is_ok(),
+        "Dropping the server hung"
+    );
+}
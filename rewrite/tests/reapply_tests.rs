@@ -0,0 +1,50 @@
+/* Tests for periodic gamma reapplication */
+
+use redshift_rebooted::reapply::ReapplyTimer;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_not_due_before_interval() {
+    let start = Instant::now();
+    let mut timer = ReapplyTimer::new(Duration::from_secs(60), start);
+
+    assert!(!timer.check(start));
+    assert!(!timer.check(start + Duration::from_secs(59)));
+}
+
+#[test]
+fn test_due_at_interval_while_idle() {
+    let start = Instant::now();
+    let mut timer = ReapplyTimer::new(Duration::from_secs(60), start);
+
+    /* Ticks with nothing else happening still reapply once a minute */
+    let due: Vec<u64> = (1..=180)
+        .filter(|&second| timer.check(start + Duration::from_secs(second)))
+        .collect();
+
+    assert_eq!(due, vec![60, 120, 180]);
+}
+
+#[test]
+fn test_late_check_restarts_interval() {
+    let start = Instant::now();
+    let mut timer = ReapplyTimer::new(Duration::from_secs(60), start);
+
+    /* A check delayed past the deadline counts from when it happened */
+    assert!(timer.check(start + Duration::from_secs(90)));
+    assert!(!timer.check(start + Duration::from_secs(120)));
+    assert!(timer.check(start + Duration::from_secs(150)));
+}
+
+#[test]
+fn test_remaining() {
+    let start = Instant::now();
+    let mut timer = ReapplyTimer::new(Duration::from_secs(60), start);
+
+    assert_eq!(timer.remaining(start), Duration::from_secs(60));
+    assert_eq!(timer.remaining(start + Duration::from_secs(45)), Duration::from_secs(15));
+    assert_eq!(timer.remaining(start + Duration::from_secs(75)), Duration::ZERO);
+
+    timer.check(start + Duration::from_secs(75));
+    assert_eq!(timer.remaining(start + Duration::from_secs(75)), Duration::from_secs(60));
+}
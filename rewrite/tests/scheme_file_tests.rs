@@ -0,0 +1,155 @@
+/* Tests for TOML scheme files */
+
+use redshift_rebooted::colorramp::Quantization;
+use redshift_rebooted::scheme_file::SchemeFile;
+use redshift_rebooted::types::*;
+use redshift_rebooted::Error;
+
+/* Parse with the hard temperature limits */
+fn parse(contents: &str) -> Result<SchemeFile, Error> {
+    SchemeFile::parse(contents, TemperatureBounds::default())
+}
+
+#[test]
+fn test_round_trip() {
+    let scheme = TransitionSchemeBuilder::new()
+        .day_temp(6200)
+        .night_temp(2900)
+        .brightness(1.0, 0.7)
+        .gamma([1.0, 1.0, 1.0], [0.9, 0.85, 0.8])
+        .contrast(1.1)
+        .elevation_high(5.0)
+        .twilight(TwilightStages {
+            civil: ColorSetting {
+                temperature: 4500,
+                ..ColorSetting::default()
+            },
+            nautical: ColorSetting {
+                temperature: 3500,
+                brightness: 0.8,
+                ..ColorSetting::default()
+            },
+        })
+        .keyframes(vec![(79200, 3000), (25200, 6500)])
+        .build()
+        .unwrap();
+    let file = SchemeFile {
+        scheme,
+        blue_cutoff_temp: Some(2000),
+        quantization: Some(Quantization::Dither),
+    };
+
+    let toml = toml::to_string_pretty(&file).unwrap();
+    let parsed = parse(&toml).unwrap();
+
+    assert_eq!(parsed, file, "Round trip changed the scheme:\n{}", toml);
+}
+
+#[test]
+fn test_round_trip_time_based() {
+    let scheme = TransitionSchemeBuilder::new()
+        .dawn(TimeRange { start: 21600, end: 25200 })
+        .dusk(TimeRange { start: 72000, end: 75600 })
+        .build()
+        .unwrap();
+    let file = SchemeFile {
+        scheme,
+        ..SchemeFile::default()
+    };
+
+    let parsed = parse(&toml::to_string_pretty(&file).unwrap()).unwrap();
+    assert_eq!(parsed, file);
+    assert!(parsed.scheme.use_time);
+}
+
+#[test]
+fn test_missing_fields_use_defaults() {
+    let file = parse(
+        r#"
+        [night]
+        temperature = 3000
+        "#,
+    )
+    .unwrap();
+
+    let defaults = TransitionScheme::default();
+    assert_eq!(file.scheme.night.temperature, 3000);
+    assert_eq!(file.scheme.night.brightness, 1.0);
+    assert_eq!(file.scheme.day, defaults.day);
+    assert_eq!(file.scheme.high, defaults.high);
+    assert_eq!(file.scheme.low, defaults.low);
+    assert!(file.quantization.is_none());
+}
+
+#[test]
+fn test_parse_keyframes_and_extras() {
+    let file = parse(
+        r#"
+        keyframes = [[79200, 3000], [25200, 6500]]
+        quantization = "truncate"
+        blue_cutoff_temp = 1900
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(file.scheme.keyframes, vec![(25200, 6500), (79200, 3000)], "Keyframes should be sorted");
+    assert_eq!(file.quantization, Some(Quantization::Truncate));
+    assert_eq!(file.blue_cutoff_temp, Some(1900));
+}
+
+#[test]
+fn test_parse_active_days() {
+    let file = parse(r#"active_days = "mon,tue,wed,thu,fri""#).unwrap();
+    let days = file.scheme.active_days.expect("active_days should be set");
+    assert_eq!(days.to_string(), "mon,tue,wed,thu,fri");
+
+    let toml = toml::to_string_pretty(&file).unwrap();
+    assert!(toml.contains(r#"active_days = "mon,tue,wed,thu,fri""#), "{}", toml);
+    assert!(parse(r#"active_days = "someday""#).is_err());
+}
+
+#[test]
+fn test_twilight_ends_at_astronomical_twilight() {
+    let file = parse(
+        r#"
+        [twilight.civil]
+        temperature = 4500
+
+        [twilight.nautical]
+        temperature = 3800
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(file.scheme.low, -18.0);
+    assert_eq!(file.scheme.twilight.unwrap().civil.temperature, 4500);
+}
+
+#[test]
+fn test_invalid_scheme_rejected() {
+    let result = parse(
+        r#"
+        [day]
+        temperature = 50000
+        "#,
+    );
+    match result {
+        Err(Error::Config(message)) => assert!(message.contains("Temperature"), "{}", message),
+        other => panic!("Expected a config error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_malformed_toml_rejected() {
+    assert!(matches!(parse("high = "), Err(Error::Config(_))));
+    assert!(matches!(parse("high = \"up\""), Err(Error::Config(_))));
+}
+
+#[test]
+fn test_load_missing_file() {
+    let result = SchemeFile::load(
+        std::path::Path::new("/nonexistent/scheme.toml"),
+        TemperatureBounds::default(),
+    );
+    assert!(matches!(result, Err(Error::Config(_))));
+}
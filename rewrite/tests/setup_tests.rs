@@ -0,0 +1,60 @@
+/// Tests for the first-run setup wizard
+/// The prompts need a terminal, so these cover the generated config
+
+use redshift_rebooted::config_ini::RedshiftConfig;
+use redshift_rebooted::setup::*;
+use redshift_rebooted::types::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn choices() -> SetupChoices {
+    SetupChoices {
+        location: Location {
+            lat: 52.52,
+            lon: 13.405,
+        },
+        city_name: "Berlin".to_string(),
+        temp_day: 6500,
+        temp_night: 3500,
+        method: "randr".to_string(),
+    }
+}
+
+#[test]
+fn test_rendered_config_round_trips() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("redshift.conf");
+    fs::write(&config_path, render_config(&choices())).unwrap();
+
+    let config = RedshiftConfig::load_from_file(&config_path).unwrap();
+
+    assert_eq!(config.temp_day, Some(6500));
+    assert_eq!(config.temp_night, Some(3500));
+    assert_eq!(config.adjustment_method.as_deref(), Some("randr"));
+    assert_eq!(config.location_provider.as_deref(), Some("manual"));
+
+    let location = config.get_manual_location().expect("Manual location should be set");
+    assert!((location.lat - 52.52).abs() < 1e-3);
+    assert!((location.lon - 13.405).abs() < 1e-3);
+}
+
+#[test]
+fn test_rendered_config_names_city() {
+    let rendered = render_config(&choices());
+    assert!(rendered.contains("Berlin"), "Config should mention the city: {}", rendered);
+}
+
+#[test]
+fn test_temperature_presets_are_valid() {
+    for (name, day, night) in TEMPERATURE_PRESETS {
+        let scheme = TransitionSchemeBuilder::new().day_temp(*day).night_temp(*night).build();
+        assert!(scheme.is_ok(), "Preset {} should be valid", name);
+        assert!(night <= day, "Preset {} should be warmer at night", name);
+    }
+}
+
+#[test]
+fn test_default_config_path() {
+    let path = RedshiftConfig::default_config_path().unwrap();
+    assert!(path.ends_with("redshift/redshift.conf"));
+}
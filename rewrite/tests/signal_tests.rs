@@ -145,6 +145,42 @@ fn test_sigusr1_double_toggle() {
     assert!(enabled_count >= 1, "Should show enabled status at least once (may be initial or re-enable), got:\n{}", combined);
 }
 
+#[test]
+fn test_toggle_logs_status_once() {
+    let mut child = start_redshift(&["-l", "40:-74", "-m", "dummy", "-v"]);
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGUSR1);
+    }
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGKILL);
+    }
+
+    let (stdout, stderr) = read_output_with_timeout(&mut child, Duration::from_secs(5));
+    let combined = format!("{}{}", stdout, stderr);
+    assert_eq!(combined.matches("Status: Disabled").count(), 1, "got:\n{}", combined);
+}
+
+#[test]
+fn test_kv_log_format_has_no_human_lines() {
+    let mut child = start_redshift(&["-l", "40:-74", "-m", "dummy", "-v", "--log-format", "kv"]);
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGUSR1);
+    }
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGKILL);
+    }
+
+    let (stdout, stderr) = read_output_with_timeout(&mut child, Duration::from_secs(5));
+    let combined = format!("{}{}", stdout, stderr);
+    assert_eq!(combined.matches("event=toggle enabled=false").count(), 1, "got:\n{}", combined);
+    assert!(!combined.contains("Status: "), "got:\n{}", combined);
+    assert!(!combined.contains("Color temperature: "), "got:\n{}", combined);
+}
+
 #[test]
 fn test_sigterm_clean_shutdown() {
     /* Start redshift */
@@ -352,3 +388,252 @@ fn test_gamma_restoration_fade() {
             "Final temperature should be close to 6500K (neutral), got {}", last_temp);
     }
 }
+
+/* Run continual mode with a fixed day/night temperature until SIGTERM and
+   return the temperatures the dummy method printed, in order. */
+fn applied_temperatures(extra_args: &[&str]) -> Vec<i32> {
+    let mut args = vec!["-l", "40:-74", "-m", "dummy", "-t", "4000", "--temp-night", "4000"];
+    args.extend_from_slice(extra_args);
+    let mut child = start_redshift(&args);
+    let pid = child.id();
+
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM);
+    }
+
+    let (stdout, _) = read_output_with_timeout(&mut child, Duration::from_secs(10));
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Temperature: "))
+        .map(|temp| temp.trim().parse().expect("Temperature should be a number"))
+        .collect()
+}
+
+#[test]
+fn test_instant_applies_target_without_fade() {
+    let temperatures = applied_temperatures(&["--instant"]);
+
+    /* Initial one-off apply, then the first loop iteration; the shutdown
+       fade back to neutral follows */
+    assert!(temperatures.len() >= 2, "Expected at least two applies, got {:?}", temperatures);
+    assert_eq!(temperatures[0], 4000);
+    assert_eq!(temperatures[1], 4000, "First loop apply should not fade: {:?}", temperatures);
+}
+
+#[test]
+fn test_fast_exit_restores_neutral_in_one_step() {
+    let temperatures = applied_temperatures(&["--instant", "--fast-exit"]);
+
+    /* The setting jumps straight from 4000K to neutral, with no fade steps
+       in between */
+    let first_neutral = temperatures
+        .iter()
+        .position(|&temperature| temperature == 6500)
+        .unwrap_or_else(|| panic!("Expected a neutral apply, got {:?}", temperatures));
+    assert!(first_neutral > 0, "Expected 4000K before exit: {:?}", temperatures);
+    assert!(
+        temperatures[..first_neutral].iter().all(|&temperature| temperature == 4000),
+        "No intermediate values before neutral: {:?}",
+        temperatures
+    );
+    assert!(
+        temperatures[first_neutral..].iter().all(|&temperature| temperature == 6500),
+        "Nothing but neutral after exit starts: {:?}",
+        temperatures
+    );
+}
+
+#[test]
+fn test_no_restore_keeps_setting_on_sigterm() {
+    let temperatures = applied_temperatures(&["--instant", "--no-restore"]);
+
+    assert!(!temperatures.is_empty(), "Expected applies before exit");
+    assert!(
+        temperatures.iter().all(|&temperature| temperature == 4000),
+        "Nothing should be restored on SIGTERM: {:?}",
+        temperatures
+    );
+}
+
+#[test]
+fn test_shutdown_logs_stats() {
+    let mut child = start_redshift(&[
+        "-l", "40:-74", "-m", "dummy", "-t", "4000", "--temp-night", "4000", "--instant", "-vv",
+    ]);
+    let pid = child.id();
+
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM);
+    }
+
+    let (_, stderr) = read_output_with_timeout(&mut child, Duration::from_secs(10));
+
+    /* Only the shutdown fade back to neutral was started */
+    assert!(
+        stderr.contains("Shutting down after: 1 fades started"),
+        "Should log stats on shutdown, got:\n{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_temporary_override_applies_to_running_instance() {
+    let runtime_dir = tempfile::tempdir().expect("Failed to create runtime dir");
+
+    let mut child = Command::new("target/debug/redshift-rebooted")
+        .args(["-l", "40:-74", "-m", "dummy", "-t", "4000", "--temp-night", "4000", "--instant", "--control-socket"])
+        .env("XDG_RUNTIME_DIR", runtime_dir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start redshift");
+    let pid = child.id();
+
+    thread::sleep(Duration::from_millis(500));
+
+    let client = Command::new("target/debug/redshift-rebooted")
+        .args(["--override", "3000", "--for", "1h"])
+        .env("XDG_RUNTIME_DIR", runtime_dir.path())
+        .output()
+        .expect("Failed to run override client");
+    assert!(
+        client.status.success(),
+        "Override should be accepted: {}",
+        String::from_utf8_lossy(&client.stderr)
+    );
+
+    /* The daemon picks up the command on its next update */
+    thread::sleep(Duration::from_millis(6000));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM);
+    }
+
+    let (stdout, _) = read_output_with_timeout(&mut child, Duration::from_secs(10));
+    assert!(
+        stdout.contains("Temperature: 3000"),
+        "Should fade to the override temperature, got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_control_fifo_disable() {
+    let runtime_dir = tempfile::tempdir().expect("Failed to create runtime dir");
+    let fifo_path = runtime_dir.path().join("redshift.fifo");
+
+    let mut child = Command::new("target/debug/redshift-rebooted")
+        .args([
+            "-l", "40:-74", "-m", "dummy", "-t", "4000", "--temp-night", "4000", "--instant",
+            "--control-fifo",
+        ])
+        .env("XDG_RUNTIME_DIR", runtime_dir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start redshift");
+
+    thread::sleep(Duration::from_millis(500));
+    std::fs::write(&fifo_path, "disable\n").expect("Failed to write to control FIFO");
+
+    /* The command is read on the next update, then faded to neutral.
+       Kill without a shutdown fade so 6500K can only come from the FIFO. */
+    thread::sleep(Duration::from_millis(10000));
+    child.kill().expect("Failed to kill redshift");
+
+    let (stdout, _) = read_output_with_timeout(&mut child, Duration::from_secs(10));
+    assert!(stdout.contains("Temperature: 4000"), "Should start at 4000K, got:\n{}", stdout);
+    assert!(
+        stdout.contains("Temperature: 6500"),
+        "Disabling through the FIFO should fade to neutral, got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_sigint_during_startup_delay_aborts_startup() {
+    let mut child = start_redshift(&["-l", "40:-74", "-m", "dummy", "--startup-delay", "30"]);
+    let pid = child.id();
+
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGINT);
+    }
+
+    let status = child
+        .wait_timeout(Duration::from_secs(5))
+        .expect("Failed to wait for child")
+        .expect("SIGINT should cut the startup delay short");
+    assert!(status.success(), "Process should exit cleanly");
+
+    let (stdout, _) = read_output_with_timeout(&mut child, Duration::from_secs(1));
+    assert!(
+        !stdout.contains("Temperature:"),
+        "No adjustment should be made before the delay ends, got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_sigint_aborts_demo_and_restores() {
+    let mut child = start_redshift(&["-l", "0:0", "-m", "dummy", "--demo", "60"]);
+    let pid = child.id();
+
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGINT);
+    }
+
+    let status = child
+        .wait_timeout(Duration::from_secs(5))
+        .expect("Failed to wait for child")
+        .expect("SIGINT should stop the demo");
+    assert!(status.success(), "Process should exit cleanly");
+
+    let (stdout, _) = read_output_with_timeout(&mut child, Duration::from_secs(1));
+    assert_eq!(
+        stdout.lines().last(),
+        Some("Temperature: 6500"),
+        "Screen should be restored after an aborted demo, got:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cycle_persist_leaves_unreadable_config_alone() {
+    let dir = tempfile::tempdir().expect("Failed to create config dir");
+    let redshift_dir = dir.path().join("redshift");
+    std::fs::create_dir_all(&redshift_dir).unwrap();
+    std::fs::write(redshift_dir.join("redshift.conf"), "[redshift]\ncycle-persist=1\n").unwrap();
+    let broken = "[location\nlat = 52.5\n";
+    std::fs::write(redshift_dir.join("config.toml"), broken).unwrap();
+
+    let mut child = Command::new("target/debug/redshift-rebooted")
+        .args(["-l", "40:-74", "-m", "dummy", "-v"])
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HOME", dir.path())
+        .env("XDG_RUNTIME_DIR", dir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to start redshift");
+    let pid = child.id();
+
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGUSR2);
+    }
+    thread::sleep(Duration::from_millis(500));
+    unsafe {
+        libc::kill(pid as i32, libc::SIGTERM);
+    }
+    let (_, stderr) = read_output_with_timeout(&mut child, Duration::from_secs(10));
+
+    assert!(stderr.contains("Cycle position not saved"), "Got:\n{}", stderr);
+    assert_eq!(
+        std::fs::read_to_string(redshift_dir.join("config.toml")).unwrap(),
+        broken,
+        "A config file that could not be read must not be overwritten"
+    );
+}
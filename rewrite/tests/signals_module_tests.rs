@@ -243,3 +243,50 @@ fn test_multiple_sigusr1_signals() {
        check_toggle will return true once, then false */
     assert!(detected, "Should detect toggle from multiple SIGUSR1 signals");
 }
+
+#[test]
+#[serial(signals)]
+fn test_sleep_unless_exiting_completes_without_signal() {
+    use std::time::{Duration, Instant};
+
+    signals::clear_exiting();
+
+    let start = Instant::now();
+    assert!(signals::sleep_unless_exiting(Duration::from_millis(250)));
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
+
+#[cfg(unix)]
+#[test]
+#[serial(signals)]
+fn test_sleep_unless_exiting_wakes_on_exit_signal() {
+    use std::time::{Duration, Instant};
+
+    signals::clear_exiting();
+    unsafe {
+        libc::kill(std::process::id() as i32, libc::SIGTERM);
+    }
+
+    let start = Instant::now();
+    assert!(!signals::sleep_unless_exiting(Duration::from_secs(30)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    signals::clear_exiting();
+}
+
+#[test]
+#[serial(signals)]
+fn test_sigusr2_advances_cycle() {
+    use redshift_rebooted::cycle::TemperatureCycle;
+
+    signals::check_cycle();
+    let mut cycle = TemperatureCycle::new(vec![6500, 4000, 2700]);
+
+    for expected in [Some(6500), Some(4000)] {
+        unsafe { libc::raise(libc::SIGUSR2) };
+        assert!(signals::check_cycle(), "SIGUSR2 should request a cycle");
+        assert_eq!(cycle.advance(), expected);
+    }
+
+    assert!(!signals::check_cycle(), "The request is cleared once checked");
+}
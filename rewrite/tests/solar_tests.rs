@@ -1,4 +1,5 @@
 use redshift_rebooted::solar::*;
+use redshift_rebooted::types::{ColorSetting, TransitionScheme, TransitionSchemeBuilder, TwilightStages};
 
 #[test]
 fn test_solar_elevation_range() {
@@ -145,3 +146,405 @@ fn test_solar_elevation_at_different_latitudes() {
         );
     }
 }
+
+/* 2024-06-21 00:00:00 UTC and 2024-12-21 00:00:00 UTC (midnight at lon 0) */
+const JUNE_SOLSTICE_MIDNIGHT: f64 = 1718928000.0;
+const DECEMBER_SOLSTICE_MIDNIGHT: f64 = 1734739200.0;
+
+#[test]
+fn test_classify_period_polar_summer_midnight_is_daytime() {
+    use redshift_rebooted::types::{Period, TransitionScheme};
+
+    let lat = 80.0;
+    let lon = 0.0;
+    let elevation = solar_elevation(JUNE_SOLSTICE_MIDNIGHT, lat, lon);
+    let table = solar_table_fill(JUNE_SOLSTICE_MIDNIGHT, lat, lon);
+
+    // The sun stays above the horizon all day
+    assert!(table[SolarTime::Sunrise as usize].is_nan());
+    assert!(table[SolarTime::Sunset as usize].is_nan());
+    assert!(elevation > 0.0, "Midnight sun should be above horizon, got {}", elevation);
+
+    // Thresholds above the midnight sun would otherwise classify this as night
+    let scheme = TransitionScheme {
+        high: 25.0,
+        low: 20.0,
+        ..TransitionScheme::default()
+    };
+    assert!(elevation <= scheme.low);
+
+    assert_eq!(classify_period(&scheme, elevation, &table), Period::Daytime);
+}
+
+#[test]
+fn test_classify_period_polar_winter_noon_is_night() {
+    use redshift_rebooted::types::{Period, TransitionScheme};
+
+    let lat = 80.0;
+    let lon = 0.0;
+    let noon = DECEMBER_SOLSTICE_MIDNIGHT + 12.0 * 3600.0;
+    let elevation = solar_elevation(noon, lat, lon);
+    let table = solar_table_fill(noon, lat, lon);
+
+    // The sun never rises
+    assert!(table[SolarTime::Sunrise as usize].is_nan());
+    assert!(table[SolarTime::Sunset as usize].is_nan());
+    assert!(elevation < 0.0, "Polar night sun should be below horizon, got {}", elevation);
+
+    // Thresholds below the noon sun would otherwise classify this as daytime
+    let scheme = TransitionScheme {
+        high: -20.0,
+        low: -30.0,
+        ..TransitionScheme::default()
+    };
+    assert!(elevation >= scheme.high);
+
+    assert_eq!(classify_period(&scheme, elevation, &table), Period::Night);
+}
+
+#[test]
+fn test_classify_period_normal_day_uses_thresholds() {
+    use redshift_rebooted::types::{Period, TransitionScheme};
+
+    let scheme = TransitionScheme::default();
+    let table = solar_table_fill(JUNE_SOLSTICE_MIDNIGHT, 40.0, 0.0);
+    assert!(!table[SolarTime::Sunrise as usize].is_nan());
+    assert!(!table[SolarTime::Sunset as usize].is_nan());
+
+    assert_eq!(classify_period(&scheme, 30.0, &table), Period::Daytime);
+    assert_eq!(classify_period(&scheme, 3.0, &table), Period::Daytime);
+    assert_eq!(classify_period(&scheme, 0.0, &table), Period::Transition);
+    assert_eq!(classify_period(&scheme, -6.0, &table), Period::Night);
+    assert_eq!(classify_period(&scheme, -20.0, &table), Period::Night);
+}
+
+#[test]
+fn test_period_and_progress_agree_at_thresholds() {
+    use redshift_rebooted::types::{Period, TransitionScheme};
+
+    let table = solar_table_fill(JUNE_SOLSTICE_MIDNIGHT, 40.0, 0.0);
+    let scheme = TransitionScheme::default();
+
+    // Exactly at high: day, fully
+    assert_eq!(classify_period(&scheme, scheme.high, &table), Period::Daytime);
+    assert_eq!(transition_progress_from_elevation(&scheme, scheme.high), 1.0);
+
+    // Exactly at low: night, not started
+    assert_eq!(classify_period(&scheme, scheme.low, &table), Period::Night);
+    assert_eq!(transition_progress_from_elevation(&scheme, scheme.low), 0.0);
+
+    // Just inside either threshold the transition is under way
+    for elevation in [scheme.high - 1e-9, scheme.low + 1e-9] {
+        assert_eq!(classify_period(&scheme, elevation, &table), Period::Transition);
+        let progress = transition_progress_from_elevation(&scheme, elevation);
+        assert!(progress > 0.0 && progress < 1.0, "Progress at {} was {}", elevation, progress);
+    }
+
+    // With high == low the shared threshold is day
+    let degenerate = TransitionScheme {
+        high: -3.0,
+        low: -3.0,
+        ..TransitionScheme::default()
+    };
+    assert_eq!(classify_period(&degenerate, -3.0, &table), Period::Daytime);
+    assert_eq!(transition_progress_from_elevation(&degenerate, -3.0), 1.0);
+    assert_eq!(classify_period(&degenerate, -3.1, &table), Period::Night);
+    assert_eq!(transition_progress_from_elevation(&degenerate, -3.1), 0.0);
+}
+
+/// Periods seen while the elevation oscillates around `high`
+fn periods_around_high(hysteresis: f64) -> Vec<redshift_rebooted::types::Period> {
+    use redshift_rebooted::types::Period;
+
+    let scheme = TransitionScheme::default();
+    let table = solar_table_fill(JUNE_SOLSTICE_MIDNIGHT, 40.0, 0.0);
+
+    let mut previous = Period::None;
+    let mut periods = Vec::new();
+    for step in 0..40 {
+        let wobble = if step % 2 == 0 { 0.2 } else { -0.2 };
+        let elevation = scheme.high + wobble;
+        let period = classify_period(&scheme, elevation, &table);
+        previous = apply_period_hysteresis(&scheme, previous, period, elevation, hysteresis);
+        periods.push(previous);
+    }
+    periods
+}
+
+#[test]
+fn test_period_hysteresis_prevents_flip_flopping() {
+    use redshift_rebooted::types::Period;
+
+    // Without hysteresis every wobble flips the period
+    let periods = periods_around_high(0.0);
+    let flips = periods.windows(2).filter(|w| w[0] != w[1]).count();
+    assert_eq!(flips, periods.len() - 1);
+
+    // With a margin wider than the wobble, day holds once reached
+    let periods = periods_around_high(0.5);
+    assert!(periods.iter().all(|&p| p == Period::Daytime), "{:?}", periods);
+}
+
+#[test]
+fn test_period_hysteresis_releases_past_margin() {
+    use redshift_rebooted::types::Period;
+
+    let scheme = TransitionScheme::default();
+    let hold = |previous, period, elevation| {
+        apply_period_hysteresis(&scheme, previous, period, elevation, 1.0)
+    };
+
+    // Day ends only once the sun is a full margin below `high`
+    assert_eq!(hold(Period::Daytime, Period::Transition, 2.5), Period::Daytime);
+    assert_eq!(hold(Period::Daytime, Period::Transition, 1.5), Period::Transition);
+
+    // Night likewise ends a margin above `low`
+    assert_eq!(hold(Period::Night, Period::Transition, -5.5), Period::Night);
+    assert_eq!(hold(Period::Night, Period::Transition, -4.5), Period::Transition);
+
+    // Entering a period is never delayed
+    assert_eq!(hold(Period::Transition, Period::Daytime, 3.0), Period::Daytime);
+    assert_eq!(hold(Period::None, Period::Transition, 2.5), Period::Transition);
+}
+
+#[test]
+fn test_transition_progress_from_elevation() {
+    let scheme = TransitionScheme::default();
+
+    assert_eq!(transition_progress_from_elevation(&scheme, -10.0), 0.0);
+    assert_eq!(transition_progress_from_elevation(&scheme, 10.0), 1.0);
+    let midpoint = (scheme.high + scheme.low) / 2.0;
+    assert!((transition_progress_from_elevation(&scheme, midpoint) - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_transition_progress_with_equal_elevations() {
+    // high == low would divide by zero; switch at the threshold instead
+    let scheme = TransitionScheme {
+        high: -3.0,
+        low: -3.0,
+        ..TransitionScheme::default()
+    };
+
+    for elevation in [-90.0, -3.1, -3.0, -2.9, 45.0] {
+        let progress = transition_progress_from_elevation(&scheme, elevation);
+        assert!(progress.is_finite(), "Progress at {} should be finite", elevation);
+        assert!((0.0..=1.0).contains(&progress));
+    }
+    assert_eq!(transition_progress_from_elevation(&scheme, -3.0), 1.0);
+    assert_eq!(transition_progress_from_elevation(&scheme, -3.1), 0.0);
+}
+
+#[test]
+fn test_transition_progress_with_reversed_elevations() {
+    let scheme = TransitionScheme {
+        high: -6.0,
+        low: 3.0,
+        ..TransitionScheme::default()
+    };
+
+    for elevation in [-10.0, -6.0, 0.0, 3.0, 10.0] {
+        let progress = transition_progress_from_elevation(&scheme, elevation);
+        assert!((0.0..=1.0).contains(&progress), "Progress at {} was {}", elevation, progress);
+    }
+}
+
+fn twilight_scheme() -> TransitionScheme {
+    TransitionSchemeBuilder::new()
+        .day_temp(6500)
+        .night_temp(2000)
+        .brightness(1.0, 0.4)
+        .twilight(TwilightStages {
+            civil: ColorSetting {
+                temperature: 4500,
+                brightness: 0.9,
+                gamma: [1.0, 1.0, 1.0],
+                contrast: 1.0,
+            },
+            nautical: ColorSetting {
+                temperature: 3000,
+                brightness: 0.6,
+                gamma: [1.0, 1.0, 1.0],
+                contrast: 1.0,
+            },
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_twilight_stages_at_boundaries() {
+    let scheme = twilight_scheme();
+    let stages = scheme.twilight.unwrap();
+
+    let civil_dusk = twilight_color_setting(&scheme, &stages, SOLAR_CIVIL_TWILIGHT_ELEV);
+    let nautical_dusk = twilight_color_setting(&scheme, &stages, SOLAR_NAUT_TWILIGHT_ELEV);
+
+    assert_eq!(civil_dusk.temperature, 4500);
+    assert_eq!(civil_dusk.brightness, 0.9);
+    assert_eq!(nautical_dusk.temperature, 3000);
+    assert_eq!(nautical_dusk.brightness, 0.6);
+    assert_ne!(civil_dusk, nautical_dusk);
+}
+
+#[test]
+fn test_twilight_stages_interpolate_between_breakpoints() {
+    let scheme = twilight_scheme();
+    let stages = scheme.twilight.unwrap();
+
+    // Halfway between nautical (-12) and civil (-6) twilight
+    let mid = twilight_color_setting(&scheme, &stages, -9.0);
+    assert_eq!(mid.temperature, 3750);
+    assert!((mid.brightness - 0.75).abs() < 1e-6);
+
+    // Night from astronomical twilight down, day from the high elevation up
+    assert_eq!(twilight_color_setting(&scheme, &stages, -30.0), scheme.night);
+    assert_eq!(twilight_color_setting(&scheme, &stages, SOLAR_ASTRO_TWILIGHT_ELEV), scheme.night);
+    assert_eq!(twilight_color_setting(&scheme, &stages, scheme.high), scheme.day);
+    assert_eq!(twilight_color_setting(&scheme, &stages, 45.0), scheme.day);
+}
+
+#[test]
+fn test_twilight_stages_decrease_monotonically_through_dusk() {
+    let scheme = twilight_scheme();
+    let stages = scheme.twilight.unwrap();
+
+    let mut previous = twilight_color_setting(&scheme, &stages, scheme.high);
+    let mut elevation = scheme.high;
+    while elevation > SOLAR_ASTRO_TWILIGHT_ELEV {
+        elevation -= 0.5;
+        let setting = twilight_color_setting(&scheme, &stages, elevation);
+        assert!(setting.temperature <= previous.temperature, "Temperature rose at {}", elevation);
+        assert!(setting.brightness <= previous.brightness, "Brightness rose at {}", elevation);
+        previous = setting;
+    }
+}
+
+/* Night at 3500K from -6°, deep night at 2000K from astronomical twilight */
+fn deep_night_scheme() -> TransitionScheme {
+    TransitionSchemeBuilder::new()
+        .night_temp(3500)
+        .deep_night(2000, SOLAR_ASTRO_TWILIGHT_ELEV)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_deep_night_interpolates_below_low_elevation() {
+    let scheme = deep_night_scheme();
+
+    let near = |elevation: f64, expected: i32| {
+        let temperature = deep_night_temperature(&scheme, elevation).unwrap();
+        assert!((temperature - expected).abs() <= 1, "{}K at {}°", temperature, elevation);
+    };
+
+    // Just past the low elevation the night barely moves
+    near(-7.0, 3375);
+    near(-15.0, 2375);
+    // Past astronomical twilight the deep night temperature is held
+    assert_eq!(deep_night_temperature(&scheme, -20.0), Some(2000));
+    assert_eq!(deep_night_temperature(&scheme, -60.0), Some(2000));
+
+    // Above the low elevation the regular transition applies
+    assert_eq!(deep_night_temperature(&scheme, scheme.low), None);
+    assert_eq!(deep_night_temperature(&scheme, 10.0), None);
+}
+
+#[test]
+fn test_deep_night_absent_keeps_two_point_night() {
+    let scheme = TransitionScheme::default();
+    for elevation in [-7.0, -15.0, -20.0] {
+        assert_eq!(deep_night_temperature(&scheme, elevation), None);
+    }
+}
+
+#[test]
+fn test_deep_night_custom_elevation() {
+    let scheme = TransitionSchemeBuilder::new()
+        .night_temp(3500)
+        .deep_night(2500, -10.0)
+        .build()
+        .unwrap();
+
+    assert_eq!(deep_night_temperature(&scheme, -8.0), Some(3000));
+    assert_eq!(deep_night_temperature(&scheme, -15.0), Some(2500));
+}
+
+#[test]
+fn test_events_today_polar_summer_has_no_sunrise_or_sunset() {
+    let events = events_today(JUNE_SOLSTICE_MIDNIGHT, 80.0, 0.0);
+    assert_eq!(events.len(), SolarTime::ALL.len());
+
+    let time_of = |wanted: SolarTime| events.iter().find(|(e, _)| *e == wanted).unwrap().1;
+    assert_eq!(time_of(SolarTime::Sunrise), None);
+    assert_eq!(time_of(SolarTime::Sunset), None);
+    assert_eq!(time_of(SolarTime::AstroDusk), None);
+    assert!(time_of(SolarTime::Noon).is_some(), "Noon always occurs");
+    assert!(time_of(SolarTime::Midnight).is_some(), "Midnight always occurs");
+}
+
+#[test]
+fn test_events_today_equator_has_every_event() {
+    let events = events_today(JUNE_SOLSTICE_MIDNIGHT, 0.0, 0.0);
+
+    let order: Vec<SolarTime> = events.iter().map(|(e, _)| *e).collect();
+    assert_eq!(order, SolarTime::ALL.to_vec());
+    for (event, time) in &events {
+        assert!(time.is_some(), "{} should occur at the equator", event.name());
+    }
+
+    // The events of the day follow each other in order
+    let time_of = |wanted: SolarTime| events.iter().find(|(e, _)| *e == wanted).unwrap().1.unwrap();
+    assert!(time_of(SolarTime::AstroDawn) < time_of(SolarTime::Sunrise));
+    assert!(time_of(SolarTime::Sunrise) < time_of(SolarTime::Noon));
+    assert!(time_of(SolarTime::Noon) < time_of(SolarTime::Sunset));
+    assert!(time_of(SolarTime::Sunset) < time_of(SolarTime::AstroDusk));
+}
+
+#[test]
+fn test_solar_time_names() {
+    assert_eq!(SolarTime::Sunrise.name(), "Sunrise");
+    assert_eq!(SolarTime::NautDusk.name(), "Nautical dusk");
+
+    let mut names: Vec<&str> = SolarTime::ALL.iter().map(|e| e.name()).collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), SolarTime::ALL.len(), "Names should be distinct");
+}
+
+#[test]
+fn test_format_time_until() {
+    assert_eq!(format_time_until(2.0 * 3600.0 + 13.0 * 60.0), "2h13m");
+    assert_eq!(format_time_until(45.0 * 60.0), "45m");
+    assert_eq!(format_time_until(3600.0 + 5.0 * 60.0), "1h05m");
+    assert_eq!(format_time_until(3600.0), "1h00m");
+    assert_eq!(format_time_until(20.0), "0m");
+    assert_eq!(format_time_until(-90.0), "0m");
+}
+
+#[test]
+fn test_next_sun_transition_follows_day() {
+    let events = events_today(JUNE_SOLSTICE_MIDNIGHT, 0.0, 0.0);
+    let time_of = |wanted: SolarTime| events.iter().find(|(e, _)| *e == wanted).unwrap().1.unwrap();
+    let sunrise = time_of(SolarTime::Sunrise);
+    let sunset = time_of(SolarTime::Sunset);
+
+    assert_eq!(
+        next_sun_transition(JUNE_SOLSTICE_MIDNIGHT, 0.0, 0.0),
+        Some((SolarTime::Sunrise, sunrise))
+    );
+    assert_eq!(
+        next_sun_transition(sunrise + 60.0, 0.0, 0.0),
+        Some((SolarTime::Sunset, sunset))
+    );
+
+    // After sunset the next event is tomorrow's sunrise
+    let (event, time) = next_sun_transition(sunset + 60.0, 0.0, 0.0).unwrap();
+    assert_eq!(event, SolarTime::Sunrise);
+    assert!(time > sunset && time - sunrise > 23.0 * 3600.0);
+}
+
+#[test]
+fn test_next_sun_transition_none_during_midnight_sun() {
+    assert_eq!(next_sun_transition(JUNE_SOLSTICE_MIDNIGHT, 80.0, 0.0), None);
+}
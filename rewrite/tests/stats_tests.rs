@@ -0,0 +1,26 @@
+/// Tests for the continual mode diagnostic counters
+
+use redshift_rebooted::stats::*;
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_stats_default_is_zero() {
+    let stats = ContinualStats::default();
+    assert_eq!(stats.fades_started, 0);
+    assert_eq!(stats.applies, 0);
+    assert_eq!(stats.rejected, 0);
+}
+
+#[test]
+fn test_stats_logger_respects_interval() {
+    let start = Instant::now();
+    let mut logger = StatsLogger::new(Duration::from_secs(60));
+    let stats = ContinualStats::default();
+
+    assert!(!logger.maybe_log(&stats, start + Duration::from_secs(30)));
+    assert!(logger.maybe_log(&stats, start + Duration::from_secs(61)));
+
+    // Interval restarts from the last log
+    assert!(!logger.maybe_log(&stats, start + Duration::from_secs(90)));
+    assert!(logger.maybe_log(&stats, start + Duration::from_secs(125)));
+}
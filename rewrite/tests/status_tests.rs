@@ -0,0 +1,121 @@
+/* Tests for steady status samples in continual mode */
+
+use redshift_rebooted::reapply::ReapplyTimer;
+use redshift_rebooted::status::wait_with_status;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+/* Loop delays from continual mode: while stable, and while fading */
+const STABLE_DELAY: Duration = Duration::from_millis(5000);
+const FADE_DELAY: Duration = Duration::from_millis(100);
+
+/* Run `updates` loop iterations `delay` apart on a fake clock, returning
+   the offsets from `start` at which status samples were emitted */
+fn sample_times(interval: Duration, delay: Duration, updates: u32) -> Vec<Duration> {
+    let start = Instant::now();
+    let clock = Cell::new(start);
+    let samples = RefCell::new(Vec::new());
+    let mut timer = ReapplyTimer::new(interval, start);
+
+    for _ in 0..updates {
+        let wake_at = clock.get() + delay;
+        wait_with_status(
+            Some(&mut timer),
+            wake_at,
+            || clock.get(),
+            |step| {
+                clock.set(clock.get() + step);
+                true
+            },
+            || samples.borrow_mut().push(clock.get() - start),
+        );
+        assert_eq!(clock.get(), wake_at, "Waits end exactly at the next update");
+    }
+    samples.into_inner()
+}
+
+#[test]
+fn test_steady_samples_during_stable_period() {
+    // Two stable updates 5s apart still give a sample every second
+    let samples = sample_times(Duration::from_secs(1), STABLE_DELAY, 2);
+    let expected: Vec<Duration> = (1..=10).map(Duration::from_secs).collect();
+    assert_eq!(samples, expected);
+}
+
+#[test]
+fn test_sample_cadence_ignores_fade_steps() {
+    // 100ms fade steps do not speed up or slow down the samples
+    let samples = sample_times(Duration::from_secs(1), FADE_DELAY, 30);
+    let expected: Vec<Duration> = (1..=3).map(Duration::from_secs).collect();
+    assert_eq!(samples, expected);
+}
+
+#[test]
+fn test_wait_without_timer_sleeps_once() {
+    let start = Instant::now();
+    let clock = Cell::new(start);
+    let sleeps = Cell::new(0);
+
+    wait_with_status(
+        None,
+        start + STABLE_DELAY,
+        || clock.get(),
+        |step| {
+            sleeps.set(sleeps.get() + 1);
+            clock.set(clock.get() + step);
+            true
+        },
+        || panic!("No samples without a timer"),
+    );
+    assert_eq!(sleeps.get(), 1);
+    assert_eq!(clock.get(), start + STABLE_DELAY);
+}
+
+#[test]
+fn test_wait_ends_when_sleep_is_cut_short() {
+    let start = Instant::now();
+    let clock = Cell::new(start);
+    let mut timer = ReapplyTimer::new(Duration::from_secs(1), start);
+
+    // A signal during the first second ends the wait without a sample
+    wait_with_status(
+        Some(&mut timer),
+        start + STABLE_DELAY,
+        || clock.get(),
+        |_| {
+            clock.set(clock.get() + Duration::from_millis(300));
+            false
+        },
+        || panic!("No sample is due yet"),
+    );
+    assert_eq!(clock.get(), start + Duration::from_millis(300));
+}
+
+#[test]
+fn test_samples_are_printed_without_verbose() {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "0:0", "-m", "dummy", "--status-interval", "100", "--log-format", "kv"])
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("XDG_RUNTIME_DIR", dir.path())
+        .env("HOME", dir.path())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to execute redshift");
+
+    let sample = BufReader::new(child.stdout.take().unwrap())
+        .lines()
+        .map_while(Result::ok)
+        .find(|line| line.starts_with("event=status"));
+    unsafe {
+        libc::kill(child.id() as i32, libc::SIGTERM);
+    }
+    child.wait().unwrap();
+
+    let sample = sample.expect("No status sample printed");
+    assert!(sample.contains("enabled=true"), "{}", sample);
+}
@@ -0,0 +1,90 @@
+/* Tests for live control from standard input */
+
+use redshift_rebooted::stdin_control::*;
+use redshift_rebooted::types::{resolve_temp, TemperatureBounds};
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use wait_timeout::ChildExt;
+
+/* Parse with the hard temperature limits */
+fn parse(line: &str) -> Result<StdinCommand, String> {
+    StdinCommand::parse(line, &TemperatureBounds::default())
+}
+
+#[test]
+fn test_parse_temperature_and_brightness() {
+    let command = parse("4200 0.8").unwrap();
+    assert_eq!(command.temperature, 4200);
+    assert_eq!(command.brightness, 0.8);
+
+    let setting = command.setting();
+    assert_eq!(setting.temperature, 4200);
+    assert_eq!(setting.brightness, 0.8);
+    assert_eq!(setting.gamma, [1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn test_parse_temperature_only_and_presets() {
+    assert_eq!(parse("  3000 ").unwrap().brightness, 1.0);
+    assert_eq!(parse("candle\t0.9").unwrap().temperature, resolve_temp("candle").unwrap());
+}
+
+#[test]
+fn test_parse_rejects_bad_lines() {
+    for line in ["", "4200 0.8 1", "hot", "500", "4200 dim", "4200 1.5", "4200 0.05"] {
+        assert!(parse(line).is_err(), "{:?} should be rejected", line);
+    }
+}
+
+#[test]
+fn test_reader_skips_invalid_lines_and_closes_at_eof() {
+    let commands = spawn_reader(
+        Cursor::new("4200 0.8\n\nbogus\n3000\n"),
+        TemperatureBounds::default(),
+    );
+
+    let received: Vec<StdinCommand> = commands.iter().collect();
+    assert_eq!(
+        received,
+        vec![
+            StdinCommand { temperature: 4200, brightness: 0.8 },
+            StdinCommand { temperature: 3000, brightness: 1.0 },
+        ]
+    );
+}
+
+#[test]
+fn test_stdin_control_applies_piped_values() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-m", "dummy", "--stdin-control"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start redshift");
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "4200 0.8").unwrap();
+    thread::sleep(Duration::from_secs(1));
+    writeln!(stdin, "3000").unwrap();
+    drop(stdin);
+
+    let status = child
+        .wait_timeout(Duration::from_secs(5))
+        .unwrap()
+        .expect("Should exit at end of input");
+    assert!(status.success());
+
+    let mut stdout = String::new();
+    std::io::Read::read_to_string(&mut child.stdout.take().unwrap(), &mut stdout).unwrap();
+    let temperatures: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("Temperature: "))
+        .collect();
+
+    assert!(temperatures.contains(&"4200"), "First value should be reached: {:?}", temperatures);
+    let n = temperatures.len();
+    assert_eq!(&temperatures[n - 2..], ["3000", "6500"], "Last value, then restored at exit");
+}
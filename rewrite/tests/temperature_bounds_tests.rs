@@ -0,0 +1,84 @@
+/// Tests for narrowing the allowed temperatures with temp-min/temp-max
+/// Policies are checked end to end by running the binary
+
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const KIOSK_CONFIG: &str = "[redshift]\ntemp-min=3000\ntemp-max=6500\n";
+
+/* One-shot with the dummy method and only `config` as the config file */
+fn run_one_shot(config: &str, args: &[&str]) -> Output {
+    let dir = TempDir::new().unwrap();
+    let config_dir = dir.path().join("redshift");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(config_dir.join("redshift.conf"), config).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "0:0", "-m", "dummy", "-o"])
+        .args(args)
+        .env_remove("REDSHIFT_METHOD")
+        .env_remove("REDSHIFT_TEMP_DAY")
+        .env_remove("REDSHIFT_TEMP_NIGHT")
+        .env("XDG_CONFIG_HOME", dir.path())
+        .env("HOME", dir.path())
+        .output()
+        .expect("Failed to execute redshift")
+}
+
+fn dummy_temperatures(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("Temperature: "))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_day_temp_inside_band_is_applied() {
+    let output = run_one_shot(KIOSK_CONFIG, &["-t", "5000", "--temp-night", "5000"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(dummy_temperatures(&output), ["5000"]);
+}
+
+#[test]
+fn test_day_temp_outside_band_is_rejected() {
+    let output = run_one_shot(KIOSK_CONFIG, &["-t", "7000", "--temp-night", "4000"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "7000K is above temp-max");
+    assert!(stderr.contains("between 3000 and 6500"), "Unexpected error: {}", stderr);
+    assert!(dummy_temperatures(&output).is_empty());
+
+    // Also from the config file itself
+    let config = format!("{}temp-day=7000\n", KIOSK_CONFIG);
+    let output = run_one_shot(&config, &[]);
+    assert!(!output.status.success(), "temp-day=7000 is above temp-max");
+}
+
+#[test]
+fn test_day_temp_outside_band_is_clamped() {
+    let output = run_one_shot(
+        KIOSK_CONFIG,
+        &["-t", "7000", "--temp-night", "7000", "--clamp-invalid"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(dummy_temperatures(&output), ["6500"]);
+    assert!(stderr.contains("Day temperature 7000K clamped to 6500K"), "Missing warning: {}", stderr);
+
+    let output = run_one_shot(
+        KIOSK_CONFIG,
+        &["-t", "2000", "--temp-night", "2000", "--clamp-invalid"],
+    );
+    assert_eq!(dummy_temperatures(&output), ["3000"]);
+}
+
+#[test]
+fn test_bounds_must_include_neutral() {
+    let output = run_one_shot("[redshift]\ntemp-max=5000\n", &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "A band without 6500K cannot turn the adjustment off");
+    assert!(stderr.contains("must include the neutral 6500K"), "Unexpected error: {}", stderr);
+}
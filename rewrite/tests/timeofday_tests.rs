@@ -0,0 +1,188 @@
+/// Tests for wall-clock time of day used by dawn-time/dusk-time transitions
+
+use chrono::{TimeZone, Weekday};
+use redshift_rebooted::timeofday::*;
+use redshift_rebooted::types::{Period, TimeRange, TransitionScheme};
+
+fn time_scheme(dawn: (i32, i32), dusk: (i32, i32)) -> TransitionScheme {
+    TransitionScheme {
+        use_time: true,
+        dawn: TimeRange { start: dawn.0, end: dawn.1 },
+        dusk: TimeRange { start: dusk.0, end: dusk.1 },
+        ..TransitionScheme::default()
+    }
+}
+
+fn hm(hours: i32, minutes: i32) -> i32 {
+    hours * 3600 + minutes * 60
+}
+
+#[test]
+fn test_seconds_since_midnight_utc() {
+    let time = chrono::Utc.with_ymd_and_hms(2024, 3, 10, 6, 30, 15).unwrap();
+    assert_eq!(seconds_since_midnight(&time), hm(6, 30) + 15);
+}
+
+#[test]
+fn test_period_from_time() {
+    let scheme = time_scheme((hm(6, 0), hm(7, 45)), (hm(18, 35), hm(20, 15)));
+
+    assert_eq!(period_from_time(&scheme, hm(3, 0)), Period::Night);
+    assert_eq!(period_from_time(&scheme, hm(6, 0)), Period::Transition);
+    assert_eq!(period_from_time(&scheme, hm(7, 45)), Period::Daytime);
+    assert_eq!(period_from_time(&scheme, hm(12, 0)), Period::Daytime);
+    assert_eq!(period_from_time(&scheme, hm(19, 0)), Period::Transition);
+    assert_eq!(period_from_time(&scheme, hm(20, 15)), Period::Night);
+}
+
+#[test]
+fn test_transition_progress_from_time() {
+    let scheme = time_scheme((hm(6, 0), hm(8, 0)), (hm(18, 0), hm(20, 0)));
+
+    assert_eq!(transition_progress_from_time(&scheme, hm(5, 0)), 0.0);
+    assert_eq!(transition_progress_from_time(&scheme, hm(7, 0)), 0.5);
+    assert_eq!(transition_progress_from_time(&scheme, hm(12, 0)), 1.0);
+    assert_eq!(transition_progress_from_time(&scheme, hm(19, 0)), 0.5);
+    assert_eq!(transition_progress_from_time(&scheme, hm(21, 0)), 0.0);
+}
+
+#[test]
+fn test_spring_forward_skips_hour_without_bad_window() {
+    // America/New_York springs forward from 02:00 EST to 03:00 EDT on
+    // 2024-03-10, so a 02:30 dawn start never shows on the wall clock.
+    let est = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+    let edt = chrono::FixedOffset::west_opt(4 * 3600).unwrap();
+    let scheme = time_scheme((hm(2, 30), hm(4, 0)), (hm(18, 0), hm(20, 0)));
+
+    // Just before the change (01:59 EST) and just after (03:00 EDT)
+    let before = est.with_ymd_and_hms(2024, 3, 10, 1, 59, 0).unwrap();
+    let after = edt.with_ymd_and_hms(2024, 3, 10, 3, 0, 0).unwrap();
+    assert_eq!(after.timestamp() - before.timestamp(), 60);
+
+    let before_offset = seconds_since_midnight(&before);
+    let after_offset = seconds_since_midnight(&after);
+    assert_eq!(before_offset, hm(1, 59));
+    assert_eq!(after_offset, hm(3, 0));
+
+    // Dawn simply starts partway through instead of going negative
+    assert_eq!(transition_progress_from_time(&scheme, before_offset), 0.0);
+    let progress = transition_progress_from_time(&scheme, after_offset);
+    assert!((progress - 1.0 / 3.0).abs() < 1e-9, "got {}", progress);
+}
+
+#[test]
+fn test_local_time_spring_forward_is_monotonic() {
+    // Use the system timezone database for a zone with daylight saving
+    std::env::set_var("TZ", "America/New_York");
+
+    let scheme = time_scheme((hm(2, 30), hm(4, 0)), (hm(18, 0), hm(20, 0)));
+
+    // 2024-03-10 05:00 UTC is 00:00 EST; step a minute at a time to 06:00 EDT
+    let start = chrono::Utc.with_ymd_and_hms(2024, 3, 10, 5, 0, 0).unwrap().timestamp();
+    let mut prev_offset = -1;
+    let mut prev_progress = 0.0;
+    let mut saw_skipped_hour = false;
+
+    for minute in 0..(5 * 60) {
+        let timestamp = (start + minute * 60) as f64;
+        let offset = local_seconds_since_midnight(timestamp);
+        let progress = transition_progress_from_time(&scheme, offset);
+
+        assert!(offset > prev_offset, "Wall clock must not repeat on spring-forward");
+        assert!(
+            (0.0..=1.0).contains(&progress),
+            "Progress {} out of range at offset {}",
+            progress,
+            offset
+        );
+        assert!(progress >= prev_progress, "Dawn progress must not go backwards");
+
+        // No wall-clock time in the skipped hour is ever produced
+        assert!(!(hm(2, 0)..hm(3, 0)).contains(&offset));
+        if offset == hm(3, 0) {
+            saw_skipped_hour = true;
+        }
+
+        prev_offset = offset;
+        prev_progress = progress;
+    }
+
+    assert!(saw_skipped_hour, "Clock should jump from 01:59 to 03:00");
+}
+
+fn keyframes() -> Vec<(i32, i32)> {
+    vec![(hm(7, 0), 7000), (hm(12, 0), 6500), (hm(20, 0), 3500), (hm(23, 0), 2500)]
+}
+
+#[test]
+fn test_keyframes_exact_times() {
+    let keyframes = keyframes();
+    for &(time, temp) in &keyframes {
+        assert_eq!(temperature_from_keyframes(&keyframes, time), Some(temp));
+    }
+}
+
+#[test]
+fn test_keyframes_interpolate_between_neighbours() {
+    let keyframes = keyframes();
+
+    // Halfway from 12:00 (6500K) to 20:00 (3500K)
+    assert_eq!(temperature_from_keyframes(&keyframes, hm(16, 0)), Some(5000));
+    // A quarter of the way from 07:00 (7000K) to 12:00 (6500K)
+    assert_eq!(temperature_from_keyframes(&keyframes, hm(8, 15)), Some(6875));
+}
+
+#[test]
+fn test_keyframes_wrap_at_midnight() {
+    let keyframes = keyframes();
+
+    // 23:00 (2500K) to 07:00 (7000K) spans eight hours across midnight
+    assert_eq!(temperature_from_keyframes(&keyframes, hm(23, 59) + 59), Some(3062));
+    assert_eq!(temperature_from_keyframes(&keyframes, 0), Some(3063));
+    assert_eq!(temperature_from_keyframes(&keyframes, hm(3, 0)), Some(4750));
+    assert_eq!(temperature_from_keyframes(&keyframes, hm(6, 59)), Some(6991));
+}
+
+#[test]
+fn test_keyframes_single_and_empty() {
+    assert_eq!(temperature_from_keyframes(&[], hm(12, 0)), None);
+
+    let single = [(hm(18, 0), 4000)];
+    for time in [0, hm(6, 0), hm(18, 0), hm(23, 59)] {
+        assert_eq!(temperature_from_keyframes(&single, time), Some(4000));
+    }
+}
+
+#[test]
+fn test_parse_active_days() {
+    let weekdays = ActiveDays::parse("mon,tue,wed,thu,fri").unwrap();
+    for day in [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri] {
+        assert!(weekdays.contains(day), "{} should be active", day);
+    }
+    assert!(!weekdays.contains(Weekday::Sat));
+    assert!(!weekdays.contains(Weekday::Sun));
+
+    let weekend = ActiveDays::parse(" Saturday , SUN ").unwrap();
+    assert!(weekend.contains(Weekday::Sat) && weekend.contains(Weekday::Sun));
+    assert!(!weekend.contains(Weekday::Mon));
+}
+
+#[test]
+fn test_parse_active_days_invalid() {
+    for value in ["", "mon,", "mon,funday", "1,2"] {
+        assert!(ActiveDays::parse(value).is_err(), "{:?} should be rejected", value);
+    }
+}
+
+#[test]
+fn test_active_days_display_round_trip() {
+    let days = ActiveDays::parse("fri,mon,wednesday").unwrap();
+    assert_eq!(days.to_string(), "mon,wed,fri");
+    assert_eq!(ActiveDays::parse(&days.to_string()).unwrap(), days);
+}
+
+#[test]
+fn test_local_weekday() {
+    let time = chrono::Local.with_ymd_and_hms(2024, 3, 16, 12, 0, 0).unwrap();
+    assert_eq!(local_weekday(time.timestamp() as f64), Weekday::Sat);
+}
@@ -32,6 +32,7 @@ fn test_color_setting_custom() {
         temperature: 3500,
         gamma: [0.9, 1.0, 1.1],
         brightness: 0.8,
+        contrast: 1.0,
     };
     assert_eq!(setting.temperature, 3500);
     assert_eq!(setting.gamma, [0.9, 1.0, 1.1]);
@@ -90,3 +91,725 @@ fn test_program_mode_variants() {
     assert_eq!(modes[0], ProgramMode::Continual);
     assert_eq!(modes[1], ProgramMode::OneShot);
 }
+
+#[test]
+fn test_transition_scheme_builder_defaults() {
+    let scheme = TransitionSchemeBuilder::new().build().unwrap();
+    let defaults = TransitionScheme::default();
+    assert_eq!(scheme.high, defaults.high);
+    assert_eq!(scheme.low, defaults.low);
+    assert!(!scheme.use_time);
+    assert_eq!(scheme.day, defaults.day);
+    assert_eq!(scheme.night, defaults.night);
+}
+
+#[test]
+fn test_transition_scheme_builder_sets_fields() {
+    let scheme = TransitionSchemeBuilder::new()
+        .day_temp(5500)
+        .night_temp(3000)
+        .elevation_high(5.0)
+        .elevation_low(-3.0)
+        .brightness(1.0, 0.7)
+        .gamma([0.9, 1.0, 1.1], [0.8, 0.8, 0.8])
+        .build()
+        .unwrap();
+
+    assert_eq!(scheme.day.temperature, 5500);
+    assert_eq!(scheme.night.temperature, 3000);
+    assert_eq!(scheme.high, 5.0);
+    assert_eq!(scheme.low, -3.0);
+    assert_eq!(scheme.day.brightness, 1.0);
+    assert_eq!(scheme.night.brightness, 0.7);
+    assert_eq!(scheme.day.gamma, [0.9, 1.0, 1.1]);
+    assert_eq!(scheme.night.gamma, [0.8, 0.8, 0.8]);
+}
+
+#[test]
+fn test_transition_scheme_builder_time_ranges() {
+    let dawn = TimeRange { start: 21600, end: 25200 };
+    let dusk = TimeRange { start: 68400, end: 72000 };
+    let scheme = TransitionSchemeBuilder::new()
+        .dawn(dawn)
+        .dusk(dusk)
+        .build()
+        .unwrap();
+
+    assert!(scheme.use_time);
+    assert_eq!(scheme.dawn.start, 21600);
+    assert_eq!(scheme.dusk.end, 72000);
+}
+
+#[test]
+fn test_transition_scheme_builder_rejects_temperature() {
+    let err = TransitionSchemeBuilder::new().day_temp(500).build().unwrap_err();
+    assert!(err.contains("Temperature"), "Unexpected error: {}", err);
+
+    let err = TransitionSchemeBuilder::new().night_temp(30000).build().unwrap_err();
+    assert!(err.contains("Temperature"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_transition_scheme_builder_rejects_brightness() {
+    let err = TransitionSchemeBuilder::new()
+        .brightness(1.5, 0.8)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("Day brightness"), "Unexpected error: {}", err);
+
+    let err = TransitionSchemeBuilder::new()
+        .brightness(1.0, 0.05)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("Night brightness"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_transition_scheme_builder_overdrive_raises_brightness_limit() {
+    let scheme = TransitionSchemeBuilder::new()
+        .brightness(1.5, 0.8)
+        .allow_overdrive(true)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.day.brightness, 1.5);
+    assert_eq!(scheme.max_brightness(), MAX_OVERDRIVE_BRIGHTNESS);
+
+    let err = TransitionSchemeBuilder::new()
+        .brightness(MAX_OVERDRIVE_BRIGHTNESS + 0.1, 0.8)
+        .allow_overdrive(true)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("Day brightness"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_transition_scheme_builder_rejects_gamma() {
+    let err = TransitionSchemeBuilder::new()
+        .gamma([1.0, 0.0, 1.0], [1.0, 1.0, 1.0])
+        .build()
+        .unwrap_err();
+    assert!(err.contains("Day gamma"), "Unexpected error: {}", err);
+
+    let err = TransitionSchemeBuilder::new()
+        .gamma([1.0, 1.0, 1.0], [1.0, 1.0, 11.0])
+        .build()
+        .unwrap_err();
+    assert!(err.contains("Night gamma"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_transition_scheme_builder_accepts_bounds() {
+    let scheme = TransitionSchemeBuilder::new()
+        .day_temp(MAX_TEMP)
+        .night_temp(MIN_TEMP)
+        .brightness(MAX_BRIGHTNESS, MIN_BRIGHTNESS)
+        .gamma([MIN_GAMMA; 3], [MAX_GAMMA; 3])
+        .build();
+    assert!(scheme.is_ok());
+}
+
+#[test]
+fn test_transition_scheme_builder_rejects_inverted_elevation() {
+    let err = TransitionSchemeBuilder::new()
+        .elevation_high(-6.0)
+        .elevation_low(3.0)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("elevation"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_color_setting_validate_accepts_defaults() {
+    assert!(ColorSetting::default().validate().is_ok());
+    assert!(TransitionScheme::default().night.validate().is_ok());
+}
+
+#[test]
+fn test_color_setting_validate_rejects_nan() {
+    let nan_brightness = ColorSetting {
+        brightness: f32::NAN,
+        ..ColorSetting::default()
+    };
+    assert!(nan_brightness.validate().is_err());
+
+    let nan_gamma = ColorSetting {
+        gamma: [1.0, f32::NAN, 1.0],
+        ..ColorSetting::default()
+    };
+    assert!(nan_gamma.validate().is_err());
+
+    // A NaN temperature cast to i32 becomes 0, which is out of range
+    let nan_temperature = ColorSetting {
+        temperature: f64::NAN as i32,
+        ..ColorSetting::default()
+    };
+    assert!(nan_temperature.validate().is_err());
+}
+
+#[test]
+fn test_color_setting_validate_rejects_infinite_and_out_of_range() {
+    let infinite = ColorSetting {
+        gamma: [f32::INFINITY, 1.0, 1.0],
+        ..ColorSetting::default()
+    };
+    assert!(infinite.validate().is_err());
+
+    let too_hot = ColorSetting {
+        temperature: MAX_TEMP + 1,
+        ..ColorSetting::default()
+    };
+    assert!(too_hot.validate().is_err());
+
+    let too_dark = ColorSetting {
+        brightness: 0.0,
+        ..ColorSetting::default()
+    };
+    assert!(too_dark.validate().is_err());
+}
+
+#[test]
+fn test_resolve_temp_presets() {
+    assert_eq!(resolve_temp("candle").unwrap(), 1900);
+    assert_eq!(resolve_temp("incandescent").unwrap(), 2700);
+    assert_eq!(resolve_temp("halogen").unwrap(), 3400);
+    assert_eq!(resolve_temp("warm-white").unwrap(), 4000);
+    assert_eq!(resolve_temp("daylight").unwrap(), 5600);
+    assert_eq!(resolve_temp("neutral").unwrap(), 6500);
+    assert_eq!(resolve_temp("overcast").unwrap(), 7000);
+}
+
+#[test]
+fn test_resolve_temp_presets_are_in_range() {
+    for (name, temp) in NAMED_TEMPERATURES {
+        assert!((MIN_TEMP..=MAX_TEMP).contains(temp), "Preset {} is out of range", name);
+    }
+}
+
+#[test]
+fn test_resolve_temp_numbers_and_case() {
+    assert_eq!(resolve_temp("4500").unwrap(), 4500);
+    assert_eq!(resolve_temp(" 3200 ").unwrap(), 3200);
+    assert_eq!(resolve_temp("Candle").unwrap(), 1900);
+    assert_eq!(resolve_temp("WARM-WHITE").unwrap(), 4000);
+}
+
+#[test]
+fn test_resolve_temp_invalid_names() {
+    let err = resolve_temp("sunset").unwrap_err();
+    assert!(err.contains("sunset"), "Error should name the input: {}", err);
+    assert!(err.contains("candle"), "Error should list the presets: {}", err);
+
+    assert!(resolve_temp("").is_err());
+    assert!(resolve_temp("warm white").is_err());
+    assert!(resolve_temp("4000K").is_err());
+}
+
+#[test]
+fn test_transition_scheme_builder_rejects_equal_elevation() {
+    let err = TransitionSchemeBuilder::new()
+        .elevation_high(-6.0)
+        .elevation_low(-6.0)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("elevation"), "Unexpected error: {}", err);
+
+    // Closer together than the margin is still too narrow
+    assert!(TransitionSchemeBuilder::new()
+        .elevation_high(-5.95)
+        .elevation_low(-6.0)
+        .build()
+        .is_err());
+
+    assert!(TransitionSchemeBuilder::new()
+        .elevation_high(-5.5)
+        .elevation_low(-6.0)
+        .build()
+        .is_ok());
+}
+
+fn stage(temperature: i32, brightness: f32) -> ColorSetting {
+    ColorSetting {
+        temperature,
+        brightness,
+        gamma: [1.0, 1.0, 1.0],
+        contrast: 1.0,
+    }
+}
+
+#[test]
+fn test_builder_twilight_extends_to_astronomical_twilight() {
+    let scheme = TransitionSchemeBuilder::new()
+        .elevation_high(3.0)
+        .twilight(TwilightStages {
+            civil: stage(4500, 0.9),
+            nautical: stage(3000, 0.6),
+        })
+        .build()
+        .unwrap();
+
+    assert_eq!(scheme.high, 3.0);
+    assert_eq!(scheme.low, -18.0);
+    assert!(scheme.twilight.is_some());
+}
+
+#[test]
+fn test_builder_twilight_refuses_other_low_elevation() {
+    let stages = TwilightStages {
+        civil: stage(4500, 0.9),
+        nautical: stage(3000, 0.6),
+    };
+
+    // A low elevation that was asked for is not silently replaced
+    let err = TransitionSchemeBuilder::new()
+        .elevation_low(-4.0)
+        .twilight(stages)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("twilight stages"), "Unexpected error: {}", err);
+
+    let scheme = TransitionSchemeBuilder::new()
+        .elevation_low(-18.0)
+        .twilight(stages)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.low, -18.0);
+
+    // A scheme file cannot tell the default low elevation from an unset one
+    let scheme = TransitionScheme {
+        twilight: Some(stages),
+        ..TransitionScheme::default()
+    };
+    assert!(TransitionSchemeBuilder::from_scheme(scheme).build().is_ok());
+    let scheme = TransitionScheme {
+        low: -4.0,
+        twilight: Some(stages),
+        ..TransitionScheme::default()
+    };
+    assert!(TransitionSchemeBuilder::from_scheme(scheme).build().is_err());
+}
+
+#[test]
+fn test_builder_twilight_rejects_invalid_stages() {
+    let stages = TwilightStages {
+        civil: stage(4500, 0.9),
+        nautical: stage(500, 0.6),
+    };
+    let err = TransitionSchemeBuilder::new().twilight(stages).build().unwrap_err();
+    assert!(err.contains("Nautical"), "Unexpected error: {}", err);
+
+    // The day side must stay above civil twilight
+    let stages = TwilightStages {
+        civil: stage(4500, 0.9),
+        nautical: stage(3000, 0.6),
+    };
+    assert!(TransitionSchemeBuilder::new()
+        .elevation_high(-8.0)
+        .twilight(stages)
+        .build()
+        .is_err());
+
+    // Wall-clock transitions have no solar elevation to follow
+    assert!(TransitionSchemeBuilder::new()
+        .dawn(TimeRange { start: 21600, end: 25200 })
+        .twilight(stages)
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_default_scheme_has_no_twilight_stages() {
+    assert!(TransitionScheme::default().twilight.is_none());
+    assert!(TransitionSchemeBuilder::new().build().unwrap().twilight.is_none());
+}
+
+#[test]
+fn test_builder_keyframes_sorted_and_validated() {
+    let scheme = TransitionSchemeBuilder::new()
+        .keyframes(vec![(72000, 3500), (25200, 7000)])
+        .build()
+        .unwrap();
+    assert_eq!(scheme.keyframes, vec![(25200, 7000), (72000, 3500)]);
+
+    assert!(TransitionSchemeBuilder::new()
+        .keyframes(vec![(25200, 7000), (72000, 500)])
+        .build()
+        .is_err());
+
+    let err = TransitionSchemeBuilder::new()
+        .keyframes(vec![(25200, 7000), (25200, 6000)])
+        .build()
+        .unwrap_err();
+    assert!(err.contains("07:00"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_contrast_validation() {
+    let setting = ColorSetting {
+        contrast: 1.5,
+        ..ColorSetting::default()
+    };
+    assert!(setting.validate().is_ok());
+
+    for contrast in [0.4, 2.1, f32::NAN] {
+        let setting = ColorSetting {
+            contrast,
+            ..ColorSetting::default()
+        };
+        assert!(setting.validate().is_err(), "Contrast {} should be rejected", contrast);
+    }
+
+    assert_eq!(TransitionSchemeBuilder::new().contrast(1.2).build().unwrap().night.contrast, 1.2);
+    assert!(TransitionSchemeBuilder::new().contrast(3.0).build().is_err());
+}
+
+fn assert_invalid(scheme: TransitionScheme, expected: &str) {
+    let err = scheme.validate().unwrap_err();
+    assert!(err.contains(expected), "Expected '{}', got: {}", expected, err);
+}
+
+#[test]
+fn test_scheme_validate_accepts_default() {
+    assert!(TransitionScheme::default().validate().is_ok());
+}
+
+#[test]
+fn test_scheme_validate_rejects_temperature() {
+    let defaults = TransitionScheme::default();
+    let scheme = TransitionScheme {
+        night: ColorSetting {
+            temperature: MIN_TEMP - 1,
+            ..defaults.night
+        },
+        ..defaults
+    };
+    assert_invalid(scheme, "Temperature");
+
+    let scheme = TransitionScheme {
+        keyframes: vec![(25200, MAX_TEMP + 1)],
+        ..TransitionScheme::default()
+    };
+    assert_invalid(scheme, "Temperature");
+}
+
+#[test]
+fn test_scheme_validate_rejects_brightness_per_period() {
+    let mut scheme = TransitionScheme::default();
+    scheme.day.brightness = MAX_BRIGHTNESS + 0.1;
+    assert_invalid(scheme, "Day brightness");
+
+    let mut scheme = TransitionScheme::default();
+    scheme.night.brightness = f32::NAN;
+    assert_invalid(scheme, "Night brightness");
+}
+
+#[test]
+fn test_scheme_validate_rejects_gamma_per_channel() {
+    for (channel, name) in CHANNEL_NAMES.iter().enumerate() {
+        let mut scheme = TransitionScheme::default();
+        scheme.night.gamma[channel] = MAX_GAMMA + 1.0;
+        assert_invalid(scheme, &format!("Night gamma must be between 0.1 and 10, but the {} channel is 11", name));
+
+        let mut scheme = TransitionScheme::default();
+        scheme.day.gamma[channel] = MIN_GAMMA / 2.0;
+        assert_invalid(scheme, &format!("the {} channel is 0.05", name));
+    }
+}
+
+#[test]
+fn test_builder_strict_gamma_error_names_channel() {
+    for (channel, name) in CHANNEL_NAMES.iter().enumerate() {
+        let mut day = [1.0; 3];
+        day[channel] = 15.0;
+        let err = TransitionSchemeBuilder::new()
+            .gamma(day, [1.0; 3])
+            .build()
+            .unwrap_err();
+        assert!(
+            err.contains(&format!("Day gamma must be between 0.1 and 10, but the {} channel is 15", name)),
+            "Unexpected error: {}",
+            err
+        );
+    }
+}
+
+#[test]
+fn test_builder_clamps_gamma_per_channel() {
+    for channel in 0..3 {
+        let mut day = [1.0; 3];
+        day[channel] = 15.0;
+        let mut night = [0.8; 3];
+        night[channel] = 0.0;
+        let scheme = TransitionSchemeBuilder::new()
+            .gamma(day, night)
+            .clamp_invalid(true)
+            .build()
+            .unwrap();
+
+        // Only the offending channel moves, to the nearest bound
+        let mut expected_day = [1.0; 3];
+        expected_day[channel] = MAX_GAMMA;
+        let mut expected_night = [0.8; 3];
+        expected_night[channel] = MIN_GAMMA;
+        assert_eq!(scheme.day.gamma, expected_day);
+        assert_eq!(scheme.night.gamma, expected_night);
+    }
+}
+
+#[test]
+fn test_clamp_ramp_values_reports_changes() {
+    let mut setting = ColorSetting {
+        brightness: 2.0,
+        gamma: [1.0, f32::NAN, 15.0],
+        contrast: 0.1,
+        ..ColorSetting::default()
+    };
+    let changes = setting.clamp_ramp_values(MAX_BRIGHTNESS);
+
+    assert_eq!(setting.brightness, MAX_BRIGHTNESS);
+    assert_eq!(setting.gamma, [1.0, 1.0, MAX_GAMMA]);
+    assert_eq!(setting.contrast, MIN_CONTRAST);
+    assert_eq!(changes.len(), 4, "{:?}", changes);
+    assert!(changes.contains(&"blue gamma 15 clamped to 10".to_string()), "{:?}", changes);
+    assert!(changes.iter().any(|change| change.starts_with("green gamma NaN")), "{:?}", changes);
+
+    // Valid values are left alone
+    let mut setting = ColorSetting::default();
+    assert!(setting.clamp_ramp_values(MAX_BRIGHTNESS).is_empty());
+    assert_eq!(setting, ColorSetting::default());
+}
+
+#[test]
+fn test_scheme_validate_rejects_contrast_per_period() {
+    let mut scheme = TransitionScheme::default();
+    scheme.day.contrast = MAX_CONTRAST + 0.5;
+    assert_invalid(scheme, "Day contrast");
+
+    let mut scheme = TransitionScheme::default();
+    scheme.night.contrast = MIN_CONTRAST - 0.1;
+    assert_invalid(scheme, "Night contrast");
+}
+
+#[test]
+fn test_scheme_validate_rejects_elevation_ordering() {
+    let scheme = TransitionScheme {
+        high: -6.0,
+        low: -6.0,
+        ..TransitionScheme::default()
+    };
+    assert_invalid(scheme, "elevation");
+}
+
+#[test]
+fn test_scheme_validate_rejects_duplicate_keyframes() {
+    let scheme = TransitionScheme {
+        keyframes: vec![(25200, 6500), (25200, 4000)],
+        ..TransitionScheme::default()
+    };
+    assert_invalid(scheme, "Duplicate schedule keyframe");
+}
+
+#[test]
+fn test_scheme_validate_rejects_twilight_stages() {
+    let stages = TwilightStages {
+        civil: stage(4500, 0.05),
+        nautical: stage(3000, 0.6),
+    };
+    let mut scheme = TransitionScheme {
+        low: -18.0,
+        twilight: Some(stages),
+        ..TransitionScheme::default()
+    };
+    assert_invalid(scheme.clone(), "Civil twilight stage");
+
+    scheme.twilight = Some(TwilightStages {
+        civil: stage(4500, 0.9),
+        nautical: stage(3000, 0.6),
+    });
+    assert!(scheme.validate().is_ok());
+
+    scheme.use_time = true;
+    assert_invalid(scheme.clone(), "dawn-time/dusk-time");
+
+    scheme.use_time = false;
+    scheme.high = -8.0;
+    assert_invalid(scheme, "civil twilight");
+}
+
+#[test]
+fn test_scheme_validate_rejects_deep_night() {
+    let scheme = TransitionSchemeBuilder::new().deep_night(2000, -18.0).build().unwrap();
+    assert_eq!(scheme.deep_night_temp, Some(2000));
+
+    let mut invalid = scheme.clone();
+    invalid.deep_night_temp = Some(MIN_TEMP - 1);
+    assert_invalid(invalid, "Temperature");
+
+    let mut invalid = scheme.clone();
+    invalid.deep_night_elevation = scheme.low;
+    assert_invalid(invalid, "Deep night elevation");
+
+    let err = TransitionSchemeBuilder::new()
+        .deep_night(2000, -18.0)
+        .dawn(TimeRange { start: 21600, end: 25200 })
+        .dusk(TimeRange { start: 72000, end: 75600 })
+        .build()
+        .unwrap_err();
+    assert!(err.contains("dawn-time/dusk-time"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_deep_night_with_twilight_stages() {
+    let stages = TwilightStages {
+        civil: stage(4500, 0.9),
+        nautical: stage(3000, 0.6),
+    };
+
+    // Without an elevation, deep night is reached below the final low
+    let scheme = TransitionSchemeBuilder::new()
+        .twilight(stages)
+        .deep_night_temp(2000)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.low, -18.0);
+    assert_eq!(scheme.deep_night_elevation, -24.0);
+
+    // Without twilight stages the default stays at astronomical twilight
+    let scheme = TransitionSchemeBuilder::new().deep_night_temp(2000).build().unwrap();
+    assert_eq!(scheme.deep_night_elevation, -18.0);
+
+    // An explicit elevation is kept, and still has to be below the low one
+    let scheme = TransitionSchemeBuilder::new()
+        .twilight(stages)
+        .deep_night(2000, -21.0)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.deep_night_elevation, -21.0);
+    let err = TransitionSchemeBuilder::new()
+        .twilight(stages)
+        .deep_night(2000, -18.0)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("Deep night elevation"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_builder_intensity_scales_deep_night() {
+    let scheme = TransitionSchemeBuilder::new()
+        .night_temp(3500)
+        .deep_night(2500, -18.0)
+        .intensity(50)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.deep_night_temp, Some(4500));
+}
+
+fn night_builder() -> TransitionSchemeBuilder {
+    TransitionSchemeBuilder::new()
+        .day_temp(6000)
+        .night_temp(3000)
+        .brightness(1.0, 0.7)
+        .gamma([1.0, 1.0, 1.0], [0.8, 0.9, 0.7])
+}
+
+#[test]
+fn test_builder_intensity_zero_gives_neutral_night() {
+    let scheme = night_builder()
+        .twilight(TwilightStages {
+            civil: stage(4500, 0.9),
+            nautical: stage(3000, 0.6),
+        })
+        .intensity(0)
+        .build()
+        .unwrap();
+
+    assert_eq!(scheme.night, ColorSetting::default());
+    let stages = scheme.twilight.unwrap();
+    assert_eq!(stages.civil, ColorSetting::default());
+    assert_eq!(stages.nautical, ColorSetting::default());
+    // The day setting is left alone
+    assert_eq!(scheme.day.temperature, 6000);
+}
+
+#[test]
+fn test_builder_full_intensity_keeps_configured_night() {
+    let configured = night_builder().build().unwrap();
+    let scheme = night_builder().intensity(100).build().unwrap();
+    assert_eq!(scheme.night, configured.night);
+
+    // Also exact when temperatures are interpolated in mireds
+    let scheme = night_builder()
+        .interpolate_in_mireds(true)
+        .intensity(100)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.night.temperature, 3000);
+}
+
+#[test]
+fn test_builder_intensity_scales_night_together() {
+    let scheme = night_builder().intensity(50).build().unwrap();
+    assert_eq!(scheme.night.temperature, 4750);
+    assert!((scheme.night.brightness - 0.85).abs() < 1e-6);
+    assert!((scheme.night.gamma[0] - 0.9).abs() < 1e-6);
+    assert!((scheme.night.gamma[1] - 0.95).abs() < 1e-6);
+    assert!((scheme.night.gamma[2] - 0.85).abs() < 1e-6);
+
+    assert!(night_builder().intensity(101).build().is_err());
+}
+
+#[test]
+fn test_temperature_bounds_narrow_hard_limits() {
+    assert_eq!(
+        TemperatureBounds::new(None, None),
+        Ok(TemperatureBounds::default())
+    );
+
+    let bounds = TemperatureBounds::new(Some(3000), Some(6500)).unwrap();
+    assert!(bounds.contains(3000) && bounds.contains(6500));
+    assert!(!bounds.contains(2999) && !bounds.contains(6501));
+    assert_eq!(bounds.check(7000), Err("Temperature 7000K is outside 3000-6500K".to_string()));
+
+    assert!(TemperatureBounds::new(Some(MIN_TEMP - 1), None).is_err());
+    assert!(TemperatureBounds::new(None, Some(MAX_TEMP + 1)).is_err());
+    assert!(TemperatureBounds::new(Some(7000), None).is_err(), "Neutral must stay allowed");
+    assert!(TemperatureBounds::new(None, Some(5000)).is_err(), "Neutral must stay allowed");
+}
+
+#[test]
+fn test_temperature_bounds_resolve_per_policy() {
+    let strict = TemperatureBounds::new(Some(3000), Some(6500)).unwrap();
+    assert_eq!(strict.resolve(4000), Ok(4000));
+    assert!(strict.resolve(7000).is_err());
+    assert!(strict.resolve(2000).is_err());
+
+    let clamping = strict.with_clamp(true);
+    assert_eq!(clamping.resolve(4000), Ok(4000));
+    assert_eq!(clamping.resolve(7000), Ok(6500));
+    assert_eq!(clamping.resolve(2000), Ok(3000));
+}
+
+#[test]
+fn test_scheme_carries_its_temperature_bounds() {
+    let kiosk = TemperatureBounds::new(Some(3000), Some(6500)).unwrap();
+
+    let err = TransitionSchemeBuilder::new()
+        .temperature_bounds(kiosk)
+        .night_temp(2500)
+        .build()
+        .unwrap_err();
+    assert!(err.contains("between 3000 and 6500"), "Unexpected error: {}", err);
+
+    // Another scheme, e.g. after a reload, brings its own bounds
+    let scheme = TransitionSchemeBuilder::new().night_temp(2500).build().unwrap();
+    assert_eq!(scheme.temperature_bounds, TemperatureBounds::default());
+
+    let scheme = TransitionSchemeBuilder::new()
+        .temperature_bounds(kiosk.with_clamp(true))
+        .clamp_invalid(true)
+        .night_temp(2500)
+        .build()
+        .unwrap();
+    assert_eq!(scheme.night.temperature, 3000);
+    assert!(scheme.night.validate_within(&scheme.temperature_bounds).is_ok());
+    assert!(ColorSetting::default().validate_within(&kiosk).is_ok());
+}
@@ -0,0 +1,119 @@
+/// Tests for exporting the adjustment as an ICC vcgt tag
+
+use redshift_rebooted::colorramp::RampOptions;
+use redshift_rebooted::types::ColorSetting;
+use redshift_rebooted::vcgt::*;
+use std::process::Command;
+
+fn be_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn be_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Decode a vcgt tag back into its channels, checking the header
+fn decode(data: &[u8]) -> Vec<Vec<u16>> {
+    assert_eq!(&data[0..4], VCGT_SIGNATURE);
+    assert_eq!(be_u32(data, 4), 0, "Reserved word");
+    assert_eq!(be_u32(data, 8), 0, "Table type");
+    let channels = be_u16(data, 12) as usize;
+    let entries = be_u16(data, 14) as usize;
+    assert_eq!(be_u16(data, 16), 2, "Entry size");
+    assert_eq!(data.len(), VCGT_HEADER_LEN + channels * entries * 2);
+
+    (0..channels)
+        .map(|c| {
+            (0..entries)
+                .map(|i| be_u16(data, VCGT_HEADER_LEN + (c * entries + i) * 2))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_encode_round_trips_ramps() {
+    let setting = ColorSetting {
+        temperature: 3000,
+        ..ColorSetting::default()
+    };
+    let ramps = vcgt_ramps(&setting, &RampOptions::default());
+    let decoded = decode(&encode_vcgt(&ramps).unwrap());
+
+    assert_eq!(decoded.len(), 3);
+    assert_eq!(decoded, ramps.to_vec());
+}
+
+#[test]
+fn test_neutral_curves_span_full_range() {
+    let ramps = vcgt_ramps(&ColorSetting::default(), &RampOptions::default());
+
+    for ramp in &ramps {
+        assert_eq!(ramp.len(), VCGT_SIZE);
+        assert_eq!(ramp[0], 0);
+        assert_eq!(ramp[VCGT_SIZE - 1], 65535);
+        assert_eq!(ramp[VCGT_SIZE / 2], 32896, "128 / 255 of full scale");
+    }
+}
+
+#[test]
+fn test_encode_rejects_mismatched_channels() {
+    assert!(encode_vcgt(&[vec![0; 256], vec![0; 256], vec![0; 128]]).is_err());
+}
+
+#[test]
+fn test_export_vcgt_flag_writes_curves() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("redshift.vcgt");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "0:0", "-t", "4000", "--temp-night", "4000", "--export-vcgt"])
+        .arg(&path)
+        .output()
+        .expect("Failed to execute redshift");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let channels = decode(&std::fs::read(&path).unwrap());
+    assert_eq!(channels.len(), 3);
+    for channel in &channels {
+        assert_eq!(channel.len(), VCGT_SIZE);
+        assert_eq!(channel[0], 0, "Black stays black");
+    }
+
+    // At 4000K red keeps full scale while blue is cut back
+    let [r, g, b] = [&channels[0], &channels[1], &channels[2]].map(|c| c[VCGT_SIZE - 1]);
+    assert_eq!(r, 65535);
+    assert!(b < g && g < r);
+}
+
+#[test]
+fn test_ramp_options_shape_the_curves() {
+    let setting = ColorSetting { temperature: 3000, ..ColorSetting::default() };
+    let options = RampOptions { blue_cutoff: Some(3500), ..RampOptions::default() };
+
+    let plain = vcgt_ramps(&setting, &RampOptions::default());
+    let cut = vcgt_ramps(&setting, &options);
+
+    assert_eq!(cut[0], plain[0]);
+    assert!(plain[2].iter().any(|&v| v > 0));
+    assert!(cut[2].iter().all(|&v| v == 0), "Blue is removed below the cutoff");
+}
+
+#[test]
+fn test_export_vcgt_flag_applies_blue_cutoff() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("redshift.vcgt");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_redshift-rebooted"))
+        .args(["-l", "0:0", "-t", "3000", "--temp-night", "3000"])
+        .args(["--blue-cutoff-temp", "3500", "--export-vcgt"])
+        .arg(&path)
+        .output()
+        .expect("Failed to execute redshift");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let channels = decode(&std::fs::read(&path).unwrap());
+    assert!(channels[0].iter().any(|&v| v > 0));
+    assert!(channels[2].iter().all(|&v| v == 0), "Blue is removed below the cutoff");
+}
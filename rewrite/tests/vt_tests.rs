@@ -0,0 +1,83 @@
+/// Tests for VT switch detection
+/// Uses a scripted VT source so no real VT switching is needed
+
+use redshift_rebooted::vt::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// VT source returning whatever VT the test sets
+struct FakeVtSource {
+    active: Rc<Cell<Option<u32>>>,
+}
+
+impl VtSource for FakeVtSource {
+    fn active_vt(&mut self) -> Option<u32> {
+        self.active.get()
+    }
+}
+
+fn fake_watcher(session_vt: u32) -> (VtWatcher, Rc<Cell<Option<u32>>>) {
+    let active = Rc::new(Cell::new(Some(session_vt)));
+    let source = FakeVtSource {
+        active: Rc::clone(&active),
+    };
+    (VtWatcher::new(Box::new(source), session_vt), active)
+}
+
+#[test]
+fn test_parse_active_vt() {
+    assert_eq!(parse_active_vt("tty2\n"), Some(2));
+    assert_eq!(parse_active_vt("tty12"), Some(12));
+    assert_eq!(parse_active_vt(""), None);
+    assert_eq!(parse_active_vt("console"), None);
+}
+
+#[test]
+fn test_no_reapply_while_staying_on_session_vt() {
+    let (mut watcher, _) = fake_watcher(2);
+
+    assert!(!watcher.poll(), "Starting on the session VT is not a switch");
+    assert!(!watcher.poll());
+}
+
+#[test]
+fn test_reapply_when_returning_to_session_vt() {
+    let (mut watcher, active) = fake_watcher(2);
+    assert!(!watcher.poll());
+
+    active.set(Some(3));
+    assert!(!watcher.poll(), "Switching away needs no reapply");
+    assert!(!watcher.poll());
+
+    active.set(Some(2));
+    assert!(watcher.poll(), "Returning to the session VT should reapply");
+    assert!(!watcher.poll(), "Only once per return");
+}
+
+#[test]
+fn test_reapply_when_started_on_another_vt() {
+    let (mut watcher, active) = fake_watcher(7);
+    active.set(Some(1));
+    assert!(!watcher.poll());
+
+    active.set(Some(7));
+    assert!(watcher.poll());
+}
+
+#[test]
+fn test_unreadable_vt_is_not_a_switch() {
+    let (mut watcher, active) = fake_watcher(2);
+    assert!(!watcher.poll());
+
+    active.set(None);
+    assert!(!watcher.poll());
+    active.set(Some(2));
+    assert!(!watcher.poll(), "A failed read in between is not a VT switch");
+
+    active.set(Some(4));
+    assert!(!watcher.poll());
+    active.set(None);
+    assert!(!watcher.poll());
+    active.set(Some(2));
+    assert!(watcher.poll(), "A switch is still seen across a failed read");
+}
@@ -0,0 +1,31 @@
+/// Tests for replacing the built-in blackbody table
+/// Kept apart from colorramp_tests since loading a table changes
+/// get_white_point for the whole test process.
+
+use redshift_rebooted::colorramp::*;
+use std::io::Write;
+
+#[test]
+fn test_loaded_table_replaces_builtin_white_points() {
+    let builtin = get_white_point(3000);
+
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "1000,1,0.2,0\n5000,1,0.6,0.4\n9000,0.8,0.9,1").unwrap();
+    assert_eq!(load_white_point_table(file.path()), Ok(3));
+
+    let custom = get_white_point(3000);
+    assert_ne!(custom, builtin);
+    assert!((custom[1] - 0.4).abs() < 1e-6, "Green: {}", custom[1]);
+    assert!((custom[2] - 0.2).abs() < 1e-6, "Blue: {}", custom[2]);
+    assert_eq!(get_white_point(9000), [0.8, 0.9, 1.0]);
+
+    // Only one table can be loaded
+    let err = load_white_point_table(file.path()).unwrap_err();
+    assert!(err.contains("already"), "Unexpected error: {}", err);
+}
+
+#[test]
+fn test_load_missing_table_fails() {
+    let err = load_white_point_table(std::path::Path::new("/nonexistent/table.csv")).unwrap_err();
+    assert!(err.contains("/nonexistent/table.csv"), "Unexpected error: {}", err);
+}